@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS orderbook_snapshots (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    exchange    TEXT NOT NULL,
+    symbol      TEXT NOT NULL,
+    timestamp   TEXT NOT NULL,
+    bids        TEXT NOT NULL,
+    asks        TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_orderbook_snapshots_lookup
+    ON orderbook_snapshots(exchange, symbol, timestamp DESC);
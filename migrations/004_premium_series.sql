@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS premium_series (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    spread_name    TEXT NOT NULL,
+    base           TEXT NOT NULL,
+    timestamp      TEXT NOT NULL,
+    upbit_price    REAL NOT NULL,
+    binance_price  REAL NOT NULL,
+    fx_rate        REAL NOT NULL,
+    premium_pct    REAL NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_premium_series_lookup
+    ON premium_series(spread_name, timestamp DESC);
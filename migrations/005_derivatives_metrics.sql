@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS derivatives_metrics (
+    exchange   TEXT NOT NULL,
+    symbol     TEXT NOT NULL,
+    metric     TEXT NOT NULL,
+    timestamp  TEXT NOT NULL,
+    value      REAL NOT NULL,
+    PRIMARY KEY (exchange, symbol, metric, timestamp)
+);
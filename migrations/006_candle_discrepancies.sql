@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS candle_discrepancies (
+    exchange         TEXT NOT NULL,
+    symbol           TEXT NOT NULL,
+    timeframe        TEXT NOT NULL,
+    open_time        TEXT NOT NULL,
+    price_diff_pct   REAL NOT NULL,
+    volume_diff      REAL NOT NULL,
+    volume_diff_pct  REAL NOT NULL,
+    PRIMARY KEY (exchange, symbol, timeframe, open_time)
+);
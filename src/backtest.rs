@@ -4,8 +4,10 @@ use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::config::{AppConfig, BacktestConfig};
+use crate::error::UnknownExchange;
+use crate::exchange::registry;
 use crate::model::{BacktestRun, BacktestTrade, Candle, ExchangeKind, TimeFrame};
-use crate::signal_input::{SignalInput, build_default_inputs, build_inputs};
+use crate::signal_input::{DerivativesSeries, SignalInput, build_default_inputs, build_inputs};
 use crate::signal_model::{
     ModelContext, SignalAction, TradingModel, build_default_model, build_model,
 };
@@ -59,8 +61,18 @@ pub async fn run(config: &AppConfig, storage: &dyn Storage) -> Result<BacktestOu
         ));
     }
 
+    let derivatives = load_derivatives(
+        storage,
+        exchange,
+        &settings.symbol,
+        timeframe,
+        &candles,
+        &inputs,
+    )
+    .await?;
+
     let model = find_model(config, settings)?;
-    let input_series = build_input_series(&inputs, &candles)?;
+    let input_series = build_input_series(&inputs, &candles, &derivatives)?;
 
     let mut engine = BacktestEngine::new(settings, exchange, timeframe, model.name().to_string());
     engine.execute(&candles, &input_series, model.as_ref())?;
@@ -91,24 +103,61 @@ fn find_model(
     build_model(model_config)
 }
 
+/// Load the derivatives samples read by `inputs` over `candles`.
+async fn load_derivatives(
+    storage: &dyn Storage,
+    exchange: ExchangeKind,
+    symbol: &str,
+    timeframe: TimeFrame,
+    candles: &[Candle],
+    inputs: &[Box<dyn SignalInput>],
+) -> Result<DerivativesSeries, String> {
+    let (Some(first), Some(last)) = (candles.first(), candles.last()) else {
+        return Ok(DerivativesSeries::new());
+    };
+
+    let mut series = DerivativesSeries::new();
+    for metric in inputs.iter().filter_map(|input| input.derivatives_metric()) {
+        if series.contains_key(&metric) {
+            continue;
+        }
+        let window = inputs
+            .iter()
+            .filter(|input| input.derivatives_metric() == Some(metric))
+            .map(|input| input.derivatives_window())
+            .max()
+            .unwrap_or_default();
+        // Include the sample in force when the first candle opened
+        let samples = storage
+            .get_derivatives_series(
+                exchange,
+                symbol,
+                metric,
+                first.open_time - timeframe.duration() - window,
+                last.open_time + timeframe.duration(),
+            )
+            .await
+            .map_err(|e| format!("failed to load {} samples: {e:?}", metric.as_str()))?;
+        series.insert(metric, samples);
+    }
+    Ok(series)
+}
+
 fn build_input_series(
     inputs: &[Box<dyn SignalInput>],
     candles: &[Candle],
+    derivatives: &DerivativesSeries,
 ) -> Result<HashMap<String, Vec<Option<f64>>>, String> {
     let mut map = HashMap::new();
     for input in inputs {
-        let values = input.series(candles)?;
+        let values = input.series(candles, derivatives)?;
         map.insert(input.name().to_string(), values);
     }
     Ok(map)
 }
 
 fn parse_exchange(name: &str) -> Result<ExchangeKind, String> {
-    match name {
-        "upbit" => Ok(ExchangeKind::Upbit),
-        "binance" => Ok(ExchangeKind::Binance),
-        _ => Err(format!("unknown exchange: {name}")),
-    }
+    name.parse().map_err(|e: UnknownExchange| e.to_string())
 }
 
 struct BacktestEngine<'a> {
@@ -365,14 +414,7 @@ fn resolve_fee_bps(exchange: ExchangeKind, overrides: &HashMap<String, f64>) ->
     if let Some(value) = overrides.get(&key) {
         return *value;
     }
-    default_fee_bps(exchange)
-}
-
-fn default_fee_bps(exchange: ExchangeKind) -> f64 {
-    match exchange {
-        ExchangeKind::Upbit => 5.0,
-        ExchangeKind::Binance => 10.0,
-    }
+    registry::spec(exchange).default_fee_bps
 }
 
 fn calculate_max_drawdown_pct(equity_curve: &[f64]) -> f64 {
@@ -407,6 +449,76 @@ mod tests {
         assert_eq!(apply_slippage(100.0, 10.0, false), 99.9);
     }
 
+    #[test]
+    fn derivatives_inputs_read_samples_as_of_each_close() {
+        use crate::config::InputConfig;
+        use crate::model::{DerivativesMetric, DerivativesSample};
+
+        let at = |minutes: i64| DateTime::from_timestamp(1_704_067_200 + minutes * 60, 0).unwrap();
+        let candle = |minutes| Candle {
+            exchange: ExchangeKind::BinanceFutures,
+            symbol: "BTCUSDT".into(),
+            instrument: crate::model::Instrument::new("BTC", "USDT"),
+            timeframe: TimeFrame::Min5,
+            open_time: at(minutes),
+            open: 1.0,
+            high: 1.0,
+            low: 1.0,
+            close: 1.0,
+            volume: 1.0,
+        };
+        let sample = |metric, minutes, value| DerivativesSample {
+            exchange: ExchangeKind::BinanceFutures,
+            symbol: "BTCUSDT".into(),
+            metric,
+            value,
+            timestamp: at(minutes),
+        };
+        let input = |name: &str, kind: &str| InputConfig {
+            name: name.into(),
+            kind: kind.into(),
+            params: toml::Table::new(),
+        };
+
+        let inputs = build_inputs(&[
+            input("funding", "funding_rate"),
+            input("oi", "open_interest_change"),
+        ])
+        .unwrap();
+        assert_eq!(
+            inputs[1].derivatives_window(),
+            chrono::Duration::seconds(5 * 60 + crate::strategy::OI_SAMPLE_MAX_AGE_SECS)
+        );
+
+        let candles = [candle(0), candle(5), candle(10), candle(15)];
+        let mut derivatives = DerivativesSeries::new();
+        derivatives.insert(
+            DerivativesMetric::FundingRate,
+            vec![
+                sample(DerivativesMetric::FundingRate, 6, 0.01),
+                sample(DerivativesMetric::FundingRate, 9, 0.02),
+                sample(DerivativesMetric::FundingRate, 10, 0.03),
+            ],
+        );
+        // Open interest polled every minute until minute 10
+        derivatives.insert(
+            DerivativesMetric::OpenInterest,
+            (0..=10)
+                .map(|m| sample(DerivativesMetric::OpenInterest, m, 100.0 + m as f64))
+                .collect(),
+        );
+
+        let series = build_input_series(&inputs, &candles, &derivatives).unwrap();
+        assert_eq!(
+            series["funding"],
+            vec![None, Some(3.0), Some(3.0), Some(3.0)]
+        );
+        assert_eq!(
+            series["oi"],
+            vec![Some(5.0), Some(5.0 / 105.0 * 100.0), None, None]
+        );
+    }
+
     #[test]
     fn max_drawdown_detects_peak_to_trough() {
         let curve = vec![100.0, 120.0, 90.0, 110.0];
@@ -0,0 +1,461 @@
+//! Rolls live trades into candles of every configured timeframe.
+//!
+//! Buckets are aligned to the Unix epoch, so 5m candles open on multiples of
+//! five minutes and 4h candles at 00:00, 04:00, ... UTC. Daily candles open
+//! at midnight in the exchange's own day-start time zone (see
+//! `ExchangeSpec::day_start_utc_offset_hours`), e.g. 00:00 KST on Upbit.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+
+use crate::config::AppConfig;
+use crate::exchange::registry;
+use crate::model::{Candle, CandleUpdate, ExchangeKind, TimeFrame, Trade};
+
+/// Open time of the `timeframe` candle of `exchange` containing `timestamp`.
+pub fn bucket_open_time(
+    exchange: ExchangeKind,
+    timeframe: TimeFrame,
+    timestamp: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let offset = match timeframe {
+        TimeFrame::Day1 => i64::from(registry::spec(exchange).day_start_utc_offset_hours) * 3600,
+        _ => 0,
+    };
+    let length = timeframe.duration().num_seconds();
+    let local = timestamp.timestamp() + offset;
+    let open = local - local.rem_euclid(length) - offset;
+    DateTime::from_timestamp(open, 0).unwrap_or(timestamp)
+}
+
+/// Candles in progress for every coin and timeframe.
+#[derive(Debug, Default)]
+pub struct CandleAggregator {
+    /// Timeframes built per coin, besides 1m
+    coin_timeframes: HashMap<(ExchangeKind, String), Vec<TimeFrame>>,
+    /// Timeframes of any coin of an exchange, for symbols added at runtime
+    exchange_timeframes: HashMap<ExchangeKind, Vec<TimeFrame>>,
+    forming: HashMap<(ExchangeKind, String, TimeFrame), Candle>,
+    /// Forming candles already reported closed by `close_elapsed`
+    closed: HashSet<(ExchangeKind, String, TimeFrame)>,
+}
+
+impl CandleAggregator {
+    /// Build the timeframes configured under `[[coins]]`.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut aggregator = Self::default();
+        for coin in &config.coins {
+            let Ok(exchange) = coin.exchange.parse::<ExchangeKind>() else {
+                continue;
+            };
+            let timeframes: Vec<TimeFrame> = coin
+                .timeframes
+                .iter()
+                .filter_map(|tf| TimeFrame::from_str(tf))
+                .collect();
+
+            let all = aggregator.exchange_timeframes.entry(exchange).or_default();
+            for &timeframe in &timeframes {
+                if !all.contains(&timeframe) {
+                    all.push(timeframe);
+                }
+            }
+            aggregator
+                .coin_timeframes
+                .insert((exchange, coin.symbol.clone()), timeframes);
+        }
+        aggregator
+    }
+
+    /// Timeframes built for a coin; always starts with 1m.
+    pub fn timeframes(&self, exchange: ExchangeKind, symbol: &str) -> Vec<TimeFrame> {
+        let configured = self
+            .coin_timeframes
+            .get(&(exchange, symbol.to_owned()))
+            .or_else(|| self.exchange_timeframes.get(&exchange));
+
+        let mut timeframes = vec![TimeFrame::Min1];
+        for &timeframe in configured.into_iter().flatten() {
+            if !timeframes.contains(&timeframe) {
+                timeframes.push(timeframe);
+            }
+        }
+        timeframes
+    }
+
+    /// Continue from a stored candle, e.g. the current bucket fetched over
+    /// REST at startup, instead of opening it afresh on the next trade.
+    /// Ignored when a newer candle is already forming.
+    pub fn seed(&mut self, candle: Candle) {
+        let key = (candle.exchange, candle.symbol.clone(), candle.timeframe);
+        match self.forming.get(&key) {
+            Some(current) if current.open_time >= candle.open_time => {}
+            _ => {
+                self.closed.remove(&key);
+                self.forming.insert(key, candle);
+            }
+        }
+    }
+
+    /// Continue the forming candle of the same bucket from `candle` instead,
+    /// e.g. the exchange's own after trades were missed. Returns whether a
+    /// forming candle was replaced.
+    pub fn reseed(&mut self, candle: &Candle) -> bool {
+        let key = (candle.exchange, candle.symbol.clone(), candle.timeframe);
+        match self.forming.get_mut(&key) {
+            Some(current) if current.open_time == candle.open_time => {
+                *current = candle.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Merge `trade` into the candle of each timeframe of its coin.
+    ///
+    /// Returns every candle that changed as a partial update, preceded by
+    /// the final state of any candle the trade closed and `close_elapsed`
+    /// has not reported yet. Trades older than a timeframe's forming candle
+    /// are ignored for that timeframe; late trades of a candle already
+    /// reported closed still count toward it.
+    pub fn apply(&mut self, trade: &Trade) -> Vec<CandleUpdate> {
+        let mut updates = Vec::new();
+
+        for timeframe in self.timeframes(trade.exchange, &trade.symbol) {
+            let open_time = bucket_open_time(trade.exchange, timeframe, trade.timestamp);
+            let key = (trade.exchange, trade.symbol.clone(), timeframe);
+
+            match self.forming.get_mut(&key) {
+                Some(candle) if candle.open_time < open_time => {
+                    let closed = std::mem::replace(candle, new_candle(trade, timeframe, open_time));
+                    if !self.closed.remove(&key) {
+                        updates.push(CandleUpdate {
+                            candle: closed,
+                            is_closed: true,
+                        });
+                    }
+                    updates.push(CandleUpdate {
+                        candle: candle.clone(),
+                        is_closed: false,
+                    });
+                }
+                Some(candle) if candle.open_time == open_time => {
+                    candle.high = candle.high.max(trade.price);
+                    candle.low = candle.low.min(trade.price);
+                    candle.close = trade.price;
+                    candle.volume += trade.volume;
+                    updates.push(CandleUpdate {
+                        candle: candle.clone(),
+                        is_closed: false,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    let candle = new_candle(trade, timeframe, open_time);
+                    self.forming.insert(key, candle.clone());
+                    updates.push(CandleUpdate {
+                        candle,
+                        is_closed: false,
+                    });
+                }
+            }
+        }
+        updates
+    }
+
+    /// Report the forming candles of `exchange` whose bucket ended by `now`
+    /// as closed, so quiet coins close on time rather than on their next
+    /// trade. Each candle is reported closed once.
+    pub fn close_elapsed(
+        &mut self,
+        exchange: ExchangeKind,
+        now: DateTime<Utc>,
+    ) -> Vec<CandleUpdate> {
+        let mut updates = Vec::new();
+        for (key, candle) in &self.forming {
+            let (kind, _, timeframe) = key;
+            if *kind != exchange || bucket_open_time(*kind, *timeframe, now) <= candle.open_time {
+                continue;
+            }
+            if self.closed.insert(key.clone()) {
+                updates.push(CandleUpdate {
+                    candle: candle.clone(),
+                    is_closed: true,
+                });
+            }
+        }
+        updates.sort_by_key(|u| (u.candle.open_time, u.candle.timeframe.duration()));
+        updates
+    }
+}
+
+fn new_candle(trade: &Trade, timeframe: TimeFrame, open_time: DateTime<Utc>) -> Candle {
+    Candle {
+        exchange: trade.exchange,
+        symbol: trade.symbol.clone(),
+        instrument: trade.instrument.clone(),
+        timeframe,
+        open_time,
+        open: trade.price,
+        high: trade.price,
+        low: trade.price,
+        close: trade.price,
+        volume: trade.volume,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Instrument, TradeSide};
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn make_trade(timestamp: i64, price: f64, volume: f64) -> Trade {
+        Trade {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            instrument: Instrument::new("SOL", "KRW"),
+            price,
+            volume,
+            side: TradeSide::Buy,
+            timestamp: DateTime::from_timestamp(timestamp, 0).unwrap(),
+            received_at: DateTime::from_timestamp(timestamp, 0).unwrap(),
+            trade_id: None,
+        }
+    }
+
+    fn with_timeframes(timeframes: &[TimeFrame]) -> CandleAggregator {
+        let mut aggregator = CandleAggregator::default();
+        aggregator
+            .coin_timeframes
+            .insert((ExchangeKind::Upbit, "KRW-SOL".into()), timeframes.to_vec());
+        aggregator
+    }
+
+    fn partial(updates: &[CandleUpdate], timeframe: TimeFrame) -> &Candle {
+        &updates
+            .iter()
+            .find(|u| !u.is_closed && u.candle.timeframe == timeframe)
+            .unwrap()
+            .candle
+    }
+
+    #[test]
+    fn buckets_align_to_epoch_and_exchange_day_start() {
+        let t = at("2024-01-01T16:47:30Z");
+        let bucket = |exchange, timeframe| bucket_open_time(exchange, timeframe, t);
+
+        assert_eq!(
+            bucket(ExchangeKind::Binance, TimeFrame::Min1),
+            at("2024-01-01T16:47:00Z")
+        );
+        assert_eq!(
+            bucket(ExchangeKind::Binance, TimeFrame::Min15),
+            at("2024-01-01T16:45:00Z")
+        );
+        assert_eq!(
+            bucket(ExchangeKind::Upbit, TimeFrame::Hour4),
+            at("2024-01-01T16:00:00Z")
+        );
+        assert_eq!(
+            bucket(ExchangeKind::Binance, TimeFrame::Day1),
+            at("2024-01-01T00:00:00Z")
+        );
+        // 01:47 KST on Jan 2: the Upbit day opened at 00:00 KST
+        assert_eq!(
+            bucket(ExchangeKind::Upbit, TimeFrame::Day1),
+            at("2024-01-01T15:00:00Z")
+        );
+        assert_eq!(
+            bucket_open_time(
+                ExchangeKind::Upbit,
+                TimeFrame::Day1,
+                at("2024-01-01T14:59:59Z")
+            ),
+            at("2023-12-31T15:00:00Z")
+        );
+    }
+
+    #[test]
+    fn merge_trade_updates_existing_minute_candle() {
+        let mut aggregator = CandleAggregator::default();
+        let first = make_trade(180, 100.0, 1.2);
+        let second = make_trade(185, 110.0, 0.8);
+
+        aggregator.apply(&first);
+        let updates = aggregator.apply(&second);
+        let updated = partial(&updates, TimeFrame::Min1);
+
+        assert_eq!(updated.open, 100.0);
+        assert_eq!(updated.high, 110.0);
+        assert_eq!(updated.low, 100.0);
+        assert_eq!(updated.close, 110.0);
+        assert_eq!(updated.volume, 2.0);
+        assert_eq!(updated.open_time.timestamp(), 180);
+    }
+
+    #[test]
+    fn merge_trade_rolls_over_to_new_minute_candle() {
+        let mut aggregator = CandleAggregator::default();
+        let first = make_trade(180, 100.0, 1.0);
+        let second = make_trade(240, 95.0, 0.5);
+
+        aggregator.apply(&first);
+        let updates = aggregator.apply(&second);
+        let rolled = partial(&updates, TimeFrame::Min1);
+
+        assert_eq!(rolled.open, 95.0);
+        assert_eq!(rolled.high, 95.0);
+        assert_eq!(rolled.low, 95.0);
+        assert_eq!(rolled.close, 95.0);
+        assert_eq!(rolled.volume, 0.5);
+        assert_eq!(rolled.open_time.timestamp(), 240);
+    }
+
+    #[test]
+    fn merge_trade_ignores_out_of_order_old_minute() {
+        let mut aggregator = CandleAggregator::default();
+        let recent = make_trade(240, 100.0, 1.0);
+        let stale = make_trade(180, 90.0, 0.5);
+
+        aggregator.apply(&recent);
+        let ignored = aggregator.apply(&stale);
+
+        assert!(ignored.is_empty());
+        let candle = &aggregator.forming[&(ExchangeKind::Upbit, "KRW-SOL".into(), TimeFrame::Min1)];
+        assert_eq!(candle.open_time.timestamp(), 240);
+        assert_eq!(candle.close, 100.0);
+        assert_eq!(candle.volume, 1.0);
+    }
+
+    #[test]
+    fn trades_roll_into_every_configured_timeframe() {
+        let mut aggregator = with_timeframes(&[TimeFrame::Min5, TimeFrame::Hour1]);
+        assert_eq!(
+            aggregator.timeframes(ExchangeKind::Upbit, "KRW-SOL"),
+            vec![TimeFrame::Min1, TimeFrame::Min5, TimeFrame::Hour1]
+        );
+
+        aggregator.apply(&make_trade(60, 100.0, 1.0));
+        aggregator.apply(&make_trade(200, 120.0, 2.0));
+        let updates = aggregator.apply(&make_trade(300, 90.0, 0.5));
+
+        // 00:05 closes the 00:00 5m candle and the 00:03 1m candle
+        let closed: Vec<(TimeFrame, i64, f64)> = updates
+            .iter()
+            .filter(|u| u.is_closed)
+            .map(|u| {
+                (
+                    u.candle.timeframe,
+                    u.candle.open_time.timestamp(),
+                    u.candle.volume,
+                )
+            })
+            .collect();
+        assert_eq!(
+            closed,
+            vec![(TimeFrame::Min1, 180, 2.0), (TimeFrame::Min5, 0, 3.0)]
+        );
+
+        let hour = partial(&updates, TimeFrame::Hour1);
+        assert_eq!(hour.open_time.timestamp(), 0);
+        assert_eq!(
+            (hour.open, hour.high, hour.low, hour.close),
+            (100.0, 120.0, 90.0, 90.0)
+        );
+        assert_eq!(hour.volume, 3.5);
+        assert_eq!(
+            partial(&updates, TimeFrame::Min5).open_time.timestamp(),
+            300
+        );
+    }
+
+    #[test]
+    fn elapsed_buckets_close_once_without_a_next_trade() {
+        let mut aggregator = with_timeframes(&[TimeFrame::Min5]);
+        aggregator.apply(&make_trade(190, 100.0, 1.0));
+
+        assert!(
+            aggregator
+                .close_elapsed(
+                    ExchangeKind::Upbit,
+                    DateTime::from_timestamp(239, 0).unwrap()
+                )
+                .is_empty()
+        );
+        let closed = aggregator.close_elapsed(
+            ExchangeKind::Upbit,
+            DateTime::from_timestamp(240, 0).unwrap(),
+        );
+        assert_eq!(closed.len(), 1);
+        assert!(closed[0].is_closed);
+        assert_eq!(closed[0].candle.timeframe, TimeFrame::Min1);
+        assert_eq!(closed[0].candle.open_time.timestamp(), 180);
+        // Reported once, and only by its own exchange's clock
+        for exchange in [ExchangeKind::Upbit, ExchangeKind::Binance] {
+            assert!(
+                aggregator
+                    .close_elapsed(exchange, DateTime::from_timestamp(250, 0).unwrap())
+                    .is_empty()
+            );
+        }
+
+        // A late trade still counts, without a second close
+        let late = aggregator.apply(&make_trade(230, 90.0, 1.0));
+        assert!(late.iter().all(|u| !u.is_closed));
+        assert_eq!(partial(&late, TimeFrame::Min1).volume, 2.0);
+
+        // The next bucket's trade reports only the 5m close
+        let updates = aggregator.apply(&make_trade(300, 95.0, 1.0));
+        let closed: Vec<TimeFrame> = updates
+            .iter()
+            .filter(|u| u.is_closed)
+            .map(|u| u.candle.timeframe)
+            .collect();
+        assert_eq!(closed, vec![TimeFrame::Min5]);
+    }
+
+    #[test]
+    fn reseed_replaces_only_the_forming_bucket() {
+        let mut aggregator = with_timeframes(&[TimeFrame::Min5]);
+        aggregator.apply(&make_trade(190, 100.0, 1.0));
+        let mut official = new_candle(
+            &make_trade(0, 90.0, 7.0),
+            TimeFrame::Min5,
+            at("1970-01-01T00:00:00Z"),
+        );
+        official.high = 120.0;
+        assert!(aggregator.reseed(&official));
+
+        let mut older = official.clone();
+        older.timeframe = TimeFrame::Min1;
+        assert!(!aggregator.reseed(&older));
+
+        let updates = aggregator.apply(&make_trade(200, 110.0, 1.0));
+        let five = partial(&updates, TimeFrame::Min5);
+        assert_eq!((five.open, five.high, five.close), (90.0, 120.0, 110.0));
+        assert_eq!(five.volume, 8.0);
+        assert_eq!(partial(&updates, TimeFrame::Min1).volume, 2.0);
+    }
+
+    #[test]
+    fn seeded_candle_continues_instead_of_restarting() {
+        let mut aggregator = with_timeframes(&[TimeFrame::Hour1]);
+        let mut stored = new_candle(
+            &make_trade(10, 80.0, 40.0),
+            TimeFrame::Hour1,
+            at("1970-01-01T00:00:00Z"),
+        );
+        stored.high = 130.0;
+        aggregator.seed(stored);
+
+        let updates = aggregator.apply(&make_trade(1800, 100.0, 1.0));
+        let hour = partial(&updates, TimeFrame::Hour1);
+        assert_eq!((hour.open, hour.high, hour.close), (80.0, 130.0, 100.0));
+        assert_eq!(hour.volume, 41.0);
+    }
+}
@@ -0,0 +1,533 @@
+//! Recent candles held in memory for the alert analysis path.
+//!
+//! The cache is warmed from storage at startup and updated by the real-time
+//! candle sync, so evaluating a rule on a ticker does not query SQLite.
+//! Storage stays the durable copy: candle changes are buffered in a
+//! `CandleWriter` and persisted in batches behind the cache.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use error_stack::Report;
+
+use crate::error::StorageError;
+use crate::model::{Candle, ExchangeKind, TimeFrame};
+use crate::storage::Storage;
+
+type SeriesKey = (ExchangeKind, String, TimeFrame);
+
+/// Closed candle changes remembered per series for `changed_since`.
+const CHANGE_LOG_LEN: usize = 64;
+
+/// Ring buffer of the latest candles per (exchange, symbol, timeframe).
+#[derive(Debug)]
+pub struct CandleCache {
+    capacity: usize,
+    series: Mutex<HashMap<SeriesKey, Series>>,
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    candles: VecDeque<Candle>,
+    /// Bumped whenever an already closed candle changes
+    revision: u64,
+    /// Revision and open time of the latest closed candle changes
+    changes: VecDeque<(u64, DateTime<Utc>)>,
+    /// Depth of the deepest load from storage; storage has nothing older
+    /// than the cache when fewer candles came back
+    loaded: usize,
+}
+
+impl CandleCache {
+    /// Keep up to `capacity` candles per series.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            series: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load the latest stored candles of one series.
+    pub async fn warm(
+        &self,
+        storage: &dyn Storage,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+    ) {
+        match storage
+            .get_recent_candles(exchange, symbol, timeframe, self.capacity)
+            .await
+        {
+            Ok(candles) => self.fill_loaded(exchange, symbol, timeframe, &candles, self.capacity),
+            Err(e) => tracing::warn!(
+                error = ?e,
+                exchange = %exchange,
+                symbol,
+                timeframe = %timeframe.as_str(),
+                "failed to warm candle cache"
+            ),
+        }
+    }
+
+    /// Insert or replace a candle, e.g. a partial update of the forming one.
+    /// Changing a closed candle, e.g. when a gap is repaired, bumps the
+    /// series' `revision`.
+    pub fn insert(&self, candle: &Candle) {
+        let mut series = self.series.lock().expect("candle cache lock poisoned");
+        let entry = series
+            .entry((candle.exchange, candle.symbol.clone(), candle.timeframe))
+            .or_default();
+        if insert_sorted(&mut entry.candles, candle, self.capacity, true) {
+            entry.revision += 1;
+            entry.changes.push_back((entry.revision, candle.open_time));
+            if entry.changes.len() > CHANGE_LOG_LEN {
+                entry.changes.pop_front();
+            }
+        }
+    }
+
+    /// Add candles loaded from storage without overwriting newer cached
+    /// versions that may not have been persisted yet.
+    pub fn fill(&self, candles: &[Candle]) {
+        let mut series = self.series.lock().expect("candle cache lock poisoned");
+        for candle in candles {
+            let entry = series
+                .entry((candle.exchange, candle.symbol.clone(), candle.timeframe))
+                .or_default();
+            insert_sorted(&mut entry.candles, candle, self.capacity, false);
+        }
+    }
+
+    /// `fill` with what a storage query of `depth` candles returned, so the
+    /// series is not loaded again for any `limit` up to `depth`.
+    fn fill_loaded(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        candles: &[Candle],
+        depth: usize,
+    ) {
+        self.fill(candles);
+        let mut series = self.series.lock().expect("candle cache lock poisoned");
+        let entry = series
+            .entry((exchange, symbol.to_owned(), timeframe))
+            .or_default();
+        entry.loaded = entry.loaded.max(depth);
+    }
+
+    /// How often closed candles of a series were changed by `insert`. State
+    /// derived from earlier candles is stale once this moves.
+    pub fn revision(&self, exchange: ExchangeKind, symbol: &str, timeframe: TimeFrame) -> u64 {
+        let series = self.series.lock().expect("candle cache lock poisoned");
+        series
+            .get(&(exchange, symbol.to_owned(), timeframe))
+            .map_or(0, |s| s.revision)
+    }
+
+    /// Oldest open time among the closed candles of a series changed after
+    /// `revision`, or `None` when those changes are no longer remembered.
+    pub fn changed_since(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        revision: u64,
+    ) -> Option<DateTime<Utc>> {
+        let series = self.series.lock().expect("candle cache lock poisoned");
+        let entry = series.get(&(exchange, symbol.to_owned(), timeframe))?;
+        let (oldest_logged, _) = entry.changes.front()?;
+        if *oldest_logged > revision + 1 {
+            return None;
+        }
+        entry
+            .changes
+            .iter()
+            .filter(|(r, _)| *r > revision)
+            .map(|(_, open_time)| *open_time)
+            .min()
+    }
+
+    /// The latest `limit` candles of a series, oldest first, or `None` when
+    /// fewer are cached.
+    pub fn recent(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        limit: usize,
+    ) -> Option<Vec<Candle>> {
+        let series = self.series.lock().expect("candle cache lock poisoned");
+        let buffer = &series
+            .get(&(exchange, symbol.to_owned(), timeframe))?
+            .candles;
+        let skip = buffer.len().checked_sub(limit)?;
+        Some(buffer.iter().skip(skip).cloned().collect())
+    }
+
+    /// Cached candles of a series opened after `open_time`, oldest first, or
+    /// `None` when the candle at `open_time` is no longer cached.
+    pub fn after(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        open_time: DateTime<Utc>,
+    ) -> Option<Vec<Candle>> {
+        let series = self.series.lock().expect("candle cache lock poisoned");
+        let buffer = &series
+            .get(&(exchange, symbol.to_owned(), timeframe))?
+            .candles;
+        let i = buffer
+            .binary_search_by_key(&open_time, |c| c.open_time)
+            .ok()?;
+        Some(buffer.range(i + 1..).cloned().collect())
+    }
+
+    /// Like `recent`, but falls back to storage when the cache is short and
+    /// storage was not yet read that deep, e.g. for a symbol whose history
+    /// was backfilled after startup. What storage returns is cached for the
+    /// next call, so a series with little history is read once, not on every
+    /// call.
+    pub async fn recent_or_load(
+        &self,
+        storage: &dyn Storage,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        limit: usize,
+    ) -> Result<Vec<Candle>, Report<StorageError>> {
+        if let Some(candles) = self.recent(exchange, symbol, timeframe, limit) {
+            return Ok(candles);
+        }
+        if limit <= self.capacity
+            && let Some(candles) = self.fully_loaded(exchange, symbol, timeframe, limit)
+        {
+            return Ok(candles);
+        }
+        let depth = limit.max(self.capacity);
+        let stored = storage
+            .get_recent_candles(exchange, symbol, timeframe, depth)
+            .await?;
+        self.fill_loaded(exchange, symbol, timeframe, &stored, depth);
+        Ok(self
+            .recent(exchange, symbol, timeframe, limit)
+            .unwrap_or_else(|| {
+                let skip = stored.len().saturating_sub(limit);
+                stored[skip..].to_vec()
+            }))
+    }
+
+    /// Every cached candle of a series once storage was read at least
+    /// `limit` deep, since it holds no more than that.
+    fn fully_loaded(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        limit: usize,
+    ) -> Option<Vec<Candle>> {
+        let series = self.series.lock().expect("candle cache lock poisoned");
+        let entry = series.get(&(exchange, symbol.to_owned(), timeframe))?;
+        (entry.loaded >= limit).then(|| entry.candles.iter().cloned().collect())
+    }
+}
+
+/// Place `candle` by open time, dropping the oldest candle beyond `capacity`.
+/// An existing candle with the same open time is replaced only if `replace`.
+///
+/// Returns whether closed history changed: a candle was placed before the
+/// newest one, or an existing candle got new values after its interval
+/// ended.
+fn insert_sorted(
+    buffer: &mut VecDeque<Candle>,
+    candle: &Candle,
+    capacity: usize,
+    replace: bool,
+) -> bool {
+    let changed_closed = match buffer.back() {
+        None => {
+            buffer.push_back(candle.clone());
+            false
+        }
+        Some(last) if last.open_time < candle.open_time => {
+            buffer.push_back(candle.clone());
+            false
+        }
+        _ => match buffer.binary_search_by_key(&candle.open_time, |c| c.open_time) {
+            Ok(i) => {
+                if !replace || same_values(&buffer[i], candle) {
+                    return false;
+                }
+                buffer[i] = candle.clone();
+                return i + 1 < buffer.len()
+                    || candle.open_time + candle.timeframe.duration() <= Utc::now();
+            }
+            // Older than everything in a full buffer
+            Err(0) if buffer.len() >= capacity => return false,
+            Err(i) => {
+                buffer.insert(i, candle.clone());
+                true
+            }
+        },
+    };
+    while buffer.len() > capacity {
+        buffer.pop_front();
+    }
+    changed_closed
+}
+
+fn same_values(a: &Candle, b: &Candle) -> bool {
+    a.open == b.open
+        && a.high == b.high
+        && a.low == b.low
+        && a.close == b.close
+        && a.volume == b.volume
+}
+
+/// Candle changes waiting to be written to storage; only the latest state
+/// of each candle is kept.
+#[derive(Debug, Default)]
+pub struct CandleWriter {
+    pending: HashMap<(ExchangeKind, String, TimeFrame, DateTime<Utc>), Candle>,
+}
+
+impl CandleWriter {
+    pub fn push(&mut self, candle: Candle) {
+        self.pending.insert(
+            (
+                candle.exchange,
+                candle.symbol.clone(),
+                candle.timeframe,
+                candle.open_time,
+            ),
+            candle,
+        );
+    }
+
+    /// Write every pending candle in one batch. Failed batches are dropped:
+    /// the next partial update of a forming candle writes it again.
+    pub async fn flush(&mut self, storage: &dyn Storage) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let candles: Vec<Candle> = self.pending.drain().map(|(_, candle)| candle).collect();
+        if let Err(e) = storage.upsert_candles(&candles).await {
+            tracing::warn!(
+                error = ?e,
+                candles = candles.len(),
+                "failed to persist real-time candles"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Instrument;
+    use crate::storage::sqlite::SqliteStorage;
+
+    fn candle(minute: i64, close: f64) -> Candle {
+        Candle {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            instrument: Instrument::new("SOL", "KRW"),
+            timeframe: TimeFrame::Min1,
+            open_time: DateTime::from_timestamp(minute * 60, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    fn closes(candles: &[Candle]) -> Vec<f64> {
+        candles.iter().map(|c| c.close).collect()
+    }
+
+    #[test]
+    fn ring_buffer_keeps_latest_candles_in_order() {
+        let cache = CandleCache::new(3);
+        for minute in [1, 2, 4, 3, 5] {
+            cache.insert(&candle(minute, minute as f64));
+        }
+        // The partial candle of minute 5 is replaced in place
+        cache.insert(&candle(5, 5.5));
+        // Too old for a full buffer
+        cache.insert(&candle(1, 1.0));
+
+        let recent = |limit| cache.recent(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1, limit);
+        assert_eq!(closes(&recent(3).unwrap()), vec![3.0, 4.0, 5.5]);
+        assert_eq!(closes(&recent(2).unwrap()), vec![4.0, 5.5]);
+        assert!(recent(4).is_none());
+
+        let after = |minute: i64| {
+            let open_time = DateTime::from_timestamp(minute * 60, 0).unwrap();
+            cache.after(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1, open_time)
+        };
+        assert_eq!(closes(&after(3).unwrap()), vec![4.0, 5.5]);
+        assert!(after(5).unwrap().is_empty());
+        // Evicted
+        assert!(after(2).is_none());
+        assert!(
+            cache
+                .recent(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min5, 1)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn fill_keeps_cached_versions() {
+        let cache = CandleCache::new(10);
+        cache.insert(&candle(2, 20.5));
+        cache.fill(&[candle(1, 10.0), candle(2, 20.0)]);
+
+        let cached = cache
+            .recent(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1, 2)
+            .unwrap();
+        assert_eq!(closes(&cached), vec![10.0, 20.5]);
+    }
+
+    #[test]
+    fn revision_moves_when_closed_candles_change() {
+        let cache = CandleCache::new(10);
+        let revision = || cache.revision(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1);
+        for minute in [1, 2, 4] {
+            cache.insert(&candle(minute, 10.0));
+        }
+        assert_eq!(revision(), 0);
+
+        // Same values again, and history loaded from storage
+        cache.insert(&candle(2, 10.0));
+        cache.fill(&[candle(3, 10.0)]);
+        assert_eq!(revision(), 0);
+
+        // A correction of a candle behind the newest one
+        cache.insert(&candle(2, 11.0));
+        assert_eq!(revision(), 1);
+        // A repaired gap
+        cache.insert(&candle(0, 10.0));
+        assert_eq!(revision(), 2);
+        // The newest candle, long closed
+        cache.insert(&candle(4, 12.0));
+        assert_eq!(revision(), 3);
+
+        // Updates of a candle still forming
+        let now = Utc::now().timestamp() / 60;
+        cache.insert(&candle(now, 10.0));
+        cache.insert(&candle(now, 10.5));
+        assert_eq!(revision(), 3);
+
+        let changed = |since| {
+            cache
+                .changed_since(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1, since)
+                .map(|t| t.timestamp() / 60)
+        };
+        assert_eq!(changed(2), Some(4));
+        assert_eq!(changed(1), Some(0));
+        assert_eq!(changed(0), Some(0));
+
+        // Changes beyond the log are no longer known
+        for _ in 0..CHANGE_LOG_LEN {
+            cache.insert(&candle(
+                3,
+                cache.revision(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1) as f64,
+            ));
+        }
+        assert_eq!(changed(2), None);
+        assert_eq!(changed(3), Some(3));
+    }
+
+    #[tokio::test]
+    async fn writes_behind_and_loads_what_the_cache_lacks() {
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage = SqliteStorage::open(&db_path).await.unwrap();
+        storage
+            .upsert_candles(&[candle(1, 10.0), candle(2, 20.0)])
+            .await
+            .unwrap();
+
+        let cache = CandleCache::new(10);
+        cache
+            .warm(&storage, ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1)
+            .await;
+
+        let mut writer = CandleWriter::default();
+        for close in [30.0, 31.0, 32.0] {
+            cache.insert(&candle(3, close));
+            writer.push(candle(3, close));
+        }
+        let recent = cache
+            .recent_or_load(&storage, ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1, 3)
+            .await
+            .unwrap();
+        assert_eq!(closes(&recent), vec![10.0, 20.0, 32.0]);
+        let stored = storage
+            .get_recent_candles(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1, 10)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+
+        writer.flush(&storage).await;
+        let stored = storage
+            .get_recent_candles(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1, 10)
+            .await
+            .unwrap();
+        assert_eq!(closes(&stored), vec![10.0, 20.0, 32.0]);
+
+        // Backfilled behind the cache's back, e.g. for a symbol added at runtime
+        let mut other = candle(1, 5.0);
+        other.symbol = "KRW-XRP".into();
+        storage.upsert_candles(&[other]).await.unwrap();
+        let loaded = cache
+            .recent_or_load(&storage, ExchangeKind::Upbit, "KRW-XRP", TimeFrame::Min1, 1)
+            .await
+            .unwrap();
+        assert_eq!(closes(&loaded), vec![5.0]);
+        assert!(
+            cache
+                .recent(ExchangeKind::Upbit, "KRW-XRP", TimeFrame::Min1, 1)
+                .is_some()
+        );
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn short_series_are_read_from_storage_once() {
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage = SqliteStorage::open(&db_path).await.unwrap();
+        storage.upsert_candles(&[candle(1, 10.0)]).await.unwrap();
+
+        let cache = CandleCache::new(10);
+        let load = |limit| {
+            cache.recent_or_load(
+                &storage,
+                ExchangeKind::Upbit,
+                "KRW-SOL",
+                TimeFrame::Min1,
+                limit,
+            )
+        };
+        assert_eq!(closes(&load(5).await.unwrap()), vec![10.0]);
+
+        // Not read again for any depth already covered, even when short
+        storage.upsert_candles(&[candle(0, 5.0)]).await.unwrap();
+        cache.insert(&candle(2, 20.0));
+        assert_eq!(closes(&load(5).await.unwrap()), vec![10.0, 20.0]);
+        assert_eq!(closes(&load(10).await.unwrap()), vec![10.0, 20.0]);
+
+        // A deeper request than any load reads storage again
+        assert_eq!(closes(&load(11).await.unwrap()), vec![5.0, 10.0]);
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+}
@@ -0,0 +1,273 @@
+//! Finds and repairs holes in the stored candle history, e.g. the minutes
+//! missed while a trade socket was down.
+//!
+//! Each scan reads the latest stored candles of a series, looks for missing
+//! buckets between them and up to the last closed bucket, and fetches those
+//! over REST. The most recently closed bucket is left alone, since its
+//! real-time candle may still be waiting to be written. Buckets the exchange
+//! returns nothing for are only given up on once they are older than
+//! `SETTLE_SECS`; right after an outage the exchange may not have finalized
+//! them yet.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::candle_aggregator::bucket_open_time;
+use crate::candle_cache::CandleCache;
+use crate::exchange::Exchange;
+use crate::model::{Candle, ExchangeKind, TimeFrame};
+use crate::storage::Storage;
+
+/// Empty buckets newer than this are fetched again on the next scan.
+const SETTLE_SECS: i64 = 600;
+
+/// Missing `[start, end)` open time ranges of `timeframe` candles, between
+/// consecutive stored `open_times` (oldest first) and after the last one up
+/// to `until`. Nothing is reported before the first candle.
+pub fn find_gaps(
+    open_times: impl IntoIterator<Item = DateTime<Utc>>,
+    timeframe: TimeFrame,
+    until: DateTime<Utc>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let step = timeframe.duration();
+    let mut gaps = Vec::new();
+    let mut previous: Option<DateTime<Utc>> = None;
+    for open_time in open_times {
+        if let Some(expected) = previous.map(|p| p + step)
+            && expected < open_time
+        {
+            gaps.push((expected, open_time));
+        }
+        previous = Some(open_time);
+    }
+    if let Some(expected) = previous.map(|p| p + step)
+        && expected < until
+    {
+        gaps.push((expected, until));
+    }
+    gaps
+}
+
+/// Scans series for gaps and re-fetches them, remembering per series up to
+/// where it is complete so buckets the exchange has no candle for (no trades
+/// that minute) are not fetched again on every scan.
+#[derive(Debug)]
+pub struct GapRepairer {
+    scan_candles: usize,
+    checked_until: HashMap<(ExchangeKind, String, TimeFrame), DateTime<Utc>>,
+}
+
+impl GapRepairer {
+    /// Scan the latest `scan_candles` stored candles of each series.
+    pub fn new(scan_candles: usize) -> Self {
+        Self {
+            scan_candles,
+            checked_until: HashMap::new(),
+        }
+    }
+
+    /// Repair the gaps of one series as of `now`. Returns the number of
+    /// candles stored.
+    pub async fn scan(
+        &mut self,
+        exchange: &dyn Exchange,
+        storage: &dyn Storage,
+        cache: &CandleCache,
+        symbol: &str,
+        timeframe: TimeFrame,
+        now: DateTime<Utc>,
+    ) -> usize {
+        let kind = exchange.kind();
+        let stored = match storage
+            .get_recent_candles(kind, symbol, timeframe, self.scan_candles)
+            .await
+        {
+            Ok(candles) => candles,
+            Err(e) => {
+                tracing::warn!(error = ?e, exchange = %kind, symbol, timeframe = %timeframe.as_str(), "failed to read candles for gap scan");
+                return 0;
+            }
+        };
+
+        let until = bucket_open_time(kind, timeframe, now - timeframe.duration());
+        let settled = bucket_open_time(
+            kind,
+            timeframe,
+            now - chrono::Duration::seconds(SETTLE_SECS),
+        );
+        let key = (kind, symbol.to_owned(), timeframe);
+        let checked_until = self.checked_until.get(&key).copied();
+
+        let mut repaired = 0;
+        let mut complete = true;
+        // Where the next scan resumes; not past recent buckets left empty
+        let mut next_checked = until;
+        for (gap_start, gap_end) in find_gaps(stored.iter().map(|c| c.open_time), timeframe, until)
+        {
+            let start = checked_until.map_or(gap_start, |checked| gap_start.max(checked));
+            if start >= gap_end {
+                continue;
+            }
+
+            let candles = match exchange
+                .fetch_candles_range(symbol, timeframe, start, gap_end)
+                .await
+            {
+                Ok(candles) => candles,
+                Err(e) => {
+                    tracing::warn!(error = ?e, exchange = %kind, symbol, timeframe = %timeframe.as_str(), from = %start, to = %gap_end, "failed to fetch candle gap");
+                    complete = false;
+                    continue;
+                }
+            };
+            let candles: Vec<Candle> = candles
+                .into_iter()
+                .filter(|c| c.open_time >= start && c.open_time < gap_end)
+                .collect();
+
+            let missing = (gap_end - start).num_seconds() / timeframe.duration().num_seconds();
+            if (candles.len() as i64) < missing && gap_end > settled {
+                next_checked = next_checked.min(start.max(settled));
+            }
+            if candles.is_empty() {
+                // Typically no trades in those buckets
+                tracing::debug!(exchange = %kind, symbol, timeframe = %timeframe.as_str(), from = %start, to = %gap_end, missing, "exchange has no candles for gap");
+                continue;
+            }
+            if let Err(e) = storage.upsert_candles(&candles).await {
+                tracing::warn!(error = ?e, exchange = %kind, symbol, timeframe = %timeframe.as_str(), "failed to store candle gap");
+                complete = false;
+                continue;
+            }
+            for candle in &candles {
+                cache.insert(candle);
+            }
+            tracing::info!(
+                exchange = %kind,
+                symbol,
+                timeframe = %timeframe.as_str(),
+                from = %start,
+                to = %gap_end,
+                missing,
+                repaired = candles.len(),
+                "candle gap repaired"
+            );
+            repaired += candles.len();
+        }
+
+        if complete {
+            self.checked_until.insert(key, next_checked);
+        }
+        repaired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::binance::BinanceExchange;
+    use crate::exchange::mock_server;
+    use crate::model::Instrument;
+    use crate::storage::sqlite::SqliteStorage;
+
+    fn minute(m: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_704_067_200 + m * 60, 0).unwrap()
+    }
+
+    fn candle(m: i64) -> Candle {
+        Candle {
+            exchange: ExchangeKind::Binance,
+            symbol: "BTCUSDT".into(),
+            instrument: Instrument::new("BTC", "USDT"),
+            timeframe: TimeFrame::Min1,
+            open_time: minute(m),
+            open: 1.0,
+            high: 1.0,
+            low: 1.0,
+            close: 1.0,
+            volume: 1.0,
+        }
+    }
+
+    #[test]
+    fn gaps_between_candles_and_up_to_until() {
+        let candles = [candle(0), candle(1), candle(4), candle(5)];
+        assert_eq!(
+            find_gaps(
+                candles.iter().map(|c| c.open_time),
+                TimeFrame::Min1,
+                minute(8)
+            ),
+            vec![(minute(2), minute(4)), (minute(6), minute(8))]
+        );
+        assert_eq!(
+            find_gaps(
+                candles.iter().map(|c| c.open_time),
+                TimeFrame::Min1,
+                minute(6)
+            ),
+            vec![(minute(2), minute(4))]
+        );
+        assert!(find_gaps([], TimeFrame::Min1, minute(8)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn scan_fetches_missing_minutes_once() {
+        let server = mock_server::binance("BTCUSDT", 42000.0).start().await;
+        let exchange = BinanceExchange::new(&server.base_url(), &server.ws_url(""));
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage = SqliteStorage::open(&db_path).await.unwrap();
+        storage
+            .upsert_candles(&[candle(0), candle(3), candle(4)])
+            .await
+            .unwrap();
+        let cache = CandleCache::new(10);
+        let mut repairer = GapRepairer::new(100);
+
+        // Minute 6 just closed and is left alone; minute 5 has no exchange candle
+        let now = minute(7) + chrono::Duration::seconds(10);
+        let repaired = repairer
+            .scan(&exchange, &storage, &cache, "BTCUSDT", TimeFrame::Min1, now)
+            .await;
+        assert_eq!(repaired, 2);
+
+        let stored = storage
+            .get_recent_candles(ExchangeKind::Binance, "BTCUSDT", TimeFrame::Min1, 10)
+            .await
+            .unwrap();
+        let closes: Vec<f64> = stored.iter().map(|c| c.close).collect();
+        assert_eq!(closes, vec![1.0, 42001.0, 42002.0, 1.0, 1.0]);
+        assert!(
+            cache
+                .recent(ExchangeKind::Binance, "BTCUSDT", TimeFrame::Min1, 2)
+                .is_some()
+        );
+
+        // Minute 5 is too recent to give up on and is asked for again
+        let key = (ExchangeKind::Binance, "BTCUSDT".to_owned(), TimeFrame::Min1);
+        assert_eq!(repairer.checked_until[&key], minute(5));
+        let repaired = repairer
+            .scan(&exchange, &storage, &cache, "BTCUSDT", TimeFrame::Min1, now)
+            .await;
+        assert_eq!(repaired, 0);
+
+        // Once settled, empty buckets are not fetched again
+        let later = minute(20) + chrono::Duration::seconds(10);
+        repairer
+            .scan(
+                &exchange,
+                &storage,
+                &cache,
+                "BTCUSDT",
+                TimeFrame::Min1,
+                later,
+            )
+            .await;
+        assert_eq!(repairer.checked_until[&key], minute(10));
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+}
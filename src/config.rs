@@ -6,7 +6,12 @@ use error_stack::{Report, ResultExt};
 use serde::Deserialize;
 
 use crate::error::ConfigError;
-use crate::model::TimeFrame;
+use crate::exchange::registry;
+use crate::exchange::replay::ReplaySpeed;
+use crate::exchange::synthetic::PriceModel;
+use crate::exchange::{decode_symbol, encode_symbol};
+use crate::model::{ExchangeKind, Instrument, TimeFrame};
+use crate::strategy::{parse_condition, uses_candles};
 
 fn default_log_level() -> String {
     "info".into()
@@ -52,6 +57,98 @@ fn default_cooldown_bars() -> usize {
     3
 }
 
+fn default_alert_timeframe() -> String {
+    "1m".into()
+}
+
+fn default_gap_scan_secs() -> u64 {
+    300
+}
+
+fn default_gap_scan_candles() -> usize {
+    200
+}
+
+fn default_reconcile_candles() -> bool {
+    true
+}
+
+fn default_reconcile_delay_secs() -> u64 {
+    5
+}
+
+fn default_evaluate_on() -> String {
+    "tick".into()
+}
+
+fn default_candle_source() -> String {
+    "trades".into()
+}
+
+fn default_candle_cache_size() -> usize {
+    500
+}
+
+fn default_candle_flush_ms() -> u64 {
+    1000
+}
+
+fn default_ws_idle_timeout_secs() -> u64 {
+    crate::exchange::ws::DEFAULT_IDLE_TIMEOUT_SECS
+}
+
+fn default_ws_healthy_secs() -> u64 {
+    crate::exchange::ws::DEFAULT_HEALTHY_AFTER_SECS
+}
+
+fn default_open_interest_poll_secs() -> u64 {
+    60
+}
+
+fn default_replay_speed() -> String {
+    "1x".into()
+}
+
+fn default_synthetic_seed() -> u64 {
+    42
+}
+
+fn default_synthetic_model() -> String {
+    "gbm".into()
+}
+
+fn default_synthetic_initial_price() -> f64 {
+    100.0
+}
+
+fn default_synthetic_volatility() -> f64 {
+    0.001
+}
+
+fn default_synthetic_tick_interval_ms() -> u64 {
+    1_000
+}
+
+fn default_synthetic_trades_per_tick() -> usize {
+    5
+}
+
+fn default_synthetic_markets() -> usize {
+    10
+}
+
+fn default_spread_fx() -> String {
+    "upbit".into()
+}
+
+fn default_spread_record_secs() -> u64 {
+    60
+}
+
+fn default_spread_max_leg_age_secs() -> u64 {
+    30
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
     pub general: GeneralConfig,
@@ -62,6 +159,8 @@ pub struct AppConfig {
     #[serde(default)]
     pub alerts: Vec<AlertConfig>,
     #[serde(default)]
+    pub spreads: Vec<SpreadConfig>,
+    #[serde(default)]
     pub inputs: Vec<InputConfig>,
     #[serde(default)]
     pub models: Vec<TradingModelConfig>,
@@ -89,16 +188,83 @@ pub struct ExchangeConfig {
     pub name: String,
     #[serde(default = "default_true")]
     pub enabled: bool,
-    #[allow(dead_code)]
+    /// REST endpoint; not used by the "synthetic" exchange.
+    #[serde(default)]
     pub base_url: String,
-    #[allow(dead_code)]
+    /// WebSocket endpoint; not used by the "synthetic" exchange.
+    #[serde(default)]
     pub ws_url: String,
+    /// Reconnect a WebSocket stream that delivers no frame, data or
+    /// ping/pong, for this many seconds. Keep it above the exchange's
+    /// heartbeat interval so quiet trade streams stay connected.
+    #[serde(default = "default_ws_idle_timeout_secs")]
+    pub ws_idle_timeout_secs: u64,
+    /// Reset the reconnect backoff once a connection stayed up this many seconds.
+    #[serde(default = "default_ws_healthy_secs")]
+    pub ws_healthy_secs: u64,
+    /// Seconds between open interest polls of each symbol ("binance_futures").
+    #[serde(default = "default_open_interest_poll_secs")]
+    pub open_interest_poll_secs: u64,
+    /// Play back this recording instead of connecting to the exchange.
+    pub replay_path: Option<String>,
+    /// Replay pace: "max" or a multiplier such as "1x" / "10x".
+    #[serde(default = "default_replay_speed")]
+    pub replay_speed: String,
+    /// Generator settings of the "synthetic" exchange.
+    #[serde(default)]
+    pub synthetic: SyntheticConfig,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyntheticConfig {
+    #[serde(default = "default_synthetic_seed")]
+    pub seed: u64,
+    /// Price model: "gbm" or "random_walk"
+    #[serde(default = "default_synthetic_model")]
+    pub model: String,
+    #[serde(default = "default_synthetic_initial_price")]
+    pub initial_price: f64,
+    /// Per-step standard deviation: a fraction of the price for "gbm", a
+    /// price change for "random_walk"
+    #[serde(default = "default_synthetic_volatility")]
+    pub volatility: f64,
+    /// Per-step mean, in the same unit as `volatility`
+    #[serde(default)]
+    pub drift: f64,
+    /// Milliseconds between price steps (and ticker updates)
+    #[serde(default = "default_synthetic_tick_interval_ms")]
+    pub tick_interval_ms: u64,
+    #[serde(default = "default_synthetic_trades_per_tick")]
+    pub trades_per_tick: usize,
+    /// Number of listed markets, named SYN1-USD, SYN2-USD, ...
+    #[serde(default = "default_synthetic_markets")]
+    pub markets: usize,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            seed: default_synthetic_seed(),
+            model: default_synthetic_model(),
+            initial_price: default_synthetic_initial_price(),
+            volatility: default_synthetic_volatility(),
+            drift: 0.0,
+            tick_interval_ms: default_synthetic_tick_interval_ms(),
+            trades_per_tick: default_synthetic_trades_per_tick(),
+            markets: default_synthetic_markets(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CoinConfig {
     pub exchange: String,
+    /// Exchange-native symbol; derived from `instrument` when omitted.
+    #[serde(default)]
     pub symbol: String,
+    /// Canonical pair written as `"BASE/QUOTE"`; derived from `symbol` when
+    /// omitted. Always `Some` after `load`.
+    pub instrument: Option<Instrument>,
     pub timeframes: Vec<String>,
 }
 
@@ -106,13 +272,52 @@ pub struct CoinConfig {
 pub struct AlertConfig {
     pub name: String,
     pub exchange: String,
+    /// Symbol of the coin to watch; may be omitted in favour of `instrument`.
+    #[serde(default)]
     pub symbol: String,
+    /// Refer to the coin by canonical pair instead of exchange symbol.
+    pub instrument: Option<Instrument>,
     pub indicator: String,
+    /// Candles the indicator is computed on; must be one of the coin's
+    /// `timeframes`. Ignored by ticker and derivatives indicators.
+    #[serde(default = "default_alert_timeframe")]
+    pub timeframe: String,
     #[serde(default)]
     pub params: toml::Table,
     pub condition: String,
     pub threshold: Option<f64>,
     pub cooldown_minutes: Option<u64>,
+    /// "tick" checks the condition on every ticker against the forming
+    /// candle, "close" once per closed candle of `timeframe`.
+    #[serde(default = "default_evaluate_on")]
+    pub evaluate_on: String,
+}
+
+/// Premium of an asset's Upbit KRW price over its Binance USDT price.
+#[derive(Debug, Deserialize)]
+pub struct SpreadConfig {
+    pub name: String,
+    /// Base asset compared across exchanges (e.g. "SOL"); both the Upbit
+    /// `BASE/KRW` and Binance `BASE/USDT` coins must be configured.
+    pub base: String,
+    /// KRW per USDT source: "upbit" (the KRW-USDT ticker) or "fixed"
+    #[serde(default = "default_spread_fx")]
+    pub fx: String,
+    /// KRW per USDT, required when `fx = "fixed"`
+    pub fx_rate: Option<f64>,
+    /// Alert condition on the premium percentage; omit to only record
+    pub condition: Option<String>,
+    pub threshold: Option<f64>,
+    /// Upper bound for the "between" condition
+    pub threshold_high: Option<f64>,
+    pub cooldown_minutes: Option<u64>,
+    /// Minimum seconds between persisted premium samples
+    #[serde(default = "default_spread_record_secs")]
+    pub record_interval_secs: u64,
+    /// Skip premiums whose price legs, or fx ticker, are further apart than
+    /// this many seconds, e.g. while one exchange's stream is stalled
+    #[serde(default = "default_spread_max_leg_age_secs")]
+    pub max_leg_age_secs: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -185,12 +390,58 @@ impl Default for RiskPolicyConfig {
     }
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize)]
 pub struct LiveConfig {
+    /// Where real-time candles come from: "trades" rolls the trade stream into
+    /// every configured timeframe, "exchange" stores the exchange's own candles
+    /// for every configured timeframe.
+    #[serde(default = "default_candle_source")]
+    pub candle_source: String,
+    /// Store an order book snapshot per coin every N seconds; omit to disable.
+    pub orderbook_snapshot_secs: Option<u64>,
+    /// Record every received ticker and trade to this gzip JSONL file; omit to disable.
+    pub record_path: Option<String>,
+    /// Recent candles kept in memory per coin and timeframe for alert analysis.
+    #[serde(default = "default_candle_cache_size")]
+    pub candle_cache_size: usize,
+    /// Write real-time candle changes to storage every N milliseconds.
+    #[serde(default = "default_candle_flush_ms")]
+    pub candle_flush_ms: u64,
+    /// Scan stored candles for missing buckets every N seconds, and after
+    /// each stream reconnect, re-fetching them over REST.
+    #[serde(default = "default_gap_scan_secs")]
+    pub gap_scan_secs: u64,
+    /// Latest candles per coin and timeframe a gap scan looks at.
+    #[serde(default = "default_gap_scan_candles")]
+    pub gap_scan_candles: usize,
+    /// Replace each trade-built 1m candle with the exchange's official one
+    /// shortly after it closes, recording any discrepancy.
+    #[serde(default = "default_reconcile_candles")]
+    pub reconcile_candles: bool,
+    /// Seconds after a minute closes before its official candle is fetched.
+    #[serde(default = "default_reconcile_delay_secs")]
+    pub reconcile_delay_secs: u64,
     #[serde(default)]
     pub risk: LiveRiskConfig,
 }
 
+impl Default for LiveConfig {
+    fn default() -> Self {
+        Self {
+            candle_source: default_candle_source(),
+            orderbook_snapshot_secs: None,
+            record_path: None,
+            candle_cache_size: default_candle_cache_size(),
+            candle_flush_ms: default_candle_flush_ms(),
+            gap_scan_secs: default_gap_scan_secs(),
+            gap_scan_candles: default_gap_scan_candles(),
+            reconcile_candles: default_reconcile_candles(),
+            reconcile_delay_secs: default_reconcile_delay_secs(),
+            risk: LiveRiskConfig::default(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Default)]
 pub struct LiveRiskConfig {
     pub max_entries_per_position: Option<usize>,
@@ -201,18 +452,96 @@ pub fn load(path: &Path) -> Result<AppConfig, Report<ConfigError>> {
         .change_context(ConfigError::ReadFile)
         .attach_with(|| format!("path: {}", path.display()))?;
 
-    let config: AppConfig = toml::from_str(&content).change_context(ConfigError::Parse {
+    let mut config: AppConfig = toml::from_str(&content).change_context(ConfigError::Parse {
         reason: "invalid TOML syntax or schema mismatch".into(),
     })?;
 
+    resolve_instruments(&mut config)?;
     validate(&config)?;
     Ok(config)
 }
 
 const VALID_CONDITIONS: &[&str] = &["above", "below", "cross_above", "cross_below", "between"];
+const VALID_EVALUATE_ON: &[&str] = &["tick", "close"];
+const VALID_CANDLE_SOURCES: &[&str] = &["trades", "exchange"];
+const VALID_SPREAD_FX: &[&str] = &["upbit", "fixed"];
+
+/// Fill in whichever of `symbol` / `instrument` each coin omits using the
+/// exchange's symbol codec, then resolve alerts that refer to a coin by
+/// instrument.
+fn resolve_instruments(config: &mut AppConfig) -> Result<(), Report<ConfigError>> {
+    for coin in &mut config.coins {
+        // Unknown exchange names are reported by validate_coin_exchanges
+        let Ok(exchange) = coin.exchange.parse::<ExchangeKind>() else {
+            continue;
+        };
+        if coin.symbol.is_empty() {
+            let Some(instrument) = &coin.instrument else {
+                return Err(Report::new(ConfigError::Validation {
+                    field: format!(
+                        "coins[exchange={}]: symbol or instrument is required",
+                        coin.exchange
+                    ),
+                }));
+            };
+            coin.symbol = encode_symbol(exchange, instrument);
+            continue;
+        }
+
+        let Some(decoded) = decode_symbol(exchange, &coin.symbol) else {
+            if coin.instrument.is_some() {
+                continue;
+            }
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "coins[exchange={}, symbol={}]: cannot derive instrument from symbol, set instrument = \"BASE/QUOTE\"",
+                    coin.exchange, coin.symbol
+                ),
+            }));
+        };
+
+        if let Some(instrument) = &coin.instrument
+            && *instrument != decoded
+        {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "coins[exchange={}, symbol={}].instrument \"{}\" does not match symbol",
+                    coin.exchange, coin.symbol, instrument
+                ),
+            }));
+        }
+        coin.instrument = Some(decoded);
+    }
+
+    for alert in &mut config.alerts {
+        let Some(instrument) = &alert.instrument else {
+            continue;
+        };
+        let coin = config
+            .coins
+            .iter()
+            .find(|c| c.exchange == alert.exchange && c.instrument.as_ref() == Some(instrument));
+
+        match coin {
+            Some(coin) if alert.symbol.is_empty() => alert.symbol = coin.symbol.clone(),
+            Some(coin) if alert.symbol == coin.symbol => {}
+            _ => {
+                return Err(Report::new(ConfigError::Validation {
+                    field: format!(
+                        "alerts[\"{}\"].instrument {} does not match any {} coin entry",
+                        alert.name, instrument, alert.exchange
+                    ),
+                }));
+            }
+        }
+    }
+
+    Ok(())
+}
 
 fn validate(config: &AppConfig) -> Result<(), Report<ConfigError>> {
     validate_timeframes(config)?;
+    validate_exchange_streams(config)?;
     validate_coin_exchanges(config)?;
     validate_alert_references(config)?;
     validate_alert_names_unique(config)?;
@@ -220,6 +549,8 @@ fn validate(config: &AppConfig) -> Result<(), Report<ConfigError>> {
     validate_input_and_model_names(config)?;
     validate_model_input_references(config)?;
     validate_backtest(config)?;
+    validate_live(config)?;
+    validate_spreads(config)?;
     Ok(())
 }
 
@@ -239,6 +570,93 @@ fn validate_timeframes(config: &AppConfig) -> Result<(), Report<ConfigError>> {
     Ok(())
 }
 
+fn validate_exchange_streams(config: &AppConfig) -> Result<(), Report<ConfigError>> {
+    for exchange in &config.exchanges {
+        let Ok(spec) = registry::lookup(&exchange.name) else {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "exchanges[name={}] is not a known exchange (expected one of: {})",
+                    exchange.name,
+                    registry::names().join(", ")
+                ),
+            }));
+        };
+        if exchange.ws_idle_timeout_secs == 0 {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "exchanges[name={}].ws_idle_timeout_secs must be greater than 0",
+                    exchange.name
+                ),
+            }));
+        }
+        if exchange.ws_healthy_secs == 0 {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "exchanges[name={}].ws_healthy_secs must be greater than 0",
+                    exchange.name
+                ),
+            }));
+        }
+        if exchange.open_interest_poll_secs == 0 {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "exchanges[name={}].open_interest_poll_secs must be greater than 0",
+                    exchange.name
+                ),
+            }));
+        }
+        let connects = spec.connects && exchange.replay_path.is_none();
+        if connects && (exchange.base_url.is_empty() || exchange.ws_url.is_empty()) {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "exchanges[name={}] requires base_url and ws_url",
+                    exchange.name
+                ),
+            }));
+        }
+        if exchange.name == "synthetic" {
+            validate_synthetic(&exchange.synthetic)?;
+        }
+        if ReplaySpeed::parse(&exchange.replay_speed).is_none() {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "exchanges[name={}].replay_speed \"{}\" is not valid (expected \"max\" or e.g. \"10x\")",
+                    exchange.name, exchange.replay_speed
+                ),
+            }));
+        }
+    }
+    Ok(())
+}
+
+fn validate_synthetic(synthetic: &SyntheticConfig) -> Result<(), Report<ConfigError>> {
+    let invalid = |field: &str| {
+        Err(Report::new(ConfigError::Validation {
+            field: format!("exchanges[name=synthetic].synthetic.{field}"),
+        }))
+    };
+
+    if PriceModel::from_str(&synthetic.model).is_none() {
+        return invalid(&format!(
+            "model \"{}\" is not valid (expected \"gbm\" or \"random_walk\")",
+            synthetic.model
+        ));
+    }
+    if synthetic.initial_price <= 0.0 {
+        return invalid("initial_price must be > 0");
+    }
+    if synthetic.volatility < 0.0 {
+        return invalid("volatility must be >= 0");
+    }
+    if synthetic.tick_interval_ms == 0 {
+        return invalid("tick_interval_ms must be > 0");
+    }
+    if synthetic.markets == 0 {
+        return invalid("markets must be > 0");
+    }
+    Ok(())
+}
+
 fn validate_coin_exchanges(config: &AppConfig) -> Result<(), Report<ConfigError>> {
     let exchange_names: HashSet<&str> = config.exchanges.iter().map(|e| e.name.as_str()).collect();
 
@@ -257,18 +675,45 @@ fn validate_coin_exchanges(config: &AppConfig) -> Result<(), Report<ConfigError>
 
 fn validate_alert_references(config: &AppConfig) -> Result<(), Report<ConfigError>> {
     for alert in &config.alerts {
-        let found = config
+        let coin = config
             .coins
             .iter()
-            .any(|c| c.exchange == alert.exchange && c.symbol == alert.symbol);
+            .find(|c| c.exchange == alert.exchange && c.symbol == alert.symbol);
 
-        if !found {
+        let Some(coin) = coin else {
             return Err(Report::new(ConfigError::Validation {
                 field: format!(
                     "alerts[\"{}\"].exchange+symbol ({}, {}) does not match any coin entry",
                     alert.name, alert.exchange, alert.symbol
                 ),
             }));
+        };
+
+        if TimeFrame::from_str(&alert.timeframe).is_none() {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "alerts[\"{}\"].timeframe: unknown timeframe \"{}\"",
+                    alert.name, alert.timeframe
+                ),
+            }));
+        }
+        if uses_candles(&alert.indicator) && !coin.timeframes.contains(&alert.timeframe) {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "alerts[\"{}\"].timeframe \"{}\" is not among the timeframes of coin ({}, {})",
+                    alert.name, alert.timeframe, coin.exchange, coin.symbol
+                ),
+            }));
+        }
+        if alert.indicator == "spread_pct"
+            && registry::lookup(&alert.exchange).is_ok_and(|spec| !spec.ticker_best_bid_ask)
+        {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "alerts[\"{}\"].indicator \"spread_pct\" needs the best bid/ask, which {} tickers do not carry",
+                    alert.name, alert.exchange
+                ),
+            }));
         }
     }
     Ok(())
@@ -310,6 +755,24 @@ fn validate_alert_conditions(config: &AppConfig) -> Result<(), Report<ConfigErro
                 ),
             }));
         }
+
+        if !VALID_EVALUATE_ON.contains(&alert.evaluate_on.as_str()) {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "alerts[\"{}\"].evaluate_on \"{}\" is not valid",
+                    alert.name, alert.evaluate_on
+                ),
+            }));
+        }
+        // Ticker and derivatives indicators have no candle to close
+        if alert.evaluate_on == "close" && !uses_candles(&alert.indicator) {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "alerts[\"{}\"].evaluate_on \"close\" requires a candle indicator, not \"{}\"",
+                    alert.name, alert.indicator
+                ),
+            }));
+        }
     }
     Ok(())
 }
@@ -405,6 +868,126 @@ fn validate_backtest(config: &AppConfig) -> Result<(), Report<ConfigError>> {
     Ok(())
 }
 
+fn validate_spreads(config: &AppConfig) -> Result<(), Report<ConfigError>> {
+    // Spreads share the alert log (and its cooldowns) with alerts
+    let mut names: HashSet<&str> = config.alerts.iter().map(|a| a.name.as_str()).collect();
+
+    for spread in &config.spreads {
+        if !names.insert(spread.name.as_str()) {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "spreads: name \"{}\" is already used by another alert or spread",
+                    spread.name
+                ),
+            }));
+        }
+
+        for (exchange, quote) in [("upbit", "KRW"), ("binance", "USDT")] {
+            let instrument = Instrument::new(&spread.base, quote);
+            if !config
+                .coins
+                .iter()
+                .any(|c| c.exchange == exchange && c.instrument.as_ref() == Some(&instrument))
+            {
+                return Err(Report::new(ConfigError::Validation {
+                    field: format!(
+                        "spreads[\"{}\"]: no {} coin entry for {}",
+                        spread.name, exchange, instrument
+                    ),
+                }));
+            }
+        }
+
+        if !VALID_SPREAD_FX.contains(&spread.fx.as_str()) {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "spreads[\"{}\"].fx \"{}\" is not valid",
+                    spread.name, spread.fx
+                ),
+            }));
+        }
+
+        if spread.fx == "fixed" && !spread.fx_rate.is_some_and(|rate| rate > 0.0) {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "spreads[\"{}\"].fx_rate must be > 0 when fx = \"fixed\"",
+                    spread.name
+                ),
+            }));
+        }
+
+        if spread.max_leg_age_secs == 0 {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!("spreads[\"{}\"].max_leg_age_secs must be > 0", spread.name),
+            }));
+        }
+
+        if let Some(condition) = &spread.condition
+            && parse_condition(condition, spread.threshold, spread.threshold_high).is_none()
+        {
+            return Err(Report::new(ConfigError::Validation {
+                field: format!(
+                    "spreads[\"{}\"].condition \"{}\" is not valid or is missing its threshold",
+                    spread.name, condition
+                ),
+            }));
+        }
+    }
+    Ok(())
+}
+
+fn validate_live(config: &AppConfig) -> Result<(), Report<ConfigError>> {
+    if !VALID_CANDLE_SOURCES.contains(&config.live.candle_source.as_str()) {
+        return Err(Report::new(ConfigError::Validation {
+            field: format!(
+                "live.candle_source \"{}\" is not valid",
+                config.live.candle_source
+            ),
+        }));
+    }
+
+    if config.live.orderbook_snapshot_secs == Some(0) {
+        return Err(Report::new(ConfigError::Validation {
+            field: "live.orderbook_snapshot_secs must be > 0".into(),
+        }));
+    }
+
+    if config.live.candle_cache_size == 0 {
+        return Err(Report::new(ConfigError::Validation {
+            field: "live.candle_cache_size must be > 0".into(),
+        }));
+    }
+
+    if config.live.candle_flush_ms == 0 {
+        return Err(Report::new(ConfigError::Validation {
+            field: "live.candle_flush_ms must be > 0".into(),
+        }));
+    }
+
+    if config.live.gap_scan_secs == 0 {
+        return Err(Report::new(ConfigError::Validation {
+            field: "live.gap_scan_secs must be > 0".into(),
+        }));
+    }
+
+    if config.live.gap_scan_candles < 2 {
+        return Err(Report::new(ConfigError::Validation {
+            field: "live.gap_scan_candles must be >= 2".into(),
+        }));
+    }
+
+    // Otherwise a pending write of the trade-built candle could land after
+    // the official one
+    if config.live.reconcile_candles
+        && config.live.reconcile_delay_secs * 1000 <= config.live.candle_flush_ms
+    {
+        return Err(Report::new(ConfigError::Validation {
+            field: "live.reconcile_delay_secs must exceed live.candle_flush_ms".into(),
+        }));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -431,6 +1014,266 @@ mod tests {
         assert!(config.models.is_empty());
         assert!(config.backtest.is_none());
         assert!(config.live.risk.max_entries_per_position.is_none());
+        assert_eq!(config.live.candle_source, "trades");
+        assert!(config.live.orderbook_snapshot_secs.is_none());
+        assert_eq!(config.live.candle_cache_size, 500);
+        assert_eq!(config.live.candle_flush_ms, 1000);
+        assert_eq!(config.live.gap_scan_secs, 300);
+        assert_eq!(config.live.gap_scan_candles, 200);
+        assert!(config.live.reconcile_candles);
+        assert_eq!(config.live.reconcile_delay_secs, 5);
+    }
+
+    #[test]
+    fn coin_instrument_and_symbol_derive_each_other() {
+        let toml = r#"
+[general]
+
+[[exchanges]]
+name = "upbit"
+base_url = "https://api.upbit.com"
+ws_url = "wss://api.upbit.com/websocket/v1"
+
+[[exchanges]]
+name = "binance"
+base_url = "https://api.binance.com"
+ws_url = "wss://stream.binance.com:9443"
+
+[[coins]]
+exchange = "upbit"
+symbol = "KRW-SOL"
+timeframes = ["1m"]
+
+[[coins]]
+exchange = "binance"
+instrument = "SOL/USDT"
+timeframes = ["1m"]
+
+[[alerts]]
+name = "sol-rsi"
+exchange = "binance"
+instrument = "SOL/USDT"
+indicator = "rsi"
+condition = "below"
+threshold = 30
+"#;
+        let mut config = parse(toml);
+        resolve_instruments(&mut config).unwrap();
+        validate(&config).unwrap();
+
+        assert_eq!(
+            config.coins[0].instrument,
+            Some(Instrument::new("SOL", "KRW"))
+        );
+        assert_eq!(config.coins[1].symbol, "SOLUSDT");
+        assert_eq!(config.alerts[0].symbol, "SOLUSDT");
+    }
+
+    #[test]
+    fn mismatched_coin_instrument_rejected() {
+        let toml = r#"
+[general]
+
+[[coins]]
+exchange = "upbit"
+symbol = "KRW-SOL"
+instrument = "BTC/KRW"
+timeframes = ["1m"]
+"#;
+        let mut config = parse(toml);
+        assert!(resolve_instruments(&mut config).is_err());
+    }
+
+    #[test]
+    fn spread_requires_both_legs_and_fixed_rate() {
+        let base = r#"
+[general]
+
+[[exchanges]]
+name = "upbit"
+base_url = "https://api.upbit.com"
+ws_url = "wss://api.upbit.com/websocket/v1"
+
+[[exchanges]]
+name = "binance"
+base_url = "https://api.binance.com"
+ws_url = "wss://stream.binance.com:9443"
+
+[[coins]]
+exchange = "upbit"
+symbol = "KRW-SOL"
+timeframes = ["1m"]
+"#;
+        let binance_coin = r#"
+[[coins]]
+exchange = "binance"
+symbol = "SOLUSDT"
+timeframes = ["1m"]
+"#;
+        let spread = r#"
+[[spreads]]
+name = "sol-kimchi"
+base = "SOL"
+fx = "fixed"
+"#;
+        let check = |toml: String| {
+            let mut config = parse(&toml);
+            resolve_instruments(&mut config).unwrap();
+            validate(&config)
+        };
+
+        assert!(check(format!("{base}{spread}")).is_err());
+        assert!(check(format!("{base}{binance_coin}{spread}")).is_err());
+        assert!(check(format!("{base}{binance_coin}{spread}fx_rate = 1400.0\n")).is_ok());
+        assert!(
+            check(format!(
+                "{base}{binance_coin}{spread}fx_rate = 1400.0\ncondition = \"above\"\n"
+            ))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn zero_ws_idle_timeout_rejected() {
+        let toml = r#"
+[general]
+
+[[exchanges]]
+name = "upbit"
+base_url = "https://api.upbit.com"
+ws_url = "wss://api.upbit.com/websocket/v1"
+ws_idle_timeout_secs = 0
+"#;
+        let config = parse(toml);
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn unknown_exchange_name_rejected() {
+        let toml = r#"
+[general]
+
+[[exchanges]]
+name = "bithumb"
+base_url = "https://api.bithumb.com"
+ws_url = "wss://pubwss.bithumb.com/pub/ws"
+"#;
+        let config = parse(toml);
+        let err = validate(&config).unwrap_err();
+        assert!(format!("{err:?}").contains("exchanges[name=bithumb] is not a known exchange"));
+    }
+
+    #[test]
+    fn invalid_replay_speed_rejected() {
+        let toml = r#"
+[general]
+
+[[exchanges]]
+name = "upbit"
+base_url = "https://api.upbit.com"
+ws_url = "wss://api.upbit.com/websocket/v1"
+replay_path = "data/session.jsonl.gz"
+replay_speed = "fast"
+"#;
+        let config = parse(toml);
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn synthetic_exchange_needs_no_urls_but_valid_settings() {
+        let toml = r#"
+[general]
+
+[[exchanges]]
+name = "synthetic"
+
+[exchanges.synthetic]
+model = "random_walk"
+markets = 200
+"#;
+        let config = parse(toml);
+        assert!(validate(&config).is_ok());
+        assert_eq!(config.exchanges[0].synthetic.markets, 200);
+        assert_eq!(config.exchanges[0].synthetic.seed, 42);
+
+        let config = parse(&toml.replace("random_walk", "brownian"));
+        assert!(validate(&config).is_err());
+
+        let config = parse(&toml.replace("synthetic\"\n", "upbit\"\n"));
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn spread_pct_rejected_without_best_bid_ask() {
+        let toml = r#"
+[general]
+
+[[exchanges]]
+name = "upbit"
+base_url = "https://api.upbit.com"
+ws_url = "wss://api.upbit.com/websocket/v1"
+
+[[exchanges]]
+name = "binance"
+base_url = "https://api.binance.com"
+ws_url = "wss://stream.binance.com:9443"
+
+[[coins]]
+exchange = "upbit"
+symbol = "KRW-BTC"
+timeframes = ["1m"]
+
+[[coins]]
+exchange = "binance"
+symbol = "BTCUSDT"
+timeframes = ["1m"]
+
+[[alerts]]
+name = "wide book"
+exchange = "upbit"
+symbol = "KRW-BTC"
+indicator = "spread_pct"
+condition = "above"
+threshold = 0.5
+"#;
+        let config = parse(toml);
+        let err = validate(&config).unwrap_err();
+        assert!(format!("{err:?}").contains("alerts[\"wide book\"].indicator \"spread_pct\""));
+
+        let toml = toml.replace(
+            "exchange = \"upbit\"\nsymbol = \"KRW-BTC\"\nindicator",
+            "exchange = \"binance\"\nsymbol = \"BTCUSDT\"\nindicator",
+        );
+        assert!(validate(&parse(&toml)).is_ok());
+    }
+
+    #[test]
+    fn unknown_candle_source_rejected() {
+        let toml = r#"
+[general]
+
+[live]
+candle_source = "ticks"
+"#;
+        let config = parse(toml);
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn reconcile_delay_within_candle_flush_rejected() {
+        let toml = r#"
+[general]
+
+[live]
+candle_flush_ms = 5000
+reconcile_delay_secs = 5
+"#;
+        let config = parse(toml);
+        let err = validate(&config).unwrap_err();
+        assert!(format!("{err:?}").contains("live.reconcile_delay_secs"));
+
+        let config = parse(&toml.replace("[live]\n", "[live]\nreconcile_candles = false\n"));
+        assert!(validate(&config).is_ok());
     }
 
     #[test]
@@ -452,6 +1295,55 @@ timeframes = ["2m"]
         assert!(validate(&config).is_err());
     }
 
+    #[test]
+    fn alert_timeframe_must_be_configured_for_its_coin() {
+        let config_with = |alert: &str| {
+            parse(&format!(
+                r#"
+[general]
+
+[[exchanges]]
+name = "upbit"
+base_url = "https://api.upbit.com"
+ws_url = "wss://api.upbit.com/websocket/v1"
+
+[[coins]]
+exchange = "upbit"
+symbol = "KRW-SOL"
+timeframes = ["1m", "1h"]
+
+[[alerts]]
+name = "sol"
+exchange = "upbit"
+symbol = "KRW-SOL"
+condition = "above"
+threshold = 1.0
+{alert}
+"#
+            ))
+        };
+
+        let defaulted = config_with(r#"indicator = "rsi""#);
+        assert_eq!(defaulted.alerts[0].timeframe, "1m");
+        assert!(validate(&defaulted).is_ok());
+        assert!(validate(&config_with("indicator = \"rsi\"\ntimeframe = \"1h\"")).is_ok());
+
+        let err = validate(&config_with("indicator = \"rsi\"\ntimeframe = \"5m\"")).unwrap_err();
+        assert!(format!("{err:?}").contains("timeframe \"5m\" is not among the timeframes"));
+        assert!(validate(&config_with("indicator = \"rsi\"\ntimeframe = \"2m\"")).is_err());
+        // Ticker indicators read no candles
+        assert!(validate(&config_with("indicator = \"price\"\ntimeframe = \"5m\"")).is_ok());
+
+        assert_eq!(defaulted.alerts[0].evaluate_on, "tick");
+        assert!(validate(&config_with("indicator = \"rsi\"\nevaluate_on = \"close\"")).is_ok());
+        assert!(validate(&config_with("indicator = \"rsi\"\nevaluate_on = \"bar\"")).is_err());
+        let err = validate(&config_with(
+            "indicator = \"price\"\nevaluate_on = \"close\"",
+        ))
+        .unwrap_err();
+        assert!(format!("{err:?}").contains("requires a candle indicator"));
+    }
+
     #[test]
     fn duplicate_input_name_rejected() {
         let toml = r#"
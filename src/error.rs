@@ -10,6 +10,13 @@ pub enum ConfigError {
     Validation { field: String },
 }
 
+/// An exchange name that is not in `exchange::registry`.
+#[derive(Debug, Display, Error)]
+#[display("unknown exchange \"{name}\"")]
+pub struct UnknownExchange {
+    pub name: String,
+}
+
 #[derive(Debug, Display, Error)]
 pub enum ExchangeError {
     #[display("failed to connect to {exchange}")]
@@ -19,8 +26,9 @@ pub enum ExchangeError {
     #[display("failed to parse response from {exchange}")]
     ResponseParse { exchange: String },
     #[display("rate limit exceeded for {exchange}")]
-    #[allow(dead_code)]
     RateLimit { exchange: String },
+    #[display("{exchange} stream went idle")]
+    StreamIdle { exchange: String },
 }
 
 #[derive(Debug, Display, Error)]
@@ -33,6 +41,18 @@ pub enum StorageError {
     Query,
 }
 
+#[derive(Debug, Display, Error)]
+pub enum RecordingError {
+    #[display("failed to open recording")]
+    Open,
+    #[display("failed to write recording")]
+    Write,
+    #[display("failed to read recording")]
+    Read,
+    #[display("invalid recording entry on line {line}")]
+    Parse { line: usize },
+}
+
 #[derive(Debug, Display, Error)]
 pub enum IndicatorError {
     #[display("insufficient data: need {required}, got {available}")]
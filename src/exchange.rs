@@ -1,13 +1,147 @@
 pub mod binance;
+#[cfg(test)]
+pub mod mock_server;
+pub mod registry;
+pub mod replay;
+pub mod rest;
+pub mod synthetic;
 pub mod upbit;
+pub mod ws;
 
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
 use error_stack::Report;
 use futures::future::BoxFuture;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc, watch};
 use tokio_util::sync::CancellationToken;
 
 use crate::error::ExchangeError;
-use crate::model::{Candle, ExchangeKind, Ticker, TimeFrame, Trade};
+use crate::exchange::ws::WsEvent;
+use crate::model::{
+    Candle, CandleUpdate, DerivativesSample, ExchangeKind, Instrument, Market, OrderBook, Ticker,
+    TimeFrame, Trade,
+};
+
+/// Symbols to stream and where to send their updates.
+#[derive(Debug, Clone)]
+pub struct StreamSink<T> {
+    pub symbols: Vec<String>,
+    pub tx: mpsc::Sender<T>,
+}
+
+/// Real-time streams requested together; `None` leaves a stream out.
+#[derive(Debug, Clone, Default)]
+pub struct MarketStreams {
+    pub ticker: Option<StreamSink<Ticker>>,
+    pub trades: Option<StreamSink<Trade>>,
+    pub orderbook: Option<StreamSink<OrderBook>>,
+}
+
+impl MarketStreams {
+    /// Stream name used in logs and `WsEvent`s.
+    pub fn name(&self) -> &'static str {
+        match (
+            self.ticker.is_some(),
+            self.trades.is_some(),
+            self.orderbook.is_some(),
+        ) {
+            (true, false, false) => "ticker",
+            (false, true, false) => "trades",
+            (false, false, true) => "orderbook",
+            _ => "market",
+        }
+    }
+}
+
+/// Current symbols of each stream of a subscription; `None` for streams the
+/// subscription was not started with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamSymbols {
+    pub ticker: Option<Vec<String>>,
+    pub trades: Option<Vec<String>>,
+    pub orderbook: Option<Vec<String>>,
+}
+
+impl StreamSymbols {
+    /// Every symbol of any stream, deduplicated.
+    pub fn all(&self) -> Vec<String> {
+        let mut all: Vec<String> = Vec::new();
+        for list in [&self.ticker, &self.trades, &self.orderbook]
+            .into_iter()
+            .flatten()
+        {
+            for symbol in list {
+                if !all.contains(symbol) {
+                    all.push(symbol.clone());
+                }
+            }
+        }
+        all
+    }
+}
+
+/// Changes the symbols of a running `subscribe_streams` subscription.
+///
+/// Clones control the same subscription. Only the streams the subscription
+/// was started with change, and the exchange applies the change to its live
+/// connection.
+#[derive(Debug, Clone)]
+pub struct SubscriptionHandle {
+    symbols: Arc<watch::Sender<StreamSymbols>>,
+}
+
+impl SubscriptionHandle {
+    /// Start with the symbols of `streams`.
+    pub fn new(streams: &MarketStreams) -> Self {
+        let (symbols, _) = watch::channel(StreamSymbols {
+            ticker: streams.ticker.as_ref().map(|s| s.symbols.clone()),
+            trades: streams.trades.as_ref().map(|s| s.symbols.clone()),
+            orderbook: streams.orderbook.as_ref().map(|s| s.symbols.clone()),
+        });
+        Self {
+            symbols: Arc::new(symbols),
+        }
+    }
+
+    /// Replace the symbols of every stream the subscription was started
+    /// with by those of the same stream in `streams`.
+    pub fn update(&self, streams: &MarketStreams) {
+        self.symbols.send_if_modified(|current| {
+            let wanted = StreamSymbols {
+                ticker: current.ticker.as_ref().map(|_| {
+                    streams
+                        .ticker
+                        .as_ref()
+                        .map(|s| s.symbols.clone())
+                        .unwrap_or_default()
+                }),
+                trades: current.trades.as_ref().map(|_| {
+                    streams
+                        .trades
+                        .as_ref()
+                        .map(|s| s.symbols.clone())
+                        .unwrap_or_default()
+                }),
+                orderbook: current.orderbook.as_ref().map(|_| {
+                    streams
+                        .orderbook
+                        .as_ref()
+                        .map(|s| s.symbols.clone())
+                        .unwrap_or_default()
+                }),
+            };
+            let modified = *current != wanted;
+            *current = wanted;
+            modified
+        });
+    }
+
+    /// Observe the symbol lists; the receiver is notified on every change.
+    pub fn subscribe(&self) -> watch::Receiver<StreamSymbols> {
+        self.symbols.subscribe()
+    }
+}
 
 /// Abstraction over a cryptocurrency exchange.
 ///
@@ -16,6 +150,9 @@ use crate::model::{Candle, ExchangeKind, Ticker, TimeFrame, Trade};
 pub trait Exchange: Send + Sync {
     fn kind(&self) -> ExchangeKind;
 
+    /// Fetch the catalogue of markets listed on the exchange via REST API.
+    fn list_markets(&self) -> BoxFuture<'_, Result<Vec<Market>, Report<ExchangeError>>>;
+
     /// Fetch historical candle data via REST API.
     fn fetch_candles(
         &self,
@@ -24,6 +161,20 @@ pub trait Exchange: Send + Sync {
         limit: usize,
     ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>>;
 
+    /// Fetch historical candles whose open time falls within `[start, end)`
+    /// via REST API, paging as needed. Returns candles oldest-first.
+    ///
+    /// Each page waits on the exchange's rate limiter. An interrupted fetch
+    /// can be resumed by calling again with a narrower window, since every
+    /// call is independent of previous ones.
+    fn fetch_candles_range(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>>;
+
     /// Subscribe to real-time ticker updates via WebSocket.
     ///
     /// Sends `Ticker` values into `tx` until `cancel` is triggered.
@@ -37,11 +188,179 @@ pub trait Exchange: Send + Sync {
     /// Subscribe to real-time trade updates via WebSocket.
     ///
     /// Sends `Trade` values into `tx` until `cancel` is triggered.
-    #[allow(dead_code)]
     fn subscribe_trades(
         &self,
         symbols: &[String],
         tx: mpsc::Sender<Trade>,
         cancel: CancellationToken,
     ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>>;
+
+    /// Subscribe to real-time candle updates via WebSocket for every
+    /// combination of `symbols` and `timeframes`.
+    ///
+    /// Sends `CandleUpdate` values into `tx` until `cancel` is triggered.
+    fn subscribe_candles(
+        &self,
+        symbols: &[String],
+        timeframes: &[TimeFrame],
+        tx: mpsc::Sender<CandleUpdate>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>>;
+
+    /// Subscribe to real-time order book updates via WebSocket.
+    ///
+    /// Each `OrderBook` sent into `tx` is a full view of the top levels, so
+    /// consumers never need to apply diffs themselves. Runs until `cancel`
+    /// is triggered.
+    fn subscribe_orderbook(
+        &self,
+        symbols: &[String],
+        tx: mpsc::Sender<OrderBook>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>>;
+
+    /// Subscribe to several real-time streams at once.
+    ///
+    /// Symbols are taken from `handle` (created from `streams`) and follow
+    /// its changes while the subscription runs. The default runs one
+    /// subscription per stream and restarts them all when the symbols change;
+    /// exchanges that can multiplex streams or change a live subscription
+    /// override it. Runs until `cancel` is triggered.
+    fn subscribe_streams(
+        &self,
+        streams: MarketStreams,
+        handle: SubscriptionHandle,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        Box::pin(async move {
+            let mut symbols = handle.subscribe();
+            loop {
+                let current = symbols.borrow_and_update().clone();
+                let round = cancel.child_token();
+
+                let mut subscriptions = Vec::new();
+                if let (Some(sink), Some(list)) = (&streams.ticker, &current.ticker) {
+                    subscriptions.push(self.subscribe_ticker(list, sink.tx.clone(), round.clone()));
+                }
+                if let (Some(sink), Some(list)) = (&streams.trades, &current.trades) {
+                    subscriptions.push(self.subscribe_trades(list, sink.tx.clone(), round.clone()));
+                }
+                if let (Some(sink), Some(list)) = (&streams.orderbook, &current.orderbook) {
+                    subscriptions.push(self.subscribe_orderbook(
+                        list,
+                        sink.tx.clone(),
+                        round.clone(),
+                    ));
+                }
+
+                tokio::select! {
+                    result = futures::future::try_join_all(subscriptions) => {
+                        result?;
+                        return Ok(());
+                    }
+                    // `handle` is owned here, so the sender outlives this loop
+                    _ = symbols.changed() => round.cancel(),
+                }
+            }
+        })
+    }
+
+    /// Subscribe to perpetual futures metrics (mark price, funding rate and
+    /// open interest) of `symbols`.
+    ///
+    /// Sends `DerivativesSample` values into `tx` until `cancel` is
+    /// triggered. The default returns immediately, for exchanges that only
+    /// list spot markets.
+    fn subscribe_derivatives(
+        &self,
+        _symbols: &[String],
+        _tx: mpsc::Sender<DerivativesSample>,
+        _cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Receive connect, idle and reconnect events of every WebSocket
+    /// subscription started on this exchange.
+    fn stream_events(&self) -> broadcast::Receiver<WsEvent>;
+}
+
+/// Decode an exchange-native symbol into its canonical `Instrument`.
+pub fn decode_symbol(exchange: ExchangeKind, symbol: &str) -> Option<Instrument> {
+    (registry::spec(exchange).decode_symbol)(symbol)
+}
+
+/// Encode an `Instrument` as the exchange-native symbol.
+pub fn encode_symbol(exchange: ExchangeKind, instrument: &Instrument) -> String {
+    (registry::spec(exchange).encode_symbol)(instrument)
+}
+
+/// Whether the trade ids of `exchange` increase by exactly one per trade of
+/// a symbol, so a skipped id means a missed trade.
+pub fn contiguous_trade_ids(exchange: ExchangeKind) -> bool {
+    registry::spec(exchange).contiguous_trade_ids
+}
+
+/// Canonical instrument for `symbol`, or `None` when it cannot be decoded.
+/// Callers drop or reject such data rather than invent an instrument for it.
+pub fn instrument_for(exchange: ExchangeKind, symbol: &str) -> Option<Instrument> {
+    decode_symbol(exchange, symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| (*s).to_owned()).collect()
+    }
+
+    #[test]
+    fn subscription_handle_changes_enabled_streams_only() {
+        let streams = |ticker: &[&str], coins: &[&str]| MarketStreams {
+            ticker: Some(StreamSink {
+                symbols: symbols(ticker),
+                tx: mpsc::channel(1).0,
+            }),
+            trades: Some(StreamSink {
+                symbols: symbols(coins),
+                tx: mpsc::channel(1).0,
+            }),
+            orderbook: Some(StreamSink {
+                symbols: symbols(coins),
+                tx: mpsc::channel(1).0,
+            }),
+        };
+        let handle = SubscriptionHandle::new(&MarketStreams {
+            orderbook: None,
+            ..streams(&["KRW-BTC", "KRW-USDT"], &["KRW-BTC"])
+        });
+        let mut rx = handle.subscribe();
+
+        handle.update(&streams(
+            &["KRW-BTC", "KRW-ETH", "KRW-USDT"],
+            &["KRW-BTC", "KRW-ETH"],
+        ));
+        assert!(rx.has_changed().unwrap());
+        let current = rx.borrow_and_update().clone();
+        assert_eq!(
+            current,
+            StreamSymbols {
+                ticker: Some(symbols(&["KRW-BTC", "KRW-ETH", "KRW-USDT"])),
+                trades: Some(symbols(&["KRW-BTC", "KRW-ETH"])),
+                orderbook: None,
+            }
+        );
+        assert_eq!(current.all(), symbols(&["KRW-BTC", "KRW-ETH", "KRW-USDT"]));
+
+        // No-op changes do not wake the exchange
+        handle.update(&streams(
+            &["KRW-BTC", "KRW-ETH", "KRW-USDT"],
+            &["KRW-BTC", "KRW-ETH"],
+        ));
+        assert!(!rx.has_changed().unwrap());
+
+        handle.update(&streams(&["KRW-ETH", "KRW-USDT"], &["KRW-ETH"]));
+        assert_eq!(rx.borrow_and_update().trades, Some(symbols(&["KRW-ETH"])));
+    }
 }
@@ -1,58 +1,577 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::num::NonZeroU32;
-use std::sync::Arc;
 use std::time::Duration;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Timelike, Utc};
 use error_stack::{Report, ResultExt};
 use futures::future::BoxFuture;
 use futures::{SinkExt, StreamExt};
-use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use governor::Quota;
+use reqwest::header::HeaderMap;
 use serde::Deserialize;
-use tokio::sync::mpsc;
-use tokio::time::sleep;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::{MissedTickBehavior, sleep_until};
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
 use crate::error::ExchangeError;
-use crate::exchange::Exchange;
-use crate::model::{Candle, ExchangeKind, Ticker, TimeFrame, Trade, TradeSide};
+use crate::exchange::rest::{RestClient, UsagePolicy};
+use crate::exchange::ws::{WsEvent, WsOptions, WsSupervisor, WsWatchdog};
+use crate::exchange::{
+    Exchange, MarketStreams, StreamSink, StreamSymbols, SubscriptionHandle, instrument_for,
+};
+use crate::model::{
+    Candle, CandleUpdate, DerivativesMetric, DerivativesSample, ExchangeKind, Instrument, Market,
+    OrderBook, OrderBookLevel, Ticker, TickerStats, TimeFrame, Trade, TradeSide,
+};
 
 const BINANCE_BASE_URL: &str = "https://api.binance.com";
-const BINANCE_WS_BASE: &str = "wss://stream.binance.com:9443/stream";
+const BINANCE_WS_BASE: &str = "wss://stream.binance.com:9443";
 const MAX_CANDLES_PER_REQUEST: usize = 1000;
 // Reconnect before 24-hour auto-disconnect (23 hours)
 const WS_RECONNECT_SECS: u64 = 23 * 60 * 60;
-const MAX_BACKOFF_SECS: u64 = 60;
 /// Binance kline endpoint costs weight 2; limit ~2500 req/min (5000 weight/min)
 /// = ~40 req/s. Use 20 for safety margin.
 const BINANCE_REQUESTS_PER_SECOND: u32 = 20;
+/// Futures klines cost weight 5 for 1000 rows against a 2400 weight/min limit.
+const BINANCE_FUTURES_REQUESTS_PER_SECOND: u32 = 5;
+/// Pause once `X-MBX-USED-WEIGHT-1M` reaches this share of the 6000 weight/min
+/// IP limit, leaving headroom for the depth snapshots of a resync.
+const BINANCE_WEIGHT_BUDGET_1M: u32 = 5000;
+/// Same as `BINANCE_WEIGHT_BUDGET_1M` for the 2400 weight/min futures limit.
+const BINANCE_FUTURES_WEIGHT_BUDGET_1M: u32 = 2000;
+/// Default time between open interest polls of each futures symbol.
+const OPEN_INTEREST_POLL_SECS: u64 = 60;
+/// Depth snapshot size used to seed the local book (weight 50 per request).
+const DEPTH_SNAPSHOT_LIMIT: usize = 1000;
+/// Number of levels per side included in emitted `OrderBook`s.
+const ORDERBOOK_DEPTH: usize = 20;
+/// Quote assets recognised when splitting concatenated symbols like
+/// `SOLUSDT`; longer assets come first so `FDUSD` wins over `USD`-like tails.
+const QUOTE_ASSETS: &[&str] = &[
+    "FDUSD", "USDT", "USDC", "TUSD", "BUSD", "BTC", "ETH", "BNB", "EUR", "TRY", "BRL", "JPY",
+];
+
+/// Binance market served by a `BinanceExchange`.
+///
+/// Spot and USDⓈ-M futures share symbols, kline and stream formats; they
+/// differ in hosts, REST paths, rate limits and the futures-only streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinanceMarket {
+    Spot,
+    /// USDⓈ-M perpetual and delivery futures
+    UsdM,
+}
+
+impl BinanceMarket {
+    fn kind(self) -> ExchangeKind {
+        match self {
+            Self::Spot => ExchangeKind::Binance,
+            Self::UsdM => ExchangeKind::BinanceFutures,
+        }
+    }
+
+    /// REST path prefix, e.g. `/api/v3` in `/api/v3/klines`
+    fn api_prefix(self) -> &'static str {
+        match self {
+            Self::Spot => "/api/v3",
+            Self::UsdM => "/fapi/v1",
+        }
+    }
+
+    /// Futures only publish aggregated trades, in the same payload shape.
+    fn trade_stream(self) -> &'static str {
+        match self {
+            Self::Spot => "trade",
+            Self::UsdM => "aggTrade",
+        }
+    }
+
+    fn quota(self) -> Quota {
+        let per_second = match self {
+            Self::Spot => BINANCE_REQUESTS_PER_SECOND,
+            Self::UsdM => BINANCE_FUTURES_REQUESTS_PER_SECOND,
+        };
+        Quota::per_second(NonZeroU32::new(per_second).unwrap())
+    }
+
+    fn usage_policy(self) -> UsagePolicy {
+        match self {
+            Self::Spot => used_weight_pause,
+            Self::UsdM => futures_used_weight_pause,
+        }
+    }
+}
 
 pub struct BinanceExchange {
-    client: reqwest::Client,
-    rate_limiter: Arc<DefaultDirectRateLimiter>,
+    market: BinanceMarket,
+    http: RestClient,
+    ws: WsSupervisor,
+    base_url: String,
+    ws_url: String,
+    /// Time between open interest polls (futures only)
+    open_interest_interval: Duration,
 }
 
 impl BinanceExchange {
-    pub fn new() -> Self {
-        let quota = Quota::per_second(NonZeroU32::new(BINANCE_REQUESTS_PER_SECOND).unwrap());
+    /// Create a client for the REST API at `base_url` and the WebSocket host
+    /// at `ws_url` (e.g. `https://api.binance.com` and
+    /// `wss://stream.binance.com:9443`). Combined streams are served from
+    /// `{ws_url}/stream`.
+    pub fn new(base_url: &str, ws_url: &str) -> Self {
+        Self::for_market(BinanceMarket::Spot, base_url, ws_url)
+    }
+
+    /// Create a USDⓈ-M futures client, e.g. for `https://fapi.binance.com`
+    /// and `wss://fstream.binance.com`.
+    pub fn usd_m(base_url: &str, ws_url: &str) -> Self {
+        Self::for_market(BinanceMarket::UsdM, base_url, ws_url)
+    }
+
+    fn for_market(market: BinanceMarket, base_url: &str, ws_url: &str) -> Self {
+        Self {
+            market,
+            ws: WsSupervisor::new(market.kind(), WsOptions::default()),
+            http: RestClient::new(market.kind(), market.quota(), market.usage_policy()),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            ws_url: ws_url.trim_end_matches('/').to_owned(),
+            open_interest_interval: Duration::from_secs(OPEN_INTEREST_POLL_SECS),
+        }
+    }
+
+    /// Replace the WebSocket idle timeout and backoff reset settings.
+    pub fn with_ws_options(mut self, options: WsOptions) -> Self {
+        self.ws.set_options(options);
+        self
+    }
+
+    /// Replace the time between open interest polls of each symbol.
+    pub fn with_open_interest_interval(mut self, interval: Duration) -> Self {
+        self.open_interest_interval = interval;
+        self
+    }
+
+    /// Fetch a single page of klines opening within `start_time..=end_time`,
+    /// oldest-first.
+    async fn fetch_klines_page(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        limit: usize,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Candle>, Report<ExchangeError>> {
+        let kind = self.market.kind();
+        let url = format!("{}{}/klines", self.base_url, self.market.api_prefix());
+
+        let mut params = vec![
+            ("symbol", symbol.to_owned()),
+            ("interval", timeframe.binance_interval().to_owned()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(start) = start_time {
+            params.push(("startTime", start.timestamp_millis().to_string()));
+        }
+        if let Some(end) = end_time {
+            params.push(("endTime", end.timestamp_millis().to_string()));
+        }
+
+        let response = self.http.send(self.http.get(&url).query(&params)).await?;
+
+        let raw: Vec<BinanceKlineRow> =
+            response
+                .json()
+                .await
+                .change_context(ExchangeError::ResponseParse {
+                    exchange: kind.to_string(),
+                })?;
+
+        raw.into_iter()
+            .map(|row: BinanceKlineRow| row.into_candle(kind, symbol, timeframe))
+            .collect()
+    }
+
+    /// Fetch a REST depth snapshot used to seed a local order book.
+    async fn fetch_depth_snapshot(
+        &self,
+        symbol: &str,
+    ) -> Result<BinanceDepthSnapshot, Report<ExchangeError>> {
+        let kind = self.market.kind();
+        let url = format!("{}{}/depth", self.base_url, self.market.api_prefix());
+        let response = self
+            .http
+            .send(self.http.get(&url).query(&[
+                ("symbol", symbol.to_owned()),
+                ("limit", DEPTH_SNAPSHOT_LIMIT.to_string()),
+            ]))
+            .await?;
+
+        response
+            .json()
+            .await
+            .change_context(ExchangeError::ResponseParse {
+                exchange: kind.to_string(),
+            })
+    }
+
+    /// Maintain local order books from `@depth` diff streams.
+    ///
+    /// Follows Binance's sync procedure: open the stream first, seed each
+    /// book from a REST snapshot, drop diffs already covered by it, and
+    /// re-seed whenever a gap in update ids is detected.
+    async fn run_orderbook_ws(
+        &self,
+        mut symbols: watch::Receiver<StreamSymbols>,
+        tx: &mpsc::Sender<OrderBook>,
+        cancel: &CancellationToken,
+        mut watchdog: WsWatchdog,
+    ) -> Result<(), Report<ExchangeError>> {
+        let kind = self.market.kind();
+        let mut streams = CombinedStreams::new(
+            "depth@100ms",
+            symbols
+                .borrow_and_update()
+                .orderbook
+                .clone()
+                .unwrap_or_default(),
+        );
+
+        let (ws_stream, _) = connect_async(streams.url(&self.ws_url))
+            .await
+            .change_context(ExchangeError::Connection {
+                exchange: kind.to_string(),
+            })?;
+
+        let (mut write, mut read) = ws_stream.split();
+
+        info!(symbols = ?streams.symbols(), "binance orderbook ws connected");
+        watchdog.connected();
+
+        // Diffs received while the snapshots load are buffered by the socket
+        // and replayed against the books below.
+        let mut books: HashMap<String, BinanceDepthBook> = HashMap::new();
+        for symbol in streams.symbols() {
+            let snapshot = self.fetch_depth_snapshot(symbol).await?;
+            books.insert(
+                symbol.to_uppercase(),
+                BinanceDepthBook::from_snapshot(snapshot),
+            );
+        }
+
+        let reconnect_timer = tokio::time::sleep(Duration::from_secs(WS_RECONNECT_SECS));
+        tokio::pin!(reconnect_timer);
+
+        loop {
+            let idle_deadline = watchdog.deadline();
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    debug!("binance orderbook ws cancelled");
+                    break;
+                }
+                _ = sleep_until(idle_deadline) => {
+                    return Err(watchdog.idle_error());
+                }
+                _ = &mut reconnect_timer => {
+                    info!("binance orderbook ws 23h limit reached, reconnecting");
+                    return Err(Report::new(ExchangeError::Connection {
+                        exchange: format!("{kind} (scheduled reconnect)"),
+                    }));
+                }
+                Ok(()) = symbols.changed() => {
+                    let next = symbols.borrow_and_update().orderbook.clone().unwrap_or_default();
+                    let change = streams.update(next);
+                    for request in change.requests {
+                        write.send(Message::Text(request.into())).await
+                            .change_context(ExchangeError::Connection { exchange: kind.to_string() })?;
+                    }
+                    for symbol in &change.removed {
+                        books.remove(&symbol.to_uppercase());
+                    }
+                    // Diffs of new symbols queue on the socket while their snapshots load
+                    for symbol in &change.added {
+                        let snapshot = self.fetch_depth_snapshot(symbol).await?;
+                        books.insert(
+                            symbol.to_uppercase(),
+                            BinanceDepthBook::from_snapshot(snapshot),
+                        );
+                    }
+                }
+                msg = read.next() => {
+                    match msg {
+                        None => break,
+                        Some(Err(e)) => return Err(Report::new(e)
+                            .change_context(ExchangeError::Connection {
+                                exchange: kind.to_string(),
+                            })),
+                        Some(Ok(Message::Text(text))) => {
+                            if is_command_response(&text) {
+                                continue;
+                            }
+                            watchdog.touch();
+                            let event = match serde_json::from_str::<BinanceCombinedMsg<BinanceDepthEvent>>(&text) {
+                                Ok(combined) => combined.data,
+                                Err(e) => {
+                                    warn!(error = %e, raw = %text, "binance depth parse error");
+                                    continue;
+                                }
+                            };
+                            let Some(book) = books.get_mut(&event.symbol) else {
+                                continue;
+                            };
+
+                            let mut result = book.apply(&event);
+                            if result == DepthApply::Gap {
+                                warn!(symbol = %event.symbol, "binance depth gap detected, resyncing from snapshot");
+                                let snapshot = self.fetch_depth_snapshot(&event.symbol).await?;
+                                *book = BinanceDepthBook::from_snapshot(snapshot);
+                                result = book.apply(&event);
+                            }
+
+                            if result == DepthApply::Applied {
+                                let timestamp = DateTime::from_timestamp_millis(event.event_time)
+                                    .unwrap_or_else(Utc::now);
+                                let _ = tx
+                                    .send(book.to_orderbook(kind, &event.symbol, timestamp, ORDERBOOK_DEPTH))
+                                    .await;
+                            }
+                        }
+                        Some(Ok(Message::Ping(data))) => {
+                            watchdog.touch();
+                            let _ = write.send(Message::Pong(data)).await;
+                        }
+                        Some(Ok(_)) => {}
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the current open interest of a futures symbol.
+    async fn fetch_open_interest(
+        &self,
+        symbol: &str,
+    ) -> Result<DerivativesSample, Report<ExchangeError>> {
+        let kind = self.market.kind();
+        let url = format!("{}{}/openInterest", self.base_url, self.market.api_prefix());
+        let response = self
+            .http
+            .send(self.http.get(&url).query(&[("symbol", symbol)]))
+            .await?;
+
+        let raw: BinanceOpenInterest =
+            response
+                .json()
+                .await
+                .change_context(ExchangeError::ResponseParse {
+                    exchange: kind.to_string(),
+                })?;
+        raw.into_sample(kind)
+    }
+
+    /// Poll the open interest of `symbols` every `open_interest_interval`
+    /// until `cancel` is triggered. A failed poll is logged and retried on
+    /// the next round.
+    async fn poll_open_interest(
+        &self,
+        symbols: &[String],
+        tx: &mpsc::Sender<DerivativesSample>,
+        cancel: &CancellationToken,
+    ) {
+        let mut timer = tokio::time::interval(self.open_interest_interval);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = timer.tick() => {}
+            }
+            for symbol in symbols {
+                match self.fetch_open_interest(symbol).await {
+                    Ok(sample) => {
+                        if tx.send(sample).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        warn!(error = ?e, symbol = %symbol, "binance open interest poll failed")
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Streams of one combined-stream connection, kept in sync with a
+/// `SubscriptionHandle` through `SUBSCRIBE` / `UNSUBSCRIBE` requests.
+struct CombinedStreams {
+    /// Stream name after the symbol, e.g. "ticker" in `solusdt@ticker`
+    suffix: &'static str,
+    symbols: Vec<String>,
+    next_request_id: u64,
+}
+
+/// Result of switching a `CombinedStreams` to a new symbol list.
+#[derive(Debug, Default)]
+struct StreamChange {
+    added: Vec<String>,
+    removed: Vec<String>,
+    /// JSON requests to send on the socket
+    requests: Vec<String>,
+}
+
+impl CombinedStreams {
+    fn new(suffix: &'static str, symbols: Vec<String>) -> Self {
         Self {
-            client: reqwest::Client::new(),
-            rate_limiter: Arc::new(RateLimiter::direct(quota)),
+            suffix,
+            symbols,
+            next_request_id: 1,
+        }
+    }
+
+    fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    fn stream_names(&self, symbols: &[String]) -> Vec<String> {
+        symbols
+            .iter()
+            .map(|s| format!("{}@{}", s.to_lowercase(), self.suffix))
+            .collect()
+    }
+
+    /// Combined stream URL for the current symbols; with none, the socket
+    /// opens without streams and waits for a `SUBSCRIBE`.
+    fn url(&self, ws_base: &str) -> String {
+        if self.symbols.is_empty() {
+            return format!("{ws_base}/stream");
+        }
+        format!(
+            "{ws_base}/stream?streams={}",
+            self.stream_names(&self.symbols).join("/")
+        )
+    }
+
+    fn update(&mut self, symbols: Vec<String>) -> StreamChange {
+        let added: Vec<String> = symbols
+            .iter()
+            .filter(|s| !self.symbols.contains(s))
+            .cloned()
+            .collect();
+        let removed: Vec<String> = self
+            .symbols
+            .iter()
+            .filter(|s| !symbols.contains(s))
+            .cloned()
+            .collect();
+
+        let mut requests = Vec::new();
+        for (method, changed) in [("SUBSCRIBE", &added), ("UNSUBSCRIBE", &removed)] {
+            if changed.is_empty() {
+                continue;
+            }
+            requests.push(
+                serde_json::json!({
+                    "method": method,
+                    "params": self.stream_names(changed),
+                    "id": self.next_request_id,
+                })
+                .to_string(),
+            );
+            self.next_request_id += 1;
+        }
+
+        self.symbols = symbols;
+        StreamChange {
+            added,
+            removed,
+            requests,
         }
     }
 }
 
+/// Whether `text` answers a `SUBSCRIBE`/`UNSUBSCRIBE` request rather than
+/// carrying stream data.
+fn is_command_response(text: &str) -> bool {
+    serde_json::from_str::<BinanceCommandResponse>(text).is_ok()
+}
+
+/// Decode a Binance symbol (`{BASE}{QUOTE}`, e.g. `SOLUSDT`) by matching a
+/// known quote asset suffix.
+pub fn decode_symbol(symbol: &str) -> Option<Instrument> {
+    let symbol = symbol.to_ascii_uppercase();
+    QUOTE_ASSETS.iter().find_map(|quote| {
+        let base = symbol.strip_suffix(quote)?;
+        (!base.is_empty()).then(|| Instrument::new(base, quote))
+    })
+}
+
+/// Encode an instrument as a Binance symbol.
+pub fn encode_symbol(instrument: &Instrument) -> String {
+    format!("{}{}", instrument.base, instrument.quote)
+}
+
+/// Pause until the weight window resets at the next minute once
+/// `X-MBX-USED-WEIGHT-1M` reaches `BINANCE_WEIGHT_BUDGET_1M`.
+fn used_weight_pause(headers: &HeaderMap) -> Option<Duration> {
+    used_weight_pause_at(headers, Utc::now(), BINANCE_WEIGHT_BUDGET_1M)
+}
+
+/// `used_weight_pause` against `BINANCE_FUTURES_WEIGHT_BUDGET_1M`.
+fn futures_used_weight_pause(headers: &HeaderMap) -> Option<Duration> {
+    used_weight_pause_at(headers, Utc::now(), BINANCE_FUTURES_WEIGHT_BUDGET_1M)
+}
+
+fn used_weight_pause_at(headers: &HeaderMap, now: DateTime<Utc>, budget: u32) -> Option<Duration> {
+    let used: u32 = headers
+        .get("X-MBX-USED-WEIGHT-1M")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    if used < budget {
+        return None;
+    }
+    let elapsed_ms = u64::from(now.second()) * 1000 + u64::from(now.timestamp_subsec_millis());
+    Some(Duration::from_millis(60_000 - elapsed_ms.min(59_999)))
+}
+
 impl Default for BinanceExchange {
     fn default() -> Self {
-        Self::new()
+        Self::new(BINANCE_BASE_URL, BINANCE_WS_BASE)
     }
 }
 
 impl Exchange for BinanceExchange {
     fn kind(&self) -> ExchangeKind {
-        ExchangeKind::Binance
+        self.market.kind()
+    }
+
+    fn list_markets(&self) -> BoxFuture<'_, Result<Vec<Market>, Report<ExchangeError>>> {
+        Box::pin(async move {
+            let kind = self.market.kind();
+            let url = format!("{}{}/exchangeInfo", self.base_url, self.market.api_prefix());
+            let response = self.http.send(self.http.get(&url)).await?;
+
+            let info: BinanceExchangeInfo =
+                response
+                    .json()
+                    .await
+                    .change_context(ExchangeError::ResponseParse {
+                        exchange: kind.to_string(),
+                    })?;
+
+            Ok(info
+                .symbols
+                .into_iter()
+                .map(|symbol| symbol.into_market(kind))
+                .collect())
+        })
     }
 
     fn fetch_candles(
@@ -63,57 +582,104 @@ impl Exchange for BinanceExchange {
     ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>> {
         let symbol = symbol.to_owned();
         Box::pin(async move {
-            // Wait for rate limiter before making the request
-            self.rate_limiter.until_ready().await;
-
-            let url = format!("{}/api/v3/klines", BINANCE_BASE_URL);
-            let interval = timeframe.binance_interval();
-            let fetch_limit = limit.min(MAX_CANDLES_PER_REQUEST);
-
-            let limit_str = fetch_limit.to_string();
-            let params = [
-                ("symbol", symbol.as_str()),
-                ("interval", interval),
-                ("limit", limit_str.as_str()),
-            ];
-
-            let response = self
-                .client
-                .get(&url)
-                .query(&params)
-                .send()
-                .await
-                .change_context(ExchangeError::Request {
-                    exchange: "binance".into(),
-                })?;
+            let mut pages: Vec<Vec<Candle>> = Vec::new();
+            let mut end_time: Option<DateTime<Utc>> = None;
+            let mut remaining = limit;
+            let mut fetched_total = 0;
 
-            if !response.status().is_success() {
-                return Err(Report::new(ExchangeError::Request {
-                    exchange: "binance".into(),
-                })
-                .attach(format!("HTTP status: {}", response.status())));
+            while remaining > 0 {
+                let count = remaining.min(MAX_CANDLES_PER_REQUEST);
+                let page = self
+                    .fetch_klines_page(&symbol, timeframe, count, None, end_time)
+                    .await?;
+
+                if page.is_empty() {
+                    break;
+                }
+
+                let fetched = page.len();
+                // Binance returns oldest-first; the next (older) page must end
+                // strictly before the oldest candle of this one.
+                end_time = page
+                    .first()
+                    .map(|c| c.open_time - chrono::Duration::milliseconds(1));
+                pages.push(page);
+
+                fetched_total += fetched;
+                remaining = remaining.saturating_sub(fetched);
+
+                if fetched < count {
+                    break;
+                }
+
+                info!(
+                    symbol = %symbol,
+                    timeframe = %timeframe,
+                    fetched = fetched_total,
+                    total = limit,
+                    "binance candle fetch progress"
+                );
             }
 
-            let raw: Vec<BinanceKlineRow> =
-                response
-                    .json()
-                    .await
-                    .change_context(ExchangeError::ResponseParse {
-                        exchange: "binance".into(),
-                    })?;
+            let candles = merge_kline_pages(pages, limit);
 
             info!(
                 symbol = %symbol,
                 timeframe = %timeframe,
-                fetched = raw.len(),
+                fetched = candles.len(),
                 "binance candle fetch complete"
             );
 
-            let candles = raw
-                .into_iter()
-                .map(|row: BinanceKlineRow| row.into_candle(&symbol, timeframe))
-                .collect::<Result<Vec<_>, _>>()?;
+            Ok(candles)
+        })
+    }
 
+    fn fetch_candles_range(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>> {
+        let symbol = symbol.to_owned();
+        Box::pin(async move {
+            let mut candles: Vec<Candle> = Vec::new();
+            // `endTime` is inclusive on Binance; stop just short of `end`.
+            let last_open = end - chrono::Duration::milliseconds(1);
+            let mut cursor = start;
+
+            while cursor < end {
+                let page = self
+                    .fetch_klines_page(
+                        &symbol,
+                        timeframe,
+                        MAX_CANDLES_PER_REQUEST,
+                        Some(cursor),
+                        Some(last_open),
+                    )
+                    .await?;
+
+                let Some(newest) = page.last() else {
+                    break;
+                };
+
+                let fetched = page.len();
+                cursor = newest.open_time + chrono::Duration::milliseconds(1);
+                candles.extend(page);
+
+                if fetched < MAX_CANDLES_PER_REQUEST {
+                    break;
+                }
+
+                debug!(
+                    symbol = %symbol,
+                    timeframe = %timeframe,
+                    fetched = candles.len(),
+                    "binance candle range fetch progress"
+                );
+            }
+
+            candles.dedup_by_key(|c| c.open_time);
             Ok(candles)
         })
     }
@@ -123,108 +689,239 @@ impl Exchange for BinanceExchange {
         symbols: &[String],
         tx: mpsc::Sender<Ticker>,
         cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let streams = MarketStreams {
+            ticker: Some(StreamSink {
+                symbols: symbols.to_vec(),
+                tx,
+            }),
+            ..MarketStreams::default()
+        };
+        let handle = SubscriptionHandle::new(&streams);
+        self.subscribe_streams(streams, handle, cancel)
+    }
+
+    fn subscribe_trades(
+        &self,
+        symbols: &[String],
+        tx: mpsc::Sender<Trade>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let streams = MarketStreams {
+            trades: Some(StreamSink {
+                symbols: symbols.to_vec(),
+                tx,
+            }),
+            ..MarketStreams::default()
+        };
+        let handle = SubscriptionHandle::new(&streams);
+        self.subscribe_streams(streams, handle, cancel)
+    }
+
+    fn subscribe_candles(
+        &self,
+        symbols: &[String],
+        timeframes: &[TimeFrame],
+        tx: mpsc::Sender<CandleUpdate>,
+        cancel: CancellationToken,
     ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
         let symbols = symbols.to_vec();
+        let timeframes = timeframes.to_vec();
         Box::pin(async move {
-            let mut backoff = Duration::from_secs(1);
+            self.ws
+                .run("candles", &cancel, |watchdog| {
+                    run_candles_ws(
+                        self.market,
+                        &self.ws_url,
+                        &symbols,
+                        &timeframes,
+                        &tx,
+                        &cancel,
+                        watchdog,
+                    )
+                })
+                .await;
+            Ok(())
+        })
+    }
 
-            loop {
-                if cancel.is_cancelled() {
-                    break;
-                }
+    fn subscribe_orderbook(
+        &self,
+        symbols: &[String],
+        tx: mpsc::Sender<OrderBook>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let streams = MarketStreams {
+            orderbook: Some(StreamSink {
+                symbols: symbols.to_vec(),
+                tx,
+            }),
+            ..MarketStreams::default()
+        };
+        let handle = SubscriptionHandle::new(&streams);
+        self.subscribe_streams(streams, handle, cancel)
+    }
 
-                match run_ticker_ws(&symbols, &tx, &cancel).await {
-                    Ok(()) => break,
-                    Err(e) => {
-                        warn!(error = %e, "binance ticker ws disconnected, retrying...");
-                        sleep(backoff).await;
-                        backoff = (backoff * 2).min(Duration::from_secs(MAX_BACKOFF_SECS));
-                    }
+    fn subscribe_streams(
+        &self,
+        streams: MarketStreams,
+        handle: SubscriptionHandle,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        Box::pin(async move {
+            let ticker = async {
+                if let Some(sink) = &streams.ticker {
+                    self.ws
+                        .run("ticker", &cancel, |watchdog| {
+                            run_ticker_ws(
+                                self.market,
+                                &self.ws_url,
+                                handle.subscribe(),
+                                &sink.tx,
+                                &cancel,
+                                watchdog,
+                            )
+                        })
+                        .await;
                 }
-            }
+            };
+            let trades = async {
+                if let Some(sink) = &streams.trades {
+                    self.ws
+                        .run("trades", &cancel, |watchdog| {
+                            run_trades_ws(
+                                self.market,
+                                &self.ws_url,
+                                handle.subscribe(),
+                                &sink.tx,
+                                &cancel,
+                                watchdog,
+                            )
+                        })
+                        .await;
+                }
+            };
+            let orderbook = async {
+                if let Some(sink) = &streams.orderbook {
+                    self.ws
+                        .run("orderbook", &cancel, |watchdog| {
+                            self.run_orderbook_ws(handle.subscribe(), &sink.tx, &cancel, watchdog)
+                        })
+                        .await;
+                }
+            };
+
+            tokio::join!(ticker, trades, orderbook);
             Ok(())
         })
     }
 
-    fn subscribe_trades(
+    /// Mark price and funding rate from the `@markPrice@1s` stream, plus
+    /// polled open interest. Spot markets have neither and return at once.
+    fn subscribe_derivatives(
         &self,
         symbols: &[String],
-        tx: mpsc::Sender<Trade>,
+        tx: mpsc::Sender<DerivativesSample>,
         cancel: CancellationToken,
     ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
         let symbols = symbols.to_vec();
         Box::pin(async move {
-            let mut backoff = Duration::from_secs(1);
-
-            loop {
-                if cancel.is_cancelled() {
-                    break;
-                }
-
-                match run_trades_ws(&symbols, &tx, &cancel).await {
-                    Ok(()) => break,
-                    Err(e) => {
-                        warn!(error = %e, "binance trades ws disconnected, retrying...");
-                        sleep(backoff).await;
-                        backoff = (backoff * 2).min(Duration::from_secs(MAX_BACKOFF_SECS));
-                    }
-                }
+            if self.market == BinanceMarket::Spot || symbols.is_empty() {
+                return Ok(());
             }
+            let mark_price = self.ws.run("mark_price", &cancel, |watchdog| {
+                run_mark_price_ws(self.market, &self.ws_url, &symbols, &tx, &cancel, watchdog)
+            });
+            let open_interest = self.poll_open_interest(&symbols, &tx, &cancel);
+
+            tokio::join!(mark_price, open_interest);
             Ok(())
         })
     }
+
+    fn stream_events(&self) -> broadcast::Receiver<WsEvent> {
+        self.ws.events()
+    }
 }
 
 async fn run_ticker_ws(
-    symbols: &[String],
+    market: BinanceMarket,
+    ws_base: &str,
+    mut symbols: watch::Receiver<StreamSymbols>,
     tx: &mpsc::Sender<Ticker>,
     cancel: &CancellationToken,
+    mut watchdog: WsWatchdog,
 ) -> Result<(), Report<ExchangeError>> {
-    let streams: Vec<String> = symbols
-        .iter()
-        .map(|s| format!("{}@ticker", s.to_lowercase()))
-        .collect();
-    let ws_url = format!("{}?streams={}", BINANCE_WS_BASE, streams.join("/"));
+    let kind = market.kind();
+    let mut streams = CombinedStreams::new(
+        "ticker",
+        symbols
+            .borrow_and_update()
+            .ticker
+            .clone()
+            .unwrap_or_default(),
+    );
 
     let (ws_stream, _) =
-        connect_async(&ws_url)
+        connect_async(streams.url(ws_base))
             .await
             .change_context(ExchangeError::Connection {
-                exchange: "binance".into(),
+                exchange: kind.to_string(),
             })?;
 
     let (mut write, mut read) = ws_stream.split();
 
-    info!(symbols = ?symbols, "binance ticker ws connected");
+    info!(symbols = ?streams.symbols(), "binance ticker ws connected");
+    watchdog.connected();
 
     // Reconnect after 23h to avoid Binance's 24h auto-disconnect
     let reconnect_timer = tokio::time::sleep(Duration::from_secs(WS_RECONNECT_SECS));
     tokio::pin!(reconnect_timer);
 
     loop {
+        let idle_deadline = watchdog.deadline();
         tokio::select! {
             _ = cancel.cancelled() => {
                 debug!("binance ticker ws cancelled");
                 break;
             }
+            _ = sleep_until(idle_deadline) => {
+                return Err(watchdog.idle_error());
+            }
             _ = &mut reconnect_timer => {
                 info!("binance ticker ws 23h limit reached, reconnecting");
                 return Err(Report::new(ExchangeError::Connection {
-                    exchange: "binance (scheduled reconnect)".into(),
+                    exchange: format!("{kind} (scheduled reconnect)"),
                 }));
             }
+            Ok(()) = symbols.changed() => {
+                let next = symbols.borrow_and_update().ticker.clone().unwrap_or_default();
+                for request in streams.update(next).requests {
+                    write.send(Message::Text(request.into())).await
+                        .change_context(ExchangeError::Connection { exchange: kind.to_string() })?;
+                }
+            }
             msg = read.next() => {
                 match msg {
                     None => break,
                     Some(Err(e)) => return Err(Report::new(e)
                         .change_context(ExchangeError::Connection {
-                            exchange: "binance".into(),
+                            exchange: kind.to_string(),
                         })),
                     Some(Ok(Message::Text(text))) => {
+                        if is_command_response(&text) {
+                            continue;
+                        }
+                        watchdog.touch();
                         match serde_json::from_str::<BinanceCombinedMsg<BinanceTickerData>>(&text) {
                             Ok(combined) => {
-                                let ticker = combined.data.into_ticker();
-                                let _ = tx.send(ticker).await;
+                                let symbol = combined.data.symbol.clone();
+                                match combined.data.into_ticker(kind) {
+                                    Some(ticker) => {
+                                        let _ = tx.send(ticker).await;
+                                    }
+                                    None => warn!(%symbol, "binance ticker with undecodable symbol"),
+                                }
                             }
                             Err(e) => {
                                 warn!(error = %e, raw = %text, "binance ticker parse error");
@@ -233,6 +930,7 @@ async fn run_ticker_ws(
                     }
                     Some(Ok(Message::Ping(data))) => {
                         // Server sends ping every 20s; must pong within 60s
+                        watchdog.touch();
                         let _ = write.send(Message::Pong(data)).await;
                     }
                     Some(Ok(_)) => {}
@@ -244,56 +942,83 @@ async fn run_ticker_ws(
     Ok(())
 }
 
-#[allow(dead_code)]
 async fn run_trades_ws(
-    symbols: &[String],
+    market: BinanceMarket,
+    ws_base: &str,
+    mut symbols: watch::Receiver<StreamSymbols>,
     tx: &mpsc::Sender<Trade>,
     cancel: &CancellationToken,
+    mut watchdog: WsWatchdog,
 ) -> Result<(), Report<ExchangeError>> {
-    let streams: Vec<String> = symbols
-        .iter()
-        .map(|s| format!("{}@trade", s.to_lowercase()))
-        .collect();
-    let ws_url = format!("{}?streams={}", BINANCE_WS_BASE, streams.join("/"));
+    let kind = market.kind();
+    let mut streams = CombinedStreams::new(
+        market.trade_stream(),
+        symbols
+            .borrow_and_update()
+            .trades
+            .clone()
+            .unwrap_or_default(),
+    );
 
     let (ws_stream, _) =
-        connect_async(&ws_url)
+        connect_async(streams.url(ws_base))
             .await
             .change_context(ExchangeError::Connection {
-                exchange: "binance".into(),
+                exchange: kind.to_string(),
             })?;
 
     let (mut write, mut read) = ws_stream.split();
 
-    info!(symbols = ?symbols, "binance trades ws connected");
+    info!(symbols = ?streams.symbols(), "binance trades ws connected");
+    watchdog.connected();
 
     let reconnect_timer = tokio::time::sleep(Duration::from_secs(WS_RECONNECT_SECS));
     tokio::pin!(reconnect_timer);
 
     loop {
+        let idle_deadline = watchdog.deadline();
         tokio::select! {
             _ = cancel.cancelled() => {
                 debug!("binance trades ws cancelled");
                 break;
             }
+            _ = sleep_until(idle_deadline) => {
+                return Err(watchdog.idle_error());
+            }
             _ = &mut reconnect_timer => {
                 info!("binance trades ws 23h limit reached, reconnecting");
                 return Err(Report::new(ExchangeError::Connection {
-                    exchange: "binance (scheduled reconnect)".into(),
+                    exchange: format!("{kind} (scheduled reconnect)"),
                 }));
             }
+            Ok(()) = symbols.changed() => {
+                let next = symbols.borrow_and_update().trades.clone().unwrap_or_default();
+                for request in streams.update(next).requests {
+                    write.send(Message::Text(request.into())).await
+                        .change_context(ExchangeError::Connection { exchange: kind.to_string() })?;
+                }
+            }
             msg = read.next() => {
                 match msg {
                     None => break,
                     Some(Err(e)) => return Err(Report::new(e)
                         .change_context(ExchangeError::Connection {
-                            exchange: "binance".into(),
+                            exchange: kind.to_string(),
                         })),
                     Some(Ok(Message::Text(text))) => {
+                        if is_command_response(&text) {
+                            continue;
+                        }
+                        watchdog.touch();
                         match serde_json::from_str::<BinanceCombinedMsg<BinanceTradeData>>(&text) {
                             Ok(combined) => {
-                                let trade = combined.data.into_trade();
-                                let _ = tx.send(trade).await;
+                                let symbol = combined.data.symbol.clone();
+                                match combined.data.into_trade(kind) {
+                                    Some(trade) => {
+                                        let _ = tx.send(trade).await;
+                                    }
+                                    None => warn!(%symbol, "binance trade with undecodable symbol"),
+                                }
                             }
                             Err(e) => {
                                 warn!(error = %e, raw = %text, "binance trade parse error");
@@ -301,6 +1026,91 @@ async fn run_trades_ws(
                         }
                     }
                     Some(Ok(Message::Ping(data))) => {
+                        watchdog.touch();
+                        let _ = write.send(Message::Pong(data)).await;
+                    }
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_candles_ws(
+    market: BinanceMarket,
+    ws_base: &str,
+    symbols: &[String],
+    timeframes: &[TimeFrame],
+    tx: &mpsc::Sender<CandleUpdate>,
+    cancel: &CancellationToken,
+    mut watchdog: WsWatchdog,
+) -> Result<(), Report<ExchangeError>> {
+    let kind = market.kind();
+    let streams: Vec<String> = symbols
+        .iter()
+        .flat_map(|s| {
+            timeframes
+                .iter()
+                .map(move |tf| format!("{}@kline_{}", s.to_lowercase(), tf.binance_interval()))
+        })
+        .collect();
+    let ws_url = format!("{}/stream?streams={}", ws_base, streams.join("/"));
+
+    let (ws_stream, _) =
+        connect_async(&ws_url)
+            .await
+            .change_context(ExchangeError::Connection {
+                exchange: kind.to_string(),
+            })?;
+
+    let (mut write, mut read) = ws_stream.split();
+
+    info!(symbols = ?symbols, timeframes = ?timeframes, "binance candles ws connected");
+    watchdog.connected();
+
+    let reconnect_timer = tokio::time::sleep(Duration::from_secs(WS_RECONNECT_SECS));
+    tokio::pin!(reconnect_timer);
+
+    loop {
+        let idle_deadline = watchdog.deadline();
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                debug!("binance candles ws cancelled");
+                break;
+            }
+            _ = sleep_until(idle_deadline) => {
+                return Err(watchdog.idle_error());
+            }
+            _ = &mut reconnect_timer => {
+                info!("binance candles ws 23h limit reached, reconnecting");
+                return Err(Report::new(ExchangeError::Connection {
+                    exchange: format!("{kind} (scheduled reconnect)"),
+                }));
+            }
+            msg = read.next() => {
+                match msg {
+                    None => break,
+                    Some(Err(e)) => return Err(Report::new(e)
+                        .change_context(ExchangeError::Connection {
+                            exchange: kind.to_string(),
+                        })),
+                    Some(Ok(Message::Text(text))) => {
+                        watchdog.touch();
+                        match serde_json::from_str::<BinanceCombinedMsg<BinanceKlineEvent>>(&text) {
+                            Ok(combined) => {
+                                if let Some(update) = combined.data.kline.into_update(kind) {
+                                    let _ = tx.send(update).await;
+                                }
+                            }
+                            Err(e) => {
+                                warn!(error = %e, raw = %text, "binance kline parse error");
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Ping(data))) => {
+                        watchdog.touch();
                         let _ = write.send(Message::Pong(data)).await;
                     }
                     Some(Ok(_)) => {}
@@ -309,60 +1119,363 @@ async fn run_trades_ws(
         }
     }
 
-    Ok(())
+    Ok(())
+}
+
+async fn run_mark_price_ws(
+    market: BinanceMarket,
+    ws_base: &str,
+    symbols: &[String],
+    tx: &mpsc::Sender<DerivativesSample>,
+    cancel: &CancellationToken,
+    mut watchdog: WsWatchdog,
+) -> Result<(), Report<ExchangeError>> {
+    let kind = market.kind();
+    let ws_url = CombinedStreams::new("markPrice@1s", symbols.to_vec()).url(ws_base);
+
+    let (ws_stream, _) =
+        connect_async(&ws_url)
+            .await
+            .change_context(ExchangeError::Connection {
+                exchange: kind.to_string(),
+            })?;
+
+    let (mut write, mut read) = ws_stream.split();
+
+    info!(symbols = ?symbols, "binance mark price ws connected");
+    watchdog.connected();
+
+    let reconnect_timer = tokio::time::sleep(Duration::from_secs(WS_RECONNECT_SECS));
+    tokio::pin!(reconnect_timer);
+
+    loop {
+        let idle_deadline = watchdog.deadline();
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                debug!("binance mark price ws cancelled");
+                break;
+            }
+            _ = sleep_until(idle_deadline) => {
+                return Err(watchdog.idle_error());
+            }
+            _ = &mut reconnect_timer => {
+                info!("binance mark price ws 23h limit reached, reconnecting");
+                return Err(Report::new(ExchangeError::Connection {
+                    exchange: format!("{kind} (scheduled reconnect)"),
+                }));
+            }
+            msg = read.next() => {
+                match msg {
+                    None => break,
+                    Some(Err(e)) => return Err(Report::new(e)
+                        .change_context(ExchangeError::Connection {
+                            exchange: kind.to_string(),
+                        })),
+                    Some(Ok(Message::Text(text))) => {
+                        watchdog.touch();
+                        match serde_json::from_str::<BinanceCombinedMsg<BinanceMarkPriceData>>(&text) {
+                            Ok(combined) => {
+                                for sample in combined.data.into_samples(kind) {
+                                    let _ = tx.send(sample).await;
+                                }
+                            }
+                            Err(e) => {
+                                warn!(error = %e, raw = %text, "binance mark price parse error");
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Ping(data))) => {
+                        watchdog.touch();
+                        let _ = write.send(Message::Pong(data)).await;
+                    }
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ── REST response types ───────────────────────────────────────────────────────
+
+/// Binance kline row: 12-element array
+/// [open_time, open, high, low, close, volume, close_time, ...]
+#[derive(Debug, Deserialize)]
+struct BinanceKlineRow(
+    i64,                        // 0: open_time (ms)
+    String,                     // 1: open
+    String,                     // 2: high
+    String,                     // 3: low
+    String,                     // 4: close
+    String,                     // 5: volume
+    #[allow(dead_code)] i64,    // 6: close_time
+    #[allow(dead_code)] String, // 7: quote asset volume
+    #[allow(dead_code)] i64,    // 8: number of trades
+    #[allow(dead_code)] String, // 9: taker buy base volume
+    #[allow(dead_code)] String, // 10: taker buy quote volume
+    #[allow(dead_code)] String, // 11: ignore
+);
+
+impl BinanceKlineRow {
+    fn into_candle(
+        self,
+        kind: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+    ) -> Result<Candle, Report<ExchangeError>> {
+        let parse_f64 = |s: &str| -> Result<f64, Report<ExchangeError>> {
+            s.parse::<f64>()
+                .change_context(ExchangeError::ResponseParse {
+                    exchange: kind.to_string(),
+                })
+        };
+
+        let open_time = DateTime::from_timestamp_millis(self.0).unwrap_or_else(Utc::now);
+        let instrument = instrument_for(kind, symbol).ok_or_else(|| {
+            Report::new(ExchangeError::ResponseParse {
+                exchange: kind.to_string(),
+            })
+            .attach(format!("undecodable symbol {symbol}"))
+        })?;
+
+        Ok(Candle {
+            exchange: kind,
+            symbol: symbol.to_owned(),
+            instrument,
+            timeframe,
+            open_time,
+            open: parse_f64(&self.1)?,
+            high: parse_f64(&self.2)?,
+            low: parse_f64(&self.3)?,
+            close: parse_f64(&self.4)?,
+            volume: parse_f64(&self.5)?,
+        })
+    }
+}
+
+/// Merge kline pages fetched newest-page-first into a single oldest-first
+/// series, dropping duplicated page boundaries and keeping the newest `limit`.
+fn merge_kline_pages(pages: Vec<Vec<Candle>>, limit: usize) -> Vec<Candle> {
+    let mut candles: Vec<Candle> = pages.into_iter().rev().flatten().collect();
+    candles.sort_by_key(|c| c.open_time);
+    candles.dedup_by_key(|c| c.open_time);
+
+    let excess = candles.len().saturating_sub(limit);
+    candles.drain(..excess);
+    candles
+}
+
+/// Response of `GET /api/v3/depth`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceDepthSnapshot {
+    last_update_id: u64,
+    bids: Vec<[String; 2]>,
+    asks: Vec<[String; 2]>,
+}
+
+/// Response of `GET /api/v3/exchangeInfo`.
+#[derive(Debug, Deserialize)]
+struct BinanceExchangeInfo {
+    symbols: Vec<BinanceSymbolInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceSymbolInfo {
+    symbol: String,
+    status: String,
+    base_asset: String,
+    quote_asset: String,
+    #[serde(default)]
+    filters: Vec<serde_json::Value>,
+}
+
+impl BinanceSymbolInfo {
+    fn into_market(self, kind: ExchangeKind) -> Market {
+        let filter_value = |filter_type: &str, field: &str| {
+            self.filters
+                .iter()
+                .find(|f| f["filterType"] == filter_type)
+                .and_then(|f| f[field].as_str())
+                .and_then(|v| v.parse::<f64>().ok())
+        };
+
+        let tick_size = filter_value("PRICE_FILTER", "tickSize");
+        let lot_step = filter_value("LOT_SIZE", "stepSize");
+        // Newer symbols use NOTIONAL; older ones still carry MIN_NOTIONAL,
+        // which futures spell with a `notional` field
+        let min_notional = filter_value("NOTIONAL", "minNotional")
+            .or_else(|| filter_value("MIN_NOTIONAL", "minNotional"))
+            .or_else(|| filter_value("MIN_NOTIONAL", "notional"));
+
+        Market {
+            exchange: kind,
+            trading: self.status == "TRADING",
+            instrument: Instrument::new(&self.base_asset, &self.quote_asset),
+            symbol: self.symbol,
+            tick_size,
+            lot_step,
+            min_notional,
+            warning: false,
+            cautions: Vec::new(),
+        }
+    }
+}
+
+/// Response of `GET /fapi/v1/openInterest`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceOpenInterest {
+    symbol: String,
+    open_interest: String,
+    /// Observation time (ms epoch)
+    time: i64,
+}
+
+impl BinanceOpenInterest {
+    fn into_sample(self, kind: ExchangeKind) -> Result<DerivativesSample, Report<ExchangeError>> {
+        let value =
+            self.open_interest
+                .parse::<f64>()
+                .change_context(ExchangeError::ResponseParse {
+                    exchange: kind.to_string(),
+                })?;
+
+        Ok(DerivativesSample {
+            exchange: kind,
+            symbol: self.symbol,
+            metric: DerivativesMetric::OpenInterest,
+            value,
+            timestamp: DateTime::from_timestamp_millis(self.time).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+// ── Local order book ──────────────────────────────────────────────────────────
+
+/// Price used as an ordered map key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PriceKey(f64);
+
+impl Eq for PriceKey {}
+
+impl PartialOrd for PriceKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PriceKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DepthApply {
+    Applied,
+    /// The diff is already covered by the snapshot
+    Stale,
+    /// Update ids skipped ahead; the book must be re-seeded
+    Gap,
 }
 
-// ── REST response types ───────────────────────────────────────────────────────
+/// Local order book kept in sync with a `@depth` diff stream.
+#[derive(Debug)]
+struct BinanceDepthBook {
+    last_update_id: u64,
+    /// Whether a diff has been applied on top of the snapshot yet
+    synced: bool,
+    bids: BTreeMap<PriceKey, f64>,
+    asks: BTreeMap<PriceKey, f64>,
+}
 
-/// Binance kline row: 12-element array
-/// [open_time, open, high, low, close, volume, close_time, ...]
-#[derive(Debug, Deserialize)]
-struct BinanceKlineRow(
-    i64,                        // 0: open_time (ms)
-    String,                     // 1: open
-    String,                     // 2: high
-    String,                     // 3: low
-    String,                     // 4: close
-    String,                     // 5: volume
-    #[allow(dead_code)] i64,    // 6: close_time
-    #[allow(dead_code)] String, // 7: quote asset volume
-    #[allow(dead_code)] i64,    // 8: number of trades
-    #[allow(dead_code)] String, // 9: taker buy base volume
-    #[allow(dead_code)] String, // 10: taker buy quote volume
-    #[allow(dead_code)] String, // 11: ignore
-);
+impl BinanceDepthBook {
+    fn from_snapshot(snapshot: BinanceDepthSnapshot) -> Self {
+        let mut book = Self {
+            last_update_id: snapshot.last_update_id,
+            synced: false,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+        };
+        apply_levels(&mut book.bids, &snapshot.bids);
+        apply_levels(&mut book.asks, &snapshot.asks);
+        book
+    }
 
-impl BinanceKlineRow {
-    fn into_candle(
-        self,
-        symbol: &str,
-        timeframe: TimeFrame,
-    ) -> Result<Candle, Report<ExchangeError>> {
-        let parse_f64 = |s: &str| -> Result<f64, Report<ExchangeError>> {
-            s.parse::<f64>()
-                .change_context(ExchangeError::ResponseParse {
-                    exchange: "binance".into(),
-                })
+    fn apply(&mut self, event: &BinanceDepthEvent) -> DepthApply {
+        if event.final_update_id <= self.last_update_id {
+            return DepthApply::Stale;
+        }
+
+        // The first diff must straddle the snapshot; later ones must be
+        // contiguous, which futures streams state through `pu` instead.
+        let expected = self.last_update_id + 1;
+        let contiguous = match (self.synced, event.previous_final_update_id) {
+            (false, _) => event.first_update_id <= expected,
+            (true, Some(previous)) => previous == self.last_update_id,
+            (true, None) => event.first_update_id == expected,
         };
+        if !contiguous {
+            return DepthApply::Gap;
+        }
 
-        let open_time = DateTime::from_timestamp_millis(self.0).unwrap_or_else(Utc::now);
+        apply_levels(&mut self.bids, &event.bids);
+        apply_levels(&mut self.asks, &event.asks);
+        self.last_update_id = event.final_update_id;
+        self.synced = true;
+        DepthApply::Applied
+    }
 
-        Ok(Candle {
-            exchange: ExchangeKind::Binance,
+    fn to_orderbook(
+        &self,
+        kind: ExchangeKind,
+        symbol: &str,
+        timestamp: DateTime<Utc>,
+        depth: usize,
+    ) -> OrderBook {
+        let level = |(price, size): (&PriceKey, &f64)| OrderBookLevel {
+            price: price.0,
+            size: *size,
+        };
+
+        OrderBook {
+            exchange: kind,
             symbol: symbol.to_owned(),
-            timeframe,
-            open_time,
-            open: parse_f64(&self.1)?,
-            high: parse_f64(&self.2)?,
-            low: parse_f64(&self.3)?,
-            close: parse_f64(&self.4)?,
-            volume: parse_f64(&self.5)?,
-        })
+            bids: self.bids.iter().rev().take(depth).map(level).collect(),
+            asks: self.asks.iter().take(depth).map(level).collect(),
+            timestamp,
+        }
+    }
+}
+
+/// Apply `[price, quantity]` levels to one side; a zero quantity removes the level.
+fn apply_levels(side: &mut BTreeMap<PriceKey, f64>, levels: &[[String; 2]]) {
+    for [price, size] in levels {
+        let (Ok(price), Ok(size)) = (price.parse::<f64>(), size.parse::<f64>()) else {
+            continue;
+        };
+        if size == 0.0 {
+            side.remove(&PriceKey(price));
+        } else {
+            side.insert(PriceKey(price), size);
+        }
     }
 }
 
 // ── WebSocket message types ───────────────────────────────────────────────────
 
+/// Reply to a `SUBSCRIBE`/`UNSUBSCRIBE` request: `{ "result": null, "id": 1 }`
+#[derive(Debug, Deserialize)]
+struct BinanceCommandResponse {
+    #[allow(dead_code)]
+    result: Option<serde_json::Value>,
+    #[allow(dead_code)]
+    id: u64,
+}
+
 /// Combined stream wrapper: `{ "stream": "...", "data": { ... } }`
 #[derive(Debug, Deserialize)]
 struct BinanceCombinedMsg<T> {
@@ -379,28 +1492,178 @@ struct BinanceTickerData {
     /// Total traded base asset volume
     #[serde(rename = "v")]
     volume: String,
+    #[serde(rename = "o")]
+    open: String,
+    #[serde(rename = "h")]
+    high: String,
+    #[serde(rename = "l")]
+    low: String,
+    /// Price change percent
+    #[serde(rename = "P")]
+    change_pct: String,
+    /// Total traded quote asset volume
+    #[serde(rename = "q")]
+    quote_volume: String,
+    /// Absent from the futures ticker
+    #[serde(rename = "b", default)]
+    best_bid: Option<String>,
+    #[serde(rename = "a", default)]
+    best_ask: Option<String>,
     /// Statistics close time (ms epoch)
     #[serde(rename = "C")]
     close_time: i64,
 }
 
 impl BinanceTickerData {
-    fn into_ticker(self) -> Ticker {
-        let price = self.price.parse::<f64>().unwrap_or(0.0);
-        let volume = self.volume.parse::<f64>().unwrap_or(0.0);
+    /// `None` when the symbol cannot be decoded.
+    fn into_ticker(self, kind: ExchangeKind) -> Option<Ticker> {
+        let parse = |value: &str| value.parse::<f64>().unwrap_or(0.0);
+        let price = parse(&self.price);
+        let volume = parse(&self.volume);
+        let stats = TickerStats {
+            open_24h: parse(&self.open),
+            high_24h: parse(&self.high),
+            low_24h: parse(&self.low),
+            change_pct_24h: parse(&self.change_pct),
+            quote_volume_24h: parse(&self.quote_volume),
+            best_bid: self.best_bid.and_then(|v| v.parse().ok()),
+            best_ask: self.best_ask.and_then(|v| v.parse().ok()),
+        };
         let timestamp = DateTime::from_timestamp_millis(self.close_time).unwrap_or_else(Utc::now);
 
-        Ticker {
-            exchange: ExchangeKind::Binance,
+        Some(Ticker {
+            exchange: kind,
+            instrument: instrument_for(kind, &self.symbol)?,
             symbol: self.symbol,
             price,
             volume,
+            stats,
             timestamp,
-        }
+            received_at: Utc::now(),
+        })
+    }
+}
+
+/// Kline stream event: `{ "e": "kline", "s": "...", "k": { ... } }`
+#[derive(Debug, Deserialize)]
+struct BinanceKlineEvent {
+    #[serde(rename = "k")]
+    kline: BinanceKlineData,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceKlineData {
+    /// Kline start time (ms epoch)
+    #[serde(rename = "t")]
+    open_time: i64,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "i")]
+    interval: String,
+    #[serde(rename = "o")]
+    open: String,
+    #[serde(rename = "h")]
+    high: String,
+    #[serde(rename = "l")]
+    low: String,
+    #[serde(rename = "c")]
+    close: String,
+    #[serde(rename = "v")]
+    volume: String,
+    /// Whether this kline is closed
+    #[serde(rename = "x")]
+    is_closed: bool,
+}
+
+impl BinanceKlineData {
+    fn into_update(self, kind: ExchangeKind) -> Option<CandleUpdate> {
+        // Binance interval strings match the config format
+        let Some(timeframe) = TimeFrame::from_str(&self.interval) else {
+            warn!(interval = %self.interval, "binance kline with unsupported interval");
+            return None;
+        };
+        let open_time = DateTime::from_timestamp_millis(self.open_time).unwrap_or_else(Utc::now);
+        let Some(instrument) = instrument_for(kind, &self.symbol) else {
+            warn!(symbol = %self.symbol, "binance kline with undecodable symbol");
+            return None;
+        };
+
+        Some(CandleUpdate {
+            candle: Candle {
+                exchange: kind,
+                instrument,
+                symbol: self.symbol,
+                timeframe,
+                open_time,
+                open: self.open.parse::<f64>().unwrap_or(0.0),
+                high: self.high.parse::<f64>().unwrap_or(0.0),
+                low: self.low.parse::<f64>().unwrap_or(0.0),
+                close: self.close.parse::<f64>().unwrap_or(0.0),
+                volume: self.volume.parse::<f64>().unwrap_or(0.0),
+            },
+            is_closed: self.is_closed,
+        })
+    }
+}
+
+/// Diff depth stream event: `{ "e": "depthUpdate", "U": ..., "u": ..., ... }`
+#[derive(Debug, Deserialize)]
+struct BinanceDepthEvent {
+    /// Event time (ms epoch)
+    #[serde(rename = "E")]
+    event_time: i64,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "U")]
+    first_update_id: u64,
+    #[serde(rename = "u")]
+    final_update_id: u64,
+    /// Final update id of the previous event; futures streams only
+    #[serde(rename = "pu", default)]
+    previous_final_update_id: Option<u64>,
+    #[serde(rename = "b")]
+    bids: Vec<[String; 2]>,
+    #[serde(rename = "a")]
+    asks: Vec<[String; 2]>,
+}
+
+/// Mark price stream event: `{ "e": "markPriceUpdate", "p": ..., "r": ..., ... }`
+#[derive(Debug, Deserialize)]
+struct BinanceMarkPriceData {
+    /// Event time (ms epoch)
+    #[serde(rename = "E")]
+    event_time: i64,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "p")]
+    mark_price: String,
+    /// Current funding rate; empty for delivery contracts
+    #[serde(rename = "r")]
+    funding_rate: String,
+}
+
+impl BinanceMarkPriceData {
+    /// Mark price and, for perpetuals, funding rate samples.
+    fn into_samples(self, kind: ExchangeKind) -> Vec<DerivativesSample> {
+        let timestamp = DateTime::from_timestamp_millis(self.event_time).unwrap_or_else(Utc::now);
+        [
+            (DerivativesMetric::MarkPrice, &self.mark_price),
+            (DerivativesMetric::FundingRate, &self.funding_rate),
+        ]
+        .into_iter()
+        .filter_map(|(metric, value)| {
+            Some(DerivativesSample {
+                exchange: kind,
+                symbol: self.symbol.clone(),
+                metric,
+                value: value.parse().ok()?,
+                timestamp,
+            })
+        })
+        .collect()
     }
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct BinanceTradeData {
     #[serde(rename = "s")]
@@ -414,10 +1677,17 @@ struct BinanceTradeData {
     is_buyer_maker: bool,
     #[serde(rename = "T")]
     trade_time: i64,
+    /// Trade id of a spot `trade` event
+    #[serde(rename = "t", default)]
+    trade_id: Option<u64>,
+    /// Aggregate trade id of a futures `aggTrade` event
+    #[serde(rename = "a", default)]
+    agg_trade_id: Option<u64>,
 }
 
 impl BinanceTradeData {
-    fn into_trade(self) -> Trade {
+    /// `None` when the symbol cannot be decoded.
+    fn into_trade(self, kind: ExchangeKind) -> Option<Trade> {
         let price = self.price.parse::<f64>().unwrap_or(0.0);
         let volume = self.quantity.parse::<f64>().unwrap_or(0.0);
         let timestamp = DateTime::from_timestamp_millis(self.trade_time).unwrap_or_else(Utc::now);
@@ -428,20 +1698,24 @@ impl BinanceTradeData {
             TradeSide::Buy
         };
 
-        Trade {
-            exchange: ExchangeKind::Binance,
+        Some(Trade {
+            exchange: kind,
+            instrument: instrument_for(kind, &self.symbol)?,
             symbol: self.symbol,
             price,
             volume,
             side,
             timestamp,
-        }
+            trade_id: self.trade_id.or(self.agg_trade_id),
+            received_at: Utc::now(),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::exchange::mock_server;
 
     #[test]
     fn binance_kline_row_parses_into_candle() {
@@ -459,7 +1733,9 @@ mod tests {
             "0".into(),
             "0".into(),
         );
-        let candle = row.into_candle("BTCUSDT", TimeFrame::Min1).unwrap();
+        let candle = row
+            .into_candle(ExchangeKind::Binance, "BTCUSDT", TimeFrame::Min1)
+            .unwrap();
         assert_eq!(candle.exchange, ExchangeKind::Binance);
         assert_eq!(candle.symbol, "BTCUSDT");
         assert_eq!(candle.open, 42000.0);
@@ -467,6 +1743,244 @@ mod tests {
         assert_eq!(candle.volume, 100.5);
     }
 
+    #[test]
+    fn combined_streams_diff_into_subscribe_requests() {
+        let mut streams = CombinedStreams::new("trade", vec!["BTCUSDT".to_owned()]);
+        assert_eq!(
+            streams.url("wss://x"),
+            "wss://x/stream?streams=btcusdt@trade"
+        );
+
+        let change = streams.update(vec!["ETHUSDT".to_owned(), "SOLUSDT".to_owned()]);
+        assert_eq!(change.added, vec!["ETHUSDT", "SOLUSDT"]);
+        assert_eq!(change.removed, vec!["BTCUSDT"]);
+        let requests: Vec<serde_json::Value> = change
+            .requests
+            .iter()
+            .map(|r| serde_json::from_str(r).unwrap())
+            .collect();
+        assert_eq!(
+            requests,
+            vec![
+                serde_json::json!({
+                    "method": "SUBSCRIBE",
+                    "params": ["ethusdt@trade", "solusdt@trade"],
+                    "id": 1,
+                }),
+                serde_json::json!({
+                    "method": "UNSUBSCRIBE",
+                    "params": ["btcusdt@trade"],
+                    "id": 2,
+                }),
+            ]
+        );
+
+        assert!(
+            streams
+                .update(streams.symbols().to_vec())
+                .requests
+                .is_empty()
+        );
+        streams.update(Vec::new());
+        assert_eq!(streams.url("wss://x"), "wss://x/stream");
+    }
+
+    #[test]
+    fn command_responses_are_not_stream_data() {
+        assert!(is_command_response(r#"{"result":null,"id":3}"#));
+        assert!(!is_command_response(
+            r#"{"stream":"btcusdt@trade","data":{"e":"trade"}}"#
+        ));
+    }
+
+    fn make_candle(open_time_secs: i64, close: f64) -> Candle {
+        Candle {
+            exchange: ExchangeKind::Binance,
+            symbol: "BTCUSDT".into(),
+            instrument: Instrument::new("BTC", "USDT"),
+            timeframe: TimeFrame::Min1,
+            open_time: DateTime::from_timestamp(open_time_secs, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    #[test]
+    fn merge_kline_pages_orders_oldest_first_and_dedups_boundary() {
+        // Pages arrive newest-first; the older page overlaps at t=120
+        let newer = vec![make_candle(120, 2.0), make_candle(180, 3.0)];
+        let older = vec![make_candle(60, 1.0), make_candle(120, 2.0)];
+        let merged = merge_kline_pages(vec![newer, older], 10);
+
+        let times: Vec<i64> = merged.iter().map(|c| c.open_time.timestamp()).collect();
+        assert_eq!(times, vec![60, 120, 180]);
+    }
+
+    #[test]
+    fn merge_kline_pages_keeps_newest_limit() {
+        let newer = vec![make_candle(180, 3.0), make_candle(240, 4.0)];
+        let older = vec![make_candle(60, 1.0), make_candle(120, 2.0)];
+        let merged = merge_kline_pages(vec![newer, older], 3);
+
+        let times: Vec<i64> = merged.iter().map(|c| c.open_time.timestamp()).collect();
+        assert_eq!(times, vec![120, 180, 240]);
+    }
+
+    #[test]
+    fn binance_kline_event_parses_into_update() {
+        let raw = r#"{"stream":"btcusdt@kline_5m","data":{"e":"kline","E":1704067500000,"s":"BTCUSDT","k":{"t":1704067200000,"T":1704067499999,"s":"BTCUSDT","i":"5m","o":"42000.0","c":"42500.0","h":"43000.0","l":"41500.0","v":"100.5","x":true}}}"#;
+        let msg: BinanceCombinedMsg<BinanceKlineEvent> = serde_json::from_str(raw).unwrap();
+        let update = msg.data.kline.into_update(ExchangeKind::Binance).unwrap();
+
+        assert!(update.is_closed);
+        assert_eq!(update.candle.symbol, "BTCUSDT");
+        assert_eq!(update.candle.timeframe, TimeFrame::Min5);
+        assert_eq!(update.candle.open_time.timestamp(), 1_704_067_200);
+        assert_eq!(update.candle.high, 43000.0);
+        assert_eq!(update.candle.close, 42500.0);
+    }
+
+    #[test]
+    fn symbol_codec_round_trip() {
+        assert_eq!(
+            decode_symbol("SOLUSDT"),
+            Some(Instrument::new("SOL", "USDT"))
+        );
+        assert_eq!(decode_symbol("ethbtc"), Some(Instrument::new("ETH", "BTC")));
+        assert_eq!(
+            decode_symbol("BTCFDUSD"),
+            Some(Instrument::new("BTC", "FDUSD"))
+        );
+        assert_eq!(decode_symbol("USDT"), None);
+        assert_eq!(encode_symbol(&Instrument::new("SOL", "USDT")), "SOLUSDT");
+    }
+
+    #[test]
+    fn used_weight_pauses_until_next_minute() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T00:00:45.500Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            used_weight_pause_at(&headers, now, BINANCE_WEIGHT_BUDGET_1M),
+            None
+        );
+
+        headers.insert("X-MBX-USED-WEIGHT-1M", "120".parse().unwrap());
+        assert_eq!(
+            used_weight_pause_at(&headers, now, BINANCE_WEIGHT_BUDGET_1M),
+            None
+        );
+
+        headers.insert(
+            "X-MBX-USED-WEIGHT-1M",
+            BINANCE_WEIGHT_BUDGET_1M.to_string().parse().unwrap(),
+        );
+        assert_eq!(
+            used_weight_pause_at(&headers, now, BINANCE_WEIGHT_BUDGET_1M),
+            Some(Duration::from_millis(14_500))
+        );
+    }
+
+    #[test]
+    fn binance_symbol_info_parses_filters() {
+        let raw = r#"{"symbols":[{"symbol":"BTCUSDT","status":"TRADING","baseAsset":"BTC","quoteAsset":"USDT","filters":[
+            {"filterType":"PRICE_FILTER","minPrice":"0.01","maxPrice":"1000000.00","tickSize":"0.01"},
+            {"filterType":"LOT_SIZE","minQty":"0.00001","maxQty":"9000.0","stepSize":"0.00001"},
+            {"filterType":"NOTIONAL","minNotional":"5.00","applyMinToMarket":true}
+        ]},{"symbol":"OLDBTC","status":"BREAK","baseAsset":"OLD","quoteAsset":"BTC","filters":[]}]}"#;
+        let info: BinanceExchangeInfo = serde_json::from_str(raw).unwrap();
+        let markets: Vec<Market> = info
+            .symbols
+            .into_iter()
+            .map(|symbol| symbol.into_market(ExchangeKind::Binance))
+            .collect();
+
+        assert_eq!(markets[0].instrument, Instrument::new("BTC", "USDT"));
+        assert_eq!(markets[0].tick_size, Some(0.01));
+        assert_eq!(markets[0].lot_step, Some(0.00001));
+        assert_eq!(markets[0].min_notional, Some(5.0));
+        assert!(markets[0].trading);
+        assert!(!markets[1].trading);
+        assert_eq!(markets[1].tick_size, None);
+    }
+
+    fn depth_event(first: u64, last: u64, bids: &[(&str, &str)]) -> BinanceDepthEvent {
+        BinanceDepthEvent {
+            event_time: 1_704_067_500_000,
+            symbol: "BTCUSDT".into(),
+            first_update_id: first,
+            final_update_id: last,
+            previous_final_update_id: None,
+            bids: bids
+                .iter()
+                .map(|(p, q)| [p.to_string(), q.to_string()])
+                .collect(),
+            asks: Vec::new(),
+        }
+    }
+
+    fn depth_book() -> BinanceDepthBook {
+        BinanceDepthBook::from_snapshot(BinanceDepthSnapshot {
+            last_update_id: 100,
+            bids: vec![
+                ["100.0".into(), "1.0".into()],
+                ["99.0".into(), "2.0".into()],
+            ],
+            asks: vec![["101.0".into(), "1.0".into()]],
+        })
+    }
+
+    #[test]
+    fn depth_book_drops_stale_and_applies_straddling_diff() {
+        let mut book = depth_book();
+
+        assert_eq!(
+            book.apply(&depth_event(90, 100, &[("100.0", "9.0")])),
+            DepthApply::Stale
+        );
+        assert_eq!(
+            book.apply(&depth_event(95, 103, &[("100.0", "0"), ("98.0", "3.0")])),
+            DepthApply::Applied
+        );
+        assert_eq!(book.apply(&depth_event(104, 105, &[])), DepthApply::Applied);
+
+        let ob = book.to_orderbook(ExchangeKind::Binance, "BTCUSDT", Utc::now(), 10);
+        let bids: Vec<(f64, f64)> = ob.bids.iter().map(|l| (l.price, l.size)).collect();
+        assert_eq!(bids, vec![(99.0, 2.0), (98.0, 3.0)]);
+        assert_eq!(ob.asks[0].price, 101.0);
+    }
+
+    #[test]
+    fn depth_book_reports_gaps() {
+        let mut book = depth_book();
+        assert_eq!(book.apply(&depth_event(102, 103, &[])), DepthApply::Gap);
+
+        book.apply(&depth_event(101, 103, &[]));
+        assert_eq!(book.apply(&depth_event(105, 106, &[])), DepthApply::Gap);
+    }
+
+    #[test]
+    fn futures_depth_book_chains_on_previous_final_update_id() {
+        let mut book = depth_book();
+        assert_eq!(book.apply(&depth_event(95, 103, &[])), DepthApply::Applied);
+
+        // Futures diffs may skip ids; `pu` links each to the previous one
+        let chained = BinanceDepthEvent {
+            previous_final_update_id: Some(103),
+            ..depth_event(110, 112, &[("98.0", "3.0")])
+        };
+        assert_eq!(book.apply(&chained), DepthApply::Applied);
+        let broken = BinanceDepthEvent {
+            previous_final_update_id: Some(111),
+            ..depth_event(113, 115, &[])
+        };
+        assert_eq!(book.apply(&broken), DepthApply::Gap);
+    }
+
     #[test]
     fn binance_trade_buyer_maker_is_sell() {
         let data = BinanceTradeData {
@@ -475,9 +1989,12 @@ mod tests {
             quantity: "0.5".into(),
             is_buyer_maker: true,
             trade_time: 1704067200000,
+            trade_id: Some(12345),
+            agg_trade_id: None,
         };
-        let trade = data.into_trade();
+        let trade = data.into_trade(ExchangeKind::Binance).unwrap();
         assert_eq!(trade.side, TradeSide::Sell);
+        assert_eq!(trade.trade_id, Some(12345));
     }
 
     #[test]
@@ -488,29 +2005,31 @@ mod tests {
             quantity: "0.5".into(),
             is_buyer_maker: false,
             trade_time: 1704067200000,
+            trade_id: None,
+            agg_trade_id: Some(678),
         };
-        let trade = data.into_trade();
+        let trade = data.into_trade(ExchangeKind::BinanceFutures).unwrap();
         assert_eq!(trade.side, TradeSide::Buy);
+        assert_eq!(trade.trade_id, Some(678));
     }
 
-    /// Integration test: requires network access. Run with `cargo test -- --ignored`
     #[tokio::test]
-    #[ignore]
-    async fn integration_fetch_candles() {
-        let exchange = BinanceExchange::new();
+    async fn mock_fetch_candles() {
+        let server = mock_server::binance("BTCUSDT", 42000.0).start().await;
+        let exchange = BinanceExchange::new(&server.base_url(), &server.ws_url(""));
         let candles = exchange
             .fetch_candles("BTCUSDT", TimeFrame::Min1, 10)
             .await
             .unwrap();
-        assert!(!candles.is_empty());
-        assert!(candles.len() <= 10);
+        assert_eq!(candles.len(), 5);
+        assert_eq!(candles[0].open_time.timestamp(), 1_704_067_200);
+        assert_eq!(candles[4].close, 42004.0);
     }
 
-    /// Integration test: requires network access. Run with `cargo test -- --ignored`
     #[tokio::test]
-    #[ignore]
-    async fn integration_subscribe_ticker() {
-        let exchange = BinanceExchange::new();
+    async fn mock_subscribe_ticker() {
+        let server = mock_server::binance("BTCUSDT", 42000.0).start().await;
+        let exchange = BinanceExchange::new(&server.base_url(), &server.ws_url(""));
         let (tx, mut rx) = mpsc::channel(10);
         let cancel = CancellationToken::new();
         let cancel_clone = cancel.clone();
@@ -528,6 +2047,161 @@ mod tests {
             .expect("channel closed");
 
         assert_eq!(ticker.exchange, ExchangeKind::Binance);
+        assert_eq!(ticker.symbol, "BTCUSDT");
+        assert_eq!(ticker.price, 42005.0);
+        assert_eq!(ticker.stats.open_24h, 42000.0);
+        assert_eq!(ticker.stats.high_24h, 42010.0);
+        assert_eq!(ticker.stats.low_24h, 41990.0);
+        assert_eq!(ticker.stats.change_pct_24h, 5.0);
+        assert_eq!(ticker.stats.quote_volume_24h, 51_850_000.0);
+        assert_eq!(ticker.stats.best_bid, Some(42004.0));
+        assert_eq!(ticker.stats.best_ask, Some(42006.0));
+        cancel.cancel();
+    }
+
+    #[tokio::test]
+    async fn mock_quiet_trade_stream_stays_connected_while_pinged() {
+        let server = mock_server::binance("BTCUSDT", 42000.0)
+            .ping_every(Duration::from_millis(100))
+            .start()
+            .await;
+        let exchange = BinanceExchange::new(&server.base_url(), &server.ws_url(""))
+            .with_ws_options(WsOptions {
+                idle_timeout: Duration::from_millis(400),
+                healthy_after: Duration::from_secs(60),
+            });
+        let mut events = exchange.stream_events();
+        let (tx, mut rx) = mpsc::channel(10);
+        let cancel = CancellationToken::new();
+        let cancel_clone = cancel.clone();
+
+        tokio::spawn(async move {
+            exchange
+                .subscribe_trades(&["BTCUSDT".to_owned()], tx, cancel_clone)
+                .await
+                .unwrap();
+        });
+
+        // One trade, then only pings for several idle timeouts.
+        tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("timeout")
+            .expect("channel closed");
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        let mut kinds = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            kinds.push(event.kind);
+        }
+        assert_eq!(kinds, vec![crate::exchange::ws::WsEventKind::Connected]);
+        cancel.cancel();
+    }
+
+    #[tokio::test]
+    async fn mock_subscribe_orderbook() {
+        let server = mock_server::binance("BTCUSDT", 42000.0).start().await;
+        let exchange = BinanceExchange::new(&server.base_url(), &server.ws_url(""));
+        let (tx, mut rx) = mpsc::channel(10);
+        let cancel = CancellationToken::new();
+        let cancel_clone = cancel.clone();
+
+        tokio::spawn(async move {
+            exchange
+                .subscribe_orderbook(&["BTCUSDT".to_owned()], tx, cancel_clone)
+                .await
+                .unwrap();
+        });
+
+        let book = tokio::time::timeout(std::time::Duration::from_secs(10), rx.recv())
+            .await
+            .expect("timeout")
+            .expect("channel closed");
+
+        // Stale diff ignored; the next one removes the best bid and resizes the best ask.
+        let bids: Vec<(f64, f64)> = book.bids.iter().map(|l| (l.price, l.size)).collect();
+        let asks: Vec<(f64, f64)> = book.asks.iter().map(|l| (l.price, l.size)).collect();
+        assert_eq!(bids, vec![(42003.0, 2.0), (42002.0, 3.0)]);
+        assert_eq!(asks, vec![(42006.0, 5.0), (42007.0, 2.0)]);
+        cancel.cancel();
+    }
+
+    #[tokio::test]
+    async fn mock_futures_ticker_has_no_book_top() {
+        let server = mock_server::binance_futures("BTCUSDT", 42000.0)
+            .start()
+            .await;
+        let exchange = BinanceExchange::usd_m(&server.base_url(), &server.ws_url(""));
+        let (tx, mut rx) = mpsc::channel(10);
+        let cancel = CancellationToken::new();
+        let cancel_clone = cancel.clone();
+
+        tokio::spawn(async move {
+            exchange
+                .subscribe_ticker(&["BTCUSDT".to_owned()], tx, cancel_clone)
+                .await
+                .unwrap();
+        });
+
+        let ticker = tokio::time::timeout(std::time::Duration::from_secs(10), rx.recv())
+            .await
+            .expect("timeout")
+            .expect("channel closed");
+
+        assert_eq!(ticker.exchange, ExchangeKind::BinanceFutures);
+        assert_eq!(ticker.price, 42005.0);
+        assert_eq!(ticker.stats.best_bid, None);
+        assert_eq!(ticker.stats.best_ask, None);
+        cancel.cancel();
+    }
+
+    #[tokio::test]
+    async fn mock_subscribe_derivatives() {
+        let server = mock_server::binance_futures("BTCUSDT", 42000.0)
+            .start()
+            .await;
+        let exchange = BinanceExchange::usd_m(&server.base_url(), &server.ws_url(""));
+        let (tx, mut rx) = mpsc::channel(10);
+        let cancel = CancellationToken::new();
+        let cancel_clone = cancel.clone();
+
+        tokio::spawn(async move {
+            exchange
+                .subscribe_derivatives(&["BTCUSDT".to_owned()], tx, cancel_clone)
+                .await
+                .unwrap();
+        });
+
+        let mut samples = Vec::new();
+        while samples.len() < 3 {
+            let sample = tokio::time::timeout(std::time::Duration::from_secs(10), rx.recv())
+                .await
+                .expect("timeout")
+                .expect("channel closed");
+            assert_eq!(sample.exchange, ExchangeKind::BinanceFutures);
+            assert_eq!(sample.symbol, "BTCUSDT");
+            samples.push((sample.metric, sample.value));
+        }
         cancel.cancel();
+
+        samples.sort_by_key(|(metric, _)| metric.as_str());
+        assert_eq!(
+            samples,
+            vec![
+                (DerivativesMetric::FundingRate, 0.0001),
+                (DerivativesMetric::MarkPrice, 42001.0),
+                (DerivativesMetric::OpenInterest, 12345.6),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn spot_subscribe_derivatives_returns_immediately() {
+        let exchange = BinanceExchange::new("http://127.0.0.1:9", "ws://127.0.0.1:9");
+        let (tx, mut rx) = mpsc::channel(1);
+        exchange
+            .subscribe_derivatives(&["BTCUSDT".to_owned()], tx, CancellationToken::new())
+            .await
+            .unwrap();
+        assert!(rx.recv().await.is_none());
     }
 }
@@ -0,0 +1,518 @@
+//! Local HTTP + WebSocket stand-in for the exchange APIs.
+//!
+//! Serves canned REST responses by path and replays canned WebSocket frames
+//! to every connection whose request URI or first client message matches a
+//! route. Point `base_url` / `ws_url` at it to exercise exchanges offline.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+
+/// How long to wait for a client's subscribe frame before routing on the URI alone.
+const FIRST_MESSAGE_WAIT_MS: u64 = 200;
+const MAX_REQUEST_HEAD_BYTES: usize = 16 * 1024;
+
+#[derive(Default, Clone)]
+pub struct MockServerBuilder {
+    http_routes: Vec<(String, String)>,
+    ws_routes: Vec<(String, Vec<Message>)>,
+    rate_limits: Vec<RateLimitRoute>,
+    ping_interval: Option<Duration>,
+}
+
+/// Answers the first `times` requests to `path` with `status` and
+/// `Retry-After`.
+#[derive(Clone)]
+struct RateLimitRoute {
+    path: String,
+    times: usize,
+    status: &'static str,
+    retry_after_secs: u64,
+    served: Arc<AtomicUsize>,
+}
+
+impl MockServerBuilder {
+    /// Respond to `GET {path}` (query string ignored) with `body` as JSON.
+    pub fn http(mut self, path: &str, body: Value) -> Self {
+        self.http_routes.push((path.to_owned(), body.to_string()));
+        self
+    }
+
+    /// Reject the first `times` requests to `path` with `429 Too Many Requests`
+    /// and a `Retry-After: {retry_after_secs}` header.
+    pub fn rate_limit(mut self, path: &str, times: usize, retry_after_secs: u64) -> Self {
+        self.rate_limits.push(RateLimitRoute {
+            path: path.to_owned(),
+            times,
+            status: "429 Too Many Requests",
+            retry_after_secs,
+            served: Arc::new(AtomicUsize::new(0)),
+        });
+        self
+    }
+
+    /// Reject the first `times` requests to `path` with Binance's IP ban
+    /// status `418 I'm a teapot` and a `Retry-After: {retry_after_secs}`
+    /// header.
+    pub fn ban(mut self, path: &str, times: usize, retry_after_secs: u64) -> Self {
+        self.rate_limits.push(RateLimitRoute {
+            path: path.to_owned(),
+            times,
+            status: "418 I'm a teapot",
+            retry_after_secs,
+            served: Arc::new(AtomicUsize::new(0)),
+        });
+        self
+    }
+
+    /// Replay `frames` on every WebSocket whose URI or first client message
+    /// contains `pattern`.
+    pub fn ws(mut self, pattern: &str, frames: Vec<Message>) -> Self {
+        self.ws_routes.push((pattern.to_owned(), frames));
+        self
+    }
+
+    /// Send a ping on every WebSocket each `interval` after its frames.
+    pub fn ping_every(mut self, interval: Duration) -> Self {
+        self.ping_interval = Some(interval);
+        self
+    }
+
+    pub async fn start(self) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock server");
+        let addr = listener.local_addr().expect("mock server address");
+        let routes = Arc::new(self);
+
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let routes = Arc::clone(&routes);
+                tokio::spawn(async move {
+                    let _ = serve_connection(stream, &routes).await;
+                });
+            }
+        });
+
+        MockServer { addr, handle }
+    }
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub fn builder() -> MockServerBuilder {
+        MockServerBuilder::default()
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn ws_url(&self, path: &str) -> String {
+        format!("ws://{}{}", self.addr, path)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn serve_connection(
+    mut stream: TcpStream,
+    routes: &MockServerBuilder,
+) -> std::io::Result<()> {
+    // Peek so the WebSocket handshake can still read the request itself.
+    let head = peek_request_head(&stream).await?;
+
+    if head.to_ascii_lowercase().contains("upgrade: websocket") {
+        return serve_websocket(stream, routes).await;
+    }
+
+    let mut consumed = vec![0u8; head.len()];
+    stream.read_exact(&mut consumed).await?;
+
+    let path = head
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .split('?')
+        .next()
+        .unwrap_or("/");
+
+    let rate_limited = routes.rate_limits.iter().find(|route| {
+        route.path == path && route.served.fetch_add(1, Ordering::SeqCst) < route.times
+    });
+
+    let mut extra_headers = String::new();
+    let (status, body) = match (
+        rate_limited,
+        routes.http_routes.iter().find(|(p, _)| p == path),
+    ) {
+        (Some(route), _) => {
+            extra_headers = format!("Retry-After: {}\r\n", route.retry_after_secs);
+            (route.status, "{}")
+        }
+        (None, Some((_, body))) => ("200 OK", body.as_str()),
+        (None, None) => ("404 Not Found", "{}"),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{extra_headers}Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn peek_request_head(stream: &TcpStream) -> std::io::Result<String> {
+    let mut buf = vec![0u8; MAX_REQUEST_HEAD_BYTES];
+    loop {
+        let n = stream.peek(&mut buf).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        if let Some(end) = buf[..n].windows(4).position(|w| w == b"\r\n\r\n") {
+            return Ok(String::from_utf8_lossy(&buf[..end + 4]).into_owned());
+        }
+        if n == buf.len() {
+            return Err(std::io::ErrorKind::InvalidData.into());
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+}
+
+// The handshake callback's error type is fixed by tungstenite.
+#[allow(clippy::result_large_err)]
+async fn serve_websocket(stream: TcpStream, routes: &MockServerBuilder) -> std::io::Result<()> {
+    let mut uri = String::new();
+    let ws = tokio_tungstenite::accept_hdr_async(stream, |req: &Request, resp: Response| {
+        uri = req.uri().to_string();
+        Ok(resp)
+    })
+    .await
+    .map_err(std::io::Error::other)?;
+
+    let (mut write, mut read) = ws.split();
+
+    // Upbit subscribes with a first frame; Binance encodes streams in the URI.
+    let mut key = uri;
+    let first =
+        tokio::time::timeout(Duration::from_millis(FIRST_MESSAGE_WAIT_MS), read.next()).await;
+    match first {
+        Ok(Some(Ok(Message::Text(text)))) => key.push_str(text.as_str()),
+        Ok(Some(Ok(Message::Binary(data)))) => key.push_str(&String::from_utf8_lossy(&data)),
+        Ok(None) | Ok(Some(Err(_))) => return Ok(()),
+        _ => {}
+    }
+
+    for (pattern, frames) in &routes.ws_routes {
+        if !key.contains(pattern.as_str()) {
+            continue;
+        }
+        for frame in frames {
+            write
+                .send(frame.clone())
+                .await
+                .map_err(std::io::Error::other)?;
+        }
+    }
+
+    // Keep the socket open (answering pings) until the client goes away.
+    let mut heartbeat = routes.ping_interval.map(tokio::time::interval);
+    loop {
+        let ping = async {
+            match heartbeat.as_mut() {
+                Some(timer) => timer.tick().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            msg = read.next() => {
+                if !matches!(msg, Some(Ok(_))) {
+                    break;
+                }
+            }
+            _ = ping => {
+                write
+                    .send(Message::Ping(Vec::new().into()))
+                    .await
+                    .map_err(std::io::Error::other)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// ── Canned payloads ───────────────────────────────────────────────────────────
+
+/// Mock server answering Upbit's candle endpoint and ticker/trade/orderbook streams for
+/// `symbol`, with prices rising from `base_price`.
+pub fn upbit(symbol: &str, base_price: f64) -> MockServerBuilder {
+    let candles: Vec<Value> = (0..5)
+        .rev()
+        .map(|i| {
+            let price = base_price + i as f64;
+            json!({
+                "market": symbol,
+                "candle_date_time_utc": format!("2024-01-01T00:0{i}:00"),
+                "candle_date_time_kst": format!("2024-01-01T09:0{i}:00"),
+                "opening_price": price,
+                "high_price": price,
+                "low_price": price,
+                "trade_price": price,
+                "timestamp": 1_704_067_200_000_i64 + i * 60_000,
+                "candle_acc_trade_price": price * 10.0,
+                "candle_acc_trade_volume": 10.0,
+                "unit": 1
+            })
+        })
+        .collect();
+
+    let ticker = json!({
+        "type": "ticker",
+        "code": symbol,
+        "trade_price": base_price + 5.0,
+        "opening_price": base_price,
+        "high_price": base_price + 10.0,
+        "low_price": base_price - 10.0,
+        "signed_change_rate": 0.05,
+        "acc_trade_price_24h": 123_450.0,
+        "acc_trade_volume_24h": 1234.5,
+        "timestamp": 1_704_067_500_000_i64
+    });
+    let trade = json!({
+        "type": "trade",
+        "code": symbol,
+        "trade_price": base_price + 5.0,
+        "trade_volume": 0.5,
+        "ask_bid": "BID",
+        "timestamp": 1_704_067_500_000_i64,
+        "sequential_id": 1_704_067_500_000_000_i64
+    });
+
+    let orderbook = json!({
+        "type": "orderbook",
+        "code": symbol,
+        "timestamp": 1_704_067_500_000_i64,
+        "orderbook_units": [
+            { "ask_price": base_price + 6.0, "bid_price": base_price + 4.0, "ask_size": 1.0, "bid_size": 2.0 },
+            { "ask_price": base_price + 7.0, "bid_price": base_price + 3.0, "ask_size": 3.0, "bid_size": 4.0 }
+        ]
+    });
+
+    let base = symbol.split_once('-').map_or(symbol, |(_, base)| base);
+    let markets = json!([{
+        "market": symbol,
+        "korean_name": base,
+        "english_name": base,
+        "market_event": { "warning": false, "caution": {} }
+    }]);
+
+    MockServer::builder()
+        .http("/v1/market/all", markets)
+        .http("/v1/candles/minutes/1", Value::Array(candles))
+        .ws(
+            "\"type\":\"ticker\"",
+            vec![Message::Binary(ticker.to_string().into_bytes().into())],
+        )
+        .ws(
+            "\"type\":\"trade\"",
+            vec![Message::Binary(trade.to_string().into_bytes().into())],
+        )
+        .ws(
+            "\"type\":\"orderbook\"",
+            vec![Message::Binary(orderbook.to_string().into_bytes().into())],
+        )
+}
+
+/// Mock server answering Binance's kline/depth endpoints and ticker/trade/depth streams for
+/// `symbol`, with prices rising from `base_price`.
+pub fn binance(symbol: &str, base_price: f64) -> MockServerBuilder {
+    let klines = binance_klines(base_price);
+
+    let stream = symbol.to_lowercase();
+    let ticker = json!({
+        "stream": format!("{stream}@ticker"),
+        "data": {
+            "e": "24hrTicker",
+            "s": symbol,
+            "c": (base_price + 5.0).to_string(),
+            "o": base_price.to_string(),
+            "h": (base_price + 10.0).to_string(),
+            "l": (base_price - 10.0).to_string(),
+            "P": "5.000",
+            "v": "1234.5",
+            "q": "51850000.0",
+            "b": (base_price + 4.0).to_string(),
+            "a": (base_price + 6.0).to_string(),
+            "C": 1_704_067_500_000_i64
+        }
+    });
+    let trade = json!({
+        "stream": format!("{stream}@trade"),
+        "data": {
+            "e": "trade",
+            "s": symbol,
+            "t": 1,
+            "p": (base_price + 5.0).to_string(),
+            "q": "0.5",
+            "m": false,
+            "T": 1_704_067_500_000_i64
+        }
+    });
+
+    // Snapshot at update id 100; the first diff is already covered by it.
+    let price = |offset: f64| (base_price + offset).to_string();
+    let depth = json!({
+        "lastUpdateId": 100,
+        "bids": [[price(4.0), "1.0"], [price(3.0), "2.0"]],
+        "asks": [[price(6.0), "1.0"], [price(7.0), "2.0"]]
+    });
+    let depth_update = |first: u64, last: u64, bids: Value, asks: Value| {
+        let event = json!({
+            "stream": format!("{stream}@depth@100ms"),
+            "data": {
+                "e": "depthUpdate",
+                "E": 1_704_067_500_000_i64,
+                "s": symbol,
+                "U": first,
+                "u": last,
+                "b": bids,
+                "a": asks
+            }
+        });
+        Message::Text(event.to_string().into())
+    };
+    let depth_frames = vec![
+        depth_update(95, 100, json!([[price(4.0), "9.0"]]), json!([])),
+        depth_update(
+            99,
+            102,
+            json!([[price(4.0), "0"], [price(2.0), "3.0"]]),
+            json!([[price(6.0), "5.0"]]),
+        ),
+    ];
+
+    MockServer::builder()
+        .http("/api/v3/exchangeInfo", binance_exchange_info(symbol))
+        .http("/api/v3/klines", klines)
+        .http("/api/v3/depth", depth)
+        .ws("@ticker", vec![Message::Text(ticker.to_string().into())])
+        .ws("@trade", vec![Message::Text(trade.to_string().into())])
+        .ws("@depth", depth_frames)
+}
+
+/// Binance USDⓈ-M futures mock for `symbol`: klines and exchange info as on
+/// spot, a ticker without best bid/ask, one aggregated trade, one mark price
+/// update with a 0.01% funding rate, and open interest of 12345.6.
+pub fn binance_futures(symbol: &str, base_price: f64) -> MockServerBuilder {
+    let stream = symbol.to_lowercase();
+    let ticker = json!({
+        "stream": format!("{stream}@ticker"),
+        "data": {
+            "e": "24hrTicker",
+            "s": symbol,
+            "c": (base_price + 5.0).to_string(),
+            "o": base_price.to_string(),
+            "h": (base_price + 10.0).to_string(),
+            "l": (base_price - 10.0).to_string(),
+            "P": "5.000",
+            "v": "1234.5",
+            "q": "51850000.0",
+            "C": 1_704_067_500_000_i64
+        }
+    });
+    let trade = json!({
+        "stream": format!("{stream}@aggTrade"),
+        "data": {
+            "e": "aggTrade",
+            "s": symbol,
+            "a": 1,
+            "p": (base_price + 5.0).to_string(),
+            "q": "0.5",
+            "m": true,
+            "T": 1_704_067_500_000_i64
+        }
+    });
+    let mark_price = json!({
+        "stream": format!("{stream}@markPrice@1s"),
+        "data": {
+            "e": "markPriceUpdate",
+            "E": 1_704_067_500_000_i64,
+            "s": symbol,
+            "p": (base_price + 1.0).to_string(),
+            "i": base_price.to_string(),
+            "P": base_price.to_string(),
+            "r": "0.00010000",
+            "T": 1_704_096_000_000_i64
+        }
+    });
+    let open_interest = json!({
+        "openInterest": "12345.6",
+        "symbol": symbol,
+        "time": 1_704_067_500_000_i64
+    });
+
+    MockServer::builder()
+        .http("/fapi/v1/exchangeInfo", binance_exchange_info(symbol))
+        .http("/fapi/v1/klines", binance_klines(base_price))
+        .http("/fapi/v1/openInterest", open_interest)
+        .ws("@ticker", vec![Message::Text(ticker.to_string().into())])
+        .ws("@aggTrade", vec![Message::Text(trade.to_string().into())])
+        .ws(
+            "@markPrice",
+            vec![Message::Text(mark_price.to_string().into())],
+        )
+}
+
+fn binance_exchange_info(symbol: &str) -> Value {
+    json!({
+        "symbols": [{
+            "symbol": symbol,
+            "status": "TRADING",
+            "baseAsset": symbol.trim_end_matches("USDT"),
+            "quoteAsset": "USDT",
+            "filters": []
+        }]
+    })
+}
+
+/// Five 1m klines from 2024-01-01 00:00 UTC closing at `base_price + i`.
+fn binance_klines(base_price: f64) -> Value {
+    let klines: Vec<Value> = (0..5)
+        .map(|i| {
+            let open_time = 1_704_067_200_000_i64 + i * 60_000;
+            let price = (base_price + i as f64).to_string();
+            json!([
+                open_time,
+                price,
+                price,
+                price,
+                price,
+                "10.0",
+                open_time + 59_999,
+                "0",
+                10,
+                "0",
+                "0",
+                "0"
+            ])
+        })
+        .collect();
+    Value::Array(klines)
+}
@@ -0,0 +1,212 @@
+//! Every exchange the app knows, with what differs between them.
+//!
+//! Adding a venue means adding its `ExchangeKind` variant and one entry in
+//! `EXCHANGES`: names, parsing, fees, symbol codecs and construction from
+//! config are all looked up here.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{ExchangeConfig, SyntheticConfig};
+use crate::error::UnknownExchange;
+use crate::exchange::Exchange;
+use crate::exchange::binance::{self, BinanceExchange};
+use crate::exchange::synthetic::{self, PriceModel, SyntheticExchange, SyntheticSettings};
+use crate::exchange::upbit::{self, UpbitExchange};
+use crate::exchange::ws::WsOptions;
+use crate::model::{ExchangeKind, Instrument};
+
+/// What the rest of the app needs to know about one exchange.
+pub struct ExchangeSpec {
+    pub kind: ExchangeKind,
+    /// Name used in config files, storage and logs
+    pub name: &'static str,
+    /// Taker fee in basis points, unless overridden in the backtest config
+    pub default_fee_bps: f64,
+    /// Talks to a remote API, so `base_url` and `ws_url` are required
+    pub connects: bool,
+    /// Trade ids increase by exactly one per trade of a symbol
+    pub contiguous_trade_ids: bool,
+    /// UTC offset, in hours, of the time zone whose midnight opens a daily
+    /// candle
+    pub day_start_utc_offset_hours: i32,
+    /// Tickers carry the best bid and ask, which `spread_pct` alerts need
+    pub ticker_best_bid_ask: bool,
+    pub decode_symbol: fn(&str) -> Option<Instrument>,
+    pub encode_symbol: fn(&Instrument) -> String,
+    /// Build the live client from its `[[exchanges]]` entry
+    pub build: fn(&ExchangeConfig) -> Arc<dyn Exchange>,
+}
+
+pub static EXCHANGES: &[ExchangeSpec] = &[
+    ExchangeSpec {
+        kind: ExchangeKind::Upbit,
+        name: "upbit",
+        default_fee_bps: 5.0,
+        connects: true,
+        // `sequential_id` is unique and increasing but skips values
+        contiguous_trade_ids: false,
+        // KST
+        day_start_utc_offset_hours: 9,
+        ticker_best_bid_ask: false,
+        decode_symbol: upbit::decode_symbol,
+        encode_symbol: upbit::encode_symbol,
+        build: build_upbit,
+    },
+    ExchangeSpec {
+        kind: ExchangeKind::Binance,
+        name: "binance",
+        default_fee_bps: 10.0,
+        connects: true,
+        contiguous_trade_ids: true,
+        day_start_utc_offset_hours: 0,
+        ticker_best_bid_ask: true,
+        decode_symbol: binance::decode_symbol,
+        encode_symbol: binance::encode_symbol,
+        build: build_binance,
+    },
+    ExchangeSpec {
+        kind: ExchangeKind::BinanceFutures,
+        name: "binance_futures",
+        // USDⓈ-M taker fee
+        default_fee_bps: 5.0,
+        connects: true,
+        // Aggregate trade ids are sequential too
+        contiguous_trade_ids: true,
+        day_start_utc_offset_hours: 0,
+        // The futures ticker stream has no book fields
+        ticker_best_bid_ask: false,
+        decode_symbol: binance::decode_symbol,
+        encode_symbol: binance::encode_symbol,
+        build: build_binance_futures,
+    },
+    ExchangeSpec {
+        kind: ExchangeKind::Synthetic,
+        name: "synthetic",
+        default_fee_bps: 0.0,
+        connects: false,
+        // Ticks skipped under load leave holes that are not missed trades
+        contiguous_trade_ids: false,
+        day_start_utc_offset_hours: 0,
+        ticker_best_bid_ask: true,
+        decode_symbol: synthetic::decode_symbol,
+        encode_symbol: synthetic::encode_symbol,
+        build: build_synthetic,
+    },
+];
+
+/// The registry entry of `kind`.
+pub fn spec(kind: ExchangeKind) -> &'static ExchangeSpec {
+    EXCHANGES
+        .iter()
+        .find(|spec| spec.kind == kind)
+        .expect("every ExchangeKind is registered")
+}
+
+/// The registry entry named `name`.
+pub fn lookup(name: &str) -> Result<&'static ExchangeSpec, UnknownExchange> {
+    EXCHANGES
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| UnknownExchange {
+            name: name.to_owned(),
+        })
+}
+
+/// Registered names, for error messages.
+pub fn names() -> Vec<&'static str> {
+    EXCHANGES.iter().map(|spec| spec.name).collect()
+}
+
+impl FromStr for ExchangeKind {
+    type Err = UnknownExchange;
+
+    /// Parse a config-format exchange name (e.g. `"upbit"`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lookup(s).map(|spec| spec.kind)
+    }
+}
+
+impl fmt::Display for ExchangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(spec(*self).name)
+    }
+}
+
+fn build_upbit(config: &ExchangeConfig) -> Arc<dyn Exchange> {
+    Arc::new(
+        UpbitExchange::new(&config.base_url, &config.ws_url).with_ws_options(ws_options(config)),
+    )
+}
+
+fn build_binance(config: &ExchangeConfig) -> Arc<dyn Exchange> {
+    Arc::new(
+        BinanceExchange::new(&config.base_url, &config.ws_url).with_ws_options(ws_options(config)),
+    )
+}
+
+fn build_binance_futures(config: &ExchangeConfig) -> Arc<dyn Exchange> {
+    Arc::new(
+        BinanceExchange::usd_m(&config.base_url, &config.ws_url)
+            .with_ws_options(ws_options(config))
+            .with_open_interest_interval(Duration::from_secs(config.open_interest_poll_secs)),
+    )
+}
+
+fn build_synthetic(config: &ExchangeConfig) -> Arc<dyn Exchange> {
+    Arc::new(SyntheticExchange::new(synthetic_settings(
+        &config.synthetic,
+    )))
+}
+
+fn ws_options(config: &ExchangeConfig) -> WsOptions {
+    WsOptions {
+        idle_timeout: Duration::from_secs(config.ws_idle_timeout_secs),
+        healthy_after: Duration::from_secs(config.ws_healthy_secs),
+    }
+}
+
+fn synthetic_settings(synthetic: &SyntheticConfig) -> SyntheticSettings {
+    SyntheticSettings {
+        seed: synthetic.seed,
+        // Validated in config
+        model: PriceModel::from_str(&synthetic.model).unwrap_or(PriceModel::Gbm),
+        initial_price: synthetic.initial_price,
+        volatility: synthetic.volatility,
+        drift: synthetic.drift,
+        tick_interval: Duration::from_millis(synthetic.tick_interval_ms),
+        trades_per_tick: synthetic.trades_per_tick,
+        markets: synthetic.markets,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_kinds_are_registered_once() {
+        for (i, spec) in EXCHANGES.iter().enumerate() {
+            assert!(EXCHANGES[..i].iter().all(|other| other.kind != spec.kind));
+            assert_eq!(spec.kind.to_string(), spec.name);
+            assert_eq!(spec.name.parse::<ExchangeKind>().unwrap(), spec.kind);
+        }
+    }
+
+    #[test]
+    fn exchange_kind_display_and_parse() {
+        assert_eq!(ExchangeKind::Upbit.to_string(), "upbit");
+        assert_eq!(ExchangeKind::Binance.to_string(), "binance");
+        assert_eq!(ExchangeKind::BinanceFutures.to_string(), "binance_futures");
+        assert_eq!(
+            "binance_futures".parse::<ExchangeKind>().unwrap(),
+            ExchangeKind::BinanceFutures
+        );
+
+        let unknown = "bithumb".parse::<ExchangeKind>().unwrap_err();
+        assert_eq!(unknown.name, "bithumb");
+        assert_eq!(unknown.to_string(), "unknown exchange \"bithumb\"");
+    }
+}
@@ -0,0 +1,429 @@
+//! Exchange that plays back a market data recording (see `recorder`).
+//!
+//! Tickers and trades are delivered from one timeline in recorded order,
+//! paced by their receive times at a configurable speed, so the live
+//! pipeline can be run deterministically against a captured session.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use error_stack::Report;
+use futures::future::BoxFuture;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{Instant, sleep_until};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::error::{ExchangeError, RecordingError};
+use crate::exchange::instrument_for;
+use crate::exchange::ws::WsEvent;
+use crate::exchange::{Exchange, MarketStreams, StreamSink, StreamSymbols, SubscriptionHandle};
+use crate::model::{
+    Candle, CandleUpdate, ExchangeKind, Market, OrderBook, Ticker, TimeFrame, Trade,
+};
+use crate::recorder::{MarketEvent, RecordedEvent, read_recording};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Recorded time runs this many times faster than wall time
+    Factor(f64),
+    /// No pacing; events are delivered as fast as they are consumed
+    Max,
+}
+
+impl ReplaySpeed {
+    /// Parse `"max"` or a multiplier such as `"1x"` or `"10x"`.
+    pub fn parse(s: &str) -> Option<Self> {
+        if s == "max" {
+            return Some(Self::Max);
+        }
+        let factor: f64 = s.strip_suffix('x')?.parse().ok()?;
+        (factor.is_finite() && factor > 0.0).then_some(Self::Factor(factor))
+    }
+
+    /// Wall time after replay start at which an event recorded `elapsed`
+    /// after the first one is due; `None` when it is due immediately.
+    fn delay(&self, elapsed: chrono::Duration) -> Option<Duration> {
+        match self {
+            Self::Factor(factor) => Some(elapsed.to_std().ok()?.div_f64(*factor)),
+            Self::Max => None,
+        }
+    }
+}
+
+pub struct ReplayExchange {
+    kind: ExchangeKind,
+    /// This exchange's recorded events, oldest first
+    events: Arc<[RecordedEvent]>,
+    speed: ReplaySpeed,
+    /// Never sent on; a replay has no connection to supervise
+    ws_events: broadcast::Sender<WsEvent>,
+}
+
+impl ReplayExchange {
+    /// Replay the events of `kind` out of `events`.
+    pub fn new(kind: ExchangeKind, events: Vec<RecordedEvent>, speed: ReplaySpeed) -> Self {
+        let (ws_events, _) = broadcast::channel(1);
+        Self {
+            kind,
+            events: events
+                .into_iter()
+                .filter(|e| e.event.exchange() == kind)
+                .collect(),
+            speed,
+            ws_events,
+        }
+    }
+
+    /// Replay the events of `kind` from the recording at `path`.
+    pub fn open(
+        kind: ExchangeKind,
+        path: &Path,
+        speed: ReplaySpeed,
+    ) -> Result<Self, Report<RecordingError>> {
+        Ok(Self::new(kind, read_recording(path)?, speed))
+    }
+
+    /// Send the recorded tickers and trades of the symbols in `handle` into
+    /// their `streams`, one event at a time in recorded order.
+    ///
+    /// Pacing is measured from the exchange's first recorded event. Symbol
+    /// changes apply from the next event on, without restarting playback.
+    async fn play(
+        &self,
+        streams: MarketStreams,
+        handle: SubscriptionHandle,
+        cancel: CancellationToken,
+    ) -> Result<(), Report<ExchangeError>> {
+        let Some(origin) = self.events.first().map(|e| e.received_at) else {
+            return Ok(());
+        };
+        let symbols = handle.subscribe();
+        let stream = streams.name();
+        let started = Instant::now();
+        info!(exchange = %self.kind, stream, symbols = ?symbols.borrow().all(), speed = ?self.speed, "replay started");
+
+        let mut sent = 0;
+        for recorded in self.events.iter() {
+            if !is_streamed(&symbols.borrow(), &recorded.event) {
+                continue;
+            }
+
+            if let Some(delay) = self.speed.delay(recorded.received_at - origin) {
+                tokio::select! {
+                    _ = cancel.cancelled() => return Ok(()),
+                    _ = sleep_until(started + delay) => {}
+                }
+            } else if cancel.is_cancelled() {
+                return Ok(());
+            }
+
+            let delivered = match (&recorded.event, &streams.ticker, &streams.trades) {
+                (MarketEvent::Ticker(ticker), Some(sink), _) => {
+                    sink.tx.send(ticker.clone()).await.is_ok()
+                }
+                (MarketEvent::Trade(trade), _, Some(sink)) => {
+                    sink.tx.send(trade.clone()).await.is_ok()
+                }
+                _ => true,
+            };
+            if !delivered {
+                break;
+            }
+            sent += 1;
+        }
+
+        info!(exchange = %self.kind, stream, sent, "replay finished");
+        Ok(())
+    }
+}
+
+/// Whether `event` belongs to a stream of the subscription and one of its
+/// current symbols.
+fn is_streamed(symbols: &StreamSymbols, event: &MarketEvent) -> bool {
+    let list = match event {
+        MarketEvent::Ticker(_) => &symbols.ticker,
+        MarketEvent::Trade(_) => &symbols.trades,
+    };
+    list.as_ref()
+        .is_some_and(|list| list.iter().any(|s| s == event.symbol()))
+}
+
+impl Exchange for ReplayExchange {
+    fn kind(&self) -> ExchangeKind {
+        self.kind
+    }
+
+    /// One market per recorded symbol.
+    fn list_markets(&self) -> BoxFuture<'_, Result<Vec<Market>, Report<ExchangeError>>> {
+        Box::pin(async move {
+            let mut markets: Vec<Market> = Vec::new();
+            for recorded in self.events.iter() {
+                let symbol = recorded.event.symbol();
+                if markets.iter().any(|m| m.symbol == symbol) {
+                    continue;
+                }
+                let Some(instrument) = instrument_for(self.kind, symbol) else {
+                    warn!(exchange = %self.kind, symbol, "skipping recorded symbol that does not decode");
+                    continue;
+                };
+                markets.push(Market {
+                    exchange: self.kind,
+                    symbol: symbol.to_owned(),
+                    instrument,
+                    tick_size: None,
+                    lot_step: None,
+                    min_notional: None,
+                    trading: true,
+                    warning: false,
+                    cautions: Vec::new(),
+                });
+            }
+            Ok(markets)
+        })
+    }
+
+    /// Recordings hold no candles; history comes from what is already stored.
+    fn fetch_candles(
+        &self,
+        _symbol: &str,
+        _timeframe: TimeFrame,
+        _limit: usize,
+    ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn fetch_candles_range(
+        &self,
+        _symbol: &str,
+        _timeframe: TimeFrame,
+        _start: DateTime<Utc>,
+        _end: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn subscribe_ticker(
+        &self,
+        symbols: &[String],
+        tx: mpsc::Sender<Ticker>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let streams = MarketStreams {
+            ticker: Some(StreamSink {
+                symbols: symbols.to_vec(),
+                tx,
+            }),
+            ..Default::default()
+        };
+        let handle = SubscriptionHandle::new(&streams);
+        self.subscribe_streams(streams, handle, cancel)
+    }
+
+    fn subscribe_trades(
+        &self,
+        symbols: &[String],
+        tx: mpsc::Sender<Trade>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let streams = MarketStreams {
+            trades: Some(StreamSink {
+                symbols: symbols.to_vec(),
+                tx,
+            }),
+            ..Default::default()
+        };
+        let handle = SubscriptionHandle::new(&streams);
+        self.subscribe_streams(streams, handle, cancel)
+    }
+
+    /// Recordings hold no candles; returns immediately.
+    fn subscribe_candles(
+        &self,
+        _symbols: &[String],
+        _timeframes: &[TimeFrame],
+        _tx: mpsc::Sender<CandleUpdate>,
+        _cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Recordings hold no order books; returns immediately.
+    fn subscribe_orderbook(
+        &self,
+        _symbols: &[String],
+        _tx: mpsc::Sender<OrderBook>,
+        _cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Plays tickers and trades from one timeline, so they reach the
+    /// pipeline in the order they were recorded. Order books are not
+    /// recorded.
+    fn subscribe_streams(
+        &self,
+        streams: MarketStreams,
+        handle: SubscriptionHandle,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        Box::pin(self.play(streams, handle, cancel))
+    }
+
+    fn stream_events(&self) -> broadcast::Receiver<WsEvent> {
+        self.ws_events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Instrument, TickerStats, TradeSide};
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_704_067_200 + secs, 0).unwrap()
+    }
+
+    fn recording() -> Vec<RecordedEvent> {
+        let ticker = |exchange, symbol: &str, secs, price| RecordedEvent {
+            received_at: at(secs),
+            event: MarketEvent::Ticker(Ticker {
+                exchange,
+                symbol: symbol.into(),
+                instrument: instrument_for(exchange, symbol).unwrap(),
+                price,
+                volume: 0.0,
+                stats: TickerStats::default(),
+                timestamp: at(secs),
+                received_at: at(secs),
+            }),
+        };
+        vec![
+            ticker(ExchangeKind::Upbit, "KRW-SOL", 0, 100.0),
+            ticker(ExchangeKind::Binance, "SOLUSDT", 1, 20.0),
+            RecordedEvent {
+                received_at: at(2),
+                event: MarketEvent::Trade(Trade {
+                    exchange: ExchangeKind::Upbit,
+                    symbol: "KRW-SOL".into(),
+                    instrument: Instrument::new("SOL", "KRW"),
+                    price: 101.0,
+                    volume: 1.0,
+                    side: TradeSide::Buy,
+                    timestamp: at(2),
+                    received_at: at(2),
+                    trade_id: None,
+                }),
+            },
+            ticker(ExchangeKind::Upbit, "KRW-BTC", 3, 9_000.0),
+            ticker(ExchangeKind::Upbit, "KRW-SOL", 10, 102.0),
+        ]
+    }
+
+    #[test]
+    fn replay_speed_parses_multipliers_and_max() {
+        assert_eq!(ReplaySpeed::parse("1x"), Some(ReplaySpeed::Factor(1.0)));
+        assert_eq!(ReplaySpeed::parse("2.5x"), Some(ReplaySpeed::Factor(2.5)));
+        assert_eq!(ReplaySpeed::parse("max"), Some(ReplaySpeed::Max));
+        assert_eq!(ReplaySpeed::parse("0x"), None);
+        assert_eq!(ReplaySpeed::parse("10"), None);
+    }
+
+    #[tokio::test]
+    async fn replay_filters_by_exchange_symbol_and_stream() {
+        let exchange = ReplayExchange::new(ExchangeKind::Upbit, recording(), ReplaySpeed::Max);
+        let markets = exchange.list_markets().await.unwrap();
+        let symbols: Vec<&str> = markets.iter().map(|m| m.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["KRW-SOL", "KRW-BTC"]);
+
+        let (tx, mut rx) = mpsc::channel(10);
+        exchange
+            .subscribe_ticker(&["KRW-SOL".to_owned()], tx, CancellationToken::new())
+            .await
+            .unwrap();
+
+        let mut prices = Vec::new();
+        while let Some(ticker) = rx.recv().await {
+            prices.push(ticker.price);
+        }
+        assert_eq!(prices, vec![100.0, 102.0]);
+    }
+
+    #[tokio::test]
+    async fn tickers_and_trades_share_one_timeline() {
+        let mut events = recording();
+        let mut second_trade = events[2].clone();
+        second_trade.received_at = at(4);
+        events.insert(4, second_trade);
+        let exchange = Arc::new(ReplayExchange::new(
+            ExchangeKind::Upbit,
+            events,
+            ReplaySpeed::Max,
+        ));
+
+        let (ticker_tx, mut ticker_rx) = mpsc::channel(10);
+        // Room for one trade only
+        let (trade_tx, mut trade_rx) = mpsc::channel(1);
+        let streams = MarketStreams {
+            ticker: Some(StreamSink {
+                symbols: vec!["KRW-SOL".to_owned()],
+                tx: ticker_tx,
+            }),
+            trades: Some(StreamSink {
+                symbols: vec!["KRW-SOL".to_owned()],
+                tx: trade_tx,
+            }),
+            orderbook: None,
+        };
+        let handle = SubscriptionHandle::new(&streams);
+        let playback = tokio::spawn({
+            let exchange = Arc::clone(&exchange);
+            async move {
+                exchange
+                    .subscribe_streams(streams, handle, CancellationToken::new())
+                    .await
+            }
+        });
+
+        assert_eq!(ticker_rx.recv().await.unwrap().price, 100.0);
+        // The later ticker waits behind the trades recorded before it
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(ticker_rx.try_recv().is_err());
+
+        for _ in 0..2 {
+            assert_eq!(trade_rx.recv().await.unwrap().price, 101.0);
+        }
+        assert_eq!(ticker_rx.recv().await.unwrap().price, 102.0);
+        playback.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replay_paces_events_by_speed_factor() {
+        let exchange = Arc::new(ReplayExchange::new(
+            ExchangeKind::Upbit,
+            recording(),
+            ReplaySpeed::Factor(2.0),
+        ));
+        let (tx, mut rx) = mpsc::channel(10);
+        let started = Instant::now();
+
+        tokio::spawn({
+            let exchange = Arc::clone(&exchange);
+            async move {
+                exchange
+                    .subscribe_ticker(&["KRW-SOL".to_owned()], tx, CancellationToken::new())
+                    .await
+                    .unwrap();
+            }
+        });
+
+        rx.recv().await.unwrap();
+        assert_eq!(started.elapsed(), Duration::ZERO);
+        let last = rx.recv().await.unwrap();
+        assert_eq!(last.price, 102.0);
+        // Recorded 10s apart, replayed at 2x
+        assert_eq!(started.elapsed(), Duration::from_secs(5));
+    }
+}
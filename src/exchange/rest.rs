@@ -0,0 +1,298 @@
+//! Rate-limit aware REST client shared by the exchange implementations.
+//!
+//! Requests are spaced by a fixed local quota, and each response's usage
+//! headers can pause further requests before the exchange's own budget runs
+//! out. Rate-limited responses (429) are retried after the server's
+//! `Retry-After` delay, or with exponential backoff when it is absent. A ban
+//! (418) is returned at once and holds further requests for its
+//! `Retry-After`, since retrying only extends it.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use error_stack::{Report, ResultExt};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{IntoUrl, RequestBuilder, Response, StatusCode};
+use tokio::time::{Instant, sleep_until};
+use tracing::warn;
+
+use crate::error::ExchangeError;
+use crate::model::ExchangeKind;
+
+/// Retries after a rate-limited response before giving up with `RateLimit`.
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+/// First backoff delay when the server sends no `Retry-After`; doubles per retry.
+const INITIAL_BACKOFF_SECS: u64 = 1;
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// Inspects a response's headers and returns how long to hold further
+/// requests, if the exchange reports its budget is (nearly) used up.
+pub type UsagePolicy = fn(&HeaderMap) -> Option<Duration>;
+
+pub struct RestClient {
+    client: reqwest::Client,
+    exchange: ExchangeKind,
+    rate_limiter: DefaultDirectRateLimiter,
+    usage_policy: UsagePolicy,
+    /// Requests wait until this instant, set by usage headers and `Retry-After`
+    paused_until: Mutex<Option<Instant>>,
+}
+
+impl RestClient {
+    pub fn new(exchange: ExchangeKind, quota: Quota, usage_policy: UsagePolicy) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            exchange,
+            rate_limiter: RateLimiter::direct(quota),
+            usage_policy,
+            paused_until: Mutex::new(None),
+        }
+    }
+
+    /// Start building a GET request; send it with [`RestClient::send`].
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// Send `request` once the local quota and any server-requested pause
+    /// allow it, retrying rate-limited responses.
+    ///
+    /// Returns `RateLimit` when the exchange still rejects the request after
+    /// all retries, and `Request` for any other non-success status.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, Report<ExchangeError>> {
+        let mut backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
+
+        for attempt in 0..=MAX_RATE_LIMIT_RETRIES {
+            self.wait_for_pause().await;
+            self.rate_limiter.until_ready().await;
+
+            // Only requests with a streaming body cannot be cloned for retries
+            let Some(attempt_request) = request.try_clone() else {
+                return Err(Report::new(self.request_error())
+                    .attach("request body cannot be cloned for retries"));
+            };
+            let response = attempt_request
+                .send()
+                .await
+                .change_context(self.request_error())?;
+
+            if let Some(pause) = (self.usage_policy)(response.headers()) {
+                self.pause_for(pause);
+            }
+
+            let status = response.status();
+            if is_banned(status) {
+                if let Some(delay) = retry_after(response.headers()) {
+                    self.pause_for(delay);
+                }
+                return Err(Report::new(ExchangeError::RateLimit {
+                    exchange: self.exchange.to_string(),
+                })
+                .attach(format!("HTTP status: {status}"))
+                .attach("IP banned by the exchange, not retrying"));
+            }
+            if is_rate_limited(status) {
+                let delay = retry_after(response.headers()).unwrap_or(backoff);
+                if attempt == MAX_RATE_LIMIT_RETRIES {
+                    return Err(Report::new(ExchangeError::RateLimit {
+                        exchange: self.exchange.to_string(),
+                    })
+                    .attach(format!("HTTP status: {status}"))
+                    .attach(format!("gave up after {MAX_RATE_LIMIT_RETRIES} retries")));
+                }
+
+                warn!(
+                    exchange = %self.exchange,
+                    %status,
+                    delay_ms = delay.as_millis() as u64,
+                    attempt = attempt + 1,
+                    "rate limited, retrying"
+                );
+                self.pause_for(delay);
+                backoff = (backoff * 2).min(Duration::from_secs(MAX_BACKOFF_SECS));
+                continue;
+            }
+
+            if !status.is_success() {
+                return Err(
+                    Report::new(self.request_error()).attach(format!("HTTP status: {status}"))
+                );
+            }
+
+            return Ok(response);
+        }
+
+        unreachable!("the final attempt always returns")
+    }
+
+    fn request_error(&self) -> ExchangeError {
+        ExchangeError::Request {
+            exchange: self.exchange.to_string(),
+        }
+    }
+
+    /// Hold all requests for at least `duration` from now.
+    fn pause_for(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut paused_until = self.paused_until.lock().unwrap();
+        if paused_until.is_none_or(|current| current < until) {
+            *paused_until = Some(until);
+        }
+    }
+
+    async fn wait_for_pause(&self) {
+        let until = *self.paused_until.lock().unwrap();
+        if let Some(until) = until
+            && until > Instant::now()
+        {
+            sleep_until(until).await;
+        }
+    }
+}
+
+fn is_rate_limited(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Binance answers 418 once an IP is banned for ignoring 429s.
+fn is_banned(status: StatusCode) -> bool {
+    status == StatusCode::IM_A_TEAPOT
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date; a date
+/// already past means no delay.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::exchange::mock_server::MockServer;
+
+    fn client() -> RestClient {
+        RestClient::new(
+            ExchangeKind::Binance,
+            Quota::per_second(NonZeroU32::new(100).unwrap()),
+            |_| None,
+        )
+    }
+
+    #[test]
+    fn retry_after_parses_seconds_and_http_dates() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        let at = Utc::now() + chrono::Duration::seconds(30);
+        headers.insert(
+            RETRY_AFTER,
+            at.format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string()
+                .parse()
+                .unwrap(),
+        );
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30));
+
+        headers.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_responses_are_retried() {
+        let server = MockServer::builder()
+            .http("/ping", json!({ "ok": true }))
+            .rate_limit("/ping", 2, 0)
+            .start()
+            .await;
+
+        let client = client();
+        let response = client
+            .send(client.get(format!("{}/ping", server.base_url())))
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+    }
+
+    #[tokio::test]
+    async fn persistent_rate_limit_returns_rate_limit_error() {
+        let server = MockServer::builder()
+            .http("/ping", json!({ "ok": true }))
+            .rate_limit("/ping", MAX_RATE_LIMIT_RETRIES as usize + 1, 0)
+            .start()
+            .await;
+
+        let client = client();
+        let err = client
+            .send(client.get(format!("{}/ping", server.base_url())))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.current_context(),
+            ExchangeError::RateLimit { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn ban_is_returned_without_retrying() {
+        let server = MockServer::builder()
+            .http("/ping", json!({ "ok": true }))
+            .ban("/ping", 1, 0)
+            .start()
+            .await;
+
+        let client = client();
+        let url = format!("{}/ping", server.base_url());
+        let err = client.send(client.get(&url)).await.unwrap_err();
+        assert!(matches!(
+            err.current_context(),
+            ExchangeError::RateLimit { .. }
+        ));
+        // Only the banned request was answered with 418
+        assert!(client.send(client.get(&url)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn usage_policy_pauses_following_requests() {
+        let server = MockServer::builder()
+            .http("/ping", json!({ "ok": true }))
+            .start()
+            .await;
+
+        let client = RestClient::new(
+            ExchangeKind::Upbit,
+            Quota::per_second(NonZeroU32::new(100).unwrap()),
+            |_| Some(Duration::from_millis(200)),
+        );
+        let url = format!("{}/ping", server.base_url());
+
+        client.send(client.get(&url)).await.unwrap();
+        let started = Instant::now();
+        client.send(client.get(&url)).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+}
@@ -0,0 +1,897 @@
+//! Exchange that generates market data instead of connecting anywhere.
+//!
+//! Prices follow a seeded random walk or geometric Brownian motion per
+//! symbol, so demos and load tests run offline and reproducibly. The ticker,
+//! trade and candle streams all read one live price path per symbol, stepped
+//! once per `tick_interval`. Historical candles come from a separate path
+//! per symbol and timeframe that reaches the live path's starting price at
+//! the close of the bucket the exchange was created in. The path is fixed for
+//! the exchange's lifetime, so separately fetched ranges join up.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, DurationRound, Utc};
+use error_stack::Report;
+use futures::future::BoxFuture;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{Instant, Interval, MissedTickBehavior, interval_at};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::error::ExchangeError;
+use crate::exchange::Exchange;
+use crate::exchange::ws::WsEvent;
+use crate::model::{
+    Candle, CandleUpdate, ExchangeKind, Instrument, Market, OrderBook, Ticker, TickerStats,
+    TimeFrame, Trade, TradeSide,
+};
+
+/// Quote currency of every listed synthetic market.
+pub const MARKET_QUOTE: &str = "USD";
+/// Price steps generated per historical candle.
+const HISTORY_STEPS_PER_CANDLE: usize = 12;
+/// Historical candles per block; block boundaries are stepped directly, so
+/// reaching a distant range costs one step per block instead of per candle.
+const HISTORY_BLOCK_CANDLES: i64 = 128;
+/// Random-walk prices never fall below this fraction of the initial price.
+const PRICE_FLOOR_RATIO: f64 = 0.01;
+/// Best bid and ask sit this fraction below and above the price.
+const HALF_SPREAD_RATIO: f64 = 0.0005;
+
+// Per-purpose seed salts, so each generator of a symbol gets its own stream
+const LIVE_SALT: u64 = 0;
+const TRADE_SALT: u64 = 1;
+const CANDLE_SALT: u64 = 2;
+const HISTORY_SALT: u64 = 3;
+const HISTORY_BLOCK_SALT: u64 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceModel {
+    /// Additive Gaussian steps
+    RandomWalk,
+    /// Geometric Brownian motion: log-normal multiplicative steps
+    Gbm,
+}
+
+impl PriceModel {
+    /// Parse a config-format model name (`"random_walk"` or `"gbm"`).
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "random_walk" => Some(Self::RandomWalk),
+            "gbm" => Some(Self::Gbm),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntheticSettings {
+    pub seed: u64,
+    pub model: PriceModel,
+    pub initial_price: f64,
+    /// Standard deviation of one step: a fraction of the price for GBM, a
+    /// price change for the random walk
+    pub volatility: f64,
+    /// Mean of one step, in the same unit as `volatility`
+    pub drift: f64,
+    /// Time between price steps (and ticker updates)
+    pub tick_interval: Duration,
+    /// Trades generated per symbol and step
+    pub trades_per_tick: usize,
+    /// Number of listed markets, named `SYN1-USD`, `SYN2-USD`, ...
+    pub markets: usize,
+}
+
+/// One seeded price path.
+struct PricePath {
+    model: PriceModel,
+    drift: f64,
+    volatility: f64,
+    floor: f64,
+    price: f64,
+    /// Extremes since the path started
+    high: f64,
+    low: f64,
+    step: u64,
+    rng: StdRng,
+}
+
+impl PricePath {
+    fn new(settings: &SyntheticSettings, seed: u64) -> Self {
+        Self {
+            model: settings.model,
+            drift: settings.drift,
+            volatility: settings.volatility,
+            floor: settings.initial_price * PRICE_FLOOR_RATIO,
+            price: settings.initial_price,
+            high: settings.initial_price,
+            low: settings.initial_price,
+            step: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    fn next(&mut self) -> f64 {
+        let z = standard_normal(&mut self.rng);
+        self.price = match self.model {
+            PriceModel::Gbm => {
+                let sigma = self.volatility;
+                self.price * ((self.drift - 0.5 * sigma * sigma) + sigma * z).exp()
+            }
+            PriceModel::RandomWalk => {
+                (self.price + self.drift + self.volatility * z).max(self.floor)
+            }
+        };
+        self.high = self.high.max(self.price);
+        self.low = self.low.min(self.price);
+        self.step += 1;
+        self.price
+    }
+
+    /// Price after `step` steps; earlier steps return the current price.
+    fn advance_to(&mut self, step: u64) -> f64 {
+        while self.step < step {
+            self.next();
+        }
+        self.price
+    }
+}
+
+/// Box-Muller transform of two uniform samples.
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    let u1 = 1.0 - rng.random::<f64>();
+    let u2 = rng.random::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+/// Seed for one generator of `symbol` (FNV-1a over the symbol, mixed with
+/// the configured seed and `salt`).
+fn seed_for(seed: u64, symbol: &str, salt: u64) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in symbol.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash ^ seed.rotate_left(17) ^ salt.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+/// Decode a synthetic symbol (`{BASE}-{QUOTE}`, e.g. `SYN1-USD`).
+pub fn decode_symbol(symbol: &str) -> Option<Instrument> {
+    let (base, quote) = symbol.split_once('-')?;
+    if base.is_empty() || quote.is_empty() {
+        return None;
+    }
+    Some(Instrument::new(base, quote))
+}
+
+/// Instrument of a symbol requested over the REST-like API.
+fn requested_instrument(symbol: &str) -> Result<Instrument, Report<ExchangeError>> {
+    decode_symbol(symbol).ok_or_else(|| {
+        Report::new(ExchangeError::ResponseParse {
+            exchange: ExchangeKind::Synthetic.to_string(),
+        })
+        .attach(format!("undecodable symbol {symbol}"))
+    })
+}
+
+/// `symbols` with their instruments; undecodable ones get no stream.
+fn decoded_symbols(symbols: &[String]) -> Vec<(String, Instrument)> {
+    symbols
+        .iter()
+        .filter_map(|symbol| match decode_symbol(symbol) {
+            Some(instrument) => Some((symbol.clone(), instrument)),
+            None => {
+                warn!(symbol, "synthetic symbol does not decode, not streaming it");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Encode an instrument as a synthetic symbol.
+pub fn encode_symbol(instrument: &Instrument) -> String {
+    format!("{}-{}", instrument.base, instrument.quote)
+}
+
+/// Known levels of one historical series; see `SyntheticExchange::history`.
+struct HistoryLevels {
+    /// Block holding the close of the bucket the exchange was created in; it
+    /// starts at level 0
+    origin_block: i64,
+    /// Level at the start of the latest block computed
+    block: (i64, f64),
+    /// Level at the close of the bucket the exchange was created in
+    anchor: Option<f64>,
+}
+
+pub struct SyntheticExchange {
+    settings: SyntheticSettings,
+    /// Start of the live price paths; step `n` is due at `origin + n * tick_interval`
+    origin: Instant,
+    created_at: DateTime<Utc>,
+    paths: Mutex<HashMap<String, PricePath>>,
+    history_levels: Mutex<HashMap<(String, TimeFrame), HistoryLevels>>,
+    /// Never sent on; there is no connection to supervise
+    ws_events: broadcast::Sender<WsEvent>,
+}
+
+impl SyntheticExchange {
+    pub fn new(settings: SyntheticSettings) -> Self {
+        let (ws_events, _) = broadcast::channel(1);
+        Self {
+            settings,
+            origin: Instant::now(),
+            created_at: Utc::now(),
+            paths: Mutex::new(HashMap::new()),
+            history_levels: Mutex::new(HashMap::new()),
+            ws_events,
+        }
+    }
+
+    /// Ticks at every price step; slow consumers skip steps instead of
+    /// bursting to catch up.
+    fn ticks(&self) -> Interval {
+        let mut ticks = interval_at(self.origin, self.settings.tick_interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        ticks
+    }
+
+    fn current_step(&self) -> u64 {
+        (self.origin.elapsed().as_nanos() / self.settings.tick_interval.as_nanos()) as u64
+    }
+
+    /// Live price of `symbol` at `step`, shared by every stream.
+    fn live_price(&self, symbol: &str, step: u64) -> f64 {
+        self.with_live_path(symbol, step, |path| path.price)
+    }
+
+    /// Run `f` on the live path of `symbol` advanced to `step`.
+    fn with_live_path<R>(&self, symbol: &str, step: u64, f: impl FnOnce(&PricePath) -> R) -> R {
+        let mut paths = self.paths.lock().unwrap();
+        let path = paths.entry(symbol.to_owned()).or_insert_with(|| {
+            PricePath::new(
+                &self.settings,
+                seed_for(self.settings.seed, symbol, LIVE_SALT),
+            )
+        });
+        path.advance_to(step);
+        f(path)
+    }
+
+    /// Ticker statistics of a live path. They cover the whole run rather
+    /// than a rolling 24h window; there is no traded value.
+    fn live_stats(&self, path: &PricePath) -> TickerStats {
+        let open = self.settings.initial_price;
+        TickerStats {
+            open_24h: open,
+            high_24h: path.high,
+            low_24h: path.low,
+            change_pct_24h: (path.price / open - 1.0) * 100.0,
+            quote_volume_24h: 0.0,
+            best_bid: Some(path.price * (1.0 - HALF_SPREAD_RATIO)),
+            best_ask: Some(path.price * (1.0 + HALF_SPREAD_RATIO)),
+        }
+    }
+
+    /// `count` consecutive candles starting at `first_open`.
+    ///
+    /// History is a walk in level space (log price for GBM, price for the
+    /// random walk). Block boundary levels are stepped outwards from the
+    /// block the exchange was created in, and each candle's steps are seeded
+    /// from its open time and bridged onto the boundaries of its block, so
+    /// every candle is fixed regardless of the range it is fetched in. Levels
+    /// are priced relative to the level at the close of the bucket the
+    /// exchange was created in, which is the initial price.
+    fn history(
+        &self,
+        symbol: &str,
+        instrument: &Instrument,
+        timeframe: TimeFrame,
+        first_open: DateTime<Utc>,
+        count: usize,
+    ) -> Vec<Candle> {
+        if count == 0 {
+            return Vec::new();
+        }
+        let step_secs = timeframe.duration().num_seconds();
+        let first = first_open.timestamp().div_euclid(step_secs);
+        let end = first + count as i64;
+
+        let anchor_index = self.created_at.timestamp().div_euclid(step_secs) + 1;
+        let mut history_levels = self.history_levels.lock().unwrap();
+        let levels = history_levels
+            .entry((symbol.to_owned(), timeframe))
+            .or_insert_with(|| {
+                let origin_block = anchor_index.div_euclid(HISTORY_BLOCK_CANDLES);
+                HistoryLevels {
+                    origin_block,
+                    block: (origin_block, 0.0),
+                    anchor: None,
+                }
+            });
+        let anchor = match levels.anchor {
+            Some(anchor) => anchor,
+            None => {
+                let anchor = self.block_candles(symbol, timeframe, levels, anchor_index)
+                    [anchor_index.rem_euclid(HISTORY_BLOCK_CANDLES) as usize]
+                    .0[0];
+                levels.anchor = Some(anchor);
+                anchor
+            }
+        };
+
+        let floor = self.settings.initial_price * PRICE_FLOOR_RATIO;
+        let price = |level: f64| match self.settings.model {
+            PriceModel::Gbm => self.settings.initial_price * (level - anchor).exp(),
+            PriceModel::RandomWalk => (self.settings.initial_price + level - anchor).max(floor),
+        };
+
+        let mut candles = Vec::with_capacity(count);
+        let mut index = first;
+        while index < end {
+            let block_candles = self.block_candles(symbol, timeframe, levels, index);
+            let block_start = index.div_euclid(HISTORY_BLOCK_CANDLES) * HISTORY_BLOCK_CANDLES;
+            let block_end = (block_start + HISTORY_BLOCK_CANDLES).min(end);
+            for candle_index in index..block_end {
+                let (candle_levels, volume) = block_candles[(candle_index - block_start) as usize];
+                let prices = candle_levels.map(price);
+                let open_time = first_open + timeframe.duration() * (candle_index - first) as i32;
+                candles.push(Candle {
+                    exchange: self.kind(),
+                    symbol: symbol.to_owned(),
+                    instrument: instrument.clone(),
+                    timeframe,
+                    open_time,
+                    open: prices[0],
+                    high: prices.iter().copied().fold(f64::MIN, f64::max),
+                    low: prices.iter().copied().fold(f64::MAX, f64::min),
+                    close: prices[HISTORY_STEPS_PER_CANDLE],
+                    volume,
+                });
+            }
+            index = block_end;
+        }
+        candles
+    }
+
+    /// Levels (open, each step, close) and volume of every candle in the
+    /// block holding candle `index`: the candles' own steps, bridged from the
+    /// block's start level to the next block's.
+    fn block_candles(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        levels: &mut HistoryLevels,
+        index: i64,
+    ) -> Vec<([f64; HISTORY_STEPS_PER_CANDLE + 1], f64)> {
+        let block = index.div_euclid(HISTORY_BLOCK_CANDLES);
+        let start_level = self.block_level(symbol, timeframe, levels, block);
+        let end_level = start_level + self.block_change(symbol, timeframe, block);
+
+        let step_secs = timeframe.duration().num_seconds();
+        let mut raw = Vec::with_capacity(HISTORY_BLOCK_CANDLES as usize);
+        let mut level = start_level;
+        for i in 0..HISTORY_BLOCK_CANDLES {
+            let open_secs = (block * HISTORY_BLOCK_CANDLES + i) * step_secs;
+            let (changes, volume) = self.history_steps(symbol, timeframe, open_secs);
+            let mut candle = [level; HISTORY_STEPS_PER_CANDLE + 1];
+            for (step, change) in changes.iter().enumerate() {
+                level += change;
+                candle[step + 1] = level;
+            }
+            raw.push((candle, volume));
+        }
+
+        // Brownian bridge: spread the miss of the block's end level linearly
+        let miss = end_level - level;
+        let total_steps = (HISTORY_BLOCK_CANDLES as usize * HISTORY_STEPS_PER_CANDLE) as f64;
+        for (i, (candle, _)) in raw.iter_mut().enumerate() {
+            for (step, level) in candle.iter_mut().enumerate() {
+                *level += miss * (i * HISTORY_STEPS_PER_CANDLE + step) as f64 / total_steps;
+            }
+        }
+        if let Some((last, _)) = raw.last_mut() {
+            last[HISTORY_STEPS_PER_CANDLE] = end_level;
+        }
+        raw
+    }
+
+    /// Level at the start of `block`, stepped from the nearest known block
+    /// start.
+    fn block_level(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        levels: &mut HistoryLevels,
+        block: i64,
+    ) -> f64 {
+        let (mut known, mut level) = levels.block;
+        if block.abs_diff(levels.origin_block) < block.abs_diff(known) {
+            (known, level) = (levels.origin_block, 0.0);
+        }
+        while known < block {
+            level += self.block_change(symbol, timeframe, known);
+            known += 1;
+        }
+        while known > block {
+            known -= 1;
+            level -= self.block_change(symbol, timeframe, known);
+        }
+        levels.block = (block, level);
+        level
+    }
+
+    /// Level change over `block`: the sum of its steps' distribution.
+    fn block_change(&self, symbol: &str, timeframe: TimeFrame, block: i64) -> f64 {
+        let salt = HISTORY_BLOCK_SALT ^ (block as u64) ^ timeframe_salt(timeframe);
+        let mut rng = StdRng::seed_from_u64(seed_for(self.settings.seed, symbol, salt));
+        let steps = (HISTORY_BLOCK_CANDLES as usize * HISTORY_STEPS_PER_CANDLE) as f64;
+        steps * self.step_mean()
+            + steps.sqrt() * self.settings.volatility * standard_normal(&mut rng)
+    }
+
+    /// Level changes of the steps of the candle opening `open_secs` after the
+    /// epoch, and its volume.
+    fn history_steps(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        open_secs: i64,
+    ) -> ([f64; HISTORY_STEPS_PER_CANDLE], f64) {
+        let salt = HISTORY_SALT ^ (open_secs as u64) ^ timeframe_salt(timeframe);
+        let mut rng = StdRng::seed_from_u64(seed_for(self.settings.seed, symbol, salt));
+        let mean = self.step_mean();
+        let changes =
+            std::array::from_fn(|_| mean + self.settings.volatility * standard_normal(&mut rng));
+        (changes, rng.random_range(1.0..100.0))
+    }
+
+    /// Mean level change of one step.
+    fn step_mean(&self) -> f64 {
+        let sigma = self.settings.volatility;
+        match self.settings.model {
+            PriceModel::Gbm => self.settings.drift - 0.5 * sigma * sigma,
+            PriceModel::RandomWalk => self.settings.drift,
+        }
+    }
+}
+
+fn timeframe_salt(timeframe: TimeFrame) -> u64 {
+    timeframe.duration().num_seconds() as u64
+}
+
+/// Open time of the `timeframe` bucket containing `time`.
+fn bucket_open(time: DateTime<Utc>, timeframe: TimeFrame) -> DateTime<Utc> {
+    time.duration_trunc(timeframe.duration()).unwrap_or(time)
+}
+
+impl Exchange for SyntheticExchange {
+    fn kind(&self) -> ExchangeKind {
+        ExchangeKind::Synthetic
+    }
+
+    fn list_markets(&self) -> BoxFuture<'_, Result<Vec<Market>, Report<ExchangeError>>> {
+        Box::pin(async move {
+            Ok((1..=self.settings.markets)
+                .map(|i| {
+                    let instrument = Instrument::new(&format!("SYN{i}"), MARKET_QUOTE);
+                    Market {
+                        exchange: self.kind(),
+                        symbol: encode_symbol(&instrument),
+                        instrument,
+                        tick_size: None,
+                        lot_step: None,
+                        min_notional: None,
+                        trading: true,
+                        warning: false,
+                        cautions: Vec::new(),
+                    }
+                })
+                .collect())
+        })
+    }
+
+    /// The latest candle closes at the initial price while the current
+    /// bucket is the one the exchange was created in.
+    fn fetch_candles(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        limit: usize,
+    ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>> {
+        let symbol = symbol.to_owned();
+        Box::pin(async move {
+            let instrument = requested_instrument(&symbol)?;
+            let last_open = bucket_open(Utc::now(), timeframe);
+            let first_open = last_open - timeframe.duration() * limit.saturating_sub(1) as i32;
+            Ok(self.history(&symbol, &instrument, timeframe, first_open, limit))
+        })
+    }
+
+    fn fetch_candles_range(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>> {
+        let symbol = symbol.to_owned();
+        Box::pin(async move {
+            let instrument = requested_instrument(&symbol)?;
+            let mut first_open = bucket_open(start, timeframe);
+            if first_open < start {
+                first_open += timeframe.duration();
+            }
+            // Nothing exists past the current bucket
+            let end = end.min(bucket_open(Utc::now(), timeframe) + timeframe.duration());
+            let span = (end - first_open).num_seconds();
+            let count = if span > 0 {
+                (span - 1) / timeframe.duration().num_seconds() + 1
+            } else {
+                0
+            };
+            Ok(self.history(&symbol, &instrument, timeframe, first_open, count as usize))
+        })
+    }
+
+    fn subscribe_ticker(
+        &self,
+        symbols: &[String],
+        tx: mpsc::Sender<Ticker>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let symbols = decoded_symbols(symbols);
+        Box::pin(async move {
+            info!(symbols = symbols.len(), "synthetic ticker stream started");
+            let mut ticks = self.ticks();
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => return Ok(()),
+                    _ = ticks.tick() => {}
+                }
+
+                let step = self.current_step();
+                let now = Utc::now();
+                for (symbol, instrument) in &symbols {
+                    let (price, stats) = self
+                        .with_live_path(symbol, step, |path| (path.price, self.live_stats(path)));
+                    let ticker = Ticker {
+                        exchange: self.kind(),
+                        symbol: symbol.clone(),
+                        instrument: instrument.clone(),
+                        price,
+                        volume: 0.0,
+                        stats,
+                        timestamp: now,
+                        received_at: now,
+                    };
+                    if tx.send(ticker).await.is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+        })
+    }
+
+    /// Each step emits `trades_per_tick` trades per symbol, moving evenly
+    /// from the previous price to the new one.
+    fn subscribe_trades(
+        &self,
+        symbols: &[String],
+        tx: mpsc::Sender<Trade>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let symbols = decoded_symbols(symbols);
+        Box::pin(async move {
+            info!(symbols = symbols.len(), "synthetic trade stream started");
+            let mut rngs: HashMap<&str, StdRng> = symbols
+                .iter()
+                .map(|(s, _)| {
+                    let seed = seed_for(self.settings.seed, s, TRADE_SALT);
+                    (s.as_str(), StdRng::seed_from_u64(seed))
+                })
+                .collect();
+            let mut last_prices: HashMap<&str, f64> = HashMap::new();
+            let trades_per_tick = self.settings.trades_per_tick;
+            let mut ticks = self.ticks();
+
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => return Ok(()),
+                    _ = ticks.tick() => {}
+                }
+
+                let step = self.current_step();
+                let now = Utc::now();
+                for (symbol, instrument) in &symbols {
+                    let price = self.live_price(symbol, step);
+                    let previous = last_prices.insert(symbol, price).unwrap_or(price);
+                    let rng = rngs.get_mut(symbol.as_str()).expect("rng per symbol");
+
+                    for i in 1..=trades_per_tick {
+                        let trade_price = if i == trades_per_tick {
+                            price
+                        } else {
+                            previous + (price - previous) * i as f64 / trades_per_tick as f64
+                        };
+                        let trade = Trade {
+                            exchange: self.kind(),
+                            symbol: symbol.clone(),
+                            instrument: instrument.clone(),
+                            price: trade_price,
+                            volume: rng.random_range(0.01..1.0),
+                            side: if rng.random_bool(0.5) {
+                                TradeSide::Buy
+                            } else {
+                                TradeSide::Sell
+                            },
+                            timestamp: now,
+                            // Unique across restarts; skipped ticks leave holes
+                            trade_id: Some(step * trades_per_tick as u64 + i as u64),
+                            received_at: now,
+                        };
+                        if tx.send(trade).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn subscribe_candles(
+        &self,
+        symbols: &[String],
+        timeframes: &[TimeFrame],
+        tx: mpsc::Sender<CandleUpdate>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let symbols = decoded_symbols(symbols);
+        let timeframes = timeframes.to_vec();
+        Box::pin(async move {
+            info!(symbols = symbols.len(), timeframes = ?timeframes, "synthetic candle stream started");
+            let mut rngs: HashMap<&str, StdRng> = symbols
+                .iter()
+                .map(|(s, _)| {
+                    let seed = seed_for(self.settings.seed, s, CANDLE_SALT);
+                    (s.as_str(), StdRng::seed_from_u64(seed))
+                })
+                .collect();
+            let mut forming: HashMap<(&str, TimeFrame), Candle> = HashMap::new();
+            let mut ticks = self.ticks();
+
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => return Ok(()),
+                    _ = ticks.tick() => {}
+                }
+
+                let step = self.current_step();
+                let now = Utc::now();
+                for (symbol, instrument) in &symbols {
+                    let price = self.live_price(symbol, step);
+                    let volume = rngs
+                        .get_mut(symbol.as_str())
+                        .expect("rng per symbol")
+                        .random_range(0.1..10.0);
+
+                    for &timeframe in &timeframes {
+                        let open_time = bucket_open(now, timeframe);
+                        let key = (symbol.as_str(), timeframe);
+
+                        if let Some(candle) = forming.get(&key)
+                            && candle.open_time != open_time
+                        {
+                            let closed = CandleUpdate {
+                                candle: forming.remove(&key).expect("checked above"),
+                                is_closed: true,
+                            };
+                            if tx.send(closed).await.is_err() {
+                                return Ok(());
+                            }
+                        }
+
+                        let candle = forming.entry(key).or_insert_with(|| Candle {
+                            exchange: ExchangeKind::Synthetic,
+                            symbol: symbol.clone(),
+                            instrument: instrument.clone(),
+                            timeframe,
+                            open_time,
+                            open: price,
+                            high: price,
+                            low: price,
+                            close: price,
+                            volume: 0.0,
+                        });
+                        candle.high = candle.high.max(price);
+                        candle.low = candle.low.min(price);
+                        candle.close = price;
+                        candle.volume += volume;
+
+                        let update = CandleUpdate {
+                            candle: candle.clone(),
+                            is_closed: false,
+                        };
+                        if tx.send(update).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Synthetic markets have no order book; returns immediately.
+    fn subscribe_orderbook(
+        &self,
+        _symbols: &[String],
+        _tx: mpsc::Sender<OrderBook>,
+        _cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn stream_events(&self) -> broadcast::Receiver<WsEvent> {
+        self.ws_events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(model: PriceModel) -> SyntheticSettings {
+        SyntheticSettings {
+            seed: 7,
+            model,
+            initial_price: 100.0,
+            volatility: 0.01,
+            drift: 0.0,
+            tick_interval: Duration::from_millis(100),
+            trades_per_tick: 4,
+            markets: 3,
+        }
+    }
+
+    #[test]
+    fn price_paths_are_reproducible_per_seed_and_symbol() {
+        let walk = |seed: u64, symbol: &str| {
+            let settings = settings(PriceModel::Gbm);
+            let mut path = PricePath::new(&settings, seed_for(seed, symbol, LIVE_SALT));
+            (0..50).map(|_| path.next()).collect::<Vec<f64>>()
+        };
+
+        assert_eq!(walk(7, "SYN1-USD"), walk(7, "SYN1-USD"));
+        assert_ne!(walk(7, "SYN1-USD"), walk(7, "SYN2-USD"));
+        assert_ne!(walk(7, "SYN1-USD"), walk(8, "SYN1-USD"));
+        assert!(walk(7, "SYN1-USD").iter().all(|p| *p > 0.0));
+    }
+
+    #[test]
+    fn random_walk_stays_above_floor() {
+        let mut settings = settings(PriceModel::RandomWalk);
+        settings.volatility = 50.0;
+        let mut path = PricePath::new(&settings, 1);
+        for _ in 0..1_000 {
+            assert!(path.next() >= 100.0 * PRICE_FLOOR_RATIO);
+        }
+    }
+
+    #[tokio::test]
+    async fn history_is_contiguous_and_ends_at_initial_price() {
+        for model in [PriceModel::Gbm, PriceModel::RandomWalk] {
+            let exchange = SyntheticExchange::new(settings(model));
+            let candles = exchange
+                .fetch_candles("SYN1-USD", TimeFrame::Min5, 20)
+                .await
+                .unwrap();
+
+            assert_eq!(candles.len(), 20);
+            assert_eq!(
+                candles[19].open_time,
+                bucket_open(Utc::now(), TimeFrame::Min5)
+            );
+            assert!((candles[19].close - 100.0).abs() < 1e-9);
+            for pair in candles.windows(2) {
+                assert_eq!(
+                    pair[1].open_time - pair[0].open_time,
+                    TimeFrame::Min5.duration()
+                );
+                assert!((pair[1].open - pair[0].close).abs() < 1e-9);
+            }
+            for c in &candles {
+                assert!(c.high >= c.open.max(c.close) && c.low <= c.open.min(c.close));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_candles_range_covers_window() {
+        let exchange = SyntheticExchange::new(settings(PriceModel::Gbm));
+        let start = DateTime::from_timestamp(1_704_067_230, 0).unwrap();
+        let end = DateTime::from_timestamp(1_704_067_200 + 600, 0).unwrap();
+
+        let candles = exchange
+            .fetch_candles_range("SYN1-USD", TimeFrame::Min1, start, end)
+            .await
+            .unwrap();
+        assert_eq!(candles.len(), 9);
+        assert_eq!(candles[0].open_time.timestamp(), 1_704_067_260);
+        assert_eq!(candles[8].open_time.timestamp(), 1_704_067_740);
+    }
+
+    #[tokio::test]
+    async fn chunked_range_fetches_join_up() {
+        for model in [PriceModel::Gbm, PriceModel::RandomWalk] {
+            let exchange = SyntheticExchange::new(settings(model));
+            let at =
+                |minute: i64| DateTime::from_timestamp(1_704_067_200 + minute * 60, 0).unwrap();
+            let fetch = |start: i64, end: i64| {
+                exchange.fetch_candles_range("SYN1-USD", TimeFrame::Min1, at(start), at(end))
+            };
+
+            // Walked backwards in chunks across a block boundary, as range downloads do
+            let later = fetch(100, 300).await.unwrap();
+            let earlier = fetch(0, 100).await.unwrap();
+            let whole = fetch(0, 300).await.unwrap();
+
+            let chunked: Vec<&Candle> = earlier.iter().chain(&later).collect();
+            assert_eq!(chunked.len(), whole.len());
+            for (a, b) in chunked.iter().zip(&whole) {
+                assert_eq!(a.open_time, b.open_time);
+                assert_eq!(
+                    (a.open, a.high, a.low, a.close),
+                    (b.open, b.high, b.low, b.close)
+                );
+                assert_eq!(a.volume, b.volume);
+            }
+            for pair in whole.windows(2) {
+                assert_eq!(pair[1].open, pair[0].close);
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn streams_share_the_live_price_path() {
+        let exchange = SyntheticExchange::new(settings(PriceModel::Gbm));
+        let markets = exchange.list_markets().await.unwrap();
+        let symbols: Vec<String> = markets.iter().map(|m| m.symbol.clone()).collect();
+        assert_eq!(symbols, vec!["SYN1-USD", "SYN2-USD", "SYN3-USD"]);
+
+        let (ticker_tx, mut ticker_rx) = mpsc::channel(64);
+        let (trade_tx, mut trade_rx) = mpsc::channel(256);
+        let cancel = CancellationToken::new();
+        let subscribed = &symbols[..1];
+
+        let streams = futures::future::join(
+            exchange.subscribe_ticker(subscribed, ticker_tx, cancel.clone()),
+            exchange.subscribe_trades(subscribed, trade_tx, cancel.clone()),
+        );
+        let stop = async {
+            tokio::time::sleep(Duration::from_millis(1_050)).await;
+            cancel.cancel();
+        };
+        let ((ticker, trades), ()) = futures::future::join(streams, stop).await;
+        ticker.unwrap();
+        trades.unwrap();
+
+        let tickers: Vec<Ticker> = std::iter::from_fn(|| ticker_rx.try_recv().ok()).collect();
+        let trades: Vec<Trade> = std::iter::from_fn(|| trade_rx.try_recv().ok()).collect();
+        assert_eq!(tickers.len(), 11);
+        assert_eq!(trades.len(), 11 * 4);
+        assert_eq!(tickers[0].price, 100.0);
+        let last = tickers.last().unwrap();
+        assert!(last.stats.high_24h >= last.price && last.stats.low_24h <= last.price);
+        assert!(last.stats.best_bid.unwrap() < last.price);
+
+        // The last trade of each step is at that step's ticker price
+        for (ticker, step_trades) in tickers.iter().zip(trades.chunks(4)) {
+            assert_eq!(step_trades[3].price, ticker.price);
+        }
+    }
+}
@@ -1,15 +1,16 @@
+use std::collections::HashMap;
 use std::num::NonZeroU32;
-use std::sync::Arc;
 use std::time::Duration;
 
 use chrono::{DateTime, NaiveDateTime, Utc};
 use error_stack::{Report, ResultExt};
 use futures::future::BoxFuture;
 use futures::{SinkExt, StreamExt};
-use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use governor::Quota;
+use reqwest::header::HeaderMap;
 use serde::Deserialize;
-use tokio::sync::mpsc;
-use tokio::time::sleep;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::sleep_until;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_util::sync::CancellationToken;
@@ -17,34 +18,53 @@ use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use crate::error::ExchangeError;
-use crate::exchange::Exchange;
-use crate::model::{Candle, ExchangeKind, Ticker, TimeFrame, Trade, TradeSide};
+use crate::exchange::rest::RestClient;
+use crate::exchange::ws::{WsEvent, WsOptions, WsSupervisor, WsWatchdog};
+use crate::exchange::{
+    Exchange, MarketStreams, StreamSink, StreamSymbols, SubscriptionHandle, instrument_for,
+};
+use crate::model::{
+    Candle, CandleUpdate, ExchangeKind, Instrument, Market, OrderBook, OrderBookLevel, Ticker,
+    TickerStats, TimeFrame, Trade, TradeSide,
+};
 
 const UPBIT_BASE_URL: &str = "https://api.upbit.com";
 const UPBIT_WS_URL: &str = "wss://api.upbit.com/websocket/v1";
 const MAX_CANDLES_PER_REQUEST: usize = 200;
 const WS_PING_INTERVAL_SECS: u64 = 60;
-const MAX_BACKOFF_SECS: u64 = 60;
 /// Upbit allows 10 req/s; use 8 for safety margin
 const UPBIT_REQUESTS_PER_SECOND: u32 = 8;
 
 pub struct UpbitExchange {
-    client: reqwest::Client,
-    rate_limiter: Arc<DefaultDirectRateLimiter>,
+    http: RestClient,
+    ws: WsSupervisor,
+    base_url: String,
+    ws_url: String,
 }
 
 impl UpbitExchange {
-    pub fn new() -> Self {
+    /// Create a client for the REST API at `base_url` and the WebSocket
+    /// endpoint at `ws_url` (e.g. `https://api.upbit.com` and
+    /// `wss://api.upbit.com/websocket/v1`).
+    pub fn new(base_url: &str, ws_url: &str) -> Self {
         // Burst=1 ensures requests are evenly spaced (~125ms apart)
         // rather than allowing 8 simultaneous requests at startup.
         let quota = Quota::per_second(NonZeroU32::new(UPBIT_REQUESTS_PER_SECOND).unwrap())
             .allow_burst(NonZeroU32::new(1).unwrap());
         Self {
-            client: reqwest::Client::new(),
-            rate_limiter: Arc::new(RateLimiter::direct(quota)),
+            ws: WsSupervisor::new(ExchangeKind::Upbit, WsOptions::default()),
+            http: RestClient::new(ExchangeKind::Upbit, quota, remaining_req_pause),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            ws_url: ws_url.to_owned(),
         }
     }
 
+    /// Replace the WebSocket idle timeout and backoff reset settings.
+    pub fn with_ws_options(mut self, options: WsOptions) -> Self {
+        self.ws.set_options(options);
+        self
+    }
+
     async fn fetch_candles_page(
         &self,
         symbol: &str,
@@ -52,11 +72,8 @@ impl UpbitExchange {
         count: usize,
         to: Option<DateTime<Utc>>,
     ) -> Result<Vec<UpbitCandle>, Report<ExchangeError>> {
-        // Wait for rate limiter before making the request
-        self.rate_limiter.until_ready().await;
-
         let endpoint = timeframe.upbit_endpoint();
-        let url = format!("{}{}", UPBIT_BASE_URL, endpoint);
+        let url = format!("{}{}", self.base_url, endpoint);
 
         let mut params = vec![
             ("market".to_owned(), symbol.to_owned()),
@@ -69,22 +86,7 @@ impl UpbitExchange {
             ));
         }
 
-        let response = self
-            .client
-            .get(&url)
-            .query(&params)
-            .send()
-            .await
-            .change_context(ExchangeError::Request {
-                exchange: "upbit".into(),
-            })?;
-
-        if !response.status().is_success() {
-            return Err(Report::new(ExchangeError::Request {
-                exchange: "upbit".into(),
-            })
-            .attach(format!("HTTP status: {}", response.status())));
-        }
+        let response = self.http.send(self.http.get(&url).query(&params)).await?;
 
         let candles: Vec<UpbitCandle> =
             response
@@ -98,9 +100,38 @@ impl UpbitExchange {
     }
 }
 
+/// Decode an Upbit market code (`{QUOTE}-{BASE}`, e.g. `KRW-SOL`).
+pub fn decode_symbol(symbol: &str) -> Option<Instrument> {
+    let (quote, base) = symbol.split_once('-')?;
+    if quote.is_empty() || base.is_empty() {
+        return None;
+    }
+    Some(Instrument::new(base, quote))
+}
+
+/// Encode an instrument as an Upbit market code.
+pub fn encode_symbol(instrument: &Instrument) -> String {
+    format!("{}-{}", instrument.quote, instrument.base)
+}
+
+/// Pause until the next second once Upbit's `Remaining-Req` header
+/// (e.g. `group=candles; min=599; sec=0`) reports no requests left this second.
+fn remaining_req_pause(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get("Remaining-Req")?.to_str().ok()?;
+    let remaining: u32 = value
+        .split(';')
+        .filter_map(|part| part.trim().split_once('='))
+        .find(|(key, _)| *key == "sec")?
+        .1
+        .trim()
+        .parse()
+        .ok()?;
+    (remaining == 0).then(|| Duration::from_secs(1))
+}
+
 impl Default for UpbitExchange {
     fn default() -> Self {
-        Self::new()
+        Self::new(UPBIT_BASE_URL, UPBIT_WS_URL)
     }
 }
 
@@ -109,6 +140,29 @@ impl Exchange for UpbitExchange {
         ExchangeKind::Upbit
     }
 
+    fn list_markets(&self) -> BoxFuture<'_, Result<Vec<Market>, Report<ExchangeError>>> {
+        Box::pin(async move {
+            let url = format!("{}/v1/market/all", self.base_url);
+            let response = self
+                .http
+                .send(self.http.get(&url).query(&[("is_details", "true")]))
+                .await?;
+
+            let markets: Vec<UpbitMarket> =
+                response
+                    .json()
+                    .await
+                    .change_context(ExchangeError::ResponseParse {
+                        exchange: "upbit".into(),
+                    })?;
+
+            Ok(markets
+                .into_iter()
+                .filter_map(UpbitMarket::into_market)
+                .collect())
+        })
+    }
+
     fn fetch_candles(
         &self,
         symbol: &str,
@@ -117,6 +171,7 @@ impl Exchange for UpbitExchange {
     ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>> {
         let symbol = symbol.to_owned();
         Box::pin(async move {
+            let instrument = rest_instrument(&symbol)?;
             let mut all_candles: Vec<Candle> = Vec::with_capacity(limit);
             let mut to: Option<DateTime<Utc>> = None;
             let mut remaining = limit;
@@ -137,7 +192,7 @@ impl Exchange for UpbitExchange {
 
                 let fetched = page.len();
                 for raw in page {
-                    all_candles.push(raw.into_candle(&symbol, timeframe));
+                    all_candles.push(raw.into_candle(&symbol, &instrument, timeframe));
                 }
 
                 remaining = remaining.saturating_sub(fetched);
@@ -163,106 +218,241 @@ impl Exchange for UpbitExchange {
         })
     }
 
-    fn subscribe_ticker(
+    fn fetch_candles_range(
         &self,
-        symbols: &[String],
-        tx: mpsc::Sender<Ticker>,
-        cancel: CancellationToken,
-    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
-        let symbols = symbols.to_vec();
+        symbol: &str,
+        timeframe: TimeFrame,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<Candle>, Report<ExchangeError>>> {
+        let symbol = symbol.to_owned();
         Box::pin(async move {
-            let mut backoff = Duration::from_secs(1);
+            let instrument = rest_instrument(&symbol)?;
+            let mut all_candles: Vec<Candle> = Vec::new();
+            // Upbit's `to` cursor is exclusive, so paging backwards from `end`
+            // never includes a candle opening at `end`.
+            let mut to = Some(end);
 
-            loop {
-                if cancel.is_cancelled() {
+            while let Some(cursor) = to {
+                let page = self
+                    .fetch_candles_page(&symbol, timeframe, MAX_CANDLES_PER_REQUEST, Some(cursor))
+                    .await?;
+
+                if page.is_empty() {
                     break;
                 }
 
-                match run_ticker_ws(&symbols, &tx, &cancel).await {
-                    Ok(()) => break,
-                    Err(e) => {
-                        warn!(error = %e, "upbit ticker ws disconnected, retrying...");
-                        sleep(backoff).await;
-                        backoff = (backoff * 2).min(Duration::from_secs(MAX_BACKOFF_SECS));
-                    }
-                }
+                let fetched = page.len();
+                let oldest_time = page
+                    .last()
+                    .and_then(|c| parse_upbit_utc_timestamp(&c.candle_date_time_utc));
+
+                all_candles.extend(
+                    page.into_iter()
+                        .map(|raw| raw.into_candle(&symbol, &instrument, timeframe))
+                        .filter(|c| c.open_time >= start),
+                );
+
+                to = match oldest_time {
+                    Some(t) if t > start && fetched == MAX_CANDLES_PER_REQUEST => Some(t),
+                    _ => None,
+                };
+
+                debug!(
+                    symbol = %symbol,
+                    timeframe = %timeframe,
+                    fetched = all_candles.len(),
+                    "upbit candle range fetch progress"
+                );
             }
-            Ok(())
+
+            // Upbit returns newest-first; reverse to oldest-first
+            all_candles.reverse();
+            Ok(all_candles)
         })
     }
 
+    fn subscribe_ticker(
+        &self,
+        symbols: &[String],
+        tx: mpsc::Sender<Ticker>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let streams = MarketStreams {
+            ticker: Some(StreamSink {
+                symbols: symbols.to_vec(),
+                tx,
+            }),
+            ..MarketStreams::default()
+        };
+        let handle = SubscriptionHandle::new(&streams);
+        self.subscribe_streams(streams, handle, cancel)
+    }
+
     fn subscribe_trades(
         &self,
         symbols: &[String],
         tx: mpsc::Sender<Trade>,
         cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let streams = MarketStreams {
+            trades: Some(StreamSink {
+                symbols: symbols.to_vec(),
+                tx,
+            }),
+            ..MarketStreams::default()
+        };
+        let handle = SubscriptionHandle::new(&streams);
+        self.subscribe_streams(streams, handle, cancel)
+    }
+
+    fn subscribe_candles(
+        &self,
+        symbols: &[String],
+        timeframes: &[TimeFrame],
+        tx: mpsc::Sender<CandleUpdate>,
+        cancel: CancellationToken,
     ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
         let symbols = symbols.to_vec();
+        // Upbit has no daily candle stream
+        let timeframes: Vec<TimeFrame> = timeframes
+            .iter()
+            .copied()
+            .filter(|tf| {
+                let supported = tf.upbit_ws_type().is_some();
+                if !supported {
+                    warn!(timeframe = %tf.as_str(), "upbit has no candle stream for timeframe, skipping");
+                }
+                supported
+            })
+            .collect();
         Box::pin(async move {
-            let mut backoff = Duration::from_secs(1);
+            if timeframes.is_empty() {
+                return Ok(());
+            }
 
-            loop {
-                if cancel.is_cancelled() {
-                    break;
-                }
+            self.ws
+                .run("candles", &cancel, |watchdog| {
+                    run_candles_ws(&self.ws_url, &symbols, &timeframes, &tx, &cancel, watchdog)
+                })
+                .await;
+            Ok(())
+        })
+    }
 
-                match run_trades_ws(&symbols, &tx, &cancel).await {
-                    Ok(()) => break,
-                    Err(e) => {
-                        warn!(error = %e, "upbit trades ws disconnected, retrying...");
-                        sleep(backoff).await;
-                        backoff = (backoff * 2).min(Duration::from_secs(MAX_BACKOFF_SECS));
-                    }
-                }
-            }
+    fn subscribe_orderbook(
+        &self,
+        symbols: &[String],
+        tx: mpsc::Sender<OrderBook>,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        let streams = MarketStreams {
+            orderbook: Some(StreamSink {
+                symbols: symbols.to_vec(),
+                tx,
+            }),
+            ..MarketStreams::default()
+        };
+        let handle = SubscriptionHandle::new(&streams);
+        self.subscribe_streams(streams, handle, cancel)
+    }
+
+    fn subscribe_streams(
+        &self,
+        streams: MarketStreams,
+        handle: SubscriptionHandle,
+        cancel: CancellationToken,
+    ) -> BoxFuture<'_, Result<(), Report<ExchangeError>>> {
+        Box::pin(async move {
+            self.ws
+                .run(streams.name(), &cancel, |watchdog| {
+                    run_market_ws(
+                        &self.ws_url,
+                        &streams,
+                        handle.subscribe(),
+                        &cancel,
+                        watchdog,
+                    )
+                })
+                .await;
             Ok(())
         })
     }
+
+    fn stream_events(&self) -> broadcast::Receiver<WsEvent> {
+        self.ws.events()
+    }
 }
 
-async fn run_ticker_ws(
-    symbols: &[String],
-    tx: &mpsc::Sender<Ticker>,
+/// Serve ticker, trade and orderbook subscriptions over one socket.
+///
+/// Upbit accepts several types in a single subscribe request and tags every
+/// message with its `type`, so one connection carries all of them and counts
+/// only once against the connection rate limit.
+async fn run_market_ws(
+    ws_url: &str,
+    streams: &MarketStreams,
+    mut symbols: watch::Receiver<StreamSymbols>,
     cancel: &CancellationToken,
+    mut watchdog: WsWatchdog,
 ) -> Result<(), Report<ExchangeError>> {
     // Use connect_async with URL string so tungstenite auto-generates
     // the required WebSocket handshake headers (sec-websocket-key, etc.).
     // Do NOT include an Origin header — it triggers Upbit's strict 1 req/10s limit.
-    let (ws_stream, _) =
-        connect_async(UPBIT_WS_URL)
-            .await
-            .change_context(ExchangeError::Connection {
-                exchange: "upbit".into(),
-            })?;
+    let (ws_stream, _) = connect_async(ws_url)
+        .await
+        .change_context(ExchangeError::Connection {
+            exchange: "upbit".into(),
+        })?;
 
     let (mut write, mut read) = ws_stream.split();
 
-    // Send subscription message
-    let codes: Vec<String> = symbols.to_vec();
-    let subscribe_msg = build_ticker_subscribe(&codes);
+    let current = symbols.borrow_and_update().clone();
     write
-        .send(Message::Text(subscribe_msg.into()))
+        .send(Message::Text(build_market_subscribe(&current).into()))
         .await
         .change_context(ExchangeError::Connection {
             exchange: "upbit".into(),
         })?;
 
-    info!(symbols = ?symbols, "upbit ticker ws subscribed");
+    info!(
+        ticker = ?current.ticker,
+        trades = ?current.trades,
+        orderbook = ?current.orderbook,
+        "upbit market ws subscribed"
+    );
+    watchdog.connected();
 
     let ping_interval = Duration::from_secs(WS_PING_INTERVAL_SECS);
     let mut ping_timer = tokio::time::interval(ping_interval);
     ping_timer.tick().await; // skip immediate first tick
 
     loop {
+        let idle_deadline = watchdog.deadline();
         tokio::select! {
             _ = cancel.cancelled() => {
-                debug!("upbit ticker ws cancelled");
+                debug!("upbit market ws cancelled");
                 break;
             }
+            _ = sleep_until(idle_deadline) => {
+                return Err(watchdog.idle_error());
+            }
             _ = ping_timer.tick() => {
                 write.send(Message::Ping(vec![].into())).await
                     .change_context(ExchangeError::Connection { exchange: "upbit".into() })?;
             }
+            Ok(()) = symbols.changed() => {
+                // A new request on the same socket replaces the previous one
+                let current = symbols.borrow_and_update().clone();
+                write.send(Message::Text(build_market_subscribe(&current).into())).await
+                    .change_context(ExchangeError::Connection { exchange: "upbit".into() })?;
+                info!(
+                    ticker = ?current.ticker,
+                    trades = ?current.trades,
+                    orderbook = ?current.orderbook,
+                    "upbit market ws resubscribed"
+                );
+            }
             msg = read.next() => {
                 match msg {
                     None => break,
@@ -271,17 +461,10 @@ async fn run_ticker_ws(
                             exchange: "upbit".into(),
                         })),
                     Some(Ok(Message::Binary(data))) => {
-                        match serde_json::from_slice::<UpbitTickerMsg>(&data) {
-                            Ok(raw) => {
-                                let ticker = raw.into_ticker();
-                                let _ = tx.send(ticker).await;
-                            }
-                            Err(e) => {
-                                warn!(error = %e, "upbit ticker parse error");
-                            }
-                        }
+                        watchdog.touch();
+                        dispatch_market_msg(&data, streams).await;
                     }
-                    Some(Ok(Message::Pong(_))) => {}
+                    Some(Ok(Message::Pong(_))) => watchdog.touch(),
                     Some(Ok(_)) => {}
                 }
             }
@@ -291,23 +474,59 @@ async fn run_ticker_ws(
     Ok(())
 }
 
-#[allow(dead_code)]
-async fn run_trades_ws(
+/// Route one market socket message to the sender for its `type`.
+async fn dispatch_market_msg(data: &[u8], streams: &MarketStreams) {
+    match serde_json::from_slice::<UpbitStreamMsg>(data) {
+        Ok(UpbitStreamMsg::Ticker(raw)) => {
+            if let Some(sink) = &streams.ticker {
+                let code = raw.code.clone();
+                match raw.into_ticker() {
+                    Some(ticker) => {
+                        let _ = sink.tx.send(ticker).await;
+                    }
+                    None => warn!(%code, "upbit ticker with undecodable code"),
+                }
+            }
+        }
+        Ok(UpbitStreamMsg::Trade(raw)) => {
+            if let Some(sink) = &streams.trades {
+                let code = raw.code.clone();
+                match raw.into_trade() {
+                    Some(trade) => {
+                        let _ = sink.tx.send(trade).await;
+                    }
+                    None => warn!(%code, "upbit trade with undecodable code"),
+                }
+            }
+        }
+        Ok(UpbitStreamMsg::Orderbook(raw)) => {
+            if let Some(sink) = &streams.orderbook {
+                let _ = sink.tx.send(raw.into_orderbook()).await;
+            }
+        }
+        Err(e) => {
+            warn!(error = %e, "upbit market message parse error");
+        }
+    }
+}
+
+async fn run_candles_ws(
+    ws_url: &str,
     symbols: &[String],
-    tx: &mpsc::Sender<Trade>,
+    timeframes: &[TimeFrame],
+    tx: &mpsc::Sender<CandleUpdate>,
     cancel: &CancellationToken,
+    mut watchdog: WsWatchdog,
 ) -> Result<(), Report<ExchangeError>> {
-    let (ws_stream, _) =
-        connect_async(UPBIT_WS_URL)
-            .await
-            .change_context(ExchangeError::Connection {
-                exchange: "upbit".into(),
-            })?;
+    let (ws_stream, _) = connect_async(ws_url)
+        .await
+        .change_context(ExchangeError::Connection {
+            exchange: "upbit".into(),
+        })?;
 
     let (mut write, mut read) = ws_stream.split();
 
-    let codes: Vec<String> = symbols.to_vec();
-    let subscribe_msg = build_trades_subscribe(&codes);
+    let subscribe_msg = build_candles_subscribe(symbols, timeframes);
     write
         .send(Message::Text(subscribe_msg.into()))
         .await
@@ -315,18 +534,25 @@ async fn run_trades_ws(
             exchange: "upbit".into(),
         })?;
 
-    info!(symbols = ?symbols, "upbit trades ws subscribed");
+    info!(symbols = ?symbols, timeframes = ?timeframes, "upbit candles ws subscribed");
+    watchdog.connected();
 
     let ping_interval = Duration::from_secs(WS_PING_INTERVAL_SECS);
     let mut ping_timer = tokio::time::interval(ping_interval);
     ping_timer.tick().await;
 
+    let mut tracker = UpbitCandleTracker::default();
+
     loop {
+        let idle_deadline = watchdog.deadline();
         tokio::select! {
             _ = cancel.cancelled() => {
-                debug!("upbit trades ws cancelled");
+                debug!("upbit candles ws cancelled");
                 break;
             }
+            _ = sleep_until(idle_deadline) => {
+                return Err(watchdog.idle_error());
+            }
             _ = ping_timer.tick() => {
                 write.send(Message::Ping(vec![].into())).await
                     .change_context(ExchangeError::Connection { exchange: "upbit".into() })?;
@@ -339,17 +565,22 @@ async fn run_trades_ws(
                             exchange: "upbit".into(),
                         })),
                     Some(Ok(Message::Binary(data))) => {
-                        match serde_json::from_slice::<UpbitTradeMsg>(&data) {
+                        watchdog.touch();
+                        match serde_json::from_slice::<UpbitCandleMsg>(&data) {
                             Ok(raw) => {
-                                let trade = raw.into_trade();
-                                let _ = tx.send(trade).await;
+                                let Some(candle) = raw.into_candle() else {
+                                    continue;
+                                };
+                                for update in tracker.observe(candle) {
+                                    let _ = tx.send(update).await;
+                                }
                             }
                             Err(e) => {
-                                warn!(error = %e, "upbit trade parse error");
+                                warn!(error = %e, "upbit candle parse error");
                             }
                         }
                     }
-                    Some(Ok(Message::Pong(_))) => {}
+                    Some(Ok(Message::Pong(_))) => watchdog.touch(),
                     Some(Ok(_)) => {}
                 }
             }
@@ -359,43 +590,96 @@ async fn run_trades_ws(
     Ok(())
 }
 
-fn build_ticker_subscribe(codes: &[String]) -> String {
+/// Build one subscribe request for every stream with at least one symbol.
+fn build_market_subscribe(symbols: &StreamSymbols) -> String {
     let ticket = Uuid::new_v4().to_string();
-    let codes_json: Vec<serde_json::Value> = codes
-        .iter()
-        .map(|c| serde_json::Value::String(c.clone()))
-        .collect();
+    let active = |list: &Option<Vec<String>>| list.clone().filter(|codes| !codes.is_empty());
 
-    serde_json::json!([
-        { "ticket": ticket },
-        {
+    let mut request = vec![serde_json::json!({ "ticket": ticket })];
+    if let Some(codes) = active(&symbols.ticker) {
+        request.push(serde_json::json!({
             "type": "ticker",
-            "codes": codes_json,
+            "codes": codes,
             "is_only_realtime": true
-        },
-        { "format": "DEFAULT" }
-    ])
-    .to_string()
+        }));
+    }
+    if let Some(codes) = active(&symbols.trades) {
+        request.push(serde_json::json!({
+            "type": "trade",
+            "codes": codes,
+            "is_only_realtime": true
+        }));
+    }
+    if let Some(codes) = active(&symbols.orderbook) {
+        request.push(serde_json::json!({
+            "type": "orderbook",
+            "codes": codes,
+        }));
+    }
+    request.push(serde_json::json!({ "format": "DEFAULT" }));
+
+    serde_json::Value::Array(request).to_string()
 }
 
-#[allow(dead_code)]
-fn build_trades_subscribe(codes: &[String]) -> String {
+fn build_candles_subscribe(codes: &[String], timeframes: &[TimeFrame]) -> String {
     let ticket = Uuid::new_v4().to_string();
     let codes_json: Vec<serde_json::Value> = codes
         .iter()
         .map(|c| serde_json::Value::String(c.clone()))
         .collect();
 
-    serde_json::json!([
-        { "ticket": ticket },
-        {
-            "type": "trade",
-            "codes": codes_json,
-            "is_only_realtime": true
-        },
-        { "format": "DEFAULT" }
-    ])
-    .to_string()
+    let mut request = vec![serde_json::json!({ "ticket": ticket })];
+    request.extend(
+        timeframes
+            .iter()
+            .filter_map(|tf| tf.upbit_ws_type())
+            .map(|ws_type| {
+                serde_json::json!({
+                    "type": ws_type,
+                    "codes": codes_json,
+                })
+            }),
+    );
+    request.push(serde_json::json!({ "format": "DEFAULT" }));
+
+    serde_json::Value::Array(request).to_string()
+}
+
+/// Infers candle closure for Upbit's candle stream.
+///
+/// Upbit pushes the in-progress candle on every trade but never flags it as
+/// closed, so a candle is considered closed once a newer candle for the same
+/// market and timeframe arrives.
+#[derive(Debug, Default)]
+struct UpbitCandleTracker {
+    current: HashMap<(String, TimeFrame), Candle>,
+}
+
+impl UpbitCandleTracker {
+    fn observe(&mut self, candle: Candle) -> Vec<CandleUpdate> {
+        let key = (candle.symbol.clone(), candle.timeframe);
+        let mut updates = Vec::new();
+
+        if let Some(previous) = self.current.get(&key) {
+            if candle.open_time < previous.open_time {
+                // Late snapshot of an already closed candle
+                return updates;
+            }
+            if candle.open_time > previous.open_time {
+                updates.push(CandleUpdate {
+                    candle: previous.clone(),
+                    is_closed: true,
+                });
+            }
+        }
+
+        self.current.insert(key, candle.clone());
+        updates.push(CandleUpdate {
+            candle,
+            is_closed: false,
+        });
+        updates
+    }
 }
 
 // ── REST response types ───────────────────────────────────────────────────────
@@ -411,13 +695,14 @@ struct UpbitCandle {
 }
 
 impl UpbitCandle {
-    fn into_candle(self, symbol: &str, timeframe: TimeFrame) -> Candle {
+    fn into_candle(self, symbol: &str, instrument: &Instrument, timeframe: TimeFrame) -> Candle {
         let open_time =
             parse_upbit_utc_timestamp(&self.candle_date_time_utc).unwrap_or_else(Utc::now);
 
         Candle {
             exchange: ExchangeKind::Upbit,
             symbol: symbol.to_owned(),
+            instrument: instrument.clone(),
             timeframe,
             open_time,
             open: self.opening_price,
@@ -429,6 +714,73 @@ impl UpbitCandle {
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct UpbitMarket {
+    market: String,
+    /// Legacy warning field: "NONE" or "CAUTION"
+    #[serde(default)]
+    market_warning: Option<String>,
+    #[serde(default)]
+    market_event: Option<UpbitMarketEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpbitMarketEvent {
+    #[serde(default)]
+    warning: bool,
+    #[serde(default)]
+    caution: HashMap<String, bool>,
+}
+
+impl UpbitMarket {
+    /// `None` for a market code that does not decode to an instrument.
+    fn into_market(self) -> Option<Market> {
+        let Some(instrument) = instrument_for(ExchangeKind::Upbit, &self.market) else {
+            warn!(market = %self.market, "skipping upbit market with undecodable code");
+            return None;
+        };
+
+        let legacy_warning = self.market_warning.as_deref() == Some("CAUTION");
+        let (warning, mut cautions) = match self.market_event {
+            Some(event) => (
+                event.warning,
+                event
+                    .caution
+                    .into_iter()
+                    .filter(|(_, active)| *active)
+                    .map(|(flag, _)| flag)
+                    .collect(),
+            ),
+            None => (false, Vec::new()),
+        };
+        cautions.sort();
+
+        Some(Market {
+            exchange: ExchangeKind::Upbit,
+            symbol: self.market,
+            instrument,
+            // Upbit tick sizes depend on the price band rather than the market
+            tick_size: None,
+            lot_step: None,
+            min_notional: None,
+            trading: true,
+            warning: warning || legacy_warning,
+            cautions,
+        })
+    }
+}
+
+/// Instrument of a symbol requested over REST; one that cannot be decoded
+/// fails the request rather than producing candles with a bogus instrument.
+fn rest_instrument(symbol: &str) -> Result<Instrument, Report<ExchangeError>> {
+    instrument_for(ExchangeKind::Upbit, symbol).ok_or_else(|| {
+        Report::new(ExchangeError::ResponseParse {
+            exchange: "upbit".into(),
+        })
+        .attach(format!("undecodable symbol {symbol}"))
+    })
+}
+
 fn parse_upbit_utc_timestamp(raw: &str) -> Option<DateTime<Utc>> {
     DateTime::parse_from_rfc3339(raw)
         .ok()
@@ -442,28 +794,147 @@ fn parse_upbit_utc_timestamp(raw: &str) -> Option<DateTime<Utc>> {
 
 // ── WebSocket message types ───────────────────────────────────────────────────
 
+/// A message of the multiplexed market socket, keyed by its `type` field.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum UpbitStreamMsg {
+    #[serde(rename = "ticker")]
+    Ticker(UpbitTickerMsg),
+    #[serde(rename = "trade")]
+    Trade(UpbitTradeMsg),
+    #[serde(rename = "orderbook")]
+    Orderbook(UpbitOrderbookMsg),
+}
+
 #[derive(Debug, Deserialize)]
 struct UpbitTickerMsg {
     code: String,
     trade_price: f64,
+    /// Trading-day open (00:00 UTC+9)
+    opening_price: f64,
+    high_price: f64,
+    low_price: f64,
+    /// Change against the previous day's close, as a fraction
+    signed_change_rate: f64,
+    acc_trade_price_24h: f64,
     acc_trade_volume_24h: f64,
     timestamp: i64,
 }
 
 impl UpbitTickerMsg {
-    fn into_ticker(self) -> Ticker {
+    /// `None` when the code cannot be decoded.
+    fn into_ticker(self) -> Option<Ticker> {
         let timestamp = DateTime::from_timestamp_millis(self.timestamp).unwrap_or_else(Utc::now);
-        Ticker {
+        Some(Ticker {
             exchange: ExchangeKind::Upbit,
+            instrument: instrument_for(ExchangeKind::Upbit, &self.code)?,
             symbol: self.code,
             price: self.trade_price,
             volume: self.acc_trade_volume_24h,
+            stats: TickerStats {
+                open_24h: self.opening_price,
+                high_24h: self.high_price,
+                low_24h: self.low_price,
+                change_pct_24h: self.signed_change_rate * 100.0,
+                quote_volume_24h: self.acc_trade_price_24h,
+                best_bid: None,
+                best_ask: None,
+            },
+            timestamp,
+            received_at: Utc::now(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UpbitCandleMsg {
+    #[serde(rename = "type")]
+    msg_type: String,
+    code: String,
+    candle_date_time_utc: String,
+    opening_price: f64,
+    high_price: f64,
+    low_price: f64,
+    trade_price: f64,
+    candle_acc_trade_volume: f64,
+}
+
+impl UpbitCandleMsg {
+    fn into_candle(self) -> Option<Candle> {
+        let Some(timeframe) = TimeFrame::from_upbit_ws_type(&self.msg_type) else {
+            warn!(msg_type = %self.msg_type, "upbit candle with unsupported type");
+            return None;
+        };
+        let open_time =
+            parse_upbit_utc_timestamp(&self.candle_date_time_utc).unwrap_or_else(Utc::now);
+        let Some(instrument) = instrument_for(ExchangeKind::Upbit, &self.code) else {
+            warn!(code = %self.code, "upbit candle with undecodable code");
+            return None;
+        };
+
+        Some(Candle {
+            exchange: ExchangeKind::Upbit,
+            instrument,
+            symbol: self.code,
+            timeframe,
+            open_time,
+            open: self.opening_price,
+            high: self.high_price,
+            low: self.low_price,
+            close: self.trade_price,
+            volume: self.candle_acc_trade_volume,
+        })
+    }
+}
+
+/// Upbit pushes the full top-of-book on every change, so each message is a
+/// complete snapshot rather than a diff.
+#[derive(Debug, Deserialize)]
+struct UpbitOrderbookMsg {
+    code: String,
+    timestamp: i64,
+    orderbook_units: Vec<UpbitOrderbookUnit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpbitOrderbookUnit {
+    ask_price: f64,
+    bid_price: f64,
+    ask_size: f64,
+    bid_size: f64,
+}
+
+impl UpbitOrderbookMsg {
+    fn into_orderbook(self) -> OrderBook {
+        let timestamp = DateTime::from_timestamp_millis(self.timestamp).unwrap_or_else(Utc::now);
+        // Units are ordered from the best quote outwards on both sides
+        let bids = self
+            .orderbook_units
+            .iter()
+            .map(|u| OrderBookLevel {
+                price: u.bid_price,
+                size: u.bid_size,
+            })
+            .collect();
+        let asks = self
+            .orderbook_units
+            .iter()
+            .map(|u| OrderBookLevel {
+                price: u.ask_price,
+                size: u.ask_size,
+            })
+            .collect();
+
+        OrderBook {
+            exchange: ExchangeKind::Upbit,
+            symbol: self.code,
+            bids,
+            asks,
             timestamp,
         }
     }
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct UpbitTradeMsg {
     code: String,
@@ -471,10 +942,14 @@ struct UpbitTradeMsg {
     trade_volume: f64,
     ask_bid: String,
     timestamp: i64,
+    /// Unique per trade and increasing, but neither contiguous nor
+    /// guaranteed to arrive in order
+    sequential_id: Option<u64>,
 }
 
 impl UpbitTradeMsg {
-    fn into_trade(self) -> Trade {
+    /// `None` when the code cannot be decoded.
+    fn into_trade(self) -> Option<Trade> {
         let timestamp = DateTime::from_timestamp_millis(self.timestamp).unwrap_or_else(Utc::now);
         let side = if self.ask_bid == "BID" {
             TradeSide::Buy
@@ -482,38 +957,235 @@ impl UpbitTradeMsg {
             TradeSide::Sell
         };
 
-        Trade {
+        Some(Trade {
             exchange: ExchangeKind::Upbit,
+            instrument: instrument_for(ExchangeKind::Upbit, &self.code)?,
             symbol: self.code,
             price: self.trade_price,
             volume: self.trade_volume,
             side,
             timestamp,
-        }
+            trade_id: self.sequential_id,
+            received_at: Utc::now(),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::exchange::mock_server;
+    use crate::exchange::ws::WsEventKind;
 
     #[test]
-    fn build_ticker_subscribe_contains_codes() {
-        let codes = vec!["KRW-BTC".to_owned(), "KRW-ETH".to_owned()];
-        let msg = build_ticker_subscribe(&codes);
-        assert!(msg.contains("ticker"));
-        assert!(msg.contains("KRW-BTC"));
-        assert!(msg.contains("KRW-ETH"));
+    fn build_market_subscribe_lists_each_requested_type() {
+        let (ticker_tx, _) = mpsc::channel(1);
+        let (trade_tx, _) = mpsc::channel(1);
+        let streams = MarketStreams {
+            ticker: Some(StreamSink {
+                symbols: vec!["KRW-BTC".to_owned(), "KRW-USDT".to_owned()],
+                tx: ticker_tx,
+            }),
+            trades: Some(StreamSink {
+                symbols: vec!["KRW-BTC".to_owned()],
+                tx: trade_tx,
+            }),
+            orderbook: None,
+        };
+
+        let symbols = SubscriptionHandle::new(&streams)
+            .subscribe()
+            .borrow()
+            .clone();
+
+        let msg: serde_json::Value =
+            serde_json::from_str(&build_market_subscribe(&symbols)).unwrap();
+        let types: Vec<&serde_json::Value> = msg
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.get("type").is_some())
+            .collect();
+        assert_eq!(types.len(), 2);
+        assert_eq!(types[0]["type"], "ticker");
+        assert_eq!(
+            types[0]["codes"],
+            serde_json::json!(["KRW-BTC", "KRW-USDT"])
+        );
+        assert_eq!(types[1]["type"], "trade");
+        assert_eq!(types[1]["codes"], serde_json::json!(["KRW-BTC"]));
+        assert_eq!(streams.name(), "market");
+    }
+
+    #[test]
+    fn build_market_subscribe_skips_emptied_streams() {
+        let symbols = StreamSymbols {
+            ticker: Some(vec!["KRW-ETH".to_owned()]),
+            trades: Some(Vec::new()),
+            orderbook: None,
+        };
+
+        let msg: serde_json::Value =
+            serde_json::from_str(&build_market_subscribe(&symbols)).unwrap();
+        let types: Vec<&str> = msg
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|entry| entry.get("type")?.as_str())
+            .collect();
+        assert_eq!(types, vec!["ticker"]);
+    }
+
+    #[tokio::test]
+    async fn market_messages_are_routed_by_type() {
+        let (ticker_tx, mut ticker_rx) = mpsc::channel(4);
+        let (trade_tx, mut trade_rx) = mpsc::channel(4);
+        let streams = MarketStreams {
+            ticker: Some(StreamSink {
+                symbols: vec!["KRW-BTC".to_owned()],
+                tx: ticker_tx,
+            }),
+            trades: Some(StreamSink {
+                symbols: vec!["KRW-BTC".to_owned()],
+                tx: trade_tx,
+            }),
+            orderbook: None,
+        };
+
+        let ticker = r#"{"type":"ticker","code":"KRW-BTC","trade_price":100.0,"opening_price":90.0,"high_price":101.0,"low_price":89.0,"signed_change_rate":0.1,"acc_trade_price_24h":500.0,"acc_trade_volume_24h":5.0,"timestamp":1704067200000}"#;
+        let trade = r#"{"type":"trade","code":"KRW-BTC","trade_price":101.0,"trade_volume":0.5,"ask_bid":"ASK","timestamp":1704067200000}"#;
+        let orderbook = r#"{"type":"orderbook","code":"KRW-BTC","timestamp":1704067200000,"orderbook_units":[]}"#;
+        for raw in [ticker, trade, orderbook, r#"{"status":"UP"}"#] {
+            dispatch_market_msg(raw.as_bytes(), &streams).await;
+        }
+
+        assert_eq!(ticker_rx.try_recv().unwrap().price, 100.0);
+        assert_eq!(trade_rx.try_recv().unwrap().price, 101.0);
+        assert!(ticker_rx.try_recv().is_err());
+        assert!(trade_rx.try_recv().is_err());
     }
 
     #[test]
-    fn build_trades_subscribe_contains_codes() {
+    fn build_candles_subscribe_lists_each_timeframe() {
         let codes = vec!["KRW-BTC".to_owned()];
-        let msg = build_trades_subscribe(&codes);
-        assert!(msg.contains("trade"));
+        let msg = build_candles_subscribe(&codes, &[TimeFrame::Min1, TimeFrame::Hour1]);
+        assert!(msg.contains("\"type\":\"candle.1m\""));
+        assert!(msg.contains("\"type\":\"candle.60m\""));
         assert!(msg.contains("KRW-BTC"));
     }
 
+    #[test]
+    fn upbit_candle_msg_parses_into_candle() {
+        let raw = r#"{"type":"candle.5m","code":"KRW-BTC","candle_date_time_utc":"2024-01-01T00:05:00","candle_date_time_kst":"2024-01-01T09:05:00","opening_price":100.0,"high_price":110.0,"low_price":95.0,"trade_price":105.0,"candle_acc_trade_volume":3.5,"candle_acc_trade_price":367.5,"timestamp":1704067510000,"stream_type":"REALTIME"}"#;
+        let candle = serde_json::from_str::<UpbitCandleMsg>(raw)
+            .unwrap()
+            .into_candle()
+            .unwrap();
+
+        assert_eq!(candle.symbol, "KRW-BTC");
+        assert_eq!(candle.timeframe, TimeFrame::Min5);
+        assert_eq!(candle.open_time.timestamp(), 1_704_067_500);
+        assert_eq!(candle.close, 105.0);
+    }
+
+    #[test]
+    fn candle_tracker_closes_previous_candle_on_newer_open_time() {
+        let candle_at = |minute: i64, close: f64| Candle {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-BTC".to_owned(),
+            instrument: Instrument::new("BTC", "KRW"),
+            timeframe: TimeFrame::Min1,
+            open_time: DateTime::from_timestamp(1_704_067_200 + minute * 60, 0).unwrap(),
+            open: 100.0,
+            high: 110.0,
+            low: 90.0,
+            close,
+            volume: 1.0,
+        };
+        let mut tracker = UpbitCandleTracker::default();
+
+        let first = tracker.observe(candle_at(0, 100.0));
+        assert_eq!(first.len(), 1);
+        assert!(!first[0].is_closed);
+
+        let same = tracker.observe(candle_at(0, 101.0));
+        assert_eq!(same.len(), 1);
+        assert!(!same[0].is_closed);
+
+        let next = tracker.observe(candle_at(1, 102.0));
+        assert_eq!(next.len(), 2);
+        assert!(next[0].is_closed);
+        assert_eq!(next[0].candle.close, 101.0);
+        assert!(!next[1].is_closed);
+
+        assert!(tracker.observe(candle_at(0, 99.0)).is_empty());
+    }
+
+    #[test]
+    fn symbol_codec_round_trip() {
+        assert_eq!(
+            decode_symbol("KRW-SOL"),
+            Some(Instrument::new("SOL", "KRW"))
+        );
+        assert_eq!(decode_symbol("KRWSOL"), None);
+        assert_eq!(encode_symbol(&Instrument::new("SOL", "KRW")), "KRW-SOL");
+    }
+
+    #[test]
+    fn remaining_req_pauses_when_second_budget_is_spent() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(remaining_req_pause(&headers), None);
+
+        headers.insert(
+            "Remaining-Req",
+            "group=candles; min=599; sec=4".parse().unwrap(),
+        );
+        assert_eq!(remaining_req_pause(&headers), None);
+
+        headers.insert(
+            "Remaining-Req",
+            "group=candles; min=599; sec=0".parse().unwrap(),
+        );
+        assert_eq!(remaining_req_pause(&headers), Some(Duration::from_secs(1)));
+    }
+
+    #[tokio::test]
+    async fn fetch_candles_retries_after_rate_limit() {
+        let server = mock_server::upbit("KRW-SOL", 100.0)
+            .rate_limit("/v1/candles/minutes/1", 1, 0)
+            .start()
+            .await;
+        let exchange = UpbitExchange::new(&server.base_url(), &server.ws_url("/websocket/v1"));
+
+        let candles = exchange
+            .fetch_candles("KRW-SOL", TimeFrame::Min1, 5)
+            .await
+            .unwrap();
+        assert_eq!(candles.len(), 5);
+    }
+
+    #[test]
+    fn upbit_market_parses_flags() {
+        let raw = r#"[
+            {"market":"KRW-BTC","korean_name":"비트코인","english_name":"Bitcoin","market_event":{"warning":false,"caution":{"PRICE_FLUCTUATIONS":false,"TRADING_VOLUME_SOARING":true,"DEPOSIT_AMOUNT_SOARING":false}}},
+            {"market":"BTC-XRP","korean_name":"리플","english_name":"Ripple","market_warning":"CAUTION"},
+            {"market":"KRWBTC","korean_name":"?","english_name":"?"}
+        ]"#;
+        let markets: Vec<Market> = serde_json::from_str::<Vec<UpbitMarket>>(raw)
+            .unwrap()
+            .into_iter()
+            .filter_map(UpbitMarket::into_market)
+            .collect();
+
+        // The undecodable code is skipped
+        assert_eq!(markets.len(), 2);
+        assert_eq!(markets[0].instrument, Instrument::new("BTC", "KRW"));
+        assert!(!markets[0].warning);
+        assert_eq!(markets[0].cautions, vec!["TRADING_VOLUME_SOARING"]);
+        assert_eq!(markets[1].instrument, Instrument::new("XRP", "BTC"));
+        assert!(markets[1].warning);
+    }
+
     #[test]
     fn upbit_candle_parses_into_candle() {
         let raw = UpbitCandle {
@@ -524,7 +1196,7 @@ mod tests {
             trade_price: 50500.0,
             candle_acc_trade_volume: 10.5,
         };
-        let candle = raw.into_candle("KRW-BTC", TimeFrame::Min1);
+        let candle = raw.into_candle("KRW-BTC", &Instrument::new("BTC", "KRW"), TimeFrame::Min1);
         assert_eq!(candle.exchange, ExchangeKind::Upbit);
         assert_eq!(candle.symbol, "KRW-BTC");
         assert_eq!(candle.open, 50000.0);
@@ -540,9 +1212,11 @@ mod tests {
             trade_volume: 0.1,
             ask_bid: "BID".to_owned(),
             timestamp: 1704067200000,
+            sequential_id: Some(17040672000000000),
         };
-        let trade = msg.into_trade();
+        let trade = msg.into_trade().unwrap();
         assert_eq!(trade.side, TradeSide::Buy);
+        assert_eq!(trade.trade_id, Some(17040672000000000));
 
         let msg2 = UpbitTradeMsg {
             code: "KRW-BTC".to_owned(),
@@ -550,8 +1224,9 @@ mod tests {
             trade_volume: 0.1,
             ask_bid: "ASK".to_owned(),
             timestamp: 1704067200000,
+            sequential_id: None,
         };
-        let trade2 = msg2.into_trade();
+        let trade2 = msg2.into_trade().unwrap();
         assert_eq!(trade2.side, TradeSide::Sell);
     }
 
@@ -561,24 +1236,24 @@ mod tests {
         assert_eq!(parsed.timestamp(), 1704067200);
     }
 
-    /// Integration test: requires network access. Run with `cargo test -- --ignored`
     #[tokio::test]
-    #[ignore]
-    async fn integration_fetch_candles() {
-        let exchange = UpbitExchange::new();
+    async fn mock_fetch_candles() {
+        let server = mock_server::upbit("KRW-BTC", 100.0).start().await;
+        let exchange = UpbitExchange::new(&server.base_url(), &server.ws_url("/websocket/v1"));
         let candles = exchange
             .fetch_candles("KRW-BTC", TimeFrame::Min1, 10)
             .await
             .unwrap();
-        assert!(!candles.is_empty());
-        assert!(candles.len() <= 10);
+        assert_eq!(candles.len(), 5);
+        // Oldest-first
+        assert_eq!(candles[0].open_time.timestamp(), 1_704_067_200);
+        assert_eq!(candles[4].close, 104.0);
     }
 
-    /// Integration test: requires network access. Run with `cargo test -- --ignored`
     #[tokio::test]
-    #[ignore]
-    async fn integration_subscribe_ticker() {
-        let exchange = UpbitExchange::new();
+    async fn mock_subscribe_ticker() {
+        let server = mock_server::upbit("KRW-BTC", 100.0).start().await;
+        let exchange = UpbitExchange::new(&server.base_url(), &server.ws_url("/websocket/v1"));
         let (tx, mut rx) = mpsc::channel(10);
         let cancel = CancellationToken::new();
         let cancel_clone = cancel.clone();
@@ -590,7 +1265,6 @@ mod tests {
                 .unwrap();
         });
 
-        // Wait for at least one ticker
         let ticker = tokio::time::timeout(std::time::Duration::from_secs(10), rx.recv())
             .await
             .expect("timeout")
@@ -598,6 +1272,158 @@ mod tests {
 
         assert_eq!(ticker.exchange, ExchangeKind::Upbit);
         assert_eq!(ticker.symbol, "KRW-BTC");
+        assert_eq!(ticker.price, 105.0);
+        assert_eq!(ticker.volume, 1234.5);
+        assert_eq!(
+            ticker.stats,
+            TickerStats {
+                open_24h: 100.0,
+                high_24h: 110.0,
+                low_24h: 90.0,
+                change_pct_24h: 5.0,
+                quote_volume_24h: 123_450.0,
+                best_bid: None,
+                best_ask: None,
+            }
+        );
+        cancel.cancel();
+    }
+
+    #[tokio::test]
+    async fn mock_subscribe_ticker_reconnects_when_idle() {
+        let server = mock_server::upbit("KRW-BTC", 100.0).start().await;
+        let exchange = UpbitExchange::new(&server.base_url(), &server.ws_url("/websocket/v1"))
+            .with_ws_options(WsOptions {
+                idle_timeout: Duration::from_millis(300),
+                healthy_after: Duration::from_secs(60),
+            });
+        let mut events = exchange.stream_events();
+        let (tx, mut rx) = mpsc::channel(10);
+        let cancel = CancellationToken::new();
+        let cancel_clone = cancel.clone();
+
+        tokio::spawn(async move {
+            exchange
+                .subscribe_ticker(&["KRW-BTC".to_owned()], tx, cancel_clone)
+                .await
+                .unwrap();
+        });
+
+        // The mock sends a single ticker, then keeps the socket open silently.
+        rx.recv().await.expect("channel closed");
+        let mut kinds = Vec::new();
+        while kinds.len() < 3 {
+            let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+                .await
+                .expect("timeout")
+                .unwrap();
+            assert_eq!(event.stream, "ticker");
+            kinds.push(event.kind);
+        }
+        assert_eq!(kinds[0], WsEventKind::Connected);
+        assert_eq!(
+            kinds[1],
+            WsEventKind::Idle {
+                timeout: Duration::from_millis(300)
+            }
+        );
+        assert!(matches!(
+            kinds[2],
+            WsEventKind::Reconnecting { attempt: 1, .. }
+        ));
+
+        // The reconnect subscribes again and receives the ticker once more.
+        let ticker = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("timeout")
+            .expect("channel closed");
+        assert_eq!(ticker.symbol, "KRW-BTC");
+        cancel.cancel();
+    }
+
+    #[tokio::test]
+    async fn mock_subscribe_streams_share_one_socket() {
+        let server = mock_server::upbit("KRW-BTC", 100.0).start().await;
+        let exchange = UpbitExchange::new(&server.base_url(), &server.ws_url("/websocket/v1"));
+        let mut events = exchange.stream_events();
+        let (ticker_tx, mut ticker_rx) = mpsc::channel(10);
+        let (trade_tx, mut trade_rx) = mpsc::channel(10);
+        let (orderbook_tx, mut orderbook_rx) = mpsc::channel(10);
+        let cancel = CancellationToken::new();
+        let cancel_clone = cancel.clone();
+        let symbols = vec!["KRW-BTC".to_owned()];
+        let streams = MarketStreams {
+            ticker: Some(StreamSink {
+                symbols: symbols.clone(),
+                tx: ticker_tx,
+            }),
+            trades: Some(StreamSink {
+                symbols: symbols.clone(),
+                tx: trade_tx,
+            }),
+            orderbook: Some(StreamSink {
+                symbols,
+                tx: orderbook_tx,
+            }),
+        };
+
+        let handle = SubscriptionHandle::new(&streams);
+        tokio::spawn(async move {
+            exchange
+                .subscribe_streams(streams, handle, cancel_clone)
+                .await
+                .unwrap();
+        });
+
+        let timeout = Duration::from_secs(10);
+        let ticker = tokio::time::timeout(timeout, ticker_rx.recv())
+            .await
+            .expect("timeout")
+            .expect("channel closed");
+        let trade = tokio::time::timeout(timeout, trade_rx.recv())
+            .await
+            .expect("timeout")
+            .expect("channel closed");
+        let book = tokio::time::timeout(timeout, orderbook_rx.recv())
+            .await
+            .expect("timeout")
+            .expect("channel closed");
+        assert_eq!(ticker.price, 105.0);
+        assert_eq!(trade.side, TradeSide::Buy);
+        assert_eq!(book.bids[0].price, 104.0);
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.stream, "market");
+        assert_eq!(event.kind, WsEventKind::Connected);
+        assert!(events.try_recv().is_err());
+        cancel.cancel();
+    }
+
+    #[tokio::test]
+    async fn mock_subscribe_orderbook() {
+        let server = mock_server::upbit("KRW-BTC", 100.0).start().await;
+        let exchange = UpbitExchange::new(&server.base_url(), &server.ws_url("/websocket/v1"));
+        let (tx, mut rx) = mpsc::channel(10);
+        let cancel = CancellationToken::new();
+        let cancel_clone = cancel.clone();
+
+        tokio::spawn(async move {
+            exchange
+                .subscribe_orderbook(&["KRW-BTC".to_owned()], tx, cancel_clone)
+                .await
+                .unwrap();
+        });
+
+        let book = tokio::time::timeout(std::time::Duration::from_secs(10), rx.recv())
+            .await
+            .expect("timeout")
+            .expect("channel closed");
+
+        assert_eq!(book.symbol, "KRW-BTC");
+        assert_eq!(book.bids[0].price, 104.0);
+        assert_eq!(book.bids[1].size, 4.0);
+        assert_eq!(book.asks[0].price, 106.0);
+        assert_eq!(book.asks[1].price, 107.0);
         cancel.cancel();
     }
 }
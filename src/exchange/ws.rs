@@ -0,0 +1,314 @@
+//! Reconnect supervision shared by the exchange WebSocket subscriptions.
+//!
+//! `WsSupervisor::run` keeps one stream alive: it reconnects with
+//! exponential backoff, resets the backoff after a connection has stayed up
+//! for `healthy_after`, and publishes `WsEvent`s so the rest of the app can
+//! react to reconnects. Each connection gets a `WsWatchdog` that forces a
+//! reconnect when no frame arrives within `idle_timeout`. Ping and pong
+//! frames count, so a thinly traded stream on a live socket is not mistaken
+//! for a dead one.
+
+use std::future::Future;
+use std::time::Duration;
+
+use error_stack::Report;
+use tokio::sync::broadcast;
+use tokio::time::{Instant, sleep};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::ExchangeError;
+use crate::model::ExchangeKind;
+
+const INITIAL_BACKOFF_SECS: u64 = 1;
+const MAX_BACKOFF_SECS: u64 = 60;
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 120;
+pub const DEFAULT_HEALTHY_AFTER_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy)]
+pub struct WsOptions {
+    /// Reconnect when no frame, data or heartbeat, arrives for this long
+    pub idle_timeout: Duration,
+    /// A connection that stayed up this long resets the reconnect backoff
+    pub healthy_after: Duration,
+}
+
+impl Default for WsOptions {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS),
+            healthy_after: Duration::from_secs(DEFAULT_HEALTHY_AFTER_SECS),
+        }
+    }
+}
+
+/// Lifecycle event of one WebSocket stream.
+#[derive(Debug, Clone)]
+pub struct WsEvent {
+    pub exchange: ExchangeKind,
+    /// Stream name, e.g. "ticker" or "orderbook"
+    pub stream: &'static str,
+    pub kind: WsEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsEventKind {
+    /// Subscribed and waiting for data
+    Connected,
+    /// No frame within the idle timeout; the socket is being dropped
+    Idle { timeout: Duration },
+    /// The connection failed or was closed with an error
+    Disconnected { error: String },
+    /// Waiting `delay` before reconnect attempt number `attempt`
+    Reconnecting { attempt: u32, delay: Duration },
+}
+
+pub struct WsSupervisor {
+    exchange: ExchangeKind,
+    options: WsOptions,
+    events: broadcast::Sender<WsEvent>,
+}
+
+impl WsSupervisor {
+    pub fn new(exchange: ExchangeKind, options: WsOptions) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            exchange,
+            options,
+            events,
+        }
+    }
+
+    pub fn set_options(&mut self, options: WsOptions) {
+        self.options = options;
+    }
+
+    /// Receive events of every stream run by this supervisor.
+    pub fn events(&self) -> broadcast::Receiver<WsEvent> {
+        self.events.subscribe()
+    }
+
+    /// Run `connect` until it returns `Ok` or `cancel` is triggered,
+    /// reconnecting after every error.
+    pub async fn run<F, Fut>(
+        &self,
+        stream: &'static str,
+        cancel: &CancellationToken,
+        mut connect: F,
+    ) where
+        F: FnMut(WsWatchdog) -> Fut,
+        Fut: Future<Output = Result<(), Report<ExchangeError>>>,
+    {
+        let mut backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
+        let mut attempt = 0;
+
+        loop {
+            if cancel.is_cancelled() {
+                break;
+            }
+
+            let started = Instant::now();
+            let Err(e) = connect(self.watchdog(stream)).await else {
+                break;
+            };
+
+            if started.elapsed() >= self.options.healthy_after {
+                backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
+                attempt = 0;
+            }
+
+            let kind = match e.current_context() {
+                ExchangeError::StreamIdle { .. } => WsEventKind::Idle {
+                    timeout: self.options.idle_timeout,
+                },
+                _ => WsEventKind::Disconnected {
+                    error: e.to_string(),
+                },
+            };
+            self.emit(stream, kind);
+
+            attempt += 1;
+            self.emit(
+                stream,
+                WsEventKind::Reconnecting {
+                    attempt,
+                    delay: backoff,
+                },
+            );
+
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = sleep(backoff) => {}
+            }
+            backoff = (backoff * 2).min(Duration::from_secs(MAX_BACKOFF_SECS));
+        }
+    }
+
+    fn watchdog(&self, stream: &'static str) -> WsWatchdog {
+        WsWatchdog {
+            exchange: self.exchange,
+            stream,
+            idle_timeout: self.options.idle_timeout,
+            last_data: Instant::now(),
+            events: self.events.clone(),
+        }
+    }
+
+    fn emit(&self, stream: &'static str, kind: WsEventKind) {
+        // No receivers is fine; events are informational.
+        let _ = self.events.send(WsEvent {
+            exchange: self.exchange,
+            stream,
+            kind,
+        });
+    }
+}
+
+/// Idle tracking for a single connection.
+///
+/// Call `connected` once subscribed, `touch` on every data or heartbeat
+/// frame, and select
+/// on `sleep_until(watchdog.deadline())` to notice a silent socket.
+pub struct WsWatchdog {
+    exchange: ExchangeKind,
+    stream: &'static str,
+    idle_timeout: Duration,
+    last_data: Instant,
+    events: broadcast::Sender<WsEvent>,
+}
+
+impl WsWatchdog {
+    pub fn connected(&mut self) {
+        self.last_data = Instant::now();
+        let _ = self.events.send(WsEvent {
+            exchange: self.exchange,
+            stream: self.stream,
+            kind: WsEventKind::Connected,
+        });
+    }
+
+    pub fn touch(&mut self) {
+        self.last_data = Instant::now();
+    }
+
+    /// Instant at which the connection counts as idle unless data arrives.
+    pub fn deadline(&self) -> Instant {
+        self.last_data + self.idle_timeout
+    }
+
+    pub fn idle_error(&self) -> Report<ExchangeError> {
+        Report::new(ExchangeError::StreamIdle {
+            exchange: self.exchange.to_string(),
+        })
+        .attach(format!(
+            "no {} data for {}s",
+            self.stream,
+            self.idle_timeout.as_secs()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(idle_ms: u64, healthy_ms: u64) -> WsOptions {
+        WsOptions {
+            idle_timeout: Duration::from_millis(idle_ms),
+            healthy_after: Duration::from_millis(healthy_ms),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_connections_reconnect_and_report_events() {
+        let supervisor = WsSupervisor::new(ExchangeKind::Upbit, options(100, 60_000));
+        let mut events = supervisor.events();
+        let cancel = CancellationToken::new();
+
+        let mut connects = 0;
+        supervisor
+            .run("ticker", &cancel, |mut watchdog| {
+                connects += 1;
+                let done = connects == 3;
+                async move {
+                    watchdog.connected();
+                    if done {
+                        return Ok(());
+                    }
+                    tokio::time::sleep_until(watchdog.deadline()).await;
+                    Err(watchdog.idle_error())
+                }
+            })
+            .await;
+        assert_eq!(connects, 3);
+
+        let mut kinds = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            assert_eq!(event.stream, "ticker");
+            kinds.push(event.kind);
+        }
+        let idle = WsEventKind::Idle {
+            timeout: Duration::from_millis(100),
+        };
+        assert_eq!(
+            kinds,
+            vec![
+                WsEventKind::Connected,
+                idle.clone(),
+                WsEventKind::Reconnecting {
+                    attempt: 1,
+                    delay: Duration::from_secs(1)
+                },
+                WsEventKind::Connected,
+                idle,
+                WsEventKind::Reconnecting {
+                    attempt: 2,
+                    delay: Duration::from_secs(2)
+                },
+                WsEventKind::Connected,
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn healthy_connection_resets_backoff() {
+        let supervisor = WsSupervisor::new(ExchangeKind::Binance, options(60_000, 500));
+        let mut events = supervisor.events();
+        let cancel = CancellationToken::new();
+
+        // Fail fast twice, stay up past `healthy_after`, then fail again.
+        let lifetimes = [0, 0, 1_000, 0];
+        let mut connects = 0;
+        supervisor
+            .run("trades", &cancel, |_| {
+                let lifetime = lifetimes.get(connects).copied();
+                connects += 1;
+                async move {
+                    let Some(ms) = lifetime else {
+                        return Ok(());
+                    };
+                    sleep(Duration::from_millis(ms)).await;
+                    Err(Report::new(ExchangeError::Connection {
+                        exchange: "binance".into(),
+                    }))
+                }
+            })
+            .await;
+
+        let delays: Vec<Duration> = std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|event| match event.kind {
+                WsEventKind::Reconnecting { delay, .. } => Some(delay),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+            ]
+        );
+    }
+}
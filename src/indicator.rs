@@ -25,6 +25,31 @@ pub trait Indicator: Send {
     /// Returns one value per output point. The number of values may be less
     /// than the number of input candles depending on the indicator's lookback.
     fn calculate(&self, candles: &[Candle]) -> Result<Vec<f64>, Report<IndicatorError>>;
+
+    /// Fresh streaming state for this indicator's parameters.
+    fn stream(&self) -> Box<dyn StreamingIndicator>;
+}
+
+/// Stateful counterpart of an `Indicator` that advances one candle at a time.
+///
+/// Feeding closed candles through `update` yields the same values as
+/// `Indicator::calculate` over the same candles, at O(1) cost per candle.
+pub trait StreamingIndicator: Send {
+    /// Advance by a closed candle. Returns the value at that candle, or
+    /// `None` while the lookback is not yet filled.
+    fn update(&mut self, candle: &Candle) -> Option<f64>;
+
+    /// Value if the forming `candle` closed now, without advancing.
+    fn peek(&self, candle: &Candle) -> Option<f64>;
+
+    /// Copy of the current state, to go back to when candles fed since are
+    /// corrected.
+    fn snapshot(&self) -> Box<dyn StreamingIndicator>;
+
+    /// Feed closed candles in order; returns the value at the last one.
+    fn seed(&mut self, candles: &[Candle]) -> Option<f64> {
+        candles.iter().fold(None, |_, c| self.update(c))
+    }
 }
 
 /// Extract close prices from a slice of candles.
@@ -1,8 +1,8 @@
 use error_stack::{Report, bail};
 
 use crate::error::IndicatorError;
-use crate::indicator::ma::Sma;
-use crate::indicator::{Indicator, close_prices};
+use crate::indicator::ma::{Sma, SmaStream};
+use crate::indicator::{Indicator, StreamingIndicator, close_prices};
 use crate::model::Candle;
 
 pub struct BollingerBands {
@@ -77,12 +77,17 @@ impl Indicator for BollingerBands {
             .map(|(_, m, _)| m)
             .collect())
     }
+
+    /// Streams the middle band, like `calculate`.
+    fn stream(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(SmaStream::new(self.period))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{ExchangeKind, TimeFrame};
+    use crate::model::{ExchangeKind, Instrument, TimeFrame};
     use chrono::Utc;
 
     fn candles_from_closes(closes: &[f64]) -> Vec<Candle> {
@@ -92,6 +97,7 @@ mod tests {
             .map(|(i, &c)| Candle {
                 exchange: ExchangeKind::Upbit,
                 symbol: "TEST".into(),
+                instrument: Instrument::new("TEST", "KRW"),
                 timeframe: TimeFrame::Min1,
                 open_time: Utc::now() + chrono::Duration::minutes(i as i64),
                 open: c,
@@ -141,4 +147,18 @@ mod tests {
             assert!((upper - middle - (middle - lower)).abs() < 1e-9);
         }
     }
+
+    #[test]
+    fn bollinger_stream_matches_middle_band() {
+        let bb = BollingerBands::new(4, 2.0).unwrap();
+        let candles = candles_from_closes(&[3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0]);
+        let batch = bb.calculate(&candles).unwrap();
+
+        let mut stream = bb.stream();
+        assert_eq!(stream.seed(&candles[..5]), Some(batch[1]));
+        for (candle, expected) in candles[5..].iter().zip(&batch[2..]) {
+            let value = stream.update(candle).unwrap();
+            assert!((value - expected).abs() < 1e-9);
+        }
+    }
 }
@@ -1,7 +1,9 @@
+use std::collections::VecDeque;
+
 use error_stack::{Report, bail};
 
 use crate::error::IndicatorError;
-use crate::indicator::{Indicator, close_prices};
+use crate::indicator::{Indicator, StreamingIndicator, close_prices};
 use crate::model::Candle;
 
 /// Simple Moving Average.
@@ -46,6 +48,67 @@ impl Indicator for Sma {
     fn calculate(&self, candles: &[Candle]) -> Result<Vec<f64>, Report<IndicatorError>> {
         self.calculate_prices(&close_prices(candles))
     }
+
+    fn stream(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(SmaStream::new(self.period))
+    }
+}
+
+/// Streaming SMA: a rolling window with a running sum.
+#[derive(Debug, Clone)]
+pub struct SmaStream {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl SmaStream {
+    pub(crate) fn new(period: usize) -> Self {
+        Self {
+            period,
+            window: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+        }
+    }
+
+    /// Advance by one value.
+    pub fn update_value(&mut self, value: f64) -> Option<f64> {
+        self.window.push_back(value);
+        self.sum += value;
+        if self.window.len() > self.period
+            && let Some(dropped) = self.window.pop_front()
+        {
+            self.sum -= dropped;
+        }
+        (self.window.len() == self.period).then(|| self.sum / self.period as f64)
+    }
+
+    /// Average if `value` were the next one, without advancing.
+    pub fn peek_value(&self, value: f64) -> Option<f64> {
+        if self.window.len() + 1 < self.period {
+            return None;
+        }
+        let dropped = if self.window.len() == self.period {
+            self.window.front().copied().unwrap_or_default()
+        } else {
+            0.0
+        };
+        Some((self.sum + value - dropped) / self.period as f64)
+    }
+}
+
+impl StreamingIndicator for SmaStream {
+    fn update(&mut self, candle: &Candle) -> Option<f64> {
+        self.update_value(candle.close)
+    }
+
+    fn peek(&self, candle: &Candle) -> Option<f64> {
+        self.peek_value(candle.close)
+    }
+
+    fn snapshot(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(self.clone())
+    }
 }
 
 /// Exponential Moving Average.
@@ -99,12 +162,74 @@ impl Indicator for Ema {
     fn calculate(&self, candles: &[Candle]) -> Result<Vec<f64>, Report<IndicatorError>> {
         self.calculate_prices(&close_prices(candles))
     }
+
+    fn stream(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(EmaStream::new(self.period))
+    }
+}
+
+/// Streaming EMA, seeded with the SMA of the first `period` values like the
+/// batch calculation.
+#[derive(Debug, Clone)]
+pub struct EmaStream {
+    period: usize,
+    seen: usize,
+    seed_sum: f64,
+    ema: Option<f64>,
+}
+
+impl EmaStream {
+    pub(crate) fn new(period: usize) -> Self {
+        Self {
+            period,
+            seen: 0,
+            seed_sum: 0.0,
+            ema: None,
+        }
+    }
+
+    /// Advance by one value.
+    pub fn update_value(&mut self, value: f64) -> Option<f64> {
+        match self.ema {
+            Some(prev) => {
+                let k = 2.0 / (self.period as f64 + 1.0);
+                self.ema = Some(value * k + prev * (1.0 - k));
+            }
+            None => {
+                self.seen += 1;
+                self.seed_sum += value;
+                if self.seen == self.period {
+                    self.ema = Some(self.seed_sum / self.period as f64);
+                }
+            }
+        }
+        self.ema
+    }
+
+    /// EMA if `value` were the next one, without advancing.
+    pub fn peek_value(&self, value: f64) -> Option<f64> {
+        self.clone().update_value(value)
+    }
+}
+
+impl StreamingIndicator for EmaStream {
+    fn update(&mut self, candle: &Candle) -> Option<f64> {
+        self.update_value(candle.close)
+    }
+
+    fn peek(&self, candle: &Candle) -> Option<f64> {
+        self.peek_value(candle.close)
+    }
+
+    fn snapshot(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(self.clone())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{ExchangeKind, TimeFrame};
+    use crate::model::{ExchangeKind, Instrument, TimeFrame};
     use chrono::Utc;
 
     fn candles_from_closes(closes: &[f64]) -> Vec<Candle> {
@@ -114,6 +239,7 @@ mod tests {
             .map(|(i, &c)| Candle {
                 exchange: ExchangeKind::Upbit,
                 symbol: "TEST".into(),
+                instrument: Instrument::new("TEST", "KRW"),
                 timeframe: TimeFrame::Min1,
                 open_time: Utc::now() + chrono::Duration::minutes(i as i64),
                 open: c,
@@ -187,4 +313,32 @@ mod tests {
         // seed = (1+2+3)/3 = 2.0
         assert!((values[0] - 2.0).abs() < 1e-9);
     }
+
+    #[test]
+    fn streams_match_batch_and_peek_does_not_advance() {
+        let closes: Vec<f64> = (0..40)
+            .map(|i| 100.0 + (i as f64 * 0.7).sin() * 5.0)
+            .collect();
+        let candles = candles_from_closes(&closes);
+        let indicators: [Box<dyn Indicator>; 2] = [
+            Box::new(Sma::new(5).unwrap()),
+            Box::new(Ema::new(5).unwrap()),
+        ];
+
+        for indicator in indicators {
+            let batch = indicator.calculate(&candles).unwrap();
+            let mut stream = indicator.stream();
+            let mut streamed = Vec::new();
+            for candle in &candles {
+                let peeked = stream.peek(candle);
+                let updated = stream.update(candle);
+                assert_eq!(peeked, updated);
+                streamed.extend(updated);
+            }
+            assert_eq!(streamed.len(), batch.len());
+            for (s, b) in streamed.iter().zip(&batch) {
+                assert!((s - b).abs() < 1e-9, "{}: {s} != {b}", indicator.name());
+            }
+        }
+    }
 }
@@ -1,8 +1,8 @@
 use error_stack::{Report, bail};
 
 use crate::error::IndicatorError;
-use crate::indicator::ma::Ema;
-use crate::indicator::{Indicator, close_prices};
+use crate::indicator::ma::{Ema, EmaStream};
+use crate::indicator::{Indicator, StreamingIndicator, close_prices};
 use crate::model::Candle;
 
 pub struct Macd {
@@ -95,12 +95,46 @@ impl Indicator for Macd {
             .map(|(m, _, _)| m)
             .collect())
     }
+
+    fn stream(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(MacdStream {
+            fast: EmaStream::new(self.fast_period),
+            slow: EmaStream::new(self.slow_period),
+            signal: EmaStream::new(self.signal_period),
+        })
+    }
+}
+
+/// Streaming MACD line. Like the batch calculation it yields values only
+/// once the signal line is available.
+#[derive(Debug, Clone)]
+pub struct MacdStream {
+    fast: EmaStream,
+    slow: EmaStream,
+    signal: EmaStream,
+}
+
+impl StreamingIndicator for MacdStream {
+    fn update(&mut self, candle: &Candle) -> Option<f64> {
+        let fast = self.fast.update_value(candle.close);
+        let slow = self.slow.update_value(candle.close)?;
+        let macd = fast? - slow;
+        self.signal.update_value(macd).map(|_| macd)
+    }
+
+    fn peek(&self, candle: &Candle) -> Option<f64> {
+        self.clone().update(candle)
+    }
+
+    fn snapshot(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(self.clone())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{ExchangeKind, TimeFrame};
+    use crate::model::{ExchangeKind, Instrument, TimeFrame};
     use chrono::Utc;
 
     fn candles_from_closes(closes: &[f64]) -> Vec<Candle> {
@@ -110,6 +144,7 @@ mod tests {
             .map(|(i, &c)| Candle {
                 exchange: ExchangeKind::Upbit,
                 symbol: "TEST".into(),
+                instrument: Instrument::new("TEST", "KRW"),
                 timeframe: TimeFrame::Min1,
                 open_time: Utc::now() + chrono::Duration::minutes(i as i64),
                 open: c,
@@ -156,4 +191,22 @@ mod tests {
         let values = macd.calculate(&candles).unwrap();
         assert!(!values.is_empty());
     }
+
+    #[test]
+    fn macd_stream_matches_batch() {
+        let macd = Macd::new(3, 5, 3).unwrap();
+        let closes: Vec<f64> = (0..30).map(|i| 10.0 + (i as f64 * 0.5).cos()).collect();
+        let candles = candles_from_closes(&closes);
+        let batch = macd.calculate(&candles).unwrap();
+
+        let mut stream = macd.stream();
+        let mut streamed = vec![];
+        for candle in &candles {
+            let peeked = stream.peek(candle);
+            let updated = stream.update(candle);
+            assert_eq!(peeked, updated);
+            streamed.extend(updated);
+        }
+        assert_eq!(streamed, batch);
+    }
 }
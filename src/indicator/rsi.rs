@@ -1,7 +1,7 @@
 use error_stack::{Report, bail};
 
 use crate::error::IndicatorError;
-use crate::indicator::{Indicator, close_prices};
+use crate::indicator::{Indicator, StreamingIndicator, close_prices};
 use crate::model::Candle;
 
 /// RSI (Relative Strength Index) using Wilder's smoothing method.
@@ -66,6 +66,60 @@ impl Indicator for Rsi {
 
         Ok(results)
     }
+
+    fn stream(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(RsiStream {
+            period: self.period,
+            prev_close: None,
+            deltas: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        })
+    }
+}
+
+/// Streaming RSI. Until `period` deltas are seen the averages hold the
+/// running sums for the seed.
+#[derive(Debug, Clone)]
+pub struct RsiStream {
+    period: usize,
+    prev_close: Option<f64>,
+    deltas: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl StreamingIndicator for RsiStream {
+    fn update(&mut self, candle: &Candle) -> Option<f64> {
+        let prev = self.prev_close.replace(candle.close)?;
+        let delta = candle.close - prev;
+        let gain = delta.max(0.0);
+        let loss = (-delta).max(0.0);
+        let period = self.period as f64;
+        self.deltas += 1;
+
+        if self.deltas < self.period {
+            self.avg_gain += gain;
+            self.avg_loss += loss;
+            return None;
+        }
+        if self.deltas == self.period {
+            self.avg_gain = (self.avg_gain + gain) / period;
+            self.avg_loss = (self.avg_loss + loss) / period;
+        } else {
+            self.avg_gain = (self.avg_gain * (period - 1.0) + gain) / period;
+            self.avg_loss = (self.avg_loss * (period - 1.0) + loss) / period;
+        }
+        Some(rsi_value(self.avg_gain, self.avg_loss))
+    }
+
+    fn peek(&self, candle: &Candle) -> Option<f64> {
+        self.clone().update(candle)
+    }
+
+    fn snapshot(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(self.clone())
+    }
 }
 
 fn rsi_value(avg_gain: f64, avg_loss: f64) -> f64 {
@@ -79,7 +133,7 @@ fn rsi_value(avg_gain: f64, avg_loss: f64) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{ExchangeKind, TimeFrame};
+    use crate::model::{ExchangeKind, Instrument, TimeFrame};
     use chrono::Utc;
 
     fn candles_from_closes(closes: &[f64]) -> Vec<Candle> {
@@ -89,6 +143,7 @@ mod tests {
             .map(|(i, &c)| Candle {
                 exchange: ExchangeKind::Upbit,
                 symbol: "TEST".into(),
+                instrument: Instrument::new("TEST", "KRW"),
                 timeframe: TimeFrame::Min1,
                 open_time: Utc::now() + chrono::Duration::minutes(i as i64),
                 open: c,
@@ -152,4 +207,28 @@ mod tests {
         // 20 prices -> 19 deltas -> 1 seed + 5 subsequent = 6 values
         assert_eq!(values.len(), 20 - 14);
     }
+
+    #[test]
+    fn rsi_stream_matches_batch() {
+        let closes: Vec<f64> = (0..50)
+            .map(|i| 100.0 + (i as f64 * 0.9).sin() * 4.0 + i as f64 * 0.1)
+            .collect();
+        let candles = candles_from_closes(&closes);
+        let rsi = Rsi::new(14).unwrap();
+        let batch = rsi.calculate(&candles).unwrap();
+
+        // Seed from history, then stream the rest
+        let mut stream = rsi.stream();
+        let seeded = stream.seed(&candles[..20]);
+        assert_eq!(seeded, batch.get(20 - 15).copied());
+        let mut streamed = vec![];
+        for candle in &candles[20..] {
+            let peeked = stream.peek(candle);
+            let updated = stream.update(candle);
+            assert_eq!(peeked, updated);
+            streamed.extend(updated);
+        }
+        assert_eq!(streamed, batch[batch.len() - streamed.len()..]);
+        assert_eq!(streamed.len(), 30);
+    }
 }
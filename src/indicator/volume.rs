@@ -1,7 +1,8 @@
 use error_stack::{Report, bail};
 
 use crate::error::IndicatorError;
-use crate::indicator::{Indicator, volumes};
+use crate::indicator::ma::SmaStream;
+use crate::indicator::{Indicator, StreamingIndicator, volumes};
 use crate::model::Candle;
 
 /// Volume Moving Average — simple average of trading volume over a period.
@@ -61,12 +62,34 @@ impl Indicator for VolumeMA {
             .map(|w| w.iter().sum::<f64>() / self.period as f64)
             .collect())
     }
+
+    fn stream(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(VolumeMaStream(SmaStream::new(self.period)))
+    }
+}
+
+/// Streaming volume MA.
+#[derive(Debug, Clone)]
+pub struct VolumeMaStream(SmaStream);
+
+impl StreamingIndicator for VolumeMaStream {
+    fn update(&mut self, candle: &Candle) -> Option<f64> {
+        self.0.update_value(candle.volume)
+    }
+
+    fn peek(&self, candle: &Candle) -> Option<f64> {
+        self.0.peek_value(candle.volume)
+    }
+
+    fn snapshot(&self) -> Box<dyn StreamingIndicator> {
+        Box::new(self.clone())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{ExchangeKind, TimeFrame};
+    use crate::model::{ExchangeKind, Instrument, TimeFrame};
     use chrono::Utc;
 
     fn candles_with_volumes(vols: &[f64]) -> Vec<Candle> {
@@ -75,6 +98,7 @@ mod tests {
             .map(|(i, &v)| Candle {
                 exchange: ExchangeKind::Upbit,
                 symbol: "TEST".into(),
+                instrument: Instrument::new("TEST", "KRW"),
                 timeframe: TimeFrame::Min1,
                 open_time: Utc::now() + chrono::Duration::minutes(i as i64),
                 open: 100.0,
@@ -117,4 +141,22 @@ mod tests {
         assert!(!surges[0]); // window [1,1,1]: current=1, ma=1 -> not surge
         assert!(surges[1]); // window [1,1,5]: current=5, ma=1 -> surge
     }
+
+    #[test]
+    fn volume_ma_stream_matches_batch() {
+        let vma = VolumeMA::new(3).unwrap();
+        let candles = candles_with_volumes(&[1.0, 4.0, 2.0, 8.0, 5.0, 7.0]);
+        let batch = vma.calculate(&candles).unwrap();
+
+        let mut stream = vma.stream();
+        let streamed: Vec<f64> = candles.iter().filter_map(|c| stream.update(c)).collect();
+        assert_eq!(streamed.len(), batch.len());
+        for (s, b) in streamed.iter().zip(&batch) {
+            assert!((s - b).abs() < 1e-9);
+        }
+        // Peeking the forming candle leaves the stream where it was
+        let forming = &candles_with_volumes(&[10.0])[0];
+        assert!((stream.peek(forming).unwrap() - 22.0 / 3.0).abs() < 1e-9);
+        assert!((stream.peek(forming).unwrap() - 22.0 / 3.0).abs() < 1e-9);
+    }
 }
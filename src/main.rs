@@ -1,17 +1,24 @@
 mod backtest;
+mod candle_aggregator;
+mod candle_cache;
+mod candle_gaps;
 mod config;
 mod error;
 mod exchange;
 mod indicator;
 mod model;
 mod notifier;
+mod reconcile;
+mod recorder;
 mod signal_input;
 mod signal_model;
+mod spread;
 mod storage;
 mod strategy;
+mod trade_sequence;
 
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -19,29 +26,43 @@ use chrono::{DateTime, Utc};
 use clap::{Parser, Subcommand};
 use derive_more::{Display, Error};
 use error_stack::{Report, ResultExt};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc, watch};
 use tokio_util::sync::CancellationToken;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
+use candle_aggregator::{CandleAggregator, bucket_open_time};
+use candle_cache::{CandleCache, CandleWriter};
+use candle_gaps::GapRepairer;
 use config::AppConfig;
 use error::ExchangeError;
-use exchange::Exchange;
-use exchange::binance::BinanceExchange;
-use exchange::upbit::UpbitExchange;
-use indicator::Indicator;
+use exchange::registry;
+use exchange::replay::{ReplayExchange, ReplaySpeed};
+use exchange::ws::{WsEvent, WsEventKind};
+use exchange::{Exchange, MarketStreams, StreamSink, SubscriptionHandle};
 use indicator::bollinger::BollingerBands;
 use indicator::ma::{Ema, Sma};
 use indicator::macd::Macd;
 use indicator::rsi::Rsi;
 use indicator::volume::VolumeMA;
-use model::{Candle, ExchangeKind, Ticker, TimeFrame, Trade};
+use indicator::{Indicator, StreamingIndicator};
+use model::{
+    Candle, CandleUpdate, DerivativesMetric, DerivativesSample, ExchangeKind, Market, OrderBook,
+    Ticker, TimeFrame, Trade,
+};
 use notifier::Notifier;
 use notifier::terminal::TerminalNotifier;
+use recorder::MarketEvent;
+use spread::{SpreadEngine, SpreadUpdate};
 use storage::Storage;
 use storage::sqlite::SqliteStorage;
-use strategy::AlertRule;
-use strategy::condition::{evaluate, should_alert};
+use strategy::condition::{cooldown_elapsed, evaluate, evaluate_spread, should_alert};
+use strategy::{
+    AlertRule, DEFAULT_BOOK_DEPTH, DEFAULT_OI_CHANGE_PERIOD, EvaluateOn, OI_SAMPLE_MAX_AGE_SECS,
+    ORDERBOOK_INDICATORS, TICKER_INDICATORS, derivatives_metric, derivatives_value,
+    derivatives_window, orderbook_value, ticker_value,
+};
+use trade_sequence::{TradeCheck, TradeSequencer};
 
 #[derive(Debug, Display, Error)]
 pub enum AppError {
@@ -75,12 +96,35 @@ enum Command {
         #[command(subcommand)]
         command: Option<BacktestCommand>,
     },
+    /// Show a spread's stored premium series from SQLite
+    Premium {
+        /// Spread name from [[spreads]]
+        name: String,
+        /// How many hours back to show
+        #[arg(long, default_value_t = 24)]
+        hours: i64,
+    },
+    /// Show where trade-built candles differed from the exchange's
+    Discrepancies {
+        /// Exchange name, e.g. "upbit"
+        exchange: String,
+        /// Exchange symbol, e.g. "KRW-BTC"
+        symbol: String,
+        /// Candle timeframe
+        #[arg(long, default_value = "1m")]
+        timeframe: String,
+        /// How many hours back to show
+        #[arg(long, default_value_t = 24)]
+        hours: i64,
+    },
 }
 
 #[derive(Subcommand)]
 enum BacktestCommand {
     /// Run historical backtest and save result into SQLite
     Run,
+    /// Download the configured backtest window from the exchange into SQLite
+    Fetch,
     /// Show backtest result summary from SQLite
     Report {
         /// Specific run id to inspect
@@ -110,15 +154,23 @@ async fn run() -> Result<(), Report<AppError>> {
     init_tracing(&config);
 
     match cli.command.unwrap_or(Command::Live) {
-        Command::Live => run_live(&config).await,
+        Command::Live => run_live(&config, Path::new(&cli.config)).await,
         Command::Backtest { command } => match command.unwrap_or(BacktestCommand::Run) {
             BacktestCommand::Run => run_backtest(&config).await,
+            BacktestCommand::Fetch => run_backtest_fetch(&config).await,
             BacktestCommand::Report {
                 run_id,
                 limit,
                 trades_limit,
             } => run_backtest_report(&config, run_id, limit, trades_limit).await,
         },
+        Command::Premium { name, hours } => run_premium_report(&config, &name, hours).await,
+        Command::Discrepancies {
+            exchange,
+            symbol,
+            timeframe,
+            hours,
+        } => run_discrepancy_report(&config, &exchange, &symbol, &timeframe, hours).await,
     }
 }
 
@@ -154,6 +206,152 @@ async fn run_backtest(config: &AppConfig) -> Result<(), Report<AppError>> {
     Ok(())
 }
 
+async fn run_backtest_fetch(config: &AppConfig) -> Result<(), Report<AppError>> {
+    let Some(settings) = &config.backtest else {
+        return Err(Report::new(AppError::Config).attach("[backtest] section is required"));
+    };
+    let timeframe = TimeFrame::from_str(&settings.timeframe).ok_or_else(|| {
+        Report::new(AppError::Config).attach(format!("unknown timeframe: {}", settings.timeframe))
+    })?;
+
+    let storage = open_storage(config).await?;
+    let exchange = build_exchanges(config)?
+        .into_iter()
+        .find(|e| e.kind().to_string() == settings.exchange)
+        .ok_or_else(|| {
+            Report::new(AppError::Config).attach(format!(
+                "exchange \"{}\" is not enabled in [[exchanges]]",
+                settings.exchange
+            ))
+        })?;
+
+    // Nothing exists past now; clamping keeps open-ended windows cheap.
+    let end_time = settings.end_time.min(Utc::now());
+    let stored = fetch_and_store_range(
+        exchange.as_ref(),
+        storage.as_ref(),
+        &settings.symbol,
+        timeframe,
+        settings.start_time,
+        end_time,
+    )
+    .await?;
+
+    println!(
+        "exchange={} symbol={} timeframe={} stored={}",
+        settings.exchange, settings.symbol, timeframe, stored
+    );
+
+    Ok(())
+}
+
+async fn run_premium_report(
+    config: &AppConfig,
+    name: &str,
+    hours: i64,
+) -> Result<(), Report<AppError>> {
+    let storage = open_storage(config).await?;
+    let end_time = Utc::now();
+    let samples = storage
+        .get_premium_series(name, end_time - chrono::Duration::hours(hours), end_time)
+        .await
+        .change_context(AppError::Storage)?;
+
+    if samples.is_empty() {
+        println!("no premium samples found for spread={name}");
+        return Ok(());
+    }
+
+    for sample in &samples {
+        println!(
+            "timestamp={} upbit={:.4} binance={:.4} fx={:.4} premium={:.3}%",
+            sample.timestamp,
+            sample.upbit_price,
+            sample.binance_price,
+            sample.fx_rate,
+            sample.premium_pct
+        );
+    }
+
+    let premiums = samples.iter().map(|s| s.premium_pct);
+    let min = premiums.clone().fold(f64::INFINITY, f64::min);
+    let max = premiums.clone().fold(f64::NEG_INFINITY, f64::max);
+    let mean = premiums.sum::<f64>() / samples.len() as f64;
+    println!(
+        "spread={} samples={} min={:.3}% max={:.3}% mean={:.3}%",
+        name,
+        samples.len(),
+        min,
+        max,
+        mean
+    );
+
+    Ok(())
+}
+
+async fn run_discrepancy_report(
+    config: &AppConfig,
+    exchange: &str,
+    symbol: &str,
+    timeframe: &str,
+    hours: i64,
+) -> Result<(), Report<AppError>> {
+    let exchange_kind = exchange
+        .parse::<ExchangeKind>()
+        .map_err(|e| Report::new(AppError::Config).attach(e))?;
+    let timeframe = TimeFrame::from_str(timeframe).ok_or_else(|| {
+        Report::new(AppError::Config).attach(format!("unknown timeframe: {timeframe}"))
+    })?;
+
+    let storage = open_storage(config).await?;
+    let end_time = Utc::now();
+    let discrepancies = storage
+        .get_candle_discrepancies(
+            exchange_kind,
+            symbol,
+            timeframe,
+            end_time - chrono::Duration::hours(hours),
+            end_time,
+        )
+        .await
+        .change_context(AppError::Storage)?;
+
+    if discrepancies.is_empty() {
+        println!(
+            "no candle discrepancies found for exchange={exchange} symbol={symbol} timeframe={}",
+            timeframe.as_str()
+        );
+        return Ok(());
+    }
+
+    for d in &discrepancies {
+        println!(
+            "open_time={} price_diff={:.4}% volume_diff={:.6} ({:.3}%)",
+            d.open_time, d.price_diff_pct, d.volume_diff, d.volume_diff_pct
+        );
+    }
+
+    let worst_price = discrepancies
+        .iter()
+        .map(|d| d.price_diff_pct)
+        .fold(0.0, f64::max);
+    let worst_volume = discrepancies
+        .iter()
+        .map(|d| d.volume_diff_pct.abs())
+        .fold(0.0, f64::max);
+    println!(
+        "exchange={} symbol={} timeframe={} discrepancies={} max_price_diff={:.4}% max_volume_diff={:.3}%",
+        exchange,
+        symbol,
+        timeframe.as_str(),
+        discrepancies.len(),
+        worst_price,
+        worst_volume
+    );
+
+    Ok(())
+}
+
 async fn run_backtest_report(
     config: &AppConfig,
     run_id: Option<String>,
@@ -242,23 +440,139 @@ async fn run_backtest_report(
     Ok(())
 }
 
-async fn run_live(config: &AppConfig) -> Result<(), Report<AppError>> {
+async fn run_live(config: &AppConfig, config_path: &Path) -> Result<(), Report<AppError>> {
     let storage = open_storage(config).await?;
+    let exchanges: Vec<Arc<dyn Exchange>> = build_exchanges(config)?;
+    let notifier: Arc<dyn Notifier> = Arc::new(TerminalNotifier);
+
+    let cancel = CancellationToken::new();
+    let shutdown = cancel.clone();
+    tokio::spawn(async move {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => info!("ctrl+c received, shutting down"),
+            Err(e) => tracing::error!(error = %e, "failed to listen for ctrl+c, shutting down"),
+        }
+        shutdown.cancel();
+    });
+
+    let (watchlist_tx, watchlist_rx) = watch::channel(configured_watchlist(config));
+    #[cfg(unix)]
+    tokio::spawn(reload_watchlist_on_hangup(
+        config_path.to_path_buf(),
+        watchlist_tx,
+        cancel.clone(),
+    ));
+    #[cfg(not(unix))]
+    let _ = (config_path, watchlist_tx);
+
+    run_live_pipeline(config, storage, exchanges, notifier, watchlist_rx, cancel).await
+}
+
+/// A watched coin and the timeframes configured for it.
+#[derive(Debug, Clone, PartialEq)]
+struct WatchedCoin {
+    symbol: String,
+    timeframes: Vec<TimeFrame>,
+}
+
+/// Coins configured under `[[coins]]`, per exchange.
+type Watchlist = HashMap<ExchangeKind, Vec<WatchedCoin>>;
+
+fn configured_watchlist(config: &AppConfig) -> Watchlist {
+    let mut watchlist = Watchlist::new();
+    for coin in &config.coins {
+        let Ok(exchange) = coin.exchange.parse::<ExchangeKind>() else {
+            continue;
+        };
+        let coins = watchlist.entry(exchange).or_default();
+        let index = match coins.iter().position(|c| c.symbol == coin.symbol) {
+            Some(index) => index,
+            None => {
+                coins.push(WatchedCoin {
+                    symbol: coin.symbol.clone(),
+                    timeframes: Vec::new(),
+                });
+                coins.len() - 1
+            }
+        };
+        for timeframe in coin
+            .timeframes
+            .iter()
+            .filter_map(|tf| TimeFrame::from_str(tf))
+        {
+            if !coins[index].timeframes.contains(&timeframe) {
+                coins[index].timeframes.push(timeframe);
+            }
+        }
+    }
+    watchlist
+}
+
+/// Re-read `[[coins]]` from `config_path` on SIGHUP and publish the new
+/// watchlist. Other settings, alerts included, still need a restart.
+#[cfg(unix)]
+async fn reload_watchlist_on_hangup(
+    config_path: PathBuf,
+    watchlist: watch::Sender<Watchlist>,
+    cancel: CancellationToken,
+) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to listen for SIGHUP; watchlist reload disabled");
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            received = hangup.recv() => {
+                if received.is_none() {
+                    break;
+                }
+            }
+        }
+
+        match config::load(&config_path) {
+            Ok(config) => {
+                info!(path = %config_path.display(), "config reloaded, applying watchlist");
+                watchlist.send_replace(configured_watchlist(&config));
+            }
+            Err(e) => {
+                tracing::warn!(error = ?e, "config reload failed; keeping the current watchlist");
+            }
+        }
+    }
+}
 
+/// Run the live pipeline (historical backfill, WebSocket streams, candle sync
+/// and alert analysis) until `cancel` is triggered.
+async fn run_live_pipeline(
+    config: &AppConfig,
+    storage: Arc<dyn Storage>,
+    exchanges: Vec<Arc<dyn Exchange>>,
+    notifier: Arc<dyn Notifier>,
+    watchlist: watch::Receiver<Watchlist>,
+    cancel: CancellationToken,
+) -> Result<(), Report<AppError>> {
     match config.live.risk.max_entries_per_position {
         Some(limit) => info!(max_entries_per_position = limit, "live risk policy loaded"),
         None => info!("live risk policy loaded: unlimited entries"),
     }
 
-    let exchanges: Vec<Arc<dyn Exchange>> = build_exchanges(config);
-
     if exchanges.is_empty() {
         tracing::warn!("no exchanges enabled; nothing to do");
         return Ok(());
     }
 
+    validate_live_symbols(config, &exchanges).await?;
+
     // ── Rules ─────────────────────────────────────────────────────────────────
     let rules: Arc<Vec<AlertRule>> = Arc::new(AlertRule::from_config(config));
+    let spread_engine = SpreadEngine::from_config(config);
     let historical_limit = config.general.historical_candles;
 
     // ── Historical data fetch ─────────────────────────────────────────────────
@@ -308,87 +622,188 @@ async fn run_live(config: &AppConfig) -> Result<(), Report<AppError>> {
         handle.await.change_context(AppError::Runtime)?;
     }
 
+    // Alert analysis reads candles from memory; storage is written behind it
+    let candle_cache = Arc::new(CandleCache::new(config.live.candle_cache_size));
+    for coin in &config.coins {
+        let Ok(exchange_kind) = coin.exchange.parse::<ExchangeKind>() else {
+            continue;
+        };
+        for timeframe in coin
+            .timeframes
+            .iter()
+            .filter_map(|tf| TimeFrame::from_str(tf))
+        {
+            candle_cache
+                .warm(storage.as_ref(), exchange_kind, &coin.symbol, timeframe)
+                .await;
+        }
+    }
+    let candle_flush_interval = Duration::from_millis(config.live.candle_flush_ms);
+
     info!("historical data fetch complete, starting WebSocket streams");
 
     // ── WebSocket channels ────────────────────────────────────────────────────
-    let cancel = CancellationToken::new();
     let (ticker_tx, ticker_rx) = mpsc::channel::<Ticker>(1024);
     let (trade_tx, trade_rx) = mpsc::channel::<Trade>(4096);
+    let (candle_tx, candle_rx) = mpsc::channel::<CandleUpdate>(4096);
+    // Candles of any timeframe as they close, for close-evaluated alerts
+    let (closed_tx, closed_rx) = mpsc::channel::<Candle>(1024);
+    let (orderbook_tx, orderbook_rx) = mpsc::channel::<OrderBook>(1024);
+    let (derivatives_tx, derivatives_rx) = mpsc::channel::<DerivativesSample>(1024);
+    let candles_from_exchange = config.live.candle_source == "exchange";
+    // Closed 1m candles to reconcile
+    let (reconcile_tx, reconcile_rx) = if !candles_from_exchange && config.live.reconcile_candles {
+        let (tx, rx) = mpsc::channel::<Candle>(1024);
+        (Some(tx), Some(rx))
+    } else {
+        (None, None)
+    };
+    let closed_sink = ClosedCandleSink {
+        evaluate: closed_tx,
+        reconcile: reconcile_tx,
+    };
 
     let mut task_handles = Vec::new();
 
-    // WebSocket ticker/trade subscriptions
-    for exchange in &exchanges {
-        let exchange_kind = exchange.kind();
-        let symbols: Vec<String> = config
-            .coins
-            .iter()
-            .filter(|c| c.exchange == exchange_kind.to_string())
-            .map(|c| c.symbol.clone())
-            .collect();
-
-        if symbols.is_empty() {
-            continue;
+    // Record what the exchanges deliver before anything else consumes it
+    let (ticker_tx, trade_tx) = match &config.live.record_path {
+        Some(path) => {
+            let (record_tx, writer) = recorder::start(Path::new(path))
+                .change_context(AppError::Runtime)
+                .attach("failed to start market data recorder")?;
+            task_handles.push(writer);
+            info!(path, "recording tickers and trades");
+            (
+                recorder::tap(ticker_tx, record_tx.clone(), MarketEvent::Ticker),
+                recorder::tap(trade_tx, record_tx, MarketEvent::Trade),
+            )
         }
+        None => (ticker_tx, trade_tx),
+    };
 
-        let ticker_exchange = Arc::clone(exchange);
-        let ticker_tx_clone = ticker_tx.clone();
-        let ticker_cancel = cancel.clone();
-        let ticker_symbols = symbols.clone();
-
-        let ticker_handle = tokio::spawn(async move {
-            if let Err(e) = ticker_exchange
-                .subscribe_ticker(&ticker_symbols, ticker_tx_clone, ticker_cancel)
-                .await
-            {
-                tracing::error!(error = ?e, "ticker subscription failed");
-            }
-        });
-        task_handles.push(ticker_handle);
+    for exchange in &exchanges {
+        task_handles.push(tokio::spawn(log_stream_events(
+            exchange.stream_events(),
+            cancel.clone(),
+        )));
+    }
 
-        let trade_exchange = Arc::clone(exchange);
-        let trade_tx_clone = trade_tx.clone();
-        let trade_cancel = cancel.clone();
+    // Streams, subscriptions and gap repair of each exchange follow its coins
+    // on the watchlist, also on exchanges with no coins yet
+    let mut startup_watchlist = configured_watchlist(config);
+    for exchange in &exchanges {
+        let exchange_kind = exchange.kind();
+        let feeds = ExchangeFeeds {
+            ticker: ticker_tx.clone(),
+            // Exchange candles replace the trade-built ones
+            trades: (!candles_from_exchange).then(|| trade_tx.clone()),
+            orderbook: (config.live.orderbook_snapshot_secs.is_some()
+                || rules.iter().any(|r| {
+                    r.exchange == exchange_kind
+                        && ORDERBOOK_INDICATORS.contains(&r.indicator_name.as_str())
+                }))
+            .then(|| orderbook_tx.clone()),
+            candles: candles_from_exchange.then(|| candle_tx.clone()),
+            derivatives: derivatives_tx.clone(),
+            extra_tickers: spread_engine.extra_ticker_symbols(exchange_kind),
+        };
 
-        let trade_handle = tokio::spawn(async move {
-            if let Err(e) = trade_exchange
-                .subscribe_trades(&symbols, trade_tx_clone, trade_cancel)
-                .await
-            {
-                tracing::error!(error = ?e, "trade subscription failed");
-            }
-        });
-        task_handles.push(trade_handle);
+        // Holes left in stored candles, e.g. by a socket outage, are re-fetched
+        let (gap_series_tx, gap_series_rx) = watch::channel(Vec::new());
+        task_handles.push(tokio::spawn(repair_candle_gaps(
+            Arc::clone(exchange),
+            Arc::clone(&storage),
+            Arc::clone(&candle_cache),
+            gap_series_rx,
+            GapRepairer::new(config.live.gap_scan_candles),
+            Duration::from_secs(config.live.gap_scan_secs),
+            cancel.clone(),
+        )));
+
+        task_handles.push(tokio::spawn(run_exchange_feeds(
+            Arc::clone(exchange),
+            feeds,
+            Arc::clone(&storage),
+            Arc::clone(&candle_cache),
+            startup_watchlist.remove(&exchange_kind).unwrap_or_default(),
+            watchlist.clone(),
+            gap_series_tx,
+            historical_limit,
+            cancel.clone(),
+        )));
     }
 
     // Drop the original sender so the receiver closes when all spawned senders drop
     drop(ticker_tx);
     drop(trade_tx);
+    drop(candle_tx);
+    drop(orderbook_tx);
+    drop(derivatives_tx);
+
+    let candle_sync_handle = if candles_from_exchange {
+        // Store the exchange's own candles for every configured timeframe
+        tokio::spawn(sync_exchange_candles(
+            candle_rx,
+            Arc::clone(&storage),
+            Arc::clone(&candle_cache),
+            candle_flush_interval,
+            closed_sink.clone(),
+        ))
+    } else {
+        // Roll trades into candles of every configured timeframe
+        tokio::spawn(sync_realtime_candles_from_trades(
+            trade_rx,
+            Arc::clone(&storage),
+            Arc::clone(&candle_cache),
+            candle_flush_interval,
+            exchanges.clone(),
+            CandleAggregator::from_config(config),
+            closed_sink.clone(),
+        ))
+    };
+    task_handles.push(candle_sync_handle);
+    drop(closed_sink);
+
+    // Trade-built minutes are replaced by the exchange's official candles
+    if let Some(reconcile_rx) = reconcile_rx {
+        task_handles.push(tokio::spawn(reconcile_trade_candles(
+            reconcile_rx,
+            exchanges.clone(),
+            Arc::clone(&storage),
+            Arc::clone(&candle_cache),
+            Duration::from_secs(config.live.reconcile_delay_secs),
+            cancel.clone(),
+        )));
+    }
 
-    // Sync real-time 1m candles from trades
-    let candle_sync_handle = tokio::spawn(sync_realtime_candles_from_trades(
-        trade_rx,
+    let order_books = Arc::new(LatestOrderBooks::default());
+    task_handles.push(tokio::spawn(track_orderbooks(
+        orderbook_rx,
+        Arc::clone(&order_books),
         Arc::clone(&storage),
-    ));
-    task_handles.push(candle_sync_handle);
+        config.live.orderbook_snapshot_secs.map(Duration::from_secs),
+    )));
+
+    task_handles.push(tokio::spawn(sync_derivatives(
+        derivatives_rx,
+        Arc::clone(&storage),
+    )));
 
     // ── Analysis loop ─────────────────────────────────────────────────────────
-    let notifier: Arc<dyn Notifier> = Arc::new(TerminalNotifier);
     let analysis_handle = tokio::spawn(analysis_loop(
         ticker_rx,
+        closed_rx,
         Arc::clone(&storage),
+        Arc::clone(&candle_cache),
         Arc::clone(&rules),
         Arc::clone(&notifier),
+        order_books,
+        spread_engine,
     ));
     task_handles.push(analysis_handle);
 
     // ── Shutdown ──────────────────────────────────────────────────────────────
-    tokio::signal::ctrl_c()
-        .await
-        .change_context(AppError::Runtime)?;
-
-    info!("ctrl+c received, shutting down");
-    cancel.cancel();
+    cancel.cancelled().await;
 
     for handle in task_handles {
         let _ = tokio::time::timeout(Duration::from_secs(5), handle).await;
@@ -398,6 +813,358 @@ async fn run_live(config: &AppConfig) -> Result<(), Report<AppError>> {
     Ok(())
 }
 
+/// Log WebSocket connect, idle and reconnect events of one exchange until
+/// shutdown.
+async fn log_stream_events(mut events: broadcast::Receiver<WsEvent>, cancel: CancellationToken) {
+    loop {
+        let event = tokio::select! {
+            _ = cancel.cancelled() => break,
+            event = events.recv() => event,
+        };
+
+        let WsEvent {
+            exchange,
+            stream,
+            kind,
+        } = match event {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(skipped, "ws event log fell behind");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        match kind {
+            WsEventKind::Connected => info!(%exchange, stream, "ws stream connected"),
+            WsEventKind::Idle { timeout } => tracing::warn!(
+                %exchange,
+                stream,
+                timeout_secs = timeout.as_secs(),
+                "ws stream idle, forcing reconnect"
+            ),
+            WsEventKind::Disconnected { error } => {
+                tracing::warn!(%exchange, stream, error, "ws stream disconnected")
+            }
+            WsEventKind::Reconnecting { attempt, delay } => info!(
+                %exchange,
+                stream,
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                "ws stream reconnecting"
+            ),
+        }
+    }
+}
+
+/// Check every configured coin against its exchange's market catalogue, so a
+/// mistyped symbol fails at startup instead of leaving its stream silent.
+async fn validate_live_symbols(
+    config: &AppConfig,
+    exchanges: &[Arc<dyn Exchange>],
+) -> Result<(), Report<AppError>> {
+    for exchange in exchanges {
+        let exchange_kind = exchange.kind();
+        let symbols: Vec<&str> = config
+            .coins
+            .iter()
+            .filter(|c| c.exchange == exchange_kind.to_string())
+            .map(|c| c.symbol.as_str())
+            .collect();
+
+        if symbols.is_empty() {
+            continue;
+        }
+
+        let markets = exchange
+            .list_markets()
+            .await
+            .change_context(AppError::Exchange)
+            .attach_with(|| format!("failed to load {exchange_kind} market catalogue"))?;
+
+        let unknown = unknown_symbols(&symbols, &markets);
+        if !unknown.is_empty() {
+            return Err(Report::new(AppError::Config).attach(format!(
+                "unknown {exchange_kind} symbols: {}",
+                unknown.join(", ")
+            )));
+        }
+
+        for market in markets
+            .iter()
+            .filter(|m| symbols.contains(&m.symbol.as_str()))
+        {
+            if !market.trading {
+                tracing::warn!(exchange = %market.exchange, symbol = %market.symbol, "market is not trading");
+            }
+            if market.warning || !market.cautions.is_empty() {
+                tracing::warn!(
+                    exchange = %market.exchange,
+                    symbol = %market.symbol,
+                    warning = market.warning,
+                    cautions = ?market.cautions,
+                    "market is flagged by the exchange"
+                );
+            }
+            tracing::debug!(
+                exchange = %market.exchange,
+                symbol = %market.symbol,
+                instrument = %market.instrument,
+                tick_size = ?market.tick_size,
+                lot_step = ?market.lot_step,
+                min_notional = ?market.min_notional,
+                "market metadata loaded"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Where the streams of one exchange deliver, fixed for the run.
+#[derive(Clone)]
+struct ExchangeFeeds {
+    ticker: mpsc::Sender<Ticker>,
+    /// `None` when exchange candles replace the trade-built ones
+    trades: Option<mpsc::Sender<Trade>>,
+    /// `None` when neither snapshots nor order book alerts need books
+    orderbook: Option<mpsc::Sender<OrderBook>>,
+    /// Exchange candles, when they replace the trade-built ones
+    candles: Option<mpsc::Sender<CandleUpdate>>,
+    derivatives: mpsc::Sender<DerivativesSample>,
+    /// Ticker symbols needed besides the coins, e.g. the spread fx rate
+    extra_tickers: Vec<String>,
+}
+
+impl ExchangeFeeds {
+    /// The market streams of `coins`.
+    fn market_streams(&self, coins: &[WatchedCoin]) -> MarketStreams {
+        let symbols: Vec<String> = coins.iter().map(|c| c.symbol.clone()).collect();
+        let mut tickers = symbols.clone();
+        for extra in &self.extra_tickers {
+            if !tickers.contains(extra) {
+                tickers.push(extra.clone());
+            }
+        }
+        MarketStreams {
+            ticker: Some(StreamSink {
+                symbols: tickers,
+                tx: self.ticker.clone(),
+            }),
+            trades: self.trades.as_ref().map(|tx| StreamSink {
+                symbols: symbols.clone(),
+                tx: tx.clone(),
+            }),
+            orderbook: self.orderbook.as_ref().map(|tx| StreamSink {
+                symbols,
+                tx: tx.clone(),
+            }),
+        }
+    }
+}
+
+/// Run the live feeds of `exchange` for its coins on `watchlist`, starting
+/// from the `startup` coins, which are already validated and backfilled.
+///
+/// This task owns everything that depends on the coins: the market streams
+/// (coins plus extra tickers), the derivatives and exchange candle
+/// subscriptions and the series scanned for gaps. Streams only run while
+/// there are coins. Coins added later are checked against the exchange's
+/// markets, unlisted ones skipped, and backfilled on their own timeframes,
+/// as are timeframes added to a watched coin.
+#[allow(clippy::too_many_arguments)]
+async fn run_exchange_feeds(
+    exchange: Arc<dyn Exchange>,
+    feeds: ExchangeFeeds,
+    storage: Arc<dyn Storage>,
+    cache: Arc<CandleCache>,
+    startup: Vec<WatchedCoin>,
+    mut watchlist: watch::Receiver<Watchlist>,
+    gap_series: watch::Sender<Vec<(String, TimeFrame)>>,
+    historical_limit: usize,
+    cancel: CancellationToken,
+) {
+    let kind = exchange.kind();
+    let mut tasks = tokio::task::JoinSet::new();
+    let mut streams: Option<(SubscriptionHandle, CancellationToken)> = None;
+    let mut derivatives: Option<CancellationToken> = None;
+    let mut candles: Option<CancellationToken> = None;
+    let mut watched: Vec<WatchedCoin> = Vec::new();
+    let mut wanted = startup;
+    let mut started = false;
+    let mut watchlist_open = true;
+
+    loop {
+        while tasks.try_join_next().is_some() {}
+
+        let symbols: Vec<String> = wanted.iter().map(|c| c.symbol.clone()).collect();
+        let watched_symbols: Vec<String> = watched.iter().map(|c| c.symbol.clone()).collect();
+
+        // Market streams change symbols in place while they run
+        match (&streams, symbols.is_empty()) {
+            (Some((handle, _)), false) => handle.update(&feeds.market_streams(&wanted)),
+            (Some(_), true) => {
+                if let Some((_, token)) = streams.take() {
+                    token.cancel();
+                }
+            }
+            (None, false) => {
+                let market = feeds.market_streams(&wanted);
+                let handle = SubscriptionHandle::new(&market);
+                let token = cancel.child_token();
+                let exchange = Arc::clone(&exchange);
+                let (task_handle, task_token) = (handle.clone(), token.clone());
+                tasks.spawn(async move {
+                    if let Err(e) = exchange
+                        .subscribe_streams(market, task_handle, task_token)
+                        .await
+                    {
+                        tracing::error!(error = ?e, "market stream subscription failed");
+                    }
+                });
+                streams = Some((handle, token));
+            }
+            (None, true) => {}
+        }
+
+        // Funding, mark price and open interest; a no-op on spot exchanges
+        if symbols != watched_symbols {
+            if let Some(token) = derivatives.take() {
+                token.cancel();
+            }
+            if !symbols.is_empty() {
+                let token = cancel.child_token();
+                derivatives = Some(token.clone());
+                let (exchange, tx, symbols) = (
+                    Arc::clone(&exchange),
+                    feeds.derivatives.clone(),
+                    symbols.clone(),
+                );
+                tasks.spawn(async move {
+                    if let Err(e) = exchange.subscribe_derivatives(&symbols, tx, token).await {
+                        tracing::error!(error = ?e, "derivatives subscription failed");
+                    }
+                });
+            }
+        }
+
+        if let Some(candle_tx) = &feeds.candles {
+            let groups = group_symbols_by_timeframes(&wanted);
+            if groups != group_symbols_by_timeframes(&watched) {
+                if let Some(token) = candles.take() {
+                    token.cancel();
+                }
+                let token = cancel.child_token();
+                for (timeframes, group_symbols) in groups {
+                    let (exchange, tx, token) =
+                        (Arc::clone(&exchange), candle_tx.clone(), token.clone());
+                    tasks.spawn(async move {
+                        if let Err(e) = exchange
+                            .subscribe_candles(&group_symbols, &timeframes, tx, token)
+                            .await
+                        {
+                            tracing::error!(error = ?e, "candle subscription failed");
+                        }
+                    });
+                }
+                candles = Some(token);
+            }
+        }
+
+        gap_series.send_if_modified(|series| {
+            let wanted_series: Vec<(String, TimeFrame)> = wanted
+                .iter()
+                .flat_map(|c| c.timeframes.iter().map(|&tf| (c.symbol.clone(), tf)))
+                .collect();
+            let modified = *series != wanted_series;
+            *series = wanted_series;
+            modified
+        });
+
+        // Coins added at runtime get the same history as configured ones
+        let backfill: Vec<(String, TimeFrame)> = if started {
+            wanted
+                .iter()
+                .flat_map(|coin| {
+                    let known = watched.iter().find(|w| w.symbol == coin.symbol);
+                    coin.timeframes
+                        .iter()
+                        .filter(move |tf| known.is_none_or(|w| !w.timeframes.contains(tf)))
+                        .map(move |&tf| (coin.symbol.clone(), tf))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        started = true;
+        watched = wanted;
+        for (symbol, timeframe) in backfill {
+            if let Err(e) = fetch_and_store_historical(
+                exchange.as_ref(),
+                storage.as_ref(),
+                &symbol,
+                timeframe,
+                historical_limit,
+            )
+            .await
+            {
+                tracing::warn!(error = ?e, symbol, "backfill of added symbol failed");
+            }
+            cache.warm(storage.as_ref(), kind, &symbol, timeframe).await;
+        }
+
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            changed = watchlist.changed(), if watchlist_open => {
+                if changed.is_err() {
+                    // No more reloads; keep the feeds running until shutdown
+                    watchlist_open = false;
+                    wanted = watched.clone();
+                    continue;
+                }
+            }
+        }
+
+        wanted = watchlist
+            .borrow_and_update()
+            .get(&kind)
+            .cloned()
+            .unwrap_or_default();
+        let added: Vec<&str> = wanted
+            .iter()
+            .map(|c| c.symbol.as_str())
+            .filter(|s| !watched.iter().any(|w| w.symbol == *s))
+            .collect();
+        if !added.is_empty() {
+            let unknown = match exchange.list_markets().await {
+                Ok(markets) => unknown_symbols(&added, &markets),
+                Err(e) => {
+                    // Left out of `watched`, so the next reload retries them
+                    tracing::warn!(error = ?e, exchange = %kind, "failed to load market catalogue; symbols not added");
+                    added.iter().map(|s| (*s).to_owned()).collect()
+                }
+            };
+            if !unknown.is_empty() {
+                tracing::warn!(exchange = %kind, symbols = ?unknown, "ignoring unknown symbols added to the watchlist");
+                wanted.retain(|c| !unknown.contains(&c.symbol));
+            }
+        }
+        if wanted != watched {
+            info!(exchange = %kind, symbols = ?wanted.iter().map(|c| &c.symbol).collect::<Vec<_>>(), "applying watchlist");
+        }
+    }
+
+    while tasks.join_next().await.is_some() {}
+}
+
+fn unknown_symbols(symbols: &[&str], markets: &[Market]) -> Vec<String> {
+    symbols
+        .iter()
+        .filter(|symbol| !markets.iter().any(|m| m.symbol == **symbol))
+        .map(|symbol| (*symbol).to_owned())
+        .collect()
+}
+
 async fn open_storage(config: &AppConfig) -> Result<Arc<dyn Storage>, Report<AppError>> {
     let data_dir = &config.general.data_dir;
     std::fs::create_dir_all(data_dir)
@@ -428,20 +1195,26 @@ fn init_tracing(config: &AppConfig) {
     }
 }
 
-fn build_exchanges(config: &AppConfig) -> Vec<Arc<dyn Exchange>> {
-    config
-        .exchanges
-        .iter()
-        .filter(|e| e.enabled)
-        .filter_map(|e| match e.name.as_str() {
-            "upbit" => Some(Arc::new(UpbitExchange::new()) as Arc<dyn Exchange>),
-            "binance" => Some(Arc::new(BinanceExchange::new()) as Arc<dyn Exchange>),
-            other => {
-                tracing::warn!(name = other, "unknown exchange in config, skipping");
-                None
-            }
-        })
-        .collect()
+fn build_exchanges(config: &AppConfig) -> Result<Vec<Arc<dyn Exchange>>, Report<AppError>> {
+    let mut exchanges: Vec<Arc<dyn Exchange>> = Vec::new();
+    for e in config.exchanges.iter().filter(|e| e.enabled) {
+        let spec = registry::lookup(&e.name).change_context(AppError::Config)?;
+        if let Some(path) = &e.replay_path {
+            let kind = spec.kind;
+            let speed = ReplaySpeed::parse(&e.replay_speed)
+                .ok_or_else(|| Report::new(AppError::Config))
+                .attach_with(|| format!("invalid replay_speed \"{}\"", e.replay_speed))?;
+            let replay = ReplayExchange::open(kind, Path::new(path), speed)
+                .change_context(AppError::Config)
+                .attach_with(|| format!("failed to load {kind} replay"))?;
+            info!(exchange = %kind, path, speed = %e.replay_speed, "replaying recorded market data");
+            exchanges.push(Arc::new(replay));
+            continue;
+        }
+
+        exchanges.push((spec.build)(e));
+    }
+    Ok(exchanges)
 }
 
 async fn fetch_and_store_historical(
@@ -479,79 +1252,808 @@ async fn fetch_and_store_historical(
     Ok(())
 }
 
-async fn analysis_loop(
-    mut rx: mpsc::Receiver<Ticker>,
-    storage: Arc<dyn Storage>,
-    rules: Arc<Vec<AlertRule>>,
-    notifier: Arc<dyn Notifier>,
-) {
-    while let Some(ticker) = rx.recv().await {
-        process_ticker(&ticker, storage.as_ref(), &rules, notifier.as_ref()).await;
-    }
-}
+/// Number of candles requested per `fetch_candles_range` call while
+/// downloading a long window, so progress is persisted between chunks.
+const RANGE_CHUNK_CANDLES: i32 = 1000;
+
+/// Download candles opening within `[start, end)` into storage.
+///
+/// The window is walked backwards from `end` in chunks, storing each chunk as
+/// it arrives. Re-running after an interruption resumes instead of starting
+/// over: see `range_fetch_segments`. A walk stops at its lower bound, or at
+/// an empty chunk with nothing older found by `probe_older_candles`, which
+/// marks the start of the exchange's history.
+async fn fetch_and_store_range(
+    exchange: &dyn Exchange,
+    storage: &dyn Storage,
+    symbol: &str,
+    timeframe: TimeFrame,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<usize, Report<AppError>> {
+    let stored_open_times = storage
+        .get_candle_open_times(exchange.kind(), symbol, timeframe, start, end)
+        .await
+        .change_context(AppError::Storage)?;
+    let chunk = timeframe.duration() * RANGE_CHUNK_CANDLES;
+
+    let mut stored = 0;
+    for (segment_start, segment_end) in
+        range_fetch_segments(start, end, timeframe, &stored_open_times)
+    {
+        // Open time of a candle known to exist further back, if any
+        let mut older_candle: Option<DateTime<Utc>> = None;
+        let mut chunk_end = segment_end;
+        while chunk_end > segment_start {
+            let chunk_start = (chunk_end - chunk).max(segment_start);
+            let candles = exchange
+                .fetch_candles_range(symbol, timeframe, chunk_start, chunk_end)
+                .await
+                .change_context(AppError::Exchange)?;
+
+            if candles.is_empty() {
+                // An outage may leave an empty stretch; stop only once
+                // nothing older turns up
+                if older_candle.is_none_or(|older| older >= chunk_start) {
+                    older_candle = probe_older_candles(
+                        exchange,
+                        symbol,
+                        timeframe,
+                        segment_start,
+                        chunk_start,
+                    )
+                    .await?;
+                    if older_candle.is_none() {
+                        break;
+                    }
+                }
+                chunk_end = chunk_start;
+                continue;
+            }
 
-async fn sync_realtime_candles_from_trades(
-    mut rx: mpsc::Receiver<Trade>,
+            storage
+                .upsert_candles(&candles)
+                .await
+                .change_context(AppError::Storage)?;
+            stored += candles.len();
+
+            info!(
+                exchange = %exchange.kind(),
+                symbol,
+                timeframe = %timeframe,
+                from = %chunk_start,
+                to = %chunk_end,
+                stored,
+                "candle range chunk stored"
+            );
+
+            chunk_end = chunk_start;
+        }
+    }
+
+    Ok(stored)
+}
+
+/// Open time of a candle within `[start, end)` found by sampling chunks at
+/// doubling distances back from `end`, or `None` when every probe is empty.
+async fn probe_older_candles(
+    exchange: &dyn Exchange,
+    symbol: &str,
+    timeframe: TimeFrame,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, Report<AppError>> {
+    for (probe_start, probe_end) in history_probe_windows(start, end, timeframe) {
+        let candles = exchange
+            .fetch_candles_range(symbol, timeframe, probe_start, probe_end)
+            .await
+            .change_context(AppError::Exchange)?;
+        if let Some(newest) = candles.last() {
+            tracing::debug!(
+                exchange = %exchange.kind(),
+                symbol,
+                timeframe = %timeframe,
+                found = %newest.open_time,
+                "older candles exist past an empty chunk"
+            );
+            return Ok(Some(newest.open_time));
+        }
+    }
+    Ok(None)
+}
+
+/// Chunk-sized windows probed for candles within `[start, end)`, newest
+/// first: the chunk right before `end`, then chunks 1, 3, 7, ... chunks
+/// further back, ending with the one at `start`.
+fn history_probe_windows(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    timeframe: TimeFrame,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let chunk = timeframe.duration() * RANGE_CHUNK_CANDLES;
+    let mut windows = Vec::new();
+    let mut skip = 0;
+    loop {
+        let probe_end = end - chunk * skip;
+        if probe_end <= start {
+            break;
+        }
+        let probe_start = (probe_end - chunk).max(start);
+        windows.push((probe_start, probe_end));
+        if probe_start == start {
+            return windows;
+        }
+        skip = skip * 2 + 1;
+    }
+    // Always look at the very start of the window last
+    windows.push((start, (start + chunk).min(end)));
+    windows
+}
+
+/// Reconcile each trade-built 1m candle from `closed_rx` with its exchange's
+/// official candle, `delay` after it closes so the exchange has finalized it.
+async fn reconcile_trade_candles(
+    mut closed_rx: mpsc::Receiver<Candle>,
+    exchanges: Vec<Arc<dyn Exchange>>,
+    storage: Arc<dyn Storage>,
+    cache: Arc<CandleCache>,
+    delay: Duration,
+    cancel: CancellationToken,
+) {
+    let mut pending = tokio::task::JoinSet::new();
+    loop {
+        let candle = tokio::select! {
+            _ = cancel.cancelled() => break,
+            closed = closed_rx.recv() => match closed {
+                Some(candle) => candle,
+                None => break,
+            },
+        };
+        while pending.try_join_next().is_some() {}
+
+        let Some(exchange) = exchanges
+            .iter()
+            .find(|e| e.kind() == candle.exchange)
+            .map(Arc::clone)
+        else {
+            continue;
+        };
+        let storage = Arc::clone(&storage);
+        let cache = Arc::clone(&cache);
+        pending.spawn(async move {
+            tokio::time::sleep(delay).await;
+            reconcile::reconcile_candle(exchange.as_ref(), storage.as_ref(), &cache, &candle).await;
+        });
+    }
+
+    if cancel.is_cancelled() {
+        pending.abort_all();
+    }
+    while pending.join_next().await.is_some() {}
+}
+
+/// Scan the current `series` of `exchange` for candle gaps every `interval`
+/// and once a stream reconnects after an idle timeout or disconnect.
+async fn repair_candle_gaps(
+    exchange: Arc<dyn Exchange>,
+    storage: Arc<dyn Storage>,
+    cache: Arc<CandleCache>,
+    series: watch::Receiver<Vec<(String, TimeFrame)>>,
+    mut repairer: GapRepairer,
+    interval: Duration,
+    cancel: CancellationToken,
+) {
+    let mut events = exchange.stream_events();
+    let mut events_open = true;
+    let mut interrupted = false;
+    let mut timer = tokio::time::interval(interval);
+    timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = timer.tick() => {}
+            event = events.recv(), if events_open => match event {
+                Ok(WsEvent {
+                    kind: WsEventKind::Idle { .. } | WsEventKind::Disconnected { .. },
+                    ..
+                }) => {
+                    interrupted = true;
+                    continue;
+                }
+                Ok(WsEvent { kind: WsEventKind::Connected, .. }) if interrupted => {
+                    interrupted = false;
+                }
+                // Missed events may have included a disconnect
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    interrupted = true;
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    events_open = false;
+                    continue;
+                }
+                Ok(_) => continue,
+            },
+        }
+
+        let mut repaired = 0;
+        let current = series.borrow().clone();
+        for (symbol, timeframe) in &current {
+            repaired += repairer
+                .scan(
+                    exchange.as_ref(),
+                    storage.as_ref(),
+                    &cache,
+                    symbol,
+                    *timeframe,
+                    Utc::now(),
+                )
+                .await;
+        }
+        if repaired > 0 {
+            info!(exchange = %exchange.kind(), repaired, "candle gap scan complete");
+        }
+    }
+}
+
+/// Split `[start, end)` into the sub-windows still worth fetching, newest first.
+///
+/// With nothing stored the whole window is fetched. Otherwise the window is
+/// resumed around what is already stored (`stored_open_times`, oldest
+/// first): from the latest stored candle (which may have been partial) up to
+/// `end`, every hole between stored candles, then from `start` up to the
+/// earliest stored candle.
+fn range_fetch_segments(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    timeframe: TimeFrame,
+    stored_open_times: &[DateTime<Utc>],
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let (Some(&earliest), Some(&latest)) = (stored_open_times.first(), stored_open_times.last())
+    else {
+        return vec![(start, end)];
+    };
+
+    let mut segments = Vec::new();
+    if latest < end {
+        segments.push((latest, end));
+    }
+    let holes = candle_gaps::find_gaps(stored_open_times.iter().copied(), timeframe, latest);
+    segments.extend(holes.into_iter().rev());
+    if start < earliest {
+        segments.push((start, earliest));
+    }
+    segments
+}
+
+/// Evaluate rules on every ticker and on every closed candle until the
+/// ticker channel closes.
+#[allow(clippy::too_many_arguments)]
+async fn analysis_loop(
+    mut rx: mpsc::Receiver<Ticker>,
+    mut closed_rx: mpsc::Receiver<Candle>,
     storage: Arc<dyn Storage>,
+    candle_cache: Arc<CandleCache>,
+    rules: Arc<Vec<AlertRule>>,
+    notifier: Arc<dyn Notifier>,
+    order_books: Arc<LatestOrderBooks>,
+    mut spread_engine: SpreadEngine,
+) {
+    let mut state = AnalysisState {
+        order_books,
+        ..Default::default()
+    };
+    let mut closes_open = true;
+    loop {
+        tokio::select! {
+            ticker = rx.recv() => {
+                let Some(ticker) = ticker else {
+                    break;
+                };
+                process_ticker(
+                    &ticker,
+                    storage.as_ref(),
+                    &candle_cache,
+                    &rules,
+                    notifier.as_ref(),
+                    &mut state,
+                )
+                .await;
+
+                let updates = spread_engine.on_ticker(&ticker);
+                process_spread_updates(&spread_engine, updates, storage.as_ref(), notifier.as_ref())
+                    .await;
+            }
+            closed = closed_rx.recv(), if closes_open => match closed {
+                Some(candle) => {
+                    process_candle_close(
+                        &candle,
+                        storage.as_ref(),
+                        &candle_cache,
+                        &rules,
+                        notifier.as_ref(),
+                        &mut state,
+                    )
+                    .await;
+                }
+                None => closes_open = false,
+            },
+        }
+    }
+}
+
+/// Persist due premium samples and evaluate spread alerts.
+async fn process_spread_updates(
+    engine: &SpreadEngine,
+    updates: Vec<SpreadUpdate>,
+    storage: &dyn Storage,
+    notifier: &dyn Notifier,
 ) {
-    let mut latest_candles: HashMap<(ExchangeKind, String), Candle> = HashMap::new();
+    let samples: Vec<_> = updates
+        .iter()
+        .filter(|u| u.record)
+        .map(|u| u.sample.clone())
+        .collect();
+    if !samples.is_empty()
+        && let Err(e) = storage.insert_premium_samples(&samples).await
+    {
+        tracing::warn!(error = ?e, "failed to store premium samples");
+    }
 
-    while let Some(trade) = rx.recv().await {
-        let Some(candle) = merge_trade_into_minute_candle(&mut latest_candles, &trade) else {
+    for update in updates {
+        let Some(alert) = &engine.monitors[update.monitor].alert else {
             continue;
         };
+        let sample = &update.sample;
+
+        let result = evaluate_spread(sample, &alert.condition, update.previous_premium);
+        if !result.triggered {
+            continue;
+        }
+
+        match cooldown_elapsed(storage, &sample.spread_name, alert.cooldown_minutes).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::debug!(spread = %sample.spread_name, "spread alert suppressed by cooldown");
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(error = ?e, spread = %sample.spread_name, "cooldown check failed");
+                continue;
+            }
+        }
+
+        notifier.notify_spread(sample, &result);
+
+        if let Err(e) = storage
+            .log_spread_alert(
+                &sample.spread_name,
+                &sample.base,
+                sample.premium_pct,
+                &result.message,
+            )
+            .await
+        {
+            tracing::warn!(error = ?e, "failed to log spread alert");
+        }
+    }
+}
+
+/// Group an exchange's coins by their configured timeframe set so each group
+/// can share one candle subscription.
+fn group_symbols_by_timeframes(coins: &[WatchedCoin]) -> Vec<(Vec<TimeFrame>, Vec<String>)> {
+    let mut groups: Vec<(Vec<TimeFrame>, Vec<String>)> = Vec::new();
 
-        if let Err(e) = storage.upsert_candles(&[candle]).await {
+    for coin in coins.iter().filter(|c| !c.timeframes.is_empty()) {
+        match groups.iter_mut().find(|(tfs, _)| *tfs == coin.timeframes) {
+            Some((_, symbols)) => symbols.push(coin.symbol.clone()),
+            None => groups.push((coin.timeframes.clone(), vec![coin.symbol.clone()])),
+        }
+    }
+
+    groups
+}
+
+/// Receivers of candles as they close.
+#[derive(Clone)]
+struct ClosedCandleSink {
+    /// Close evaluation; waits for room so no close-evaluated alert is missed
+    evaluate: mpsc::Sender<Candle>,
+    /// Reconciliation of 1m candles; skipped when it falls behind rather
+    /// than stall candle building
+    reconcile: Option<mpsc::Sender<Candle>>,
+}
+
+impl ClosedCandleSink {
+    async fn send(&self, candle: &Candle) {
+        if let Some(reconcile) = &self.reconcile
+            && candle.timeframe == TimeFrame::Min1
+            && let Err(mpsc::error::TrySendError::Full(_)) = reconcile.try_send(candle.clone())
+        {
             tracing::warn!(
-                error = ?e,
-                exchange = %trade.exchange,
-                symbol = %trade.symbol,
-                "failed to upsert realtime 1m candle"
+                exchange = %candle.exchange,
+                symbol = %candle.symbol,
+                open_time = %candle.open_time,
+                "candle reconciliation fell behind, skipping candle"
             );
         }
+        let _ = self.evaluate.send(candle.clone()).await;
     }
 }
 
-fn merge_trade_into_minute_candle(
-    latest_candles: &mut HashMap<(ExchangeKind, String), Candle>,
-    trade: &Trade,
-) -> Option<Candle> {
-    let key = (trade.exchange, trade.symbol.clone());
-    let minute_open = minute_open_time(trade.timestamp);
+/// Cache the exchange's candles as they arrive and persist them every
+/// `flush_interval` and once more when `rx` closes. Closed candles are also
+/// sent to `closed`.
+async fn sync_exchange_candles(
+    mut rx: mpsc::Receiver<CandleUpdate>,
+    storage: Arc<dyn Storage>,
+    cache: Arc<CandleCache>,
+    flush_interval: Duration,
+    closed: ClosedCandleSink,
+) {
+    let mut writer = CandleWriter::default();
+    let mut timer = tokio::time::interval(flush_interval);
+    timer.tick().await; // skip immediate first tick
+
+    loop {
+        tokio::select! {
+            update = rx.recv() => {
+                let Some(update) = update else {
+                    break;
+                };
+                cache.insert(&update.candle);
+                if update.is_closed {
+                    let candle = &update.candle;
+                    tracing::debug!(
+                        exchange = %candle.exchange,
+                        symbol = %candle.symbol,
+                        timeframe = %candle.timeframe.as_str(),
+                        open_time = %candle.open_time,
+                        "exchange candle closed"
+                    );
+                    closed.send(candle).await;
+                }
+                writer.push(update.candle);
+            }
+            _ = timer.tick() => writer.flush(storage.as_ref()).await,
+        }
+    }
+    writer.flush(storage.as_ref()).await;
+}
+
+/// Seconds between writes of collected derivatives samples.
+const DERIVATIVES_FLUSH_SECS: u64 = 10;
+
+/// Persist derivatives samples as one value per metric and minute, the last
+/// one received in that minute.
+///
+/// Mark price updates arrive every second, so samples are collected and
+/// written every `DERIVATIVES_FLUSH_SECS` and once more when `rx` closes.
+async fn sync_derivatives(mut rx: mpsc::Receiver<DerivativesSample>, storage: Arc<dyn Storage>) {
+    type SampleKey = (ExchangeKind, String, DerivativesMetric, DateTime<Utc>);
+    let mut pending: HashMap<SampleKey, DerivativesSample> = HashMap::new();
+    let mut timer = tokio::time::interval(Duration::from_secs(DERIVATIVES_FLUSH_SECS));
+    timer.tick().await; // skip immediate first tick
+
+    loop {
+        tokio::select! {
+            sample = rx.recv() => {
+                let Some(mut sample) = sample else {
+                    break;
+                };
+                sample.timestamp = minute_open_time(sample.timestamp);
+                let key = (sample.exchange, sample.symbol.clone(), sample.metric, sample.timestamp);
+                pending.insert(key, sample);
+            }
+            _ = timer.tick() => flush_derivatives(&mut pending, storage.as_ref()).await,
+        }
+    }
+    flush_derivatives(&mut pending, storage.as_ref()).await;
+}
+
+async fn flush_derivatives<K>(pending: &mut HashMap<K, DerivativesSample>, storage: &dyn Storage) {
+    if pending.is_empty() {
+        return;
+    }
+    let samples: Vec<DerivativesSample> = pending.drain().map(|(_, sample)| sample).collect();
+    if let Err(e) = storage.upsert_derivatives_samples(&samples).await {
+        tracing::warn!(error = ?e, "failed to store derivatives samples");
+    }
+}
+
+/// Latest order book per coin, for the order book alert indicators.
+#[derive(Debug, Default)]
+struct LatestOrderBooks {
+    books: std::sync::Mutex<HashMap<(ExchangeKind, String), OrderBook>>,
+}
+
+impl LatestOrderBooks {
+    fn insert(&self, book: OrderBook) {
+        self.books
+            .lock()
+            .unwrap()
+            .insert((book.exchange, book.symbol.clone()), book);
+    }
+
+    /// Value of order book `indicator` on the latest book of a coin, or
+    /// `None` before its first book.
+    fn value(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        indicator: &str,
+        depth: usize,
+    ) -> Option<f64> {
+        let books = self.books.lock().unwrap();
+        let book = books.get(&(exchange, symbol.to_owned()))?;
+        orderbook_value(indicator, depth, book)
+    }
+}
+
+/// Keep the latest order book per coin in `latest` and, with a
+/// `snapshot_interval`, persist those that changed once every interval.
+async fn track_orderbooks(
+    mut rx: mpsc::Receiver<OrderBook>,
+    latest: Arc<LatestOrderBooks>,
+    storage: Arc<dyn Storage>,
+    snapshot_interval: Option<Duration>,
+) {
+    let mut changed: HashMap<(ExchangeKind, String), OrderBook> = HashMap::new();
+    let mut timer = tokio::time::interval(snapshot_interval.unwrap_or(Duration::MAX));
+    timer.tick().await; // skip immediate first tick
+
+    loop {
+        tokio::select! {
+            book = rx.recv() => {
+                let Some(book) = book else {
+                    break;
+                };
+                if snapshot_interval.is_some() {
+                    changed.insert((book.exchange, book.symbol.clone()), book.clone());
+                }
+                latest.insert(book);
+            }
+            _ = timer.tick(), if snapshot_interval.is_some() => {
+                if changed.is_empty() {
+                    continue;
+                }
+                let books: Vec<OrderBook> = changed.drain().map(|(_, book)| book).collect();
+                if let Err(e) = storage.insert_orderbook_snapshots(&books).await {
+                    tracing::warn!(error = ?e, "failed to store orderbook snapshots");
+                }
+            }
+        }
+    }
+}
+
+/// Seconds between checks for buckets that ended without a next trade.
+const CLOSE_CHECK_SECS: u64 = 1;
+
+/// Trades this late still land in their bucket before it is reported closed.
+const CLOSE_GRACE_SECS: i64 = 2;
+
+/// Roll trades into candles of every configured timeframe, cache every
+/// update and persist them every `flush_interval` and once more when `rx`
+/// closes. Closed candles are also sent to `closed`, either when a trade
+/// opens the next bucket or once the bucket has ended by the exchange's
+/// clock: its latest trade time plus the time since that trade arrived.
+///
+/// Trades delivered twice are dropped. When trade ids reveal missed trades,
+/// the candles of every timeframe overlapping those minutes are fetched
+/// again from `exchanges` over REST once the last minute has closed,
+/// replacing the incomplete trade-built candles. Buckets still forming
+/// continue from the exchange's candle.
+async fn sync_realtime_candles_from_trades(
+    mut rx: mpsc::Receiver<Trade>,
+    storage: Arc<dyn Storage>,
+    cache: Arc<CandleCache>,
+    flush_interval: Duration,
+    exchanges: Vec<Arc<dyn Exchange>>,
+    mut aggregator: CandleAggregator,
+    closed: ClosedCandleSink,
+) {
+    let mut writer = CandleWriter::default();
+    let mut timer = tokio::time::interval(flush_interval);
+    timer.tick().await; // skip immediate first tick
+    let mut seeded: HashSet<(ExchangeKind, String)> = HashSet::new();
+    let mut sequencer = TradeSequencer::default();
+    // First and last minute open time to fetch again, per coin
+    let mut refetches: HashMap<(ExchangeKind, String), (DateTime<Utc>, DateTime<Utc>)> =
+        HashMap::new();
+    let mut refetch_tasks = tokio::task::JoinSet::new();
+    let mut clocks: HashMap<ExchangeKind, (DateTime<Utc>, tokio::time::Instant)> = HashMap::new();
+    let mut close_timer = tokio::time::interval(Duration::from_secs(CLOSE_CHECK_SECS));
+
+    loop {
+        let trade = tokio::select! {
+            trade = rx.recv() => match trade {
+                Some(trade) => trade,
+                None => break,
+            },
+            _ = timer.tick() => {
+                writer.flush(storage.as_ref()).await;
+                continue;
+            }
+            Some(refetched) = refetch_tasks.join_next(), if !refetch_tasks.is_empty() => {
+                for candle in refetched.unwrap_or_default() {
+                    // Pending partial candles would overwrite it otherwise
+                    if aggregator.reseed(&candle) {
+                        cache.insert(&candle);
+                        writer.push(candle);
+                    }
+                }
+                continue;
+            }
+            _ = close_timer.tick() => {
+                for (&exchange, &(latest, received)) in &clocks {
+                    let now = latest + chrono::Duration::from_std(received.elapsed()).unwrap_or_default()
+                        - chrono::Duration::seconds(CLOSE_GRACE_SECS);
+                    for update in aggregator.close_elapsed(exchange, now) {
+                        closed.send(&update.candle).await;
+                    }
+                }
+                continue;
+            }
+        };
+        let clock = clocks
+            .entry(trade.exchange)
+            .or_insert((trade.timestamp, tokio::time::Instant::now()));
+        if trade.timestamp >= clock.0 {
+            *clock = (trade.timestamp, tokio::time::Instant::now());
+        }
+
+        let key = (trade.exchange, trade.symbol.clone());
+        match sequencer.check(&trade) {
+            TradeCheck::New => {}
+            TradeCheck::Duplicate => continue,
+            TradeCheck::Gap(gap) => {
+                tracing::warn!(
+                    exchange = %gap.exchange,
+                    symbol = %gap.symbol,
+                    missing = gap.missing,
+                    from = %gap.from,
+                    to = %gap.to,
+                    "trade id gap; affected candles will be fetched again"
+                );
+                let (first, last) = (minute_open_time(gap.from), minute_open_time(gap.to));
+                let range = refetches.entry(key.clone()).or_insert((first, last));
+                *range = (range.0.min(first), range.1.max(last));
+            }
+        }
+
+        if seeded.insert(key.clone()) {
+            seed_forming_candles(&mut aggregator, storage.as_ref(), &cache, &trade).await;
+        }
+
+        let updates = aggregator.apply(&trade);
+        if updates.is_empty() {
+            continue;
+        }
+        let past_gap = refetches.get(&key).is_some_and(|&(_, last)| {
+            updates
+                .iter()
+                .any(|u| u.candle.timeframe == TimeFrame::Min1 && u.candle.open_time > last)
+        });
 
-    match latest_candles.get_mut(&key) {
-        Some(candle) if candle.open_time < minute_open => {
-            *candle = new_minute_candle(trade, minute_open);
-            Some(candle.clone())
+        for update in updates {
+            cache.insert(&update.candle);
+            if update.is_closed {
+                closed.send(&update.candle).await;
+            }
+            writer.push(update.candle);
         }
-        Some(candle) if candle.open_time == minute_open => {
-            candle.high = candle.high.max(trade.price);
-            candle.low = candle.low.min(trade.price);
-            candle.close = trade.price;
-            candle.volume += trade.volume;
-            Some(candle.clone())
+
+        if past_gap
+            && let Some((first, last)) = refetches.remove(&key)
+            && let Some(exchange) = exchanges.iter().find(|e| e.kind() == trade.exchange)
+        {
+            // The re-fetched candles must not be overwritten by pending partial ones
+            writer.flush(storage.as_ref()).await;
+            let ranges = gap_refetch_ranges(
+                trade.exchange,
+                &aggregator.timeframes(trade.exchange, &trade.symbol),
+                first,
+                last,
+            );
+            refetch_tasks.spawn(refetch_gap_candles(
+                Arc::clone(exchange),
+                Arc::clone(&storage),
+                Arc::clone(&cache),
+                trade.symbol.clone(),
+                ranges,
+            ));
         }
-        Some(_) => None,
-        None => {
-            let candle = new_minute_candle(trade, minute_open);
-            latest_candles.insert(key, candle.clone());
-            Some(candle)
+    }
+
+    writer.flush(storage.as_ref()).await;
+    while refetch_tasks.join_next().await.is_some() {}
+}
+
+/// Continue the stored candles of the trade's current buckets, so e.g. the
+/// daily candle fetched at startup keeps its earlier volume and range.
+async fn seed_forming_candles(
+    aggregator: &mut CandleAggregator,
+    storage: &dyn Storage,
+    cache: &CandleCache,
+    trade: &Trade,
+) {
+    for timeframe in aggregator.timeframes(trade.exchange, &trade.symbol) {
+        let open_time = bucket_open_time(trade.exchange, timeframe, trade.timestamp);
+        match cache
+            .recent_or_load(storage, trade.exchange, &trade.symbol, timeframe, 1)
+            .await
+        {
+            Ok(candles) => {
+                for candle in candles.into_iter().filter(|c| c.open_time == open_time) {
+                    aggregator.seed(candle);
+                }
+            }
+            Err(e) => tracing::warn!(
+                error = ?e,
+                exchange = %trade.exchange,
+                symbol = %trade.symbol,
+                timeframe = %timeframe.as_str(),
+                "failed to load stored candle to continue"
+            ),
         }
     }
 }
 
-fn new_minute_candle(trade: &Trade, open_time: DateTime<Utc>) -> Candle {
-    Candle {
-        exchange: trade.exchange,
-        symbol: trade.symbol.clone(),
-        timeframe: TimeFrame::Min1,
-        open_time,
-        open: trade.price,
-        high: trade.price,
-        low: trade.price,
-        close: trade.price,
-        volume: trade.volume,
+/// `[start, end)` open time ranges of the buckets of each timeframe that
+/// overlap the minutes `first..=last`.
+fn gap_refetch_ranges(
+    exchange: ExchangeKind,
+    timeframes: &[TimeFrame],
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
+) -> Vec<(TimeFrame, DateTime<Utc>, DateTime<Utc>)> {
+    timeframes
+        .iter()
+        .map(|&timeframe| {
+            let start = bucket_open_time(exchange, timeframe, first);
+            let end = bucket_open_time(exchange, timeframe, last) + timeframe.duration();
+            (timeframe, start, end)
+        })
+        .collect()
+}
+
+/// Replace the cached and stored candles within `ranges` with the
+/// exchange's own. Returns the candles stored.
+async fn refetch_gap_candles(
+    exchange: Arc<dyn Exchange>,
+    storage: Arc<dyn Storage>,
+    cache: Arc<CandleCache>,
+    symbol: String,
+    ranges: Vec<(TimeFrame, DateTime<Utc>, DateTime<Utc>)>,
+) -> Vec<Candle> {
+    let mut refetched = Vec::new();
+    for (timeframe, start, end) in ranges {
+        let candles = match exchange
+            .fetch_candles_range(&symbol, timeframe, start, end)
+            .await
+        {
+            Ok(candles) => candles,
+            Err(e) => {
+                tracing::warn!(error = ?e, exchange = %exchange.kind(), symbol, timeframe = %timeframe.as_str(), "failed to re-fetch candles after trade gap");
+                continue;
+            }
+        };
+        let candles: Vec<Candle> = candles
+            .into_iter()
+            .filter(|c| c.open_time >= start && c.open_time < end)
+            .collect();
+        if let Err(e) = storage.upsert_candles(&candles).await {
+            tracing::warn!(error = ?e, exchange = %exchange.kind(), symbol, timeframe = %timeframe.as_str(), "failed to store re-fetched candles");
+            continue;
+        }
+        for candle in &candles {
+            cache.insert(candle);
+        }
+        info!(exchange = %exchange.kind(), symbol, timeframe = %timeframe.as_str(), from = %start, to = %end, candles = candles.len(), "candles re-fetched after trade gap");
+        refetched.extend(candles);
     }
+    refetched
 }
 
 fn minute_open_time(timestamp: DateTime<Utc>) -> DateTime<Utc> {
@@ -560,15 +2062,154 @@ fn minute_open_time(timestamp: DateTime<Utc>) -> DateTime<Utc> {
     DateTime::from_timestamp(bucket_seconds, 0).unwrap_or(timestamp)
 }
 
+/// Per-rule evaluation state carried across tickers.
+#[derive(Default)]
+struct AnalysisState {
+    /// Ticker or order book indicator value of each rule on the previous
+    /// ticker
+    last_ticker_values: HashMap<String, f64>,
+    order_books: Arc<LatestOrderBooks>,
+    /// Streaming candle indicator of each rule
+    indicator_streams: HashMap<String, IndicatorStream>,
+}
+
+/// Closed candles a stream can step back over when they are corrected,
+/// e.g. by reconciliation, instead of being seeded again.
+const STREAM_CHECKPOINTS: usize = 8;
+
+/// A rule's streaming indicator, advanced up to its newest closed candle.
+struct IndicatorStream {
+    indicator: Box<dyn StreamingIndicator>,
+    /// Oldest candle the indicator was fed
+    first_closed: DateTime<Utc>,
+    last_closed: DateTime<Utc>,
+    last_value: Option<f64>,
+    /// `CandleCache::revision` of the series the stream is consistent with
+    revision: u64,
+    /// State before each of the latest candles fed through `update`
+    checkpoints: VecDeque<StreamCheckpoint>,
+}
+
+struct StreamCheckpoint {
+    /// The candle fed after this state
+    open_time: DateTime<Utc>,
+    indicator: Box<dyn StreamingIndicator>,
+    last_closed: DateTime<Utc>,
+    last_value: Option<f64>,
+}
+
+impl IndicatorStream {
+    /// Feed `indicator` the closed `candles` of cache revision `revision`.
+    /// Without any, the stream counts as advanced up to `next`, the candle
+    /// that follows them.
+    fn seeded(
+        mut indicator: Box<dyn StreamingIndicator>,
+        next: DateTime<Utc>,
+        candles: &[Candle],
+        revision: u64,
+    ) -> Self {
+        // Only the latest candles get checkpoints
+        let (early, late) = candles.split_at(candles.len().saturating_sub(STREAM_CHECKPOINTS));
+        let last_value = indicator.seed(early);
+        let mut stream = Self {
+            indicator,
+            first_closed: candles.first().map_or(next, |c| c.open_time),
+            last_closed: early
+                .last()
+                .map_or(DateTime::<Utc>::MIN_UTC, |c| c.open_time),
+            last_value,
+            revision,
+            checkpoints: VecDeque::new(),
+        };
+        for candle in late {
+            stream.update(candle);
+        }
+        if candles.is_empty() {
+            stream.last_closed = next;
+        }
+        stream
+    }
+
+    /// Advance by a closed candle.
+    fn update(&mut self, candle: &Candle) -> Option<f64> {
+        self.checkpoints.push_back(StreamCheckpoint {
+            open_time: candle.open_time,
+            indicator: self.indicator.snapshot(),
+            last_closed: self.last_closed,
+            last_value: self.last_value,
+        });
+        if self.checkpoints.len() > STREAM_CHECKPOINTS {
+            self.checkpoints.pop_front();
+        }
+        self.last_value = self.indicator.update(candle);
+        self.last_closed = candle.open_time;
+        self.last_value
+    }
+
+    /// Catch up with cache revision `revision`, whose closed candles from
+    /// `changed` on were corrected: step back before the first fed candle
+    /// they touch, so it is fed again. Returns `false` when that is further
+    /// back than the checkpoints reach and the stream must be seeded again.
+    fn rewind(&mut self, changed: Option<DateTime<Utc>>, revision: u64) -> bool {
+        let Some(changed) = changed else {
+            return false;
+        };
+        self.revision = revision;
+        if changed > self.last_closed || changed < self.first_closed {
+            return true;
+        }
+        let Some(i) = self
+            .checkpoints
+            .iter()
+            .position(|c| c.open_time >= changed)
+            .filter(|&i| self.checkpoints[i].last_closed < changed)
+        else {
+            return false;
+        };
+        let Some(checkpoint) = self.checkpoints.drain(i..).next() else {
+            return false;
+        };
+        self.indicator = checkpoint.indicator;
+        self.last_closed = checkpoint.last_closed;
+        self.last_value = checkpoint.last_value;
+        true
+    }
+}
+
+/// Bring `rule`'s stream in line with corrections of its series, or drop it
+/// to be seeded again.
+fn rewind_stream(
+    rule: &AlertRule,
+    exchange: ExchangeKind,
+    symbol: &str,
+    candle_cache: &CandleCache,
+    streams: &mut HashMap<String, IndicatorStream>,
+) {
+    let revision = candle_cache.revision(exchange, symbol, rule.timeframe);
+    let Some(stream) = streams.get_mut(&rule.name) else {
+        return;
+    };
+    if stream.revision == revision {
+        return;
+    }
+    let changed = candle_cache.changed_since(exchange, symbol, rule.timeframe, stream.revision);
+    if !stream.rewind(changed, revision) {
+        streams.remove(&rule.name);
+    }
+}
+
 async fn process_ticker(
     ticker: &Ticker,
     storage: &dyn Storage,
+    candle_cache: &CandleCache,
     rules: &[AlertRule],
     notifier: &dyn Notifier,
+    state: &mut AnalysisState,
 ) {
     let matching_rules: Vec<&AlertRule> = rules
         .iter()
         .filter(|r| r.exchange == ticker.exchange && r.symbol == ticker.symbol)
+        .filter(|r| r.evaluate_on == EvaluateOn::Tick)
         .collect();
 
     if matching_rules.is_empty() {
@@ -576,81 +2217,347 @@ async fn process_ticker(
     }
 
     for rule in matching_rules {
-        // Find an appropriate timeframe for this rule (use first available from DB)
-        // We try 1m candles for signal computation
-        let timeframe = TimeFrame::Min1;
-        let indicator = build_indicator(rule);
-        let required = indicator.required_candles();
-
-        // Fetch enough candles for the indicator (need +1 for previous value)
-        let candles = match storage
-            .get_recent_candles(ticker.exchange, &ticker.symbol, timeframe, required + 1)
-            .await
-        {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::warn!(error = ?e, rule = %rule.name, "failed to fetch candles");
+        let (current, previous) = if TICKER_INDICATORS.contains(&rule.indicator_name.as_str()) {
+            let Some(current) = ticker_value(&rule.indicator_name, ticker) else {
                 continue;
+            };
+            // Cross conditions compare against the rule's value on the previous ticker
+            let previous = state.last_ticker_values.insert(rule.name.clone(), current);
+            (current, previous)
+        } else if ORDERBOOK_INDICATORS.contains(&rule.indicator_name.as_str()) {
+            let depth = rule.indicator_params.period.unwrap_or(DEFAULT_BOOK_DEPTH);
+            let Some(current) = state.order_books.value(
+                ticker.exchange,
+                &ticker.symbol,
+                &rule.indicator_name,
+                depth,
+            ) else {
+                continue;
+            };
+            let previous = state.last_ticker_values.insert(rule.name.clone(), current);
+            (current, previous)
+        } else if let Some(metric) = derivatives_metric(&rule.indicator_name) {
+            match derivatives_indicator_values(rule, metric, ticker, storage).await {
+                Some(values) => values,
+                None => continue,
+            }
+        } else {
+            match candle_indicator_values(
+                rule,
+                ticker,
+                storage,
+                candle_cache,
+                &mut state.indicator_streams,
+            )
+            .await
+            {
+                Some(values) => values,
+                None => continue,
             }
         };
 
-        if candles.len() < required {
-            tracing::debug!(
-                rule = %rule.name,
-                available = candles.len(),
-                required,
-                "insufficient candles for indicator"
-            );
+        fire_alert(rule, ticker, current, previous, storage, notifier).await;
+    }
+}
+
+/// Evaluate the close-evaluated rules of `candle`'s series once it has
+/// closed, on indicator values over closed candles only.
+async fn process_candle_close(
+    candle: &Candle,
+    storage: &dyn Storage,
+    candle_cache: &CandleCache,
+    rules: &[AlertRule],
+    notifier: &dyn Notifier,
+    state: &mut AnalysisState,
+) {
+    let matching_rules = rules.iter().filter(|r| {
+        r.evaluate_on == EvaluateOn::Close
+            && r.exchange == candle.exchange
+            && r.symbol == candle.symbol
+            && r.timeframe == candle.timeframe
+    });
+
+    for rule in matching_rules {
+        let Some((current, previous)) = closed_candle_indicator_values(
+            rule,
+            candle,
+            storage,
+            candle_cache,
+            &mut state.indicator_streams,
+        )
+        .await
+        else {
             continue;
+        };
+
+        // Notifiers report a ticker; the candle's close stands in for it
+        let ticker = Ticker {
+            exchange: candle.exchange,
+            symbol: candle.symbol.clone(),
+            instrument: candle.instrument.clone(),
+            price: candle.close,
+            volume: 0.0,
+            stats: Default::default(),
+            timestamp: candle.open_time + candle.timeframe.duration(),
+            received_at: Utc::now(),
+        };
+        fire_alert(rule, &ticker, current, previous, storage, notifier).await;
+    }
+}
+
+/// Notify and log `rule` if its condition holds and its cooldown has passed.
+async fn fire_alert(
+    rule: &AlertRule,
+    ticker: &Ticker,
+    current: f64,
+    previous: Option<f64>,
+    storage: &dyn Storage,
+    notifier: &dyn Notifier,
+) {
+    let result = evaluate(rule, current, previous);
+    if !result.triggered {
+        return;
+    }
+
+    match should_alert(storage, rule).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::debug!(rule = %rule.name, "alert suppressed by cooldown");
+            return;
+        }
+        Err(e) => {
+            tracing::warn!(error = ?e, rule = %rule.name, "cooldown check failed");
+            return;
         }
+    }
 
-        let values = match indicator.calculate(&candles) {
-            Ok(v) => v,
-            Err(e) => {
-                tracing::warn!(error = ?e, rule = %rule.name, "indicator calculation failed");
-                continue;
-            }
-        };
+    notifier.notify(ticker, &result);
 
-        if values.is_empty() {
-            continue;
+    if let Err(e) = storage
+        .log_alert(
+            &rule.name,
+            ticker.exchange,
+            &ticker.symbol,
+            result.indicator_value,
+            &result.message,
+        )
+        .await
+    {
+        tracing::warn!(error = ?e, "failed to log alert");
+    }
+}
+
+/// Latest and previous value of `rule`'s derivatives indicator as of the
+/// ticker from the stored samples of `metric`, or `None` when there are not
+/// enough yet. The previous value is the one just before the latest sample.
+async fn derivatives_indicator_values(
+    rule: &AlertRule,
+    metric: DerivativesMetric,
+    ticker: &Ticker,
+    storage: &dyn Storage,
+) -> Option<(f64, Option<f64>)> {
+    let period = rule
+        .indicator_params
+        .period
+        .unwrap_or(DEFAULT_OI_CHANGE_PERIOD);
+    let window = derivatives_window(&rule.indicator_name, period);
+    let at = ticker.timestamp;
+
+    let samples = if window.is_zero() {
+        // The latest sample, and the one before for the previous value
+        storage
+            .get_recent_derivatives_samples(ticker.exchange, &ticker.symbol, metric, 2)
+            .await
+    } else {
+        // The previous value's samples may be up to one sample age older
+        let start = at - window - chrono::Duration::seconds(OI_SAMPLE_MAX_AGE_SECS);
+        storage
+            .get_derivatives_series(ticker.exchange, &ticker.symbol, metric, start, at)
+            .await
+    };
+    let samples = match samples {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!(error = ?e, rule = %rule.name, "failed to fetch derivatives samples");
+            return None;
         }
+    };
 
-        let current = *values.last().unwrap();
-        let previous = values.len().checked_sub(2).map(|i| values[i]);
+    let current = derivatives_value(&rule.indicator_name, period, &samples, at)?;
+    let previous = samples
+        .iter()
+        .rev()
+        .find(|s| s.timestamp <= at)
+        .and_then(|latest| {
+            let before = latest.timestamp - chrono::Duration::milliseconds(1);
+            derivatives_value(&rule.indicator_name, period, &samples, before)
+        });
+    Some((current, previous))
+}
 
-        let result = evaluate(rule, current, previous);
-        if !result.triggered {
-            continue;
+/// Latest and previous value of `rule`'s candle indicator, or `None` when
+/// there are not enough candles yet.
+///
+/// The newest cached candle is the forming one and is only peeked; older
+/// candles are fed to the rule's stream once. The stream is re-seeded from
+/// the latest candles when it has fallen out of the cache or closed candles
+/// it consumed were corrected since.
+async fn candle_indicator_values(
+    rule: &AlertRule,
+    ticker: &Ticker,
+    storage: &dyn Storage,
+    candle_cache: &CandleCache,
+    streams: &mut HashMap<String, IndicatorStream>,
+) -> Option<(f64, Option<f64>)> {
+    let timeframe = rule.timeframe;
+    rewind_stream(rule, ticker.exchange, &ticker.symbol, candle_cache, streams);
+    let revision = candle_cache.revision(ticker.exchange, &ticker.symbol, timeframe);
+
+    let newer = streams.get(&rule.name).and_then(|stream| {
+        candle_cache.after(
+            ticker.exchange,
+            &ticker.symbol,
+            timeframe,
+            stream.last_closed,
+        )
+    });
+    if let Some(stream) = streams.get_mut(&rule.name)
+        && let Some((forming, closed)) = newer.as_deref().and_then(|c| c.split_last())
+    {
+        for candle in closed {
+            stream.update(candle);
         }
+        let current = stream.indicator.peek(forming)?;
+        return Some((current, stream.last_value));
+    }
 
-        match should_alert(storage, rule).await {
-            Ok(true) => {}
-            Ok(false) => {
-                tracing::debug!(rule = %rule.name, "alert suppressed by cooldown");
-                continue;
-            }
-            Err(e) => {
-                tracing::warn!(error = ?e, rule = %rule.name, "cooldown check failed");
-                continue;
-            }
+    let indicator = build_indicator(rule);
+    let required = indicator.required_candles();
+
+    // Fetch enough candles for the indicator (need +1 for previous value)
+    let candles = recent_rule_candles(
+        rule,
+        ticker.exchange,
+        &ticker.symbol,
+        storage,
+        candle_cache,
+        required + 1,
+        required,
+    )
+    .await?;
+
+    let (forming, closed) = candles.split_last()?;
+    let stream = IndicatorStream::seeded(indicator.stream(), forming.open_time, closed, revision);
+    let previous = stream.last_value;
+    let current = stream.indicator.peek(forming);
+    streams.insert(rule.name.clone(), stream);
+    Some((current?, previous))
+}
+
+/// Value of `rule`'s candle indicator at the just closed `closed` candle
+/// and at the one before it, or `None` when there are not enough candles or
+/// this close was already evaluated.
+async fn closed_candle_indicator_values(
+    rule: &AlertRule,
+    closed: &Candle,
+    storage: &dyn Storage,
+    candle_cache: &CandleCache,
+    streams: &mut HashMap<String, IndicatorStream>,
+) -> Option<(f64, Option<f64>)> {
+    // Corrections since may step the stream back or start it over, so
+    // remember whether this close was already evaluated
+    let evaluated = streams
+        .get(&rule.name)
+        .is_some_and(|stream| stream.last_closed >= closed.open_time);
+    rewind_stream(rule, closed.exchange, &closed.symbol, candle_cache, streams);
+    let revision = candle_cache.revision(closed.exchange, &closed.symbol, rule.timeframe);
+
+    let newer = streams.get(&rule.name).and_then(|stream| {
+        candle_cache.after(
+            closed.exchange,
+            &closed.symbol,
+            rule.timeframe,
+            stream.last_closed,
+        )
+    });
+    if let Some(stream) = streams.get_mut(&rule.name)
+        && let Some(newer) = newer
+    {
+        let mut values = None;
+        for candle in newer.iter().take_while(|c| c.open_time <= closed.open_time) {
+            let previous = stream.last_value;
+            values = Some((stream.update(candle), previous));
         }
+        if evaluated {
+            return None;
+        }
+        let (current, previous) = values?;
+        return Some((current?, previous));
+    }
 
-        notifier.notify(ticker.exchange, &ticker.symbol, ticker.price, &result);
+    let indicator = build_indicator(rule);
+    let required = indicator.required_candles();
+
+    // One more for the previous value, one more for a candle already forming
+    let candles = recent_rule_candles(
+        rule,
+        closed.exchange,
+        &closed.symbol,
+        storage,
+        candle_cache,
+        required + 2,
+        required,
+    )
+    .await?;
+    let candles: Vec<Candle> = candles
+        .into_iter()
+        .filter(|c| c.open_time <= closed.open_time)
+        .collect();
 
-        if let Err(e) = storage
-            .log_alert(
-                &rule.name,
-                ticker.exchange,
-                &ticker.symbol,
-                result.indicator_value,
-                &result.message,
-            )
-            .await
-        {
-            tracing::warn!(error = ?e, "failed to log alert");
+    let (last, older) = candles.split_last()?;
+    let mut stream = IndicatorStream::seeded(indicator.stream(), last.open_time, older, revision);
+    let previous = stream.last_value;
+    let current = stream.update(last);
+    streams.insert(rule.name.clone(), stream);
+    if evaluated {
+        return None;
+    }
+    Some((current?, previous))
+}
+
+/// The latest `limit` candles of `rule`'s series, or `None` when fewer than
+/// `required` are available.
+async fn recent_rule_candles(
+    rule: &AlertRule,
+    exchange: ExchangeKind,
+    symbol: &str,
+    storage: &dyn Storage,
+    candle_cache: &CandleCache,
+    limit: usize,
+    required: usize,
+) -> Option<Vec<Candle>> {
+    let candles = match candle_cache
+        .recent_or_load(storage, exchange, symbol, rule.timeframe, limit)
+        .await
+    {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!(error = ?e, rule = %rule.name, "failed to fetch candles");
+            return None;
         }
+    };
+
+    if candles.len() < required {
+        tracing::debug!(
+            rule = %rule.name,
+            timeframe = %rule.timeframe,
+            available = candles.len(),
+            required,
+            "insufficient candles for indicator"
+        );
+        return None;
     }
+    Some(candles)
 }
 
 fn build_indicator(rule: &AlertRule) -> Box<dyn Indicator> {
@@ -694,17 +2601,74 @@ fn build_indicator(rule: &AlertRule) -> Box<dyn Indicator> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::TradeSide;
+    use crate::exchange::binance::BinanceExchange;
+    use crate::exchange::mock_server;
+    use crate::model::{Candle, Instrument, PremiumSample, TickerStats, TradeSide};
+    use crate::strategy::condition::EvaluationResult;
+    use crate::strategy::{ConditionType, EvaluateOn, IndicatorParams};
 
-    fn make_trade(timestamp: i64, price: f64, volume: f64) -> Trade {
-        Trade {
-            exchange: ExchangeKind::Upbit,
-            symbol: "KRW-SOL".into(),
-            price,
-            volume,
-            side: TradeSide::Buy,
-            timestamp: DateTime::from_timestamp(timestamp, 0).unwrap(),
-        }
+    #[test]
+    fn range_fetch_segments_without_stored_candles_fetches_whole_window() {
+        let start = DateTime::from_timestamp(0, 0).unwrap();
+        let end = DateTime::from_timestamp(6000, 0).unwrap();
+        assert_eq!(
+            range_fetch_segments(start, end, TimeFrame::Min1, &[]),
+            vec![(start, end)]
+        );
+    }
+
+    #[test]
+    fn range_fetch_segments_resumes_around_and_between_stored_candles() {
+        let at = |secs| DateTime::from_timestamp(secs, 0).unwrap();
+        let stored = [at(1200), at(1260), at(1500), at(1560), at(3000)];
+
+        let segments = range_fetch_segments(at(0), at(6000), TimeFrame::Min1, &stored);
+        assert_eq!(
+            segments,
+            vec![
+                (at(3000), at(6000)),
+                (at(1620), at(3000)),
+                (at(1320), at(1500)),
+                (at(0), at(1200)),
+            ]
+        );
+    }
+
+    #[test]
+    fn range_fetch_segments_skips_fully_covered_sides() {
+        let start = DateTime::from_timestamp(0, 0).unwrap();
+        let end = DateTime::from_timestamp(120, 0).unwrap();
+        let stored = [start, DateTime::from_timestamp(60, 0).unwrap(), end];
+
+        let segments = range_fetch_segments(start, end, TimeFrame::Min1, &stored);
+        assert!(segments.is_empty());
+    }
+
+    #[test]
+    fn history_probes_reach_back_at_doubling_distances() {
+        let chunk = TimeFrame::Min1.duration() * RANGE_CHUNK_CANDLES;
+        let end = DateTime::from_timestamp(1_704_067_200, 0).unwrap();
+        let before = |chunks: i32| end - chunk * chunks;
+
+        let windows = history_probe_windows(before(20), end, TimeFrame::Min1);
+        assert_eq!(
+            windows,
+            vec![
+                (before(1), end),
+                (before(2), before(1)),
+                (before(4), before(3)),
+                (before(8), before(7)),
+                (before(16), before(15)),
+                (before(20), before(19)),
+            ]
+        );
+
+        // A window shorter than a chunk is probed whole
+        let short = end - chrono::Duration::minutes(5);
+        assert_eq!(
+            history_probe_windows(short, end, TimeFrame::Min1),
+            vec![(short, end)]
+        );
     }
 
     #[test]
@@ -714,54 +2678,1087 @@ mod tests {
     }
 
     #[test]
-    fn merge_trade_updates_existing_minute_candle() {
-        let mut latest = HashMap::new();
-        let first = make_trade(180, 100.0, 1.2);
-        let second = make_trade(185, 110.0, 0.8);
+    fn group_symbols_by_timeframes_shares_identical_sets() {
+        let config: AppConfig = toml::from_str(
+            r#"
+[general]
+
+[[coins]]
+exchange = "binance"
+symbol = "BTCUSDT"
+timeframes = ["1m", "5m"]
+
+[[coins]]
+exchange = "binance"
+symbol = "ETHUSDT"
+timeframes = ["1m", "5m"]
+
+[[coins]]
+exchange = "binance"
+symbol = "XRPUSDT"
+timeframes = ["1h"]
+
+[[coins]]
+exchange = "upbit"
+symbol = "KRW-BTC"
+timeframes = ["1m"]
+"#,
+        )
+        .unwrap();
+
+        let groups =
+            group_symbols_by_timeframes(&configured_watchlist(&config)[&ExchangeKind::Binance]);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, vec![TimeFrame::Min1, TimeFrame::Min5]);
+        assert_eq!(groups[0].1, vec!["BTCUSDT", "ETHUSDT"]);
+        assert_eq!(groups[1].0, vec![TimeFrame::Hour1]);
+        assert_eq!(groups[1].1, vec!["XRPUSDT"]);
+    }
 
-        let _ = merge_trade_into_minute_candle(&mut latest, &first);
-        let updated = merge_trade_into_minute_candle(&mut latest, &second).unwrap();
+    #[test]
+    fn unknown_symbols_reports_missing_markets() {
+        let market = |symbol: &str| Market {
+            exchange: ExchangeKind::Upbit,
+            symbol: symbol.to_owned(),
+            instrument: Instrument::default(),
+            tick_size: None,
+            lot_step: None,
+            min_notional: None,
+            trading: true,
+            warning: false,
+            cautions: Vec::new(),
+        };
+        let markets = vec![market("KRW-BTC"), market("KRW-SOL")];
 
-        assert_eq!(updated.open, 100.0);
-        assert_eq!(updated.high, 110.0);
-        assert_eq!(updated.low, 100.0);
-        assert_eq!(updated.close, 110.0);
-        assert_eq!(updated.volume, 2.0);
-        assert_eq!(updated.open_time.timestamp(), 180);
+        assert!(unknown_symbols(&["KRW-BTC", "KRW-SOL"], &markets).is_empty());
+        assert_eq!(
+            unknown_symbols(&["KRW-BTC", "KRW-SOLL"], &markets),
+            vec!["KRW-SOLL"]
+        );
     }
 
-    #[test]
-    fn merge_trade_rolls_over_to_new_minute_candle() {
-        let mut latest = HashMap::new();
-        let first = make_trade(180, 100.0, 1.0);
-        let second = make_trade(240, 95.0, 0.5);
+    #[tokio::test]
+    async fn exchange_feeds_follow_the_watchlist() {
+        let server = mock_server::binance("BTCUSDT", 42000.0).start().await;
+        let exchange: Arc<dyn Exchange> =
+            Arc::new(BinanceExchange::new(&server.base_url(), &server.ws_url("")));
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open(&db_path).await.unwrap());
+        let (ticker_tx, mut ticker_rx) = mpsc::channel(16);
+        let feeds = ExchangeFeeds {
+            ticker: ticker_tx,
+            trades: None,
+            orderbook: None,
+            candles: None,
+            derivatives: mpsc::channel(16).0,
+            extra_tickers: Vec::new(),
+        };
+        let (watchlist_tx, watchlist_rx) = watch::channel(Watchlist::new());
+        let (gap_series_tx, mut gap_series) = watch::channel(Vec::new());
+        let cancel = CancellationToken::new();
+        // No coins on this exchange at startup
+        let task = tokio::spawn(run_exchange_feeds(
+            exchange,
+            feeds,
+            Arc::clone(&storage),
+            Arc::new(CandleCache::new(10)),
+            Vec::new(),
+            watchlist_rx,
+            gap_series_tx,
+            5,
+            cancel.clone(),
+        ));
+
+        // The mock lists only BTCUSDT
+        let coin = |symbol: &str, timeframes: &[TimeFrame]| WatchedCoin {
+            symbol: symbol.into(),
+            timeframes: timeframes.to_vec(),
+        };
+        watchlist_tx.send_replace(Watchlist::from([(
+            ExchangeKind::Binance,
+            vec![
+                coin("BTCUSDT", &[TimeFrame::Min1, TimeFrame::Min5]),
+                coin("NOPEUSDT", &[TimeFrame::Min1]),
+            ],
+        )]));
+        let wait = Duration::from_secs(5);
+        tokio::time::timeout(wait, gap_series.wait_for(|s| !s.is_empty()))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            *gap_series.borrow(),
+            vec![
+                ("BTCUSDT".to_owned(), TimeFrame::Min1),
+                ("BTCUSDT".to_owned(), TimeFrame::Min5)
+            ]
+        );
+        let ticker = tokio::time::timeout(wait, ticker_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ticker.symbol, "BTCUSDT");
+
+        // The added coin is backfilled on its own timeframes
+        tokio::time::timeout(wait, async {
+            while storage
+                .get_recent_candles(ExchangeKind::Binance, "BTCUSDT", TimeFrame::Min5, 10)
+                .await
+                .unwrap()
+                .is_empty()
+            {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
 
-        let _ = merge_trade_into_minute_candle(&mut latest, &first);
-        let rolled = merge_trade_into_minute_candle(&mut latest, &second).unwrap();
+        watchlist_tx.send_replace(Watchlist::new());
+        tokio::time::timeout(wait, gap_series.wait_for(|s| s.is_empty()))
+            .await
+            .unwrap()
+            .unwrap();
 
-        assert_eq!(rolled.open, 95.0);
-        assert_eq!(rolled.high, 95.0);
-        assert_eq!(rolled.low, 95.0);
-        assert_eq!(rolled.close, 95.0);
-        assert_eq!(rolled.volume, 0.5);
-        assert_eq!(rolled.open_time.timestamp(), 240);
+        cancel.cancel();
+        task.await.unwrap();
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
     }
 
     #[test]
-    fn merge_trade_ignores_out_of_order_old_minute() {
-        let mut latest = HashMap::new();
-        let recent = make_trade(240, 100.0, 1.0);
-        let stale = make_trade(180, 90.0, 0.5);
+    fn gap_refetch_covers_every_overlapping_bucket() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let ranges = gap_refetch_ranges(
+            ExchangeKind::Upbit,
+            &[TimeFrame::Min1, TimeFrame::Min5, TimeFrame::Day1],
+            at("2024-01-01T14:58:00Z"),
+            at("2024-01-01T15:01:00Z"),
+        );
+        assert_eq!(
+            ranges,
+            vec![
+                (
+                    TimeFrame::Min1,
+                    at("2024-01-01T14:58:00Z"),
+                    at("2024-01-01T15:02:00Z")
+                ),
+                (
+                    TimeFrame::Min5,
+                    at("2024-01-01T14:55:00Z"),
+                    at("2024-01-01T15:05:00Z")
+                ),
+                // Both Upbit days around 00:00 KST
+                (
+                    TimeFrame::Day1,
+                    at("2023-12-31T15:00:00Z"),
+                    at("2024-01-02T15:00:00Z")
+                ),
+            ]
+        );
+    }
 
-        let _ = merge_trade_into_minute_candle(&mut latest, &recent);
-        let ignored = merge_trade_into_minute_candle(&mut latest, &stale);
+    #[tokio::test]
+    async fn quiet_trade_candles_close_without_a_next_trade() {
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open(&db_path).await.unwrap());
+        let (tx, rx) = mpsc::channel(16);
+        let (closed_tx, mut closed_rx) = mpsc::channel(16);
+        let sync = tokio::spawn(sync_realtime_candles_from_trades(
+            rx,
+            storage,
+            Arc::new(CandleCache::new(10)),
+            Duration::from_secs(1),
+            Vec::new(),
+            CandleAggregator::default(),
+            ClosedCandleSink {
+                evaluate: closed_tx,
+                reconcile: None,
+            },
+        ));
+
+        // The only trade, a second before its minute ends
+        tx.send(Trade {
+            exchange: ExchangeKind::Binance,
+            symbol: "BTCUSDT".into(),
+            instrument: Instrument::new("BTC", "USDT"),
+            price: 100.0,
+            volume: 1.0,
+            side: TradeSide::Buy,
+            timestamp: DateTime::from_timestamp(1_704_067_259, 0).unwrap(),
+            received_at: DateTime::from_timestamp(1_704_067_259, 0).unwrap(),
+            trade_id: None,
+        })
+        .await
+        .unwrap();
 
-        assert!(ignored.is_none());
-        let candle = latest
-            .get(&(ExchangeKind::Upbit, "KRW-SOL".to_string()))
+        let candle = tokio::time::timeout(Duration::from_secs(10), closed_rx.recv())
+            .await
+            .expect("timeout")
             .unwrap();
-        assert_eq!(candle.open_time.timestamp(), 240);
-        assert_eq!(candle.close, 100.0);
+        assert_eq!(candle.open_time.timestamp(), 1_704_067_200);
         assert_eq!(candle.volume, 1.0);
+
+        drop(tx);
+        sync.await.unwrap();
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn trade_candles_drop_duplicates_and_refetch_gaps() {
+        let server = mock_server::binance("BTCUSDT", 42000.0).start().await;
+        let exchange: Arc<dyn Exchange> =
+            Arc::new(BinanceExchange::new(&server.base_url(), &server.ws_url("")));
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open(&db_path).await.unwrap());
+        let trade = |id, secs: i64| Trade {
+            exchange: ExchangeKind::Binance,
+            symbol: "BTCUSDT".into(),
+            instrument: Instrument::new("BTC", "USDT"),
+            price: 100.0,
+            volume: 1.0,
+            side: TradeSide::Buy,
+            timestamp: DateTime::from_timestamp(1_704_067_200 + secs, 0).unwrap(),
+            received_at: DateTime::from_timestamp(1_704_067_200 + secs, 0).unwrap(),
+            trade_id: Some(id),
+        };
+
+        let (tx, rx) = mpsc::channel(16);
+        // Trades 2..=4 are missed within minutes 1 and 2; trade 5 arrives twice
+        for (id, secs) in [(1, 70), (5, 130), (5, 130), (6, 300), (6, 300)] {
+            tx.send(trade(id, secs)).await.unwrap();
+        }
+        drop(tx);
+        let cache = Arc::new(CandleCache::new(10));
+        sync_realtime_candles_from_trades(
+            rx,
+            Arc::clone(&storage),
+            Arc::clone(&cache),
+            Duration::from_secs(1),
+            vec![exchange],
+            CandleAggregator::default(),
+            ClosedCandleSink {
+                evaluate: mpsc::channel(16).0,
+                reconcile: None,
+            },
+        )
+        .await;
+
+        let candles = storage
+            .get_recent_candles(ExchangeKind::Binance, "BTCUSDT", TimeFrame::Min1, 10)
+            .await
+            .unwrap();
+        let by_minute = |minute: i64| {
+            candles
+                .iter()
+                .find(|c| c.open_time.timestamp() == 1_704_067_200 + minute * 60)
+                .unwrap()
+        };
+        // The gap's minutes hold the exchange's candles, not the partial ones
+        assert_eq!(by_minute(1).close, 42001.0);
+        assert_eq!(by_minute(2).close, 42002.0);
+        assert_eq!(by_minute(2).volume, 10.0);
+        // The repeated trade counted once
+        assert_eq!(by_minute(5).close, 100.0);
+        assert_eq!(by_minute(5).volume, 1.0);
+        // Analysis sees the same candles without reading storage
+        let cached = cache
+            .recent(
+                ExchangeKind::Binance,
+                "BTCUSDT",
+                TimeFrame::Min1,
+                candles.len(),
+            )
+            .unwrap();
+        let summary = |candles: &[Candle]| -> Vec<(i64, f64, f64)> {
+            candles
+                .iter()
+                .map(|c| (c.open_time.timestamp(), c.close, c.volume))
+                .collect()
+        };
+        assert_eq!(summary(&cached), summary(&candles));
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn candle_alerts_evaluate_on_their_own_timeframe() {
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage = SqliteStorage::open(&db_path).await.unwrap();
+        let candles = |timeframe: TimeFrame, close: f64| -> Vec<Candle> {
+            (0..3)
+                .map(|i| Candle {
+                    exchange: ExchangeKind::Upbit,
+                    symbol: "KRW-SOL".into(),
+                    instrument: Instrument::new("SOL", "KRW"),
+                    timeframe,
+                    open_time: DateTime::from_timestamp(1_704_067_200, 0).unwrap()
+                        + timeframe.duration() * i,
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 1.0,
+                })
+                .collect()
+        };
+        storage
+            .upsert_candles(&candles(TimeFrame::Min1, 100.0))
+            .await
+            .unwrap();
+        storage
+            .upsert_candles(&candles(TimeFrame::Hour1, 200.0))
+            .await
+            .unwrap();
+
+        let notifier = RecordingNotifier::default();
+        let rule = |name: &str, timeframe| AlertRule {
+            name: name.into(),
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            indicator_name: "sma".into(),
+            indicator_params: IndicatorParams {
+                period: Some(2),
+                ..Default::default()
+            },
+            timeframe,
+            condition: ConditionType::Above(150.0),
+            cooldown_minutes: 0,
+            evaluate_on: EvaluateOn::Tick,
+        };
+        let rules = vec![
+            rule("sma-1m", TimeFrame::Min1),
+            rule("sma-1h", TimeFrame::Hour1),
+        ];
+        let ticker = Ticker {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            instrument: Instrument::new("SOL", "KRW"),
+            price: 100.0,
+            volume: 0.0,
+            stats: TickerStats::default(),
+            timestamp: Utc::now(),
+            received_at: Utc::now(),
+        };
+
+        process_ticker(
+            &ticker,
+            &storage,
+            &CandleCache::new(10),
+            &rules,
+            &notifier,
+            &mut AnalysisState::default(),
+        )
+        .await;
+
+        assert_eq!(
+            *notifier.alerts.lock().unwrap(),
+            vec![(ExchangeKind::Upbit, "sma-1h".to_owned())]
+        );
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn close_evaluated_alerts_ignore_the_forming_candle() {
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage = SqliteStorage::open(&db_path).await.unwrap();
+        let notifier = RecordingNotifier::default();
+        let candle = |minute: i64, close: f64| Candle {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            instrument: Instrument::new("SOL", "KRW"),
+            timeframe: TimeFrame::Min1,
+            open_time: DateTime::from_timestamp(1_704_067_200 + minute * 60, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+        };
+        let rule = |name: &str, evaluate_on| AlertRule {
+            name: name.into(),
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            indicator_name: "sma".into(),
+            indicator_params: IndicatorParams {
+                period: Some(2),
+                ..Default::default()
+            },
+            timeframe: TimeFrame::Min1,
+            condition: ConditionType::Above(150.0),
+            cooldown_minutes: 0,
+            evaluate_on,
+        };
+        let rules = vec![
+            rule("sma-tick", EvaluateOn::Tick),
+            rule("sma-close", EvaluateOn::Close),
+        ];
+        let ticker = Ticker {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            instrument: Instrument::new("SOL", "KRW"),
+            price: 300.0,
+            volume: 0.0,
+            stats: TickerStats::default(),
+            timestamp: Utc::now(),
+            received_at: Utc::now(),
+        };
+        let cache = CandleCache::new(10);
+        let mut state = AnalysisState::default();
+        for c in [candle(0, 100.0), candle(1, 100.0), candle(2, 300.0)] {
+            cache.insert(&c);
+        }
+
+        // Mid-bar spike: only the tick rule sees SMA(100, 300) = 200
+        process_ticker(&ticker, &storage, &cache, &rules, &notifier, &mut state).await;
+
+        // The bar closes back at 120: SMA(100, 120) = 110
+        cache.insert(&candle(2, 120.0));
+        cache.insert(&candle(3, 250.0));
+        process_candle_close(
+            &candle(2, 120.0),
+            &storage,
+            &cache,
+            &rules,
+            &notifier,
+            &mut state,
+        )
+        .await;
+        // The next bar closes at 250: SMA(120, 250) = 185, reported once
+        for _ in 0..2 {
+            process_candle_close(
+                &candle(3, 250.0),
+                &storage,
+                &cache,
+                &rules,
+                &notifier,
+                &mut state,
+            )
+            .await;
+        }
+
+        assert_eq!(
+            *notifier.alerts.lock().unwrap(),
+            vec![
+                (ExchangeKind::Upbit, "sma-tick".to_owned()),
+                (ExchangeKind::Upbit, "sma-close".to_owned()),
+            ]
+        );
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn corrected_candles_reach_indicator_streams() {
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage = SqliteStorage::open(&db_path).await.unwrap();
+        let notifier = RecordingNotifier::default();
+        let candle = |minute: i64, close: f64| Candle {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            instrument: Instrument::new("SOL", "KRW"),
+            timeframe: TimeFrame::Min1,
+            open_time: DateTime::from_timestamp(1_704_067_200 + minute * 60, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+        };
+        let rule = AlertRule {
+            name: "sma".into(),
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            indicator_name: "sma".into(),
+            indicator_params: IndicatorParams {
+                period: Some(3),
+                ..Default::default()
+            },
+            timeframe: TimeFrame::Min1,
+            condition: ConditionType::Above(f64::MAX),
+            cooldown_minutes: 0,
+            evaluate_on: EvaluateOn::Tick,
+        };
+        let rules = vec![rule.clone()];
+        let ticker = Ticker {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            instrument: Instrument::new("SOL", "KRW"),
+            price: 100.0,
+            volume: 0.0,
+            stats: TickerStats::default(),
+            timestamp: Utc::now(),
+            received_at: Utc::now(),
+        };
+        let cache = CandleCache::new(20);
+        let mut state = AnalysisState::default();
+        for minute in 0..8 {
+            cache.insert(&candle(minute, 100.0 + minute as f64));
+        }
+        process_ticker(&ticker, &storage, &cache, &rules, &notifier, &mut state).await;
+
+        // Minute 7 closes, and minute 6, already fed to the stream, is
+        // corrected, e.g. by reconciliation
+        cache.insert(&candle(8, 108.0));
+        cache.insert(&candle(6, 90.0));
+        process_ticker(&ticker, &storage, &cache, &rules, &notifier, &mut state).await;
+
+        let candles = cache
+            .recent(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1, 9)
+            .unwrap();
+        let batch = build_indicator(&rule)
+            .calculate(&candles[..8])
+            .unwrap()
+            .last()
+            .copied();
+        assert_eq!(state.indicator_streams["sma"].last_value, batch);
+        // Stepped back over minute 6 rather than seeded again
+        let seeded_from = candle(4, 0.0).open_time;
+        assert_eq!(state.indicator_streams["sma"].first_closed, seeded_from);
+
+        // A correction older than anything the stream was fed leaves it alone
+        cache.insert(&candle(1, 50.0));
+        process_ticker(&ticker, &storage, &cache, &rules, &notifier, &mut state).await;
+        assert_eq!(state.indicator_streams["sma"].first_closed, seeded_from);
+        assert_eq!(state.indicator_streams["sma"].last_value, batch);
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        alerts: std::sync::Mutex<Vec<(ExchangeKind, String)>>,
+        spread_alerts: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn notify(&self, ticker: &Ticker, result: &EvaluationResult) {
+            self.alerts
+                .lock()
+                .unwrap()
+                .push((ticker.exchange, result.alert_name.clone()));
+        }
+
+        fn notify_spread(&self, sample: &PremiumSample, result: &EvaluationResult) {
+            assert_eq!(sample.spread_name, result.alert_name);
+            self.spread_alerts
+                .lock()
+                .unwrap()
+                .push(result.alert_name.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn live_pipeline_runs_end_to_end_against_mock_exchanges() {
+        let upbit = mock_server::upbit("KRW-SOL", 100.0).start().await;
+        let binance = mock_server::binance("SOLUSDT", 20.0).start().await;
+
+        let config: AppConfig = toml::from_str(&format!(
+            r#"
+[general]
+historical_candles = 10
+
+[[exchanges]]
+name = "upbit"
+base_url = "{}"
+ws_url = "{}"
+
+[[exchanges]]
+name = "binance"
+base_url = "{}"
+ws_url = "{}"
+
+[[coins]]
+exchange = "upbit"
+symbol = "KRW-SOL"
+timeframes = ["1m"]
+
+[[coins]]
+exchange = "binance"
+symbol = "SOLUSDT"
+timeframes = ["1m"]
+
+[[alerts]]
+name = "upbit-sma"
+exchange = "upbit"
+symbol = "KRW-SOL"
+indicator = "sma"
+params = {{ period = 2 }}
+condition = "above"
+threshold = 0.0
+
+[[alerts]]
+name = "binance-sma"
+exchange = "binance"
+symbol = "SOLUSDT"
+indicator = "sma"
+params = {{ period = 2 }}
+condition = "above"
+threshold = 0.0
+
+[[spreads]]
+name = "sol-kimchi"
+base = "SOL"
+fx = "fixed"
+fx_rate = 4.0
+condition = "above"
+threshold = 1.0
+"#,
+            upbit.base_url(),
+            upbit.ws_url("/websocket/v1"),
+            binance.base_url(),
+            binance.ws_url(""),
+        ))
+        .unwrap();
+
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open(&db_path).await.unwrap());
+        let notifier = Arc::new(RecordingNotifier::default());
+        let cancel = CancellationToken::new();
+
+        let pipeline = tokio::spawn({
+            let storage = Arc::clone(&storage);
+            let notifier: Arc<dyn Notifier> = notifier.clone();
+            let cancel = cancel.clone();
+            async move {
+                let exchanges = build_exchanges(&config).unwrap();
+                run_live_pipeline(
+                    &config,
+                    storage,
+                    exchanges,
+                    notifier,
+                    watch::channel(configured_watchlist(&config)).1,
+                    cancel,
+                )
+                .await
+            }
+        });
+
+        // Historical klines cover 00:00-00:04; the streamed trade opens 00:05.
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        loop {
+            let alerts = notifier.alerts.lock().unwrap().len();
+            let spread_alerts = notifier.spread_alerts.lock().unwrap().len();
+            let upbit_candles = storage
+                .get_recent_candles(ExchangeKind::Upbit, "KRW-SOL", TimeFrame::Min1, 10)
+                .await
+                .unwrap();
+            let binance_candles = storage
+                .get_recent_candles(ExchangeKind::Binance, "SOLUSDT", TimeFrame::Min1, 10)
+                .await
+                .unwrap();
+            if alerts >= 2
+                && spread_alerts >= 1
+                && upbit_candles.len() == 6
+                && binance_candles.len() == 6
+            {
+                break;
+            }
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "pipeline did not settle: alerts={alerts} spread_alerts={spread_alerts} upbit={} binance={}",
+                upbit_candles.len(),
+                binance_candles.len()
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        cancel.cancel();
+        pipeline.await.unwrap().unwrap();
+
+        assert!(
+            storage
+                .last_alert_time("upbit-sma")
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            storage
+                .last_alert_time("binance-sma")
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        // Upbit 105 KRW vs Binance 25 USDT at 4 KRW/USDT is a 5% premium.
+        let premiums = storage
+            .get_premium_series(
+                "sol-kimchi",
+                DateTime::from_timestamp(0, 0).unwrap(),
+                Utc::now(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(premiums.len(), 1);
+        assert!((premiums[0].premium_pct - 5.0).abs() < 1e-9);
+        assert!(
+            storage
+                .last_alert_time("sol-kimchi")
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn live_pipeline_handles_many_synthetic_symbols() {
+        const SYMBOLS: usize = 100;
+        let mut toml = String::from(
+            r#"
+[general]
+historical_candles = 5
+
+[[exchanges]]
+name = "synthetic"
+
+[exchanges.synthetic]
+tick_interval_ms = 20
+markets = 100
+
+[[alerts]]
+name = "syn-sma"
+exchange = "synthetic"
+symbol = "SYN100-USD"
+indicator = "sma"
+params = { period = 2 }
+condition = "above"
+threshold = 0.0
+"#,
+        );
+        for i in 1..=SYMBOLS {
+            toml.push_str(&format!(
+                "\n[[coins]]\nexchange = \"synthetic\"\nsymbol = \"SYN{i}-USD\"\ntimeframes = [\"1m\"]\n"
+            ));
+        }
+        let config: AppConfig = toml::from_str(&toml).unwrap();
+
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open(&db_path).await.unwrap());
+        let notifier = Arc::new(RecordingNotifier::default());
+        let cancel = CancellationToken::new();
+
+        let pipeline = tokio::spawn({
+            let storage = Arc::clone(&storage);
+            let notifier: Arc<dyn Notifier> = notifier.clone();
+            let cancel = cancel.clone();
+            async move {
+                let exchanges = build_exchanges(&config).unwrap();
+                run_live_pipeline(
+                    &config,
+                    storage,
+                    exchanges,
+                    notifier,
+                    watch::channel(configured_watchlist(&config)).1,
+                    cancel,
+                )
+                .await
+            }
+        });
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(20);
+        while notifier.alerts.lock().unwrap().is_empty() {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "synthetic pipeline raised no alert"
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        cancel.cancel();
+        pipeline.await.unwrap().unwrap();
+
+        for i in 1..=SYMBOLS {
+            let candles = storage
+                .get_recent_candles(
+                    ExchangeKind::Synthetic,
+                    &format!("SYN{i}-USD"),
+                    TimeFrame::Min1,
+                    10,
+                )
+                .await
+                .unwrap();
+            assert!(
+                candles.len() >= 5,
+                "SYN{i}-USD has {} candles",
+                candles.len()
+            );
+        }
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn live_pipeline_replays_a_recording_deterministically() {
+        let temp = |ext: &str| {
+            std::env::temp_dir().join(format!("coin-notifier-{}.{ext}", uuid::Uuid::new_v4()))
+        };
+        let replay_path = temp("jsonl.gz");
+        let record_path = temp("jsonl.gz");
+        let db_path = temp("db");
+
+        let ticker = |exchange, symbol: &str, price, secs: i64| recorder::RecordedEvent {
+            received_at: DateTime::from_timestamp(1_704_067_200 + secs, 0).unwrap(),
+            event: MarketEvent::Ticker(Ticker {
+                exchange,
+                symbol: symbol.into(),
+                instrument: exchange::instrument_for(exchange, symbol).unwrap(),
+                price,
+                volume: 0.0,
+                stats: TickerStats::default(),
+                timestamp: DateTime::from_timestamp(1_704_067_200 + secs, 0).unwrap(),
+                received_at: DateTime::from_timestamp(1_704_067_200 + secs, 0).unwrap(),
+            }),
+        };
+        let mut recording = recorder::Recorder::create(&replay_path).unwrap();
+        for event in [
+            ticker(ExchangeKind::Binance, "SOLUSDT", 25.0, 0),
+            ticker(ExchangeKind::Upbit, "KRW-SOL", 100.0, 1),
+            ticker(ExchangeKind::Upbit, "KRW-SOL", 105.0, 2),
+        ] {
+            recording.write(&event).unwrap();
+        }
+        recording.finish().unwrap();
+
+        let config: AppConfig = toml::from_str(&format!(
+            r#"
+[general]
+
+[live]
+record_path = "{record}"
+
+[[exchanges]]
+name = "upbit"
+base_url = "http://unused"
+ws_url = "ws://unused"
+replay_path = "{replay}"
+replay_speed = "max"
+
+[[exchanges]]
+name = "binance"
+base_url = "http://unused"
+ws_url = "ws://unused"
+replay_path = "{replay}"
+replay_speed = "max"
+
+[[coins]]
+exchange = "upbit"
+symbol = "KRW-SOL"
+timeframes = ["1m"]
+
+[[coins]]
+exchange = "binance"
+symbol = "SOLUSDT"
+timeframes = ["1m"]
+
+[[spreads]]
+name = "sol-kimchi"
+base = "SOL"
+fx = "fixed"
+fx_rate = 4.0
+condition = "above"
+threshold = 2.0
+"#,
+            record = record_path.display(),
+            replay = replay_path.display(),
+        ))
+        .unwrap();
+
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open(&db_path).await.unwrap());
+        let notifier = Arc::new(RecordingNotifier::default());
+        let cancel = CancellationToken::new();
+
+        let pipeline = tokio::spawn({
+            let storage = Arc::clone(&storage);
+            let notifier: Arc<dyn Notifier> = notifier.clone();
+            let cancel = cancel.clone();
+            async move {
+                let exchanges = build_exchanges(&config).unwrap();
+                run_live_pipeline(
+                    &config,
+                    storage,
+                    exchanges,
+                    notifier,
+                    watch::channel(configured_watchlist(&config)).1,
+                    cancel,
+                )
+                .await
+            }
+        });
+
+        // 100 KRW is at par with 25 USDT; only the 105 KRW tick is a 5% premium
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        while notifier.spread_alerts.lock().unwrap().is_empty() {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "replay raised no alert"
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        cancel.cancel();
+        pipeline.await.unwrap().unwrap();
+
+        assert_eq!(
+            *notifier.spread_alerts.lock().unwrap(),
+            vec!["sol-kimchi".to_owned()]
+        );
+        assert!(notifier.alerts.lock().unwrap().is_empty());
+
+        // The replayed session was itself recorded
+        let rerecorded = recorder::read_recording(&record_path).unwrap();
+        assert_eq!(rerecorded.len(), 3);
+
+        drop(storage);
+        for path in [replay_path, record_path, db_path] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[tokio::test]
+    async fn ticker_stat_alerts_fire_from_24h_statistics() {
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage = SqliteStorage::open(&db_path).await.unwrap();
+        let notifier = RecordingNotifier::default();
+        let rule = |name: &str, indicator: &str, condition| AlertRule {
+            name: name.into(),
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            indicator_name: indicator.into(),
+            indicator_params: Default::default(),
+            timeframe: TimeFrame::Min1,
+            condition,
+            cooldown_minutes: 0,
+            evaluate_on: EvaluateOn::Tick,
+        };
+        let rules = vec![
+            rule("pump", "change_24h", ConditionType::CrossAbove(10.0)),
+            rule("liquid", "quote_volume_24h", ConditionType::Above(1e9)),
+            // Upbit tickers carry no bid/ask, so this never evaluates
+            rule("wide", "spread_pct", ConditionType::Above(0.0)),
+        ];
+        let ticker = |change_pct_24h, quote_volume_24h| Ticker {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-SOL".into(),
+            instrument: Instrument::new("SOL", "KRW"),
+            price: 100.0,
+            volume: 0.0,
+            stats: TickerStats {
+                change_pct_24h,
+                quote_volume_24h,
+                ..Default::default()
+            },
+            timestamp: Utc::now(),
+            received_at: Utc::now(),
+        };
+
+        let mut state = AnalysisState::default();
+        let cache = CandleCache::new(10);
+        for (change, quote_volume) in [(8.0, 5e8), (12.0, 5e8), (15.0, 2e9)] {
+            process_ticker(
+                &ticker(change, quote_volume),
+                &storage,
+                &cache,
+                &rules,
+                &notifier,
+                &mut state,
+            )
+            .await;
+        }
+
+        // The cross fires once on 8% -> 12%; the traded value only on the last ticker
+        assert_eq!(
+            *notifier.alerts.lock().unwrap(),
+            vec![
+                (ExchangeKind::Upbit, "pump".to_owned()),
+                (ExchangeKind::Upbit, "liquid".to_owned()),
+            ]
+        );
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn orderbook_alerts_read_the_latest_book() {
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage = SqliteStorage::open(&db_path).await.unwrap();
+        let notifier = RecordingNotifier::default();
+        let rule = |name: &str, indicator: &str, condition| AlertRule {
+            name: name.into(),
+            exchange: ExchangeKind::Binance,
+            symbol: "BTCUSDT".into(),
+            indicator_name: indicator.into(),
+            indicator_params: Default::default(),
+            timeframe: TimeFrame::Min1,
+            condition,
+            cooldown_minutes: 0,
+            evaluate_on: EvaluateOn::Tick,
+        };
+        let rules = vec![
+            rule("bids", "book_imbalance", ConditionType::CrossAbove(50.0)),
+            rule("ask wall", "ask_wall_size", ConditionType::Above(100.0)),
+        ];
+        let ticker = Ticker {
+            exchange: ExchangeKind::Binance,
+            symbol: "BTCUSDT".into(),
+            instrument: Instrument::new("BTC", "USDT"),
+            price: 100.0,
+            volume: 0.0,
+            stats: TickerStats::default(),
+            timestamp: Utc::now(),
+            received_at: Utc::now(),
+        };
+        let book = |bid_size, ask_size| OrderBook {
+            exchange: ExchangeKind::Binance,
+            symbol: "BTCUSDT".into(),
+            bids: vec![crate::model::OrderBookLevel {
+                price: 99.0,
+                size: bid_size,
+            }],
+            asks: vec![crate::model::OrderBookLevel {
+                price: 101.0,
+                size: ask_size,
+            }],
+            timestamp: Utc::now(),
+        };
+
+        let mut state = AnalysisState::default();
+        let cache = CandleCache::new(10);
+        // Nothing to evaluate before the first book
+        process_ticker(&ticker, &storage, &cache, &rules, &notifier, &mut state).await;
+        for (bid_size, ask_size) in [(10.0, 10.0), (90.0, 10.0), (50.0, 150.0)] {
+            state.order_books.insert(book(bid_size, ask_size));
+            process_ticker(&ticker, &storage, &cache, &rules, &notifier, &mut state).await;
+        }
+
+        // Imbalance crosses 0% -> 80%; the ask wall only shows on the last book
+        assert_eq!(
+            *notifier.alerts.lock().unwrap(),
+            vec![
+                (ExchangeKind::Binance, "bids".to_owned()),
+                (ExchangeKind::Binance, "ask wall".to_owned()),
+            ]
+        );
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
     }
 }
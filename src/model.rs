@@ -3,19 +3,67 @@ use std::fmt;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// A supported exchange. Its name, parsing (`FromStr`, `Display`) and
+/// per-exchange settings live in `exchange::registry`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ExchangeKind {
     Upbit,
     Binance,
+    /// Binance USDⓈ-M futures
+    BinanceFutures,
+    /// Generated market data, see `exchange::synthetic`
+    Synthetic,
 }
 
-impl fmt::Display for ExchangeKind {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Upbit => write!(f, "upbit"),
-            Self::Binance => write!(f, "binance"),
+/// Exchange-independent trading pair, e.g. SOL quoted in KRW.
+///
+/// Each exchange module provides a codec between its native symbol format
+/// (`KRW-SOL` on Upbit, `SOLUSDT` on Binance) and this type. Written as
+/// `"BASE/QUOTE"` in config files and reports.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Instrument {
+    pub base: String,
+    pub quote: String,
+}
+
+impl Instrument {
+    pub fn new(base: &str, quote: &str) -> Self {
+        Self {
+            base: base.to_ascii_uppercase(),
+            quote: quote.to_ascii_uppercase(),
         }
     }
+
+    /// Parse a `"BASE/QUOTE"` string.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (base, quote) = s.split_once('/')?;
+        if base.is_empty() || quote.is_empty() {
+            return None;
+        }
+        Some(Self::new(base.trim(), quote.trim()))
+    }
+}
+
+impl fmt::Display for Instrument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.base, self.quote)
+    }
+}
+
+impl TryFrom<String> for Instrument {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+            .ok_or_else(|| format!("invalid instrument \"{value}\", expected BASE/QUOTE"))
+    }
+}
+
+impl From<Instrument> for String {
+    fn from(value: Instrument) -> Self {
+        value.to_string()
+    }
 }
 
 /// Candle timeframe supported by the application.
@@ -63,6 +111,20 @@ impl TimeFrame {
         }
     }
 
+    /// Return the length of one candle of this timeframe.
+    pub fn duration(self) -> chrono::Duration {
+        match self {
+            Self::Min1 => chrono::Duration::minutes(1),
+            Self::Min3 => chrono::Duration::minutes(3),
+            Self::Min5 => chrono::Duration::minutes(5),
+            Self::Min15 => chrono::Duration::minutes(15),
+            Self::Min30 => chrono::Duration::minutes(30),
+            Self::Hour1 => chrono::Duration::hours(1),
+            Self::Hour4 => chrono::Duration::hours(4),
+            Self::Day1 => chrono::Duration::days(1),
+        }
+    }
+
     /// Return the Upbit REST endpoint path segment for this timeframe.
     pub fn upbit_endpoint(self) -> &'static str {
         match self {
@@ -77,6 +139,36 @@ impl TimeFrame {
         }
     }
 
+    /// Return the Upbit WebSocket candle type for this timeframe, if streamed.
+    ///
+    /// Upbit only streams minute candles up to 240m; there is no daily stream.
+    pub fn upbit_ws_type(self) -> Option<&'static str> {
+        match self {
+            Self::Min1 => Some("candle.1m"),
+            Self::Min3 => Some("candle.3m"),
+            Self::Min5 => Some("candle.5m"),
+            Self::Min15 => Some("candle.15m"),
+            Self::Min30 => Some("candle.30m"),
+            Self::Hour1 => Some("candle.60m"),
+            Self::Hour4 => Some("candle.240m"),
+            Self::Day1 => None,
+        }
+    }
+
+    /// Parse an Upbit candle WebSocket type (e.g. "candle.5m").
+    pub fn from_upbit_ws_type(s: &str) -> Option<Self> {
+        match s {
+            "candle.1m" => Some(Self::Min1),
+            "candle.3m" => Some(Self::Min3),
+            "candle.5m" => Some(Self::Min5),
+            "candle.15m" => Some(Self::Min15),
+            "candle.30m" => Some(Self::Min30),
+            "candle.60m" => Some(Self::Hour1),
+            "candle.240m" => Some(Self::Hour4),
+            _ => None,
+        }
+    }
+
     /// Return the Binance kline interval string for this timeframe.
     pub fn binance_interval(self) -> &'static str {
         match self {
@@ -102,6 +194,7 @@ impl fmt::Display for TimeFrame {
 pub struct Candle {
     pub exchange: ExchangeKind,
     pub symbol: String,
+    pub instrument: Instrument,
     pub timeframe: TimeFrame,
     pub open_time: DateTime<Utc>,
     pub open: f64,
@@ -111,15 +204,137 @@ pub struct Candle {
     pub volume: f64,
 }
 
+/// A real-time candle update from an exchange stream.
+///
+/// The same candle is typically sent many times while it forms; `is_closed`
+/// is set once its interval has ended and the values are final.
 #[derive(Debug, Clone)]
+pub struct CandleUpdate {
+    pub candle: Candle,
+    pub is_closed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ticker {
     pub exchange: ExchangeKind,
     pub symbol: String,
+    /// Not recorded; re-derived from `symbol` when a recording is read back
+    #[serde(skip)]
+    pub instrument: Instrument,
     pub price: f64,
-    // Reserved for future analytics
-    #[allow(dead_code)]
+    /// Traded base volume over the last 24h
     pub volume: f64,
-    #[allow(dead_code)]
+    /// Missing from recordings made before the statistics were added
+    #[serde(default)]
+    pub stats: TickerStats,
+    pub timestamp: DateTime<Utc>,
+    /// When this process decoded it; kept by recordings as the line's
+    /// `received_at` rather than with the ticker
+    #[serde(skip)]
+    pub received_at: DateTime<Utc>,
+}
+
+/// Daily statistics and top of book carried by exchange ticker streams.
+///
+/// Binance reports a rolling 24h window. Upbit's open/high/low and change
+/// are for the current UTC+9 trading day, its traded value is rolling 24h,
+/// and its ticker has no best bid/ask.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TickerStats {
+    pub open_24h: f64,
+    pub high_24h: f64,
+    pub low_24h: f64,
+    /// Price change over the window, in percent
+    pub change_pct_24h: f64,
+    /// Traded value over the last 24h, in the quote currency
+    pub quote_volume_24h: f64,
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+}
+
+/// Perpetual futures metric streamed or polled alongside market data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DerivativesMetric {
+    MarkPrice,
+    /// Current funding rate per funding interval, as a fraction
+    FundingRate,
+    /// Open contracts, in the base asset
+    OpenInterest,
+}
+
+impl DerivativesMetric {
+    /// Storage-format metric name (e.g. `"funding_rate"`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MarkPrice => "mark_price",
+            Self::FundingRate => "funding_rate",
+            Self::OpenInterest => "open_interest",
+        }
+    }
+}
+
+/// One observation of a `DerivativesMetric`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivativesSample {
+    pub exchange: ExchangeKind,
+    pub symbol: String,
+    pub metric: DerivativesMetric,
+    pub value: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// How far a candle built from trades was from the exchange's official one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandleDiscrepancy {
+    pub exchange: ExchangeKind,
+    pub symbol: String,
+    pub timeframe: TimeFrame,
+    pub open_time: DateTime<Utc>,
+    /// Largest open/high/low/close difference, in percent of the official price
+    pub price_diff_pct: f64,
+    /// Official minus built volume
+    pub volume_diff: f64,
+    /// `volume_diff` in percent of the official volume
+    pub volume_diff_pct: f64,
+}
+
+/// Tradable market metadata from an exchange's catalogue.
+#[derive(Debug, Clone)]
+pub struct Market {
+    pub exchange: ExchangeKind,
+    pub symbol: String,
+    pub instrument: Instrument,
+    /// Minimum price increment, when the exchange publishes a fixed one
+    pub tick_size: Option<f64>,
+    /// Minimum quantity increment
+    pub lot_step: Option<f64>,
+    /// Minimum order value in the quote currency
+    pub min_notional: Option<f64>,
+    /// Whether the market currently accepts orders
+    pub trading: bool,
+    /// Upbit investment warning designation
+    pub warning: bool,
+    /// Active Upbit caution flags (e.g. "PRICE_FLUCTUATIONS")
+    pub cautions: Vec<String>,
+}
+
+/// A single price level of an order book.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OrderBookLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+/// An order book snapshot.
+///
+/// Bids are sorted by price descending and asks by price ascending, so the
+/// first entry of each side is the best quote.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    pub exchange: ExchangeKind,
+    pub symbol: String,
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -132,14 +347,41 @@ pub enum TradeSide {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
     pub exchange: ExchangeKind,
     pub symbol: String,
+    /// Not recorded; re-derived from `symbol` when a recording is read back
+    #[serde(skip)]
+    pub instrument: Instrument,
     pub price: f64,
     pub volume: f64,
     pub side: TradeSide,
     pub timestamp: DateTime<Utc>,
+    /// Exchange-assigned trade id, increasing per symbol; `None` when the
+    /// exchange does not provide one (and in recordings made without ids)
+    #[serde(default)]
+    pub trade_id: Option<u64>,
+    /// When this process decoded it; kept by recordings as the line's
+    /// `received_at` rather than with the trade
+    #[serde(skip)]
+    pub received_at: DateTime<Utc>,
+}
+
+/// One observation of a cross-exchange premium (kimchi premium).
+#[derive(Debug, Clone)]
+pub struct PremiumSample {
+    pub spread_name: String,
+    pub base: String,
+    /// Upbit price in KRW
+    pub upbit_price: f64,
+    /// Binance price in USDT
+    pub binance_price: f64,
+    /// KRW per USDT used for the conversion
+    pub fx_rate: f64,
+    /// Premium of the Upbit price over the converted Binance price, in percent
+    pub premium_pct: f64,
+    pub timestamp: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
@@ -180,6 +422,16 @@ pub struct BacktestTrade {
 mod tests {
     use super::*;
 
+    #[test]
+    fn instrument_parse_and_display() {
+        let instrument = Instrument::parse("sol/krw").unwrap();
+        assert_eq!(instrument, Instrument::new("SOL", "KRW"));
+        assert_eq!(instrument.to_string(), "SOL/KRW");
+
+        assert!(Instrument::parse("SOLKRW").is_none());
+        assert!(Instrument::parse("/KRW").is_none());
+    }
+
     #[test]
     fn timeframe_round_trip() {
         let frames = [
@@ -199,15 +451,16 @@ mod tests {
     }
 
     #[test]
-    fn timeframe_invalid_string_returns_none() {
-        assert_eq!(TimeFrame::from_str("2m"), None);
-        assert_eq!(TimeFrame::from_str(""), None);
+    fn timeframe_duration_matches_interval() {
+        assert_eq!(TimeFrame::Min1.duration().num_seconds(), 60);
+        assert_eq!(TimeFrame::Hour4.duration().num_seconds(), 4 * 60 * 60);
+        assert_eq!(TimeFrame::Day1.duration().num_seconds(), 24 * 60 * 60);
     }
 
     #[test]
-    fn exchange_kind_display() {
-        assert_eq!(ExchangeKind::Upbit.to_string(), "upbit");
-        assert_eq!(ExchangeKind::Binance.to_string(), "binance");
+    fn timeframe_invalid_string_returns_none() {
+        assert_eq!(TimeFrame::from_str("2m"), None);
+        assert_eq!(TimeFrame::from_str(""), None);
     }
 
     #[test]
@@ -1,9 +1,14 @@
 pub mod terminal;
 
-use crate::model::ExchangeKind;
+use crate::model::{PremiumSample, Ticker};
 use crate::strategy::condition::EvaluationResult;
 
 /// Sink for alert notifications.
 pub trait Notifier: Send + Sync {
-    fn notify(&self, exchange: ExchangeKind, symbol: &str, price: f64, result: &EvaluationResult);
+    /// Report a triggered alert along with the ticker that triggered it.
+    fn notify(&self, ticker: &Ticker, result: &EvaluationResult);
+
+    /// Report a triggered spread alert along with the premium that
+    /// triggered it.
+    fn notify_spread(&self, sample: &PremiumSample, result: &EvaluationResult);
 }
@@ -1,17 +1,31 @@
-use crate::model::ExchangeKind;
+use crate::model::{PremiumSample, Ticker};
 use crate::notifier::Notifier;
 use crate::strategy::condition::EvaluationResult;
 
 pub struct TerminalNotifier;
 
 impl Notifier for TerminalNotifier {
-    fn notify(&self, exchange: ExchangeKind, symbol: &str, price: f64, result: &EvaluationResult) {
+    fn notify(&self, ticker: &Ticker, result: &EvaluationResult) {
         tracing::warn!(
-            exchange = %exchange,
-            symbol = symbol,
+            exchange = %ticker.exchange,
+            symbol = %ticker.symbol,
+            instrument = %ticker.instrument,
             alert = %result.alert_name,
             indicator_value = result.indicator_value,
-            price = price,
+            price = ticker.price,
+            "ALERT: {}",
+            result.message,
+        );
+    }
+
+    fn notify_spread(&self, sample: &PremiumSample, result: &EvaluationResult) {
+        tracing::warn!(
+            spread = %sample.spread_name,
+            base = %sample.base,
+            upbit_price = sample.upbit_price,
+            binance_price = sample.binance_price,
+            fx_rate = sample.fx_rate,
+            premium_pct = sample.premium_pct,
             "ALERT: {}",
             result.message,
         );
@@ -21,8 +35,9 @@ impl Notifier for TerminalNotifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::{ExchangeKind, Instrument, TickerStats, TimeFrame};
     use crate::strategy::condition::evaluate;
-    use crate::strategy::{AlertRule, ConditionType, IndicatorParams};
+    use crate::strategy::{AlertRule, ConditionType, EvaluateOn, IndicatorParams};
 
     #[test]
     fn terminal_notifier_does_not_panic() {
@@ -40,11 +55,23 @@ mod tests {
                 std_dev_multiplier: None,
                 surge_multiplier: None,
             },
+            timeframe: TimeFrame::Min1,
             condition: ConditionType::Below(30.0),
             cooldown_minutes: 5,
+            evaluate_on: EvaluateOn::Tick,
         };
         let result = evaluate(&rule, 28.5, None);
         // Should not panic
-        notifier.notify(ExchangeKind::Upbit, "KRW-BTC", 120_500_000.0, &result);
+        let ticker = Ticker {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-BTC".into(),
+            instrument: Instrument::new("BTC", "KRW"),
+            price: 120_500_000.0,
+            volume: 1.0,
+            stats: TickerStats::default(),
+            timestamp: chrono::Utc::now(),
+            received_at: chrono::Utc::now(),
+        };
+        notifier.notify(&ticker, &result);
     }
 }
@@ -0,0 +1,220 @@
+//! Reconciles candles built from trades with the exchange's official ones.
+//!
+//! A trade-built candle can miss trades, drop out-of-order ones, or count
+//! volume differently from the exchange. Shortly after a candle closes the
+//! official candle is fetched over REST and replaces it in storage and in the
+//! cache, so the history later backtested on matches the exchange and alert
+//! indicators are re-seeded from it. Any difference is stored as a
+//! `CandleDiscrepancy`.
+
+use crate::candle_cache::CandleCache;
+use crate::exchange::Exchange;
+use crate::model::{Candle, CandleDiscrepancy};
+use crate::storage::Storage;
+
+/// Differences below this many percent are rounding, not discrepancies.
+const TOLERANCE_PCT: f64 = 1e-6;
+
+/// How far `built` is from `official`, or `None` when they agree.
+pub fn discrepancy(built: &Candle, official: &Candle) -> Option<CandleDiscrepancy> {
+    let price_diff_pct = [
+        (built.open, official.open),
+        (built.high, official.high),
+        (built.low, official.low),
+        (built.close, official.close),
+    ]
+    .into_iter()
+    .map(|(b, o)| percent_of(o - b, o).abs())
+    .fold(0.0, f64::max);
+    let volume_diff = official.volume - built.volume;
+    let volume_diff_pct = percent_of(volume_diff, official.volume);
+
+    if price_diff_pct < TOLERANCE_PCT && volume_diff_pct.abs() < TOLERANCE_PCT {
+        return None;
+    }
+    Some(CandleDiscrepancy {
+        exchange: official.exchange,
+        symbol: official.symbol.clone(),
+        timeframe: official.timeframe,
+        open_time: official.open_time,
+        price_diff_pct,
+        volume_diff,
+        volume_diff_pct,
+    })
+}
+
+/// `diff` in percent of `base`; a difference from zero counts as 100%.
+fn percent_of(diff: f64, base: f64) -> f64 {
+    if base == 0.0 {
+        return if diff == 0.0 {
+            0.0
+        } else {
+            diff.signum() * 100.0
+        };
+    }
+    diff / base * 100.0
+}
+
+/// Replace the closed trade-built `built` candle with the exchange's own and
+/// record how far apart they were. Returns the discrepancy, if any.
+pub async fn reconcile_candle(
+    exchange: &dyn Exchange,
+    storage: &dyn Storage,
+    cache: &CandleCache,
+    built: &Candle,
+) -> Option<CandleDiscrepancy> {
+    // Closes of quiet symbols can be reported minutes late, so the exact
+    // bucket is fetched rather than the latest ones
+    let end = built.open_time + built.timeframe.duration();
+    let official = match exchange
+        .fetch_candles_range(&built.symbol, built.timeframe, built.open_time, end)
+        .await
+    {
+        Ok(candles) => candles.into_iter().find(|c| c.open_time == built.open_time),
+        Err(e) => {
+            tracing::warn!(error = ?e, exchange = %built.exchange, symbol = %built.symbol, "failed to fetch candle to reconcile");
+            return None;
+        }
+    };
+    let Some(official) = official else {
+        tracing::warn!(
+            exchange = %built.exchange,
+            symbol = %built.symbol,
+            timeframe = %built.timeframe.as_str(),
+            open_time = %built.open_time,
+            "exchange has no candle to reconcile with, keeping the trade-built one"
+        );
+        return None;
+    };
+
+    if let Err(e) = storage
+        .upsert_candles(std::slice::from_ref(&official))
+        .await
+    {
+        tracing::warn!(error = ?e, exchange = %built.exchange, symbol = %built.symbol, "failed to store reconciled candle");
+        return None;
+    }
+    // Moves the series' revision, so indicator streams that consumed the
+    // built candle start over
+    cache.insert(&official);
+
+    let discrepancy = discrepancy(built, &official)?;
+    tracing::info!(
+        exchange = %built.exchange,
+        symbol = %built.symbol,
+        timeframe = %built.timeframe.as_str(),
+        open_time = %built.open_time,
+        price_diff_pct = discrepancy.price_diff_pct,
+        volume_diff = discrepancy.volume_diff,
+        volume_diff_pct = discrepancy.volume_diff_pct,
+        "trade-built candle differed from the exchange's"
+    );
+    if let Err(e) = storage
+        .upsert_candle_discrepancies(std::slice::from_ref(&discrepancy))
+        .await
+    {
+        tracing::warn!(error = ?e, "failed to record candle discrepancy");
+    }
+    Some(discrepancy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    use crate::exchange::binance::BinanceExchange;
+    use crate::exchange::mock_server;
+    use crate::model::{ExchangeKind, Instrument, TimeFrame};
+    use crate::storage::sqlite::SqliteStorage;
+
+    fn candle(minute: i64, close: f64, volume: f64) -> Candle {
+        Candle {
+            exchange: ExchangeKind::Binance,
+            symbol: "BTCUSDT".into(),
+            instrument: Instrument::new("BTC", "USDT"),
+            timeframe: TimeFrame::Min1,
+            open_time: DateTime::from_timestamp(1_704_067_200 + minute * 60, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume,
+        }
+    }
+
+    #[test]
+    fn discrepancy_measures_price_and_volume() {
+        let official = candle(0, 100.0, 10.0);
+        assert!(discrepancy(&official, &official).is_none());
+
+        let mut built = candle(0, 100.0, 8.0);
+        built.high = 102.0;
+        let d = discrepancy(&built, &official).unwrap();
+        assert!((d.price_diff_pct - 2.0).abs() < 1e-9);
+        assert!((d.volume_diff - 2.0).abs() < 1e-9);
+        assert!((d.volume_diff_pct - 20.0).abs() < 1e-9);
+
+        // Volume where the exchange saw none
+        let d = discrepancy(&candle(0, 100.0, 1.0), &candle(0, 100.0, 0.0)).unwrap();
+        assert_eq!(d.volume_diff_pct, -100.0);
+    }
+
+    #[tokio::test]
+    async fn reconcile_overwrites_built_candle_and_records_difference() {
+        let server = mock_server::binance("BTCUSDT", 42000.0).start().await;
+        let exchange = BinanceExchange::new(&server.base_url(), &server.ws_url(""));
+        let db_path =
+            std::env::temp_dir().join(format!("coin-notifier-{}.db", uuid::Uuid::new_v4()));
+        let storage = SqliteStorage::open(&db_path).await.unwrap();
+        let cache = CandleCache::new(10);
+
+        // The mock's minute 3 kline closes at 42003 with volume 10
+        let built = candle(3, 42003.0, 9.5);
+        storage
+            .upsert_candles(std::slice::from_ref(&built))
+            .await
+            .unwrap();
+        cache.insert(&built);
+        let d = reconcile_candle(&exchange, &storage, &cache, &built)
+            .await
+            .unwrap();
+        assert!((d.volume_diff - 0.5).abs() < 1e-9);
+
+        let stored = storage
+            .get_recent_candles(ExchangeKind::Binance, "BTCUSDT", TimeFrame::Min1, 1)
+            .await
+            .unwrap();
+        assert_eq!(stored[0].volume, 10.0);
+        let cached = cache
+            .recent(ExchangeKind::Binance, "BTCUSDT", TimeFrame::Min1, 1)
+            .unwrap();
+        assert_eq!(cached[0].volume, 10.0);
+        assert_eq!(
+            cache.revision(ExchangeKind::Binance, "BTCUSDT", TimeFrame::Min1),
+            1
+        );
+        let recorded = storage
+            .get_candle_discrepancies(
+                ExchangeKind::Binance,
+                "BTCUSDT",
+                TimeFrame::Min1,
+                built.open_time,
+                built.open_time,
+            )
+            .await
+            .unwrap();
+        assert_eq!(recorded, vec![d]);
+
+        // A candle that matches is stored without a discrepancy
+        let matching = stored[0].clone();
+        assert!(
+            reconcile_candle(&exchange, &storage, &cache, &matching)
+                .await
+                .is_none()
+        );
+
+        drop(storage);
+        let _ = std::fs::remove_file(&db_path);
+    }
+}
@@ -0,0 +1,360 @@
+//! Raw market data recording, for finding out why an alert fired or didn't.
+//!
+//! Every ticker and trade the live pipeline receives is written with the
+//! time it was decoded as one JSON object per line of a gzip file. Recordings are
+//! played back through `exchange::replay::ReplayExchange`.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use error_stack::{Report, ResultExt};
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+use crate::error::RecordingError;
+use crate::exchange::instrument_for;
+use crate::model::{ExchangeKind, Ticker, Trade};
+
+/// Flush the compressed stream at least this often, so a recording cut short
+/// by a crash is readable up to the last flush.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const RECORD_CHANNEL_CAPACITY: usize = 4096;
+/// Report events dropped by a lagging recorder at most this often.
+const DROP_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MarketEvent {
+    Ticker(Ticker),
+    Trade(Trade),
+}
+
+impl MarketEvent {
+    pub fn exchange(&self) -> ExchangeKind {
+        match self {
+            Self::Ticker(ticker) => ticker.exchange,
+            Self::Trade(trade) => trade.exchange,
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        match self {
+            Self::Ticker(ticker) => &ticker.symbol,
+            Self::Trade(trade) => &trade.symbol,
+        }
+    }
+
+    /// When the event was decoded.
+    pub fn received_at(&self) -> DateTime<Utc> {
+        match self {
+            Self::Ticker(ticker) => ticker.received_at,
+            Self::Trade(trade) => trade.received_at,
+        }
+    }
+}
+
+/// One line of a recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// When the live pipeline decoded the event
+    pub received_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: MarketEvent,
+}
+
+/// Writer of a gzip-compressed JSONL recording.
+pub struct Recorder {
+    writer: GzEncoder<BufWriter<File>>,
+    last_flush: Instant,
+}
+
+impl Recorder {
+    /// Create (or truncate) the recording at `path`, creating its directory.
+    pub fn create(path: &Path) -> Result<Self, Report<RecordingError>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .change_context(RecordingError::Open)
+                .attach_with(|| format!("directory: {}", dir.display()))?;
+        }
+        let file = File::create(path)
+            .change_context(RecordingError::Open)
+            .attach_with(|| format!("path: {}", path.display()))?;
+
+        Ok(Self {
+            writer: GzEncoder::new(BufWriter::new(file), Compression::default()),
+            last_flush: Instant::now(),
+        })
+    }
+
+    pub fn write(&mut self, event: &RecordedEvent) -> Result<(), Report<RecordingError>> {
+        serde_json::to_writer(&mut self.writer, event).change_context(RecordingError::Write)?;
+        self.writer
+            .write_all(b"\n")
+            .change_context(RecordingError::Write)?;
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.writer.flush().change_context(RecordingError::Write)?;
+            self.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+
+    /// Write the gzip trailer and flush the file.
+    pub fn finish(self) -> Result<(), Report<RecordingError>> {
+        self.writer
+            .finish()
+            .change_context(RecordingError::Write)?
+            .flush()
+            .change_context(RecordingError::Write)
+    }
+}
+
+/// Open a recording at `path` and start its writer on a blocking thread.
+///
+/// Events sent into the returned sender are written in order; the file is
+/// finished once every sender has been dropped.
+pub fn start(
+    path: &Path,
+) -> Result<(mpsc::Sender<RecordedEvent>, JoinHandle<()>), Report<RecordingError>> {
+    let mut recorder = Recorder::create(path)?;
+    let (tx, mut rx) = mpsc::channel::<RecordedEvent>(RECORD_CHANNEL_CAPACITY);
+
+    let writer = tokio::task::spawn_blocking(move || {
+        while let Some(event) = rx.blocking_recv() {
+            if let Err(e) = recorder.write(&event) {
+                error!(error = ?e, "recording write failed, recorder stopped");
+                return;
+            }
+        }
+        if let Err(e) = recorder.finish() {
+            error!(error = ?e, "failed to finish recording");
+        }
+    });
+    Ok((tx, writer))
+}
+
+/// Put a recording tap in front of `out`: values sent into the returned
+/// sender are recorded with the time they were decoded, then forwarded to
+/// `out`.
+///
+/// Values the recorder has no room for are forwarded but not recorded.
+pub fn tap<T: Clone + Send + 'static>(
+    out: mpsc::Sender<T>,
+    record: mpsc::Sender<RecordedEvent>,
+    wrap: fn(T) -> MarketEvent,
+) -> mpsc::Sender<T> {
+    let (tx, mut rx) = mpsc::channel::<T>(out.max_capacity());
+    tokio::spawn(async move {
+        let mut dropped: u64 = 0;
+        let mut last_drop_log: Option<Instant> = None;
+        while let Some(value) = rx.recv().await {
+            let event = wrap(value.clone());
+            let recorded = RecordedEvent {
+                received_at: event.received_at(),
+                event,
+            };
+            // A failed or lagging recorder must not stall the live pipeline
+            if let Err(TrySendError::Full(_)) = record.try_send(recorded) {
+                dropped += 1;
+                if last_drop_log.is_none_or(|at| at.elapsed() >= DROP_LOG_INTERVAL) {
+                    warn!(dropped, "recorder is falling behind, events not recorded");
+                    last_drop_log = Some(Instant::now());
+                    dropped = 0;
+                }
+            }
+            if out.send(value).await.is_err() {
+                break;
+            }
+        }
+        if dropped > 0 {
+            warn!(dropped, "recorder is falling behind, events not recorded");
+        }
+    });
+    tx
+}
+
+/// Read a whole recording, oldest event first.
+///
+/// A recording whose writer never finished (e.g. the process was killed)
+/// is read up to its last flush.
+pub fn read_recording(path: &Path) -> Result<Vec<RecordedEvent>, Report<RecordingError>> {
+    let file = File::open(path)
+        .change_context(RecordingError::Open)
+        .attach_with(|| format!("path: {}", path.display()))?;
+
+    let mut events = Vec::new();
+    for (index, line) in BufReader::new(MultiGzDecoder::new(file))
+        .lines()
+        .enumerate()
+    {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                warn!(path = %path.display(), "recording ends abruptly, replaying what was flushed");
+                break;
+            }
+            Err(e) => return Err(Report::new(e).change_context(RecordingError::Read)),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut recorded: RecordedEvent = serde_json::from_str(&line)
+            .change_context(RecordingError::Parse { line: index + 1 })?;
+        let (exchange, symbol, instrument, received_at) = match &mut recorded.event {
+            MarketEvent::Ticker(ticker) => (
+                ticker.exchange,
+                &ticker.symbol,
+                &mut ticker.instrument,
+                &mut ticker.received_at,
+            ),
+            MarketEvent::Trade(trade) => (
+                trade.exchange,
+                &trade.symbol,
+                &mut trade.instrument,
+                &mut trade.received_at,
+            ),
+        };
+        let Some(decoded) = instrument_for(exchange, symbol) else {
+            warn!(line = index + 1, %exchange, %symbol, "skipping recorded event with undecodable symbol");
+            continue;
+        };
+        *instrument = decoded;
+        *received_at = recorded.received_at;
+        events.push(recorded);
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Instrument, TickerStats, TradeSide};
+
+    fn temp_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("coin-notifier-{}.jsonl.gz", uuid::Uuid::new_v4()))
+    }
+
+    fn ticker(secs: i64, price: f64) -> RecordedEvent {
+        let timestamp = DateTime::from_timestamp(1_704_067_200 + secs, 0).unwrap();
+        RecordedEvent {
+            received_at: timestamp,
+            event: MarketEvent::Ticker(Ticker {
+                exchange: ExchangeKind::Upbit,
+                symbol: "KRW-SOL".into(),
+                instrument: Instrument::new("SOL", "KRW"),
+                price,
+                volume: 1.5,
+                stats: TickerStats::default(),
+                timestamp,
+                received_at: timestamp,
+            }),
+        }
+    }
+
+    #[test]
+    fn recording_round_trips_and_restores_instruments() {
+        let path = temp_path();
+        let trade_time = DateTime::from_timestamp(1_704_067_201, 0).unwrap();
+
+        let mut recorder = Recorder::create(&path).unwrap();
+        recorder.write(&ticker(0, 100.0)).unwrap();
+        recorder
+            .write(&RecordedEvent {
+                received_at: trade_time,
+                event: MarketEvent::Trade(Trade {
+                    exchange: ExchangeKind::Binance,
+                    symbol: "SOLUSDT".into(),
+                    instrument: Instrument::new("SOL", "USDT"),
+                    price: 20.0,
+                    volume: 3.0,
+                    side: TradeSide::Sell,
+                    timestamp: trade_time,
+                    received_at: trade_time,
+                    trade_id: Some(7),
+                }),
+            })
+            .unwrap();
+        recorder.finish().unwrap();
+
+        let events = read_recording(&path).unwrap();
+        assert_eq!(events.len(), 2);
+        let MarketEvent::Ticker(ticker) = &events[0].event else {
+            panic!("expected ticker, got {:?}", events[0].event);
+        };
+        assert_eq!(ticker.price, 100.0);
+        assert_eq!(ticker.instrument, Instrument::new("SOL", "KRW"));
+        let MarketEvent::Trade(trade) = &events[1].event else {
+            panic!("expected trade, got {:?}", events[1].event);
+        };
+        assert_eq!(trade.side, TradeSide::Sell);
+        assert_eq!(trade.instrument, Instrument::new("SOL", "USDT"));
+        assert_eq!(trade.trade_id, Some(7));
+        assert_eq!(events[1].received_at, trade_time);
+        assert_eq!(trade.received_at, trade_time);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn tap_records_and_forwards_values() {
+        let path = temp_path();
+        let (record_tx, writer) = start(&path).unwrap();
+        let (out_tx, mut out_rx) = mpsc::channel(4);
+
+        let tx = tap(out_tx, record_tx, MarketEvent::Ticker);
+        let MarketEvent::Ticker(value) = ticker(0, 100.0).event else {
+            unreachable!()
+        };
+        tx.send(value).await.unwrap();
+        assert_eq!(out_rx.recv().await.unwrap().price, 100.0);
+
+        // Dropping the last sender finishes the file
+        drop(tx);
+        assert!(out_rx.recv().await.is_none());
+        writer.await.unwrap();
+
+        let events = read_recording(&path).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event.symbol(), "KRW-SOL");
+        // Stamped when decoded, not when the tap got to it
+        assert_eq!(events[0].received_at, ticker(0, 100.0).received_at);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn full_recorder_does_not_stall_forwarding() {
+        let (record_tx, mut record_rx) = mpsc::channel(1);
+        let (out_tx, mut out_rx) = mpsc::channel(4);
+
+        let tx = tap(out_tx, record_tx, MarketEvent::Ticker);
+        for price in [100.0, 101.0, 102.0] {
+            let MarketEvent::Ticker(value) = ticker(0, price).event else {
+                unreachable!()
+            };
+            tx.send(value).await.unwrap();
+        }
+        for price in [100.0, 101.0, 102.0] {
+            assert_eq!(out_rx.recv().await.unwrap().price, price);
+        }
+
+        // Only the first event fit
+        drop(tx);
+        assert!(out_rx.recv().await.is_none());
+        let MarketEvent::Ticker(recorded) = record_rx.recv().await.unwrap().event else {
+            unreachable!()
+        };
+        assert_eq!(recorded.price, 100.0);
+        assert!(record_rx.recv().await.is_none());
+    }
+}
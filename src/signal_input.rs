@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::config::InputConfig;
 use crate::indicator::Indicator;
 use crate::indicator::bollinger::BollingerBands;
@@ -5,12 +7,36 @@ use crate::indicator::ma::{Ema, Sma};
 use crate::indicator::macd::Macd;
 use crate::indicator::rsi::Rsi;
 use crate::indicator::volume::VolumeMA;
-use crate::model::Candle;
+use crate::model::{Candle, DerivativesMetric, DerivativesSample};
+use crate::strategy::{
+    DEFAULT_OI_CHANGE_PERIOD, derivatives_metric, derivatives_value, derivatives_window,
+};
+
+/// Stored samples of each derivatives metric, oldest first, covering the
+/// candles an input is evaluated on and its `derivatives_window` before.
+pub type DerivativesSeries = HashMap<DerivativesMetric, Vec<DerivativesSample>>;
 
 pub trait SignalInput: Send {
     fn name(&self) -> &str;
     fn required_candles(&self) -> usize;
-    fn series(&self, candles: &[Candle]) -> Result<Vec<Option<f64>>, String>;
+
+    /// Derivatives metric the input reads besides candles; the caller loads
+    /// it into the `DerivativesSeries` passed to `series`.
+    fn derivatives_metric(&self) -> Option<DerivativesMetric> {
+        None
+    }
+
+    /// How far before a candle's close the input reads derivatives samples,
+    /// besides the latest one.
+    fn derivatives_window(&self) -> chrono::Duration {
+        chrono::Duration::zero()
+    }
+
+    fn series(
+        &self,
+        candles: &[Candle],
+        derivatives: &DerivativesSeries,
+    ) -> Result<Vec<Option<f64>>, String>;
 }
 
 pub fn build_inputs(configs: &[InputConfig]) -> Result<Vec<Box<dyn SignalInput>>, String> {
@@ -88,6 +114,13 @@ fn build_input(config: &InputConfig) -> Result<Box<dyn SignalInput>, String> {
                 Box::new(indicator),
             )))
         }
+        "funding_rate" | "open_interest" | "open_interest_change" => {
+            Ok(Box::new(DerivativesInput {
+                name: config.name.clone(),
+                kind: config.kind.clone(),
+                period: get_usize(config, "period", DEFAULT_OI_CHANGE_PERIOD),
+            }))
+        }
         other => Err(format!("unknown input kind: {other}")),
     }
 }
@@ -129,7 +162,11 @@ impl SignalInput for IndicatorInput {
         self.indicator.required_candles()
     }
 
-    fn series(&self, candles: &[Candle]) -> Result<Vec<Option<f64>>, String> {
+    fn series(
+        &self,
+        candles: &[Candle],
+        _derivatives: &DerivativesSeries,
+    ) -> Result<Vec<Option<f64>>, String> {
         let values = self
             .indicator
             .calculate(candles)
@@ -151,11 +188,63 @@ impl SignalInput for CloseInput {
         1
     }
 
-    fn series(&self, candles: &[Candle]) -> Result<Vec<Option<f64>>, String> {
+    fn series(
+        &self,
+        candles: &[Candle],
+        _derivatives: &DerivativesSeries,
+    ) -> Result<Vec<Option<f64>>, String> {
         Ok(candles.iter().map(|c| Some(c.close)).collect())
     }
 }
 
+/// Funding rate (in percent), open interest or its percent change over
+/// `period` minutes as of each candle's close, computed like the alert
+/// indicators of the same name.
+struct DerivativesInput {
+    name: String,
+    kind: String,
+    period: usize,
+}
+
+impl SignalInput for DerivativesInput {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn required_candles(&self) -> usize {
+        1
+    }
+
+    fn derivatives_metric(&self) -> Option<DerivativesMetric> {
+        derivatives_metric(&self.kind)
+    }
+
+    fn derivatives_window(&self) -> chrono::Duration {
+        derivatives_window(&self.kind, self.period)
+    }
+
+    fn series(
+        &self,
+        candles: &[Candle],
+        derivatives: &DerivativesSeries,
+    ) -> Result<Vec<Option<f64>>, String> {
+        let Some(samples) = self
+            .derivatives_metric()
+            .and_then(|metric| derivatives.get(&metric))
+        else {
+            return Ok(vec![None; candles.len()]);
+        };
+
+        Ok(candles
+            .iter()
+            .map(|candle| {
+                let close_time = candle.open_time + candle.timeframe.duration();
+                derivatives_value(&self.kind, self.period, samples, close_time)
+            })
+            .collect())
+    }
+}
+
 fn align_series(total_len: usize, values: Vec<f64>) -> Vec<Option<f64>> {
     let offset = total_len.saturating_sub(values.len());
     let mut output = vec![None; total_len];
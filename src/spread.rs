@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::{AppConfig, SpreadConfig};
+use crate::exchange::encode_symbol;
+use crate::model::{ExchangeKind, Instrument, PremiumSample, Ticker};
+use crate::strategy::{ConditionType, parse_condition};
+
+/// Upbit ticker quoting USDT in KRW, used as the default conversion rate.
+pub const UPBIT_FX_SYMBOL: &str = "KRW-USDT";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FxSource {
+    /// Latest price of the Upbit `KRW-USDT` ticker
+    UpbitTicker,
+    /// Fixed KRW per USDT rate from config
+    Fixed(f64),
+}
+
+/// Alert on a spread's premium percentage.
+#[derive(Debug, Clone)]
+pub struct SpreadAlert {
+    pub condition: ConditionType,
+    pub cooldown_minutes: u64,
+}
+
+/// Tracks one asset's premium of its Upbit KRW price over its Binance USDT price.
+#[derive(Debug)]
+pub struct SpreadMonitor {
+    pub name: String,
+    pub base: String,
+    pub upbit_symbol: String,
+    pub binance_symbol: String,
+    pub fx: FxSource,
+    /// Alert on the premium percentage, when configured
+    pub alert: Option<SpreadAlert>,
+    record_interval: Duration,
+    max_leg_age: Duration,
+    last_recorded: Option<DateTime<Utc>>,
+    last_premium: Option<f64>,
+}
+
+impl SpreadMonitor {
+    fn from_config(spread: &SpreadConfig, default_cooldown: u64) -> Self {
+        let upbit_symbol =
+            encode_symbol(ExchangeKind::Upbit, &Instrument::new(&spread.base, "KRW"));
+        let binance_symbol = encode_symbol(
+            ExchangeKind::Binance,
+            &Instrument::new(&spread.base, "USDT"),
+        );
+
+        let fx = match spread.fx_rate {
+            Some(rate) if spread.fx == "fixed" => FxSource::Fixed(rate),
+            _ => FxSource::UpbitTicker,
+        };
+
+        let alert = spread.condition.as_deref().and_then(|condition| {
+            Some(SpreadAlert {
+                condition: parse_condition(condition, spread.threshold, spread.threshold_high)?,
+                cooldown_minutes: spread.cooldown_minutes.unwrap_or(default_cooldown),
+            })
+        });
+
+        Self {
+            name: spread.name.clone(),
+            base: spread.base.to_ascii_uppercase(),
+            upbit_symbol,
+            binance_symbol,
+            fx,
+            alert,
+            record_interval: Duration::seconds(spread.record_interval_secs as i64),
+            max_leg_age: Duration::seconds(spread.max_leg_age_secs as i64),
+            last_recorded: None,
+            last_premium: None,
+        }
+    }
+}
+
+/// A freshly computed premium for one monitor.
+#[derive(Debug, Clone)]
+pub struct SpreadUpdate {
+    /// Index into `SpreadEngine::monitors`
+    pub monitor: usize,
+    pub sample: PremiumSample,
+    /// Premium computed on the previous update, for cross conditions
+    pub previous_premium: Option<f64>,
+    /// Whether the sample is due to be persisted
+    pub record: bool,
+}
+
+/// Computes cross-exchange premiums from the live ticker streams.
+#[derive(Debug, Default)]
+pub struct SpreadEngine {
+    pub monitors: Vec<SpreadMonitor>,
+    latest: HashMap<(ExchangeKind, String), Ticker>,
+}
+
+impl SpreadEngine {
+    /// Build monitors for every `[[spreads]]` entry of a validated config.
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            monitors: config
+                .spreads
+                .iter()
+                .map(|s| SpreadMonitor::from_config(s, config.general.default_cooldown_minutes))
+                .collect(),
+            latest: HashMap::new(),
+        }
+    }
+
+    /// Ticker symbols the engine needs on `exchange` in addition to the
+    /// configured coins.
+    pub fn extra_ticker_symbols(&self, exchange: ExchangeKind) -> Vec<String> {
+        let needs_fx = self.monitors.iter().any(|m| m.fx == FxSource::UpbitTicker);
+
+        if exchange == ExchangeKind::Upbit && needs_fx {
+            vec![UPBIT_FX_SYMBOL.to_owned()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Record `ticker` and recompute every premium it affects.
+    pub fn on_ticker(&mut self, ticker: &Ticker) -> Vec<SpreadUpdate> {
+        self.latest
+            .insert((ticker.exchange, ticker.symbol.clone()), ticker.clone());
+
+        let mut updates = Vec::new();
+        for (index, monitor) in self.monitors.iter_mut().enumerate() {
+            // Only the two legs and the fx ticker matter; other venues never do
+            let upbit_leg = ticker.exchange == ExchangeKind::Upbit
+                && (ticker.symbol == monitor.upbit_symbol
+                    || (ticker.symbol == UPBIT_FX_SYMBOL && monitor.fx == FxSource::UpbitTicker));
+            let binance_leg =
+                ticker.exchange == ExchangeKind::Binance && ticker.symbol == monitor.binance_symbol;
+            let affected = upbit_leg || binance_leg;
+            if !affected {
+                continue;
+            }
+
+            let upbit = self
+                .latest
+                .get(&(ExchangeKind::Upbit, monitor.upbit_symbol.clone()));
+            let binance = self
+                .latest
+                .get(&(ExchangeKind::Binance, monitor.binance_symbol.clone()));
+            let fx = match monitor.fx {
+                FxSource::Fixed(rate) => Some((rate, None)),
+                FxSource::UpbitTicker => self
+                    .latest
+                    .get(&(ExchangeKind::Upbit, UPBIT_FX_SYMBOL.to_owned()))
+                    .map(|t| (t.price, Some(t.timestamp))),
+            };
+            let (Some(upbit), Some(binance), Some((fx_rate, fx_timestamp))) = (upbit, binance, fx)
+            else {
+                continue;
+            };
+            // A stalled leg, or a stalled fx ticker, would compare against a
+            // price long gone
+            let timestamps = [Some(upbit.timestamp), Some(binance.timestamp), fx_timestamp];
+            let newest = timestamps.iter().flatten().max();
+            let oldest = timestamps.iter().flatten().min();
+            if let (Some(&newest), Some(&oldest)) = (newest, oldest)
+                && newest - oldest > monitor.max_leg_age
+            {
+                continue;
+            }
+            let Some(premium_pct) = premium_pct(upbit.price, binance.price, fx_rate) else {
+                continue;
+            };
+
+            let record = monitor
+                .last_recorded
+                .is_none_or(|last| ticker.timestamp - last >= monitor.record_interval);
+            if record {
+                monitor.last_recorded = Some(ticker.timestamp);
+            }
+
+            updates.push(SpreadUpdate {
+                monitor: index,
+                sample: PremiumSample {
+                    spread_name: monitor.name.clone(),
+                    base: monitor.base.clone(),
+                    upbit_price: upbit.price,
+                    binance_price: binance.price,
+                    fx_rate,
+                    premium_pct,
+                    timestamp: ticker.timestamp,
+                },
+                previous_premium: monitor.last_premium.replace(premium_pct),
+                record,
+            });
+        }
+        updates
+    }
+}
+
+/// Premium of `krw_price` over `usdt_price` converted at `krw_per_usdt`, in percent.
+pub fn premium_pct(krw_price: f64, usdt_price: f64, krw_per_usdt: f64) -> Option<f64> {
+    let converted = usdt_price * krw_per_usdt;
+    if converted <= 0.0 {
+        return None;
+    }
+    Some((krw_price / converted - 1.0) * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::instrument_for;
+    use crate::model::TickerStats;
+
+    fn config(extra: &str) -> AppConfig {
+        toml::from_str(&format!(
+            r#"
+[general]
+
+[[spreads]]
+name = "sol-kimchi"
+base = "SOL"
+{extra}
+"#
+        ))
+        .unwrap()
+    }
+
+    fn ticker(exchange: ExchangeKind, symbol: &str, price: f64, secs: i64) -> Ticker {
+        Ticker {
+            exchange,
+            symbol: symbol.to_owned(),
+            instrument: instrument_for(exchange, symbol).unwrap(),
+            price,
+            volume: 0.0,
+            stats: TickerStats::default(),
+            timestamp: DateTime::from_timestamp(1_704_067_200 + secs, 0).unwrap(),
+            received_at: DateTime::from_timestamp(1_704_067_200 + secs, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn premium_pct_compares_converted_prices() {
+        let premium = premium_pct(210_000.0, 150.0, 1_350.0).unwrap();
+        assert!((premium - 3.7037).abs() < 1e-3);
+        assert!(premium_pct(210_000.0, 0.0, 1_350.0).is_none());
+    }
+
+    #[test]
+    fn engine_waits_for_all_legs_and_uses_upbit_fx() {
+        let mut engine = SpreadEngine::from_config(&config(""));
+        assert_eq!(
+            engine.extra_ticker_symbols(ExchangeKind::Upbit),
+            vec![UPBIT_FX_SYMBOL]
+        );
+
+        assert!(
+            engine
+                .on_ticker(&ticker(ExchangeKind::Upbit, "KRW-SOL", 210_000.0, 0))
+                .is_empty()
+        );
+        assert!(
+            engine
+                .on_ticker(&ticker(ExchangeKind::Binance, "SOLUSDT", 150.0, 1))
+                .is_empty()
+        );
+
+        let updates = engine.on_ticker(&ticker(ExchangeKind::Upbit, UPBIT_FX_SYMBOL, 1_400.0, 2));
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].sample.premium_pct, 0.0);
+        assert_eq!(updates[0].sample.fx_rate, 1_400.0);
+        assert!(updates[0].record);
+        assert!(updates[0].previous_premium.is_none());
+    }
+
+    #[test]
+    fn engine_throttles_recording_and_tracks_previous_premium() {
+        let mut engine = SpreadEngine::from_config(&config(
+            "fx = \"fixed\"\nfx_rate = 1000.0\nrecord_interval_secs = 60\nmax_leg_age_secs = 120\ncondition = \"above\"\nthreshold = 5.0",
+        ));
+        assert!(engine.extra_ticker_symbols(ExchangeKind::Upbit).is_empty());
+        assert!(engine.monitors[0].alert.is_some());
+
+        engine.on_ticker(&ticker(ExchangeKind::Binance, "SOLUSDT", 100.0, 0));
+        let first = engine.on_ticker(&ticker(ExchangeKind::Upbit, "KRW-SOL", 102_000.0, 1));
+        let second = engine.on_ticker(&ticker(ExchangeKind::Upbit, "KRW-SOL", 106_000.0, 30));
+        let third = engine.on_ticker(&ticker(ExchangeKind::Upbit, "KRW-SOL", 106_000.0, 61));
+
+        assert!(first[0].record);
+        assert!(!second[0].record);
+        assert!(third[0].record);
+        assert!((second[0].sample.premium_pct - 6.0).abs() < 1e-9);
+        assert!((second[0].previous_premium.unwrap() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn engine_skips_premium_against_a_stale_leg() {
+        let mut engine = SpreadEngine::from_config(&config(
+            "fx = \"fixed\"\nfx_rate = 1000.0\nmax_leg_age_secs = 10",
+        ));
+
+        engine.on_ticker(&ticker(ExchangeKind::Binance, "SOLUSDT", 100.0, 0));
+        assert_eq!(
+            engine
+                .on_ticker(&ticker(ExchangeKind::Upbit, "KRW-SOL", 102_000.0, 10))
+                .len(),
+            1
+        );
+        // The Binance stream stalled
+        assert!(
+            engine
+                .on_ticker(&ticker(ExchangeKind::Upbit, "KRW-SOL", 106_000.0, 11))
+                .is_empty()
+        );
+
+        let updates = engine.on_ticker(&ticker(ExchangeKind::Binance, "SOLUSDT", 100.0, 12));
+        assert_eq!(updates.len(), 1);
+        assert!((updates[0].sample.premium_pct - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn engine_skips_premium_against_a_stale_fx_rate() {
+        let mut engine = SpreadEngine::from_config(&config("max_leg_age_secs = 10"));
+
+        engine.on_ticker(&ticker(ExchangeKind::Upbit, UPBIT_FX_SYMBOL, 1_000.0, 0));
+        engine.on_ticker(&ticker(ExchangeKind::Binance, "SOLUSDT", 100.0, 5));
+        assert_eq!(
+            engine
+                .on_ticker(&ticker(ExchangeKind::Upbit, "KRW-SOL", 102_000.0, 10))
+                .len(),
+            1
+        );
+        // Both price legs are fresh, but KRW-USDT stalled
+        engine.on_ticker(&ticker(ExchangeKind::Binance, "SOLUSDT", 100.0, 20));
+        assert!(
+            engine
+                .on_ticker(&ticker(ExchangeKind::Upbit, "KRW-SOL", 106_000.0, 21))
+                .is_empty()
+        );
+
+        let updates = engine.on_ticker(&ticker(ExchangeKind::Upbit, UPBIT_FX_SYMBOL, 1_000.0, 22));
+        assert_eq!(updates.len(), 1);
+        assert!((updates[0].sample.premium_pct - 6.0).abs() < 1e-9);
+    }
+}
@@ -5,7 +5,10 @@ use error_stack::Report;
 use futures::future::BoxFuture;
 
 use crate::error::StorageError;
-use crate::model::{BacktestRun, BacktestTrade, Candle, ExchangeKind, TimeFrame, Trade};
+use crate::model::{
+    BacktestRun, BacktestTrade, Candle, CandleDiscrepancy, DerivativesMetric, DerivativesSample,
+    ExchangeKind, OrderBook, PremiumSample, TimeFrame, Trade,
+};
 
 pub trait Storage: Send + Sync {
     fn upsert_candles(&self, candles: &[Candle])
@@ -32,6 +35,81 @@ pub trait Storage: Send + Sync {
         end_time: DateTime<Utc>,
     ) -> BoxFuture<'_, Result<Vec<Candle>, Report<StorageError>>>;
 
+    /// Return the open times of the stored candles within
+    /// `[start_time, end_time]`, oldest first.
+    fn get_candle_open_times(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<DateTime<Utc>>, Report<StorageError>>>;
+
+    /// Store candle reconciliation results; a discrepancy replaces a stored
+    /// one of the same candle.
+    fn upsert_candle_discrepancies(
+        &self,
+        discrepancies: &[CandleDiscrepancy],
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>>;
+
+    /// Return a series' discrepancies with open times within
+    /// `[start_time, end_time]`, oldest first.
+    fn get_candle_discrepancies(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<CandleDiscrepancy>, Report<StorageError>>>;
+
+    /// Store order book snapshots; every call appends new rows.
+    fn insert_orderbook_snapshots(
+        &self,
+        books: &[OrderBook],
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>>;
+
+    fn insert_premium_samples(
+        &self,
+        samples: &[PremiumSample],
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>>;
+
+    /// Return a spread's premium samples within `[start_time, end_time]`,
+    /// oldest first.
+    fn get_premium_series(
+        &self,
+        spread_name: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<PremiumSample>, Report<StorageError>>>;
+
+    /// Store derivatives samples; a sample replaces a stored one of the same
+    /// metric and timestamp.
+    fn upsert_derivatives_samples(
+        &self,
+        samples: &[DerivativesSample],
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>>;
+
+    /// Return the newest `limit` samples of a metric, oldest first.
+    fn get_recent_derivatives_samples(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        metric: DerivativesMetric,
+        limit: usize,
+    ) -> BoxFuture<'_, Result<Vec<DerivativesSample>, Report<StorageError>>>;
+
+    /// Return a metric's samples within `[start_time, end_time]`, oldest first.
+    fn get_derivatives_series(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        metric: DerivativesMetric,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<DerivativesSample>, Report<StorageError>>>;
+
     fn log_alert(
         &self,
         alert_name: &str,
@@ -41,6 +119,16 @@ pub trait Storage: Send + Sync {
         message: &str,
     ) -> BoxFuture<'_, Result<(), Report<StorageError>>>;
 
+    /// Log a spread alert. Spreads span two exchanges, so the entry names
+    /// the spread's base asset instead of one exchange's symbol.
+    fn log_spread_alert(
+        &self,
+        spread_name: &str,
+        base: &str,
+        premium_pct: f64,
+        message: &str,
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>>;
+
     fn last_alert_time(
         &self,
         alert_name: &str,
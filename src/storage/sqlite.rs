@@ -9,7 +9,11 @@ use std::path::Path;
 use std::str::FromStr;
 
 use crate::error::StorageError;
-use crate::model::{BacktestRun, BacktestTrade, Candle, ExchangeKind, TimeFrame, Trade, TradeSide};
+use crate::exchange::instrument_for;
+use crate::model::{
+    BacktestRun, BacktestTrade, Candle, CandleDiscrepancy, DerivativesMetric, DerivativesSample,
+    ExchangeKind, Instrument, OrderBook, PremiumSample, TimeFrame, Trade, TradeSide,
+};
 use crate::storage::Storage;
 
 type BacktestRunRow = (
@@ -157,37 +161,33 @@ impl Storage for SqliteStorage {
     ) -> BoxFuture<'_, Result<Vec<Candle>, Report<StorageError>>> {
         let symbol = symbol.to_string();
         Box::pin(async move {
+            let instrument = stored_instrument(exchange, &symbol)?;
             #[allow(clippy::type_complexity)]
-            let rows: Vec<(String, String, String, String, f64, f64, f64, f64, f64)> =
-                sqlx::query_as(
-                    "SELECT exchange, symbol, timeframe, open_time, open, high, low, close, volume \
+            let rows: Vec<(String, String, String, f64, f64, f64, f64, f64)> = sqlx::query_as(
+                "SELECT symbol, timeframe, open_time, open, high, low, close, volume \
                      FROM candles \
                      WHERE exchange = ? AND symbol = ? AND timeframe = ? \
                      ORDER BY open_time DESC \
                      LIMIT ?",
-                )
-                .bind(exchange.to_string())
-                .bind(&symbol)
-                .bind(timeframe.as_str())
-                .bind(limit as i64)
-                .fetch_all(&self.pool)
-                .await
-                .change_context(StorageError::Query)?;
+            )
+            .bind(exchange.to_string())
+            .bind(&symbol)
+            .bind(timeframe.as_str())
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .change_context(StorageError::Query)?;
 
             let mut candles: Vec<Candle> = rows
                 .into_iter()
-                .map(|(exch, sym, tf, ot, open, high, low, close, volume)| {
-                    let exchange = if exch == "upbit" {
-                        ExchangeKind::Upbit
-                    } else {
-                        ExchangeKind::Binance
-                    };
+                .map(|(sym, tf, ot, open, high, low, close, volume)| {
                     let timeframe = TimeFrame::from_str(&tf).unwrap_or(TimeFrame::Min1);
                     let open_time = DateTime::parse_from_rfc3339(&ot)
                         .map(|dt| dt.with_timezone(&Utc))
                         .unwrap_or_else(|_| Utc::now());
                     Candle {
                         exchange,
+                        instrument: instrument.clone(),
                         symbol: sym,
                         timeframe,
                         open_time,
@@ -216,38 +216,34 @@ impl Storage for SqliteStorage {
     ) -> BoxFuture<'_, Result<Vec<Candle>, Report<StorageError>>> {
         let symbol = symbol.to_string();
         Box::pin(async move {
+            let instrument = stored_instrument(exchange, &symbol)?;
             #[allow(clippy::type_complexity)]
-            let rows: Vec<(String, String, String, String, f64, f64, f64, f64, f64)> =
-                sqlx::query_as(
-                    "SELECT exchange, symbol, timeframe, open_time, open, high, low, close, volume \
+            let rows: Vec<(String, String, String, f64, f64, f64, f64, f64)> = sqlx::query_as(
+                "SELECT symbol, timeframe, open_time, open, high, low, close, volume \
                      FROM candles \
                      WHERE exchange = ? AND symbol = ? AND timeframe = ? \
                      AND open_time >= ? AND open_time <= ? \
                      ORDER BY open_time ASC",
-                )
-                .bind(exchange.to_string())
-                .bind(&symbol)
-                .bind(timeframe.as_str())
-                .bind(start_time.to_rfc3339())
-                .bind(end_time.to_rfc3339())
-                .fetch_all(&self.pool)
-                .await
-                .change_context(StorageError::Query)?;
+            )
+            .bind(exchange.to_string())
+            .bind(&symbol)
+            .bind(timeframe.as_str())
+            .bind(start_time.to_rfc3339())
+            .bind(end_time.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .change_context(StorageError::Query)?;
 
             let candles = rows
                 .into_iter()
-                .map(|(exch, sym, tf, ot, open, high, low, close, volume)| {
-                    let exchange = if exch == "upbit" {
-                        ExchangeKind::Upbit
-                    } else {
-                        ExchangeKind::Binance
-                    };
+                .map(|(sym, tf, ot, open, high, low, close, volume)| {
                     let timeframe = TimeFrame::from_str(&tf).unwrap_or(TimeFrame::Min1);
                     let open_time = DateTime::parse_from_rfc3339(&ot)
                         .map(|dt| dt.with_timezone(&Utc))
                         .unwrap_or_else(|_| Utc::now());
                     Candle {
                         exchange,
+                        instrument: instrument.clone(),
                         symbol: sym,
                         timeframe,
                         open_time,
@@ -264,6 +260,338 @@ impl Storage for SqliteStorage {
         })
     }
 
+    fn get_candle_open_times(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<DateTime<Utc>>, Report<StorageError>>> {
+        let symbol = symbol.to_string();
+        Box::pin(async move {
+            let rows: Vec<(String,)> = sqlx::query_as(
+                "SELECT open_time FROM candles \
+                 WHERE exchange = ? AND symbol = ? AND timeframe = ? \
+                 AND open_time >= ? AND open_time <= ? \
+                 ORDER BY open_time ASC",
+            )
+            .bind(exchange.to_string())
+            .bind(&symbol)
+            .bind(timeframe.as_str())
+            .bind(start_time.to_rfc3339())
+            .bind(end_time.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .change_context(StorageError::Query)?;
+
+            Ok(rows
+                .into_iter()
+                .map(|(open_time,)| parse_time_utc(&open_time))
+                .collect())
+        })
+    }
+
+    fn upsert_candle_discrepancies(
+        &self,
+        discrepancies: &[CandleDiscrepancy],
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>> {
+        let discrepancies = discrepancies.to_vec();
+        Box::pin(async move {
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .change_context(StorageError::Insert)?;
+
+            for d in &discrepancies {
+                sqlx::query(
+                    "INSERT OR REPLACE INTO candle_discrepancies \
+                     (exchange, symbol, timeframe, open_time, price_diff_pct, volume_diff, volume_diff_pct) \
+                     VALUES (?, ?, ?, ?, ?, ?, ?)",
+                )
+                .bind(d.exchange.to_string())
+                .bind(&d.symbol)
+                .bind(d.timeframe.as_str())
+                .bind(d.open_time.to_rfc3339())
+                .bind(d.price_diff_pct)
+                .bind(d.volume_diff)
+                .bind(d.volume_diff_pct)
+                .execute(&mut *tx)
+                .await
+                .change_context(StorageError::Insert)?;
+            }
+
+            tx.commit().await.change_context(StorageError::Insert)?;
+            Ok(())
+        })
+    }
+
+    fn get_candle_discrepancies(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        timeframe: TimeFrame,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<CandleDiscrepancy>, Report<StorageError>>> {
+        let symbol = symbol.to_string();
+        Box::pin(async move {
+            let rows: Vec<(String, f64, f64, f64)> = sqlx::query_as(
+                "SELECT open_time, price_diff_pct, volume_diff, volume_diff_pct \
+                 FROM candle_discrepancies \
+                 WHERE exchange = ? AND symbol = ? AND timeframe = ? \
+                 AND open_time >= ? AND open_time <= ? \
+                 ORDER BY open_time ASC",
+            )
+            .bind(exchange.to_string())
+            .bind(&symbol)
+            .bind(timeframe.as_str())
+            .bind(start_time.to_rfc3339())
+            .bind(end_time.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .change_context(StorageError::Query)?;
+
+            Ok(rows
+                .into_iter()
+                .map(
+                    |(ts, price_diff_pct, volume_diff, volume_diff_pct)| CandleDiscrepancy {
+                        exchange,
+                        symbol: symbol.clone(),
+                        timeframe,
+                        open_time: parse_time_utc(&ts),
+                        price_diff_pct,
+                        volume_diff,
+                        volume_diff_pct,
+                    },
+                )
+                .collect())
+        })
+    }
+
+    fn insert_orderbook_snapshots(
+        &self,
+        books: &[OrderBook],
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>> {
+        let books = books.to_vec();
+        Box::pin(async move {
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .change_context(StorageError::Insert)?;
+
+            for book in &books {
+                let bids =
+                    serde_json::to_string(&book.bids).change_context(StorageError::Insert)?;
+                let asks =
+                    serde_json::to_string(&book.asks).change_context(StorageError::Insert)?;
+                sqlx::query(
+                    "INSERT INTO orderbook_snapshots (exchange, symbol, timestamp, bids, asks) \
+                     VALUES (?, ?, ?, ?, ?)",
+                )
+                .bind(book.exchange.to_string())
+                .bind(&book.symbol)
+                .bind(book.timestamp.to_rfc3339())
+                .bind(bids)
+                .bind(asks)
+                .execute(&mut *tx)
+                .await
+                .change_context(StorageError::Insert)?;
+            }
+
+            tx.commit().await.change_context(StorageError::Insert)?;
+            Ok(())
+        })
+    }
+
+    fn insert_premium_samples(
+        &self,
+        samples: &[PremiumSample],
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>> {
+        let samples = samples.to_vec();
+        Box::pin(async move {
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .change_context(StorageError::Insert)?;
+
+            for s in &samples {
+                sqlx::query(
+                    "INSERT INTO premium_series \
+                     (spread_name, base, timestamp, upbit_price, binance_price, fx_rate, premium_pct) \
+                     VALUES (?, ?, ?, ?, ?, ?, ?)",
+                )
+                .bind(&s.spread_name)
+                .bind(&s.base)
+                .bind(s.timestamp.to_rfc3339())
+                .bind(s.upbit_price)
+                .bind(s.binance_price)
+                .bind(s.fx_rate)
+                .bind(s.premium_pct)
+                .execute(&mut *tx)
+                .await
+                .change_context(StorageError::Insert)?;
+            }
+
+            tx.commit().await.change_context(StorageError::Insert)?;
+            Ok(())
+        })
+    }
+
+    fn get_premium_series(
+        &self,
+        spread_name: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<PremiumSample>, Report<StorageError>>> {
+        let spread_name = spread_name.to_string();
+        Box::pin(async move {
+            #[allow(clippy::type_complexity)]
+            let rows: Vec<(String, String, String, f64, f64, f64, f64)> = sqlx::query_as(
+                "SELECT spread_name, base, timestamp, upbit_price, binance_price, fx_rate, premium_pct \
+                 FROM premium_series \
+                 WHERE spread_name = ? AND timestamp >= ? AND timestamp <= ? \
+                 ORDER BY timestamp ASC",
+            )
+            .bind(&spread_name)
+            .bind(start_time.to_rfc3339())
+            .bind(end_time.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .change_context(StorageError::Query)?;
+
+            Ok(rows
+                .into_iter()
+                .map(
+                    |(spread_name, base, ts, upbit_price, binance_price, fx_rate, premium_pct)| {
+                        PremiumSample {
+                            spread_name,
+                            base,
+                            upbit_price,
+                            binance_price,
+                            fx_rate,
+                            premium_pct,
+                            timestamp: parse_time_utc(&ts),
+                        }
+                    },
+                )
+                .collect())
+        })
+    }
+
+    fn upsert_derivatives_samples(
+        &self,
+        samples: &[DerivativesSample],
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>> {
+        let samples = samples.to_vec();
+        Box::pin(async move {
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .change_context(StorageError::Insert)?;
+
+            for s in &samples {
+                sqlx::query(
+                    "INSERT INTO derivatives_metrics (exchange, symbol, metric, timestamp, value) \
+                     VALUES (?, ?, ?, ?, ?) \
+                     ON CONFLICT(exchange, symbol, metric, timestamp) DO UPDATE SET \
+                     value = excluded.value",
+                )
+                .bind(s.exchange.to_string())
+                .bind(&s.symbol)
+                .bind(s.metric.as_str())
+                .bind(s.timestamp.to_rfc3339())
+                .bind(s.value)
+                .execute(&mut *tx)
+                .await
+                .change_context(StorageError::Insert)?;
+            }
+
+            tx.commit().await.change_context(StorageError::Insert)?;
+            Ok(())
+        })
+    }
+
+    fn get_recent_derivatives_samples(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        metric: DerivativesMetric,
+        limit: usize,
+    ) -> BoxFuture<'_, Result<Vec<DerivativesSample>, Report<StorageError>>> {
+        let symbol = symbol.to_string();
+        Box::pin(async move {
+            let rows: Vec<(String, f64)> = sqlx::query_as(
+                "SELECT timestamp, value FROM derivatives_metrics \
+                 WHERE exchange = ? AND symbol = ? AND metric = ? \
+                 ORDER BY timestamp DESC \
+                 LIMIT ?",
+            )
+            .bind(exchange.to_string())
+            .bind(&symbol)
+            .bind(metric.as_str())
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .change_context(StorageError::Query)?;
+
+            // Return in ascending chronological order (oldest first)
+            Ok(rows
+                .into_iter()
+                .rev()
+                .map(|(ts, value)| DerivativesSample {
+                    exchange,
+                    symbol: symbol.clone(),
+                    metric,
+                    value,
+                    timestamp: parse_time_utc(&ts),
+                })
+                .collect())
+        })
+    }
+
+    fn get_derivatives_series(
+        &self,
+        exchange: ExchangeKind,
+        symbol: &str,
+        metric: DerivativesMetric,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<DerivativesSample>, Report<StorageError>>> {
+        let symbol = symbol.to_string();
+        Box::pin(async move {
+            let rows: Vec<(String, f64)> = sqlx::query_as(
+                "SELECT timestamp, value FROM derivatives_metrics \
+                 WHERE exchange = ? AND symbol = ? AND metric = ? \
+                 AND timestamp >= ? AND timestamp <= ? \
+                 ORDER BY timestamp ASC",
+            )
+            .bind(exchange.to_string())
+            .bind(&symbol)
+            .bind(metric.as_str())
+            .bind(start_time.to_rfc3339())
+            .bind(end_time.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .change_context(StorageError::Query)?;
+
+            Ok(rows
+                .into_iter()
+                .map(|(ts, value)| DerivativesSample {
+                    exchange,
+                    symbol: symbol.clone(),
+                    metric,
+                    value,
+                    timestamp: parse_time_utc(&ts),
+                })
+                .collect())
+        })
+    }
+
     fn log_alert(
         &self,
         alert_name: &str,
@@ -276,21 +604,38 @@ impl Storage for SqliteStorage {
         let symbol = symbol.to_string();
         let message = message.to_string();
         Box::pin(async move {
-            sqlx::query(
-                "INSERT INTO alerts_log \
-                 (alert_name, exchange, symbol, triggered_at, indicator_value, message) \
-                 VALUES (?, ?, ?, ?, ?, ?)",
+            insert_alert_log(
+                &self.pool,
+                &alert_name,
+                &exchange.to_string(),
+                &symbol,
+                indicator_value,
+                &message,
+            )
+            .await
+        })
+    }
+
+    fn log_spread_alert(
+        &self,
+        spread_name: &str,
+        base: &str,
+        premium_pct: f64,
+        message: &str,
+    ) -> BoxFuture<'_, Result<(), Report<StorageError>>> {
+        let spread_name = spread_name.to_string();
+        let base = base.to_string();
+        let message = message.to_string();
+        Box::pin(async move {
+            insert_alert_log(
+                &self.pool,
+                &spread_name,
+                SPREAD_ALERT_EXCHANGE,
+                &base,
+                premium_pct,
+                &message,
             )
-            .bind(&alert_name)
-            .bind(exchange.to_string())
-            .bind(&symbol)
-            .bind(Utc::now().to_rfc3339())
-            .bind(indicator_value)
-            .bind(&message)
-            .execute(&self.pool)
             .await
-            .change_context(StorageError::Insert)?;
-            Ok(())
         })
     }
 
@@ -402,7 +747,7 @@ impl Storage for SqliteStorage {
             .await
             .change_context(StorageError::Query)?;
 
-            Ok(rows.into_iter().map(map_backtest_run_row).collect())
+            rows.into_iter().map(map_backtest_run_row).collect()
         })
     }
 
@@ -423,7 +768,7 @@ impl Storage for SqliteStorage {
             .await
             .change_context(StorageError::Query)?;
 
-            Ok(row.map(map_backtest_run_row))
+            row.map(map_backtest_run_row).transpose()
         })
     }
 
@@ -445,7 +790,7 @@ impl Storage for SqliteStorage {
             .await
             .change_context(StorageError::Query)?;
 
-            Ok(rows.into_iter().map(map_backtest_trade_row).collect())
+            rows.into_iter().map(map_backtest_trade_row).collect()
         })
     }
 }
@@ -467,11 +812,11 @@ fn map_backtest_run_row(
         trade_count,
         created_at,
     ): BacktestRunRow,
-) -> BacktestRun {
-    BacktestRun {
+) -> Result<BacktestRun, Report<StorageError>> {
+    Ok(BacktestRun {
         run_id,
         model_name,
-        exchange: parse_exchange_kind(&exchange),
+        exchange: parse_exchange_kind(&exchange)?,
         symbol,
         timeframe: TimeFrame::from_str(&timeframe).unwrap_or(TimeFrame::Min1),
         start_time: parse_time_utc(&start_time),
@@ -483,7 +828,7 @@ fn map_backtest_run_row(
         win_rate_pct,
         trade_count: trade_count.max(0) as usize,
         created_at: parse_time_utc(&created_at),
-    }
+    })
 }
 
 fn map_backtest_trade_row(
@@ -501,10 +846,10 @@ fn map_backtest_trade_row(
         fee_paid,
         reason,
     ): BacktestTradeRow,
-) -> BacktestTrade {
-    BacktestTrade {
+) -> Result<BacktestTrade, Report<StorageError>> {
+    Ok(BacktestTrade {
         run_id,
-        exchange: parse_exchange_kind(&exchange),
+        exchange: parse_exchange_kind(&exchange)?,
         symbol,
         entry_time: parse_time_utc(&entry_time),
         exit_time: parse_time_utc(&exit_time),
@@ -515,14 +860,14 @@ fn map_backtest_trade_row(
         net_pnl,
         fee_paid,
         reason,
-    }
+    })
 }
 
-fn parse_exchange_kind(value: &str) -> ExchangeKind {
-    if value == "upbit" {
-        return ExchangeKind::Upbit;
-    }
-    ExchangeKind::Binance
+fn parse_exchange_kind(value: &str) -> Result<ExchangeKind, Report<StorageError>> {
+    value
+        .parse()
+        .change_context(StorageError::Query)
+        .attach_with(|| format!("stored exchange \"{value}\" is not registered"))
 }
 
 fn parse_time_utc(value: &str) -> DateTime<Utc> {
@@ -531,10 +876,49 @@ fn parse_time_utc(value: &str) -> DateTime<Utc> {
         .unwrap_or_else(|_| Utc::now())
 }
 
+/// Instrument of a symbol being read back. Only decodable symbols are
+/// stored, so one that is not cannot be turned into candles.
+fn stored_instrument(
+    exchange: ExchangeKind,
+    symbol: &str,
+) -> Result<Instrument, Report<StorageError>> {
+    instrument_for(exchange, symbol).ok_or_else(|| {
+        Report::new(StorageError::Query).attach(format!("undecodable {exchange} symbol {symbol}"))
+    })
+}
+
+/// `alerts_log.exchange` of spread alerts, which belong to no one exchange.
+const SPREAD_ALERT_EXCHANGE: &str = "spread";
+
+async fn insert_alert_log(
+    pool: &SqlitePool,
+    alert_name: &str,
+    exchange: &str,
+    symbol: &str,
+    indicator_value: f64,
+    message: &str,
+) -> Result<(), Report<StorageError>> {
+    sqlx::query(
+        "INSERT INTO alerts_log \
+         (alert_name, exchange, symbol, triggered_at, indicator_value, message) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(alert_name)
+    .bind(exchange)
+    .bind(symbol)
+    .bind(Utc::now().to_rfc3339())
+    .bind(indicator_value)
+    .bind(message)
+    .execute(pool)
+    .await
+    .change_context(StorageError::Insert)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::TradeSide;
+    use crate::model::{Instrument, OrderBookLevel, TradeSide};
 
     async fn in_memory_storage() -> SqliteStorage {
         let opts = SqliteConnectOptions::from_str("sqlite::memory:")
@@ -549,6 +933,7 @@ mod tests {
         Candle {
             exchange: ExchangeKind::Upbit,
             symbol: symbol.to_string(),
+            instrument: instrument_for(ExchangeKind::Upbit, symbol).unwrap(),
             timeframe: TimeFrame::Min1,
             open_time,
             open: close,
@@ -580,6 +965,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(clippy::cloned_ref_to_slice_refs)]
     async fn upsert_deduplication() {
         let storage = in_memory_storage().await;
         let t = Utc::now();
@@ -601,21 +987,195 @@ mod tests {
         assert_eq!(result[0].close, 200.0);
     }
 
+    #[tokio::test]
+    async fn candle_open_times_within_window() {
+        let storage = in_memory_storage().await;
+        let t = DateTime::from_timestamp(1_704_067_200, 0).unwrap();
+        let candles: Vec<Candle> = [0, 1, 3, 4]
+            .into_iter()
+            .map(|i| make_candle("KRW-BTC", t + chrono::Duration::minutes(i), 100.0))
+            .collect();
+        storage.upsert_candles(&candles).await.unwrap();
+
+        let open_times = storage
+            .get_candle_open_times(
+                ExchangeKind::Upbit,
+                "KRW-BTC",
+                TimeFrame::Min1,
+                t + chrono::Duration::minutes(1),
+                t + chrono::Duration::minutes(3),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            open_times,
+            vec![
+                t + chrono::Duration::minutes(1),
+                t + chrono::Duration::minutes(3)
+            ]
+        );
+
+        let empty = storage
+            .get_candle_open_times(
+                ExchangeKind::Upbit,
+                "KRW-ETH",
+                TimeFrame::Min1,
+                t,
+                t + chrono::Duration::minutes(5),
+            )
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+
     #[tokio::test]
     async fn insert_trade() {
         let storage = in_memory_storage().await;
         let trade = Trade {
             exchange: ExchangeKind::Binance,
             symbol: "BTCUSDT".into(),
+            instrument: Instrument::new("BTC", "USDT"),
             price: 50000.0,
             volume: 0.5,
             side: TradeSide::Buy,
             timestamp: Utc::now(),
+            received_at: Utc::now(),
+            trade_id: Some(1),
         };
         // Verify no error on insert
         storage.insert_trades(&[trade]).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn orderbook_snapshots_are_appended() {
+        let storage = in_memory_storage().await;
+        let t = Utc::now();
+        let book_at = |timestamp: DateTime<Utc>, bid: f64| OrderBook {
+            exchange: ExchangeKind::Upbit,
+            symbol: "KRW-BTC".into(),
+            bids: vec![OrderBookLevel {
+                price: bid,
+                size: 1.5,
+            }],
+            asks: vec![OrderBookLevel {
+                price: bid + 1.0,
+                size: 2.0,
+            }],
+            timestamp,
+        };
+
+        storage
+            .insert_orderbook_snapshots(&[
+                book_at(t - chrono::Duration::seconds(10), 99.0),
+                book_at(t, 100.0),
+            ])
+            .await
+            .unwrap();
+
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT bids, asks FROM orderbook_snapshots \
+             WHERE exchange = 'upbit' AND symbol = 'KRW-BTC' \
+             ORDER BY timestamp",
+        )
+        .fetch_all(&storage.pool)
+        .await
+        .unwrap();
+        assert_eq!(rows.len(), 2);
+        let bids: Vec<OrderBookLevel> = serde_json::from_str(&rows[1].0).unwrap();
+        let asks: Vec<OrderBookLevel> = serde_json::from_str(&rows[1].1).unwrap();
+        assert_eq!(bids[0].price, 100.0);
+        assert_eq!(bids[0].size, 1.5);
+        assert_eq!(asks[0].price, 101.0);
+    }
+
+    #[tokio::test]
+    async fn premium_series_round_trip() {
+        let storage = in_memory_storage().await;
+        let t = Utc::now();
+        let sample_at = |timestamp: DateTime<Utc>, premium_pct: f64| PremiumSample {
+            spread_name: "sol-kimchi".into(),
+            base: "SOL".into(),
+            upbit_price: 200_000.0,
+            binance_price: 140.0,
+            fx_rate: 1_400.0,
+            premium_pct,
+            timestamp,
+        };
+
+        storage
+            .insert_premium_samples(&[
+                sample_at(t - chrono::Duration::minutes(2), 1.5),
+                sample_at(t - chrono::Duration::minutes(1), 2.0),
+                sample_at(t + chrono::Duration::minutes(1), 2.5),
+            ])
+            .await
+            .unwrap();
+
+        let series = storage
+            .get_premium_series("sol-kimchi", t - chrono::Duration::minutes(5), t)
+            .await
+            .unwrap();
+        let premiums: Vec<f64> = series.iter().map(|s| s.premium_pct).collect();
+        assert_eq!(premiums, vec![1.5, 2.0]);
+    }
+
+    #[tokio::test]
+    async fn derivatives_samples_upsert_and_query() {
+        let storage = in_memory_storage().await;
+        let t = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let sample = |minutes: i64, metric, value| DerivativesSample {
+            exchange: ExchangeKind::BinanceFutures,
+            symbol: "BTCUSDT".into(),
+            metric,
+            value,
+            timestamp: t + chrono::Duration::minutes(minutes),
+        };
+
+        storage
+            .upsert_derivatives_samples(&[
+                sample(0, DerivativesMetric::FundingRate, 0.0001),
+                sample(1, DerivativesMetric::FundingRate, 0.0002),
+                sample(2, DerivativesMetric::FundingRate, 0.0003),
+                sample(1, DerivativesMetric::OpenInterest, 5_000.0),
+            ])
+            .await
+            .unwrap();
+        // Same minute again replaces the stored value
+        storage
+            .upsert_derivatives_samples(&[sample(2, DerivativesMetric::FundingRate, 0.0004)])
+            .await
+            .unwrap();
+
+        let recent = storage
+            .get_recent_derivatives_samples(
+                ExchangeKind::BinanceFutures,
+                "BTCUSDT",
+                DerivativesMetric::FundingRate,
+                2,
+            )
+            .await
+            .unwrap();
+        let values: Vec<f64> = recent.iter().map(|s| s.value).collect();
+        assert_eq!(values, vec![0.0002, 0.0004]);
+
+        let series = storage
+            .get_derivatives_series(
+                ExchangeKind::BinanceFutures,
+                "BTCUSDT",
+                DerivativesMetric::OpenInterest,
+                t,
+                t + chrono::Duration::minutes(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            series,
+            vec![sample(1, DerivativesMetric::OpenInterest, 5_000.0)]
+        );
+    }
+
     #[tokio::test]
     async fn alert_log_and_last_alert_time() {
         let storage = in_memory_storage().await;
@@ -638,6 +1198,26 @@ mod tests {
         assert!(last.is_some());
     }
 
+    #[tokio::test]
+    async fn spread_alerts_share_the_alert_log() {
+        let storage = in_memory_storage().await;
+
+        storage
+            .log_spread_alert("sol-kimchi", "SOL", 5.2, "[sol-kimchi] SOL premium 5.200%")
+            .await
+            .unwrap();
+
+        let last = storage.last_alert_time("sol-kimchi").await.unwrap();
+        assert!(last.is_some());
+        let (exchange, symbol): (String, String) =
+            sqlx::query_as("SELECT exchange, symbol FROM alerts_log WHERE alert_name = ?")
+                .bind("sol-kimchi")
+                .fetch_one(&storage.pool)
+                .await
+                .unwrap();
+        assert_eq!((exchange.as_str(), symbol.as_str()), ("spread", "SOL"));
+    }
+
     #[tokio::test]
     async fn save_and_query_backtest_results() {
         let storage = in_memory_storage().await;
@@ -688,4 +1268,21 @@ mod tests {
         assert_eq!(fetched_trades.len(), 1);
         assert_eq!(fetched_trades[0].reason, "model_sell");
     }
+
+    #[tokio::test]
+    async fn unknown_stored_exchange_is_an_error() {
+        let storage = in_memory_storage().await;
+        sqlx::query(
+            "INSERT INTO backtest_runs VALUES \
+             ('run-1', 'm', 'bithumb', 'KRW-BTC', '1m', '2024-01-01T00:00:00+00:00', \
+              '2024-01-02T00:00:00+00:00', 1000.0, 1000.0, 0.0, 0.0, 0.0, 0, \
+              '2024-01-02T00:00:00+00:00')",
+        )
+        .execute(&storage.pool)
+        .await
+        .unwrap();
+
+        assert!(storage.list_backtest_runs(10).await.is_err());
+        assert!(storage.get_backtest_run("run-1").await.is_err());
+    }
 }
@@ -1,7 +1,12 @@
 pub mod condition;
 
+use chrono::{DateTime, Utc};
+
 use crate::config::{AlertConfig, AppConfig};
-use crate::model::ExchangeKind;
+use crate::model::{
+    DerivativesMetric, DerivativesSample, ExchangeKind, OrderBook, OrderBookLevel, Ticker,
+    TimeFrame,
+};
 
 #[derive(Debug, Clone)]
 pub enum ConditionType {
@@ -12,7 +17,7 @@ pub enum ConditionType {
     Between { low: f64, high: f64 },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct IndicatorParams {
     pub period: Option<usize>,
     pub fast_period: Option<usize>,
@@ -23,6 +28,27 @@ pub struct IndicatorParams {
     pub surge_multiplier: Option<f64>,
 }
 
+/// When a rule's condition is checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvaluateOn {
+    /// On every ticker, against the still-forming candle
+    #[default]
+    Tick,
+    /// Once per closed candle of the rule's timeframe, like a backtest
+    Close,
+}
+
+impl EvaluateOn {
+    /// Parse the config `evaluate_on` value.
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "tick" => Some(Self::Tick),
+            "close" => Some(Self::Close),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AlertRule {
     pub name: String,
@@ -30,8 +56,11 @@ pub struct AlertRule {
     pub symbol: String,
     pub indicator_name: String,
     pub indicator_params: IndicatorParams,
+    /// Candles a candle indicator is computed on
+    pub timeframe: TimeFrame,
     pub condition: ConditionType,
     pub cooldown_minutes: u64,
+    pub evaluate_on: EvaluateOn,
 }
 
 impl AlertRule {
@@ -46,15 +75,18 @@ impl AlertRule {
 }
 
 fn build_rule(alert: &AlertConfig, default_cooldown: u64) -> Option<AlertRule> {
-    let exchange = match alert.exchange.as_str() {
-        "upbit" => ExchangeKind::Upbit,
-        "binance" => ExchangeKind::Binance,
-        _ => return None,
-    };
+    // Exchange names are validated in config
+    let exchange = alert.exchange.parse().ok()?;
 
-    let condition = parse_condition(alert)?;
+    let threshold_high = alert
+        .params
+        .get("threshold_high")
+        .and_then(|v| v.as_float());
+    let condition = parse_condition(&alert.condition, alert.threshold, threshold_high)?;
+    let timeframe = TimeFrame::from_str(&alert.timeframe)?;
     let params = parse_indicator_params(alert);
     let cooldown = alert.cooldown_minutes.unwrap_or(default_cooldown);
+    let evaluate_on = EvaluateOn::from_str(&alert.evaluate_on)?;
 
     Some(AlertRule {
         name: alert.name.clone(),
@@ -62,25 +94,178 @@ fn build_rule(alert: &AlertConfig, default_cooldown: u64) -> Option<AlertRule> {
         symbol: alert.symbol.clone(),
         indicator_name: alert.indicator.clone(),
         indicator_params: params,
+        timeframe,
         condition,
         cooldown_minutes: cooldown,
+        evaluate_on,
     })
 }
 
-fn parse_condition(alert: &AlertConfig) -> Option<ConditionType> {
-    match alert.condition.as_str() {
-        "above" => Some(ConditionType::Above(alert.threshold?)),
-        "below" => Some(ConditionType::Below(alert.threshold?)),
-        "cross_above" => Some(ConditionType::CrossAbove(alert.threshold?)),
-        "cross_below" => Some(ConditionType::CrossBelow(alert.threshold?)),
-        "between" => {
-            let low = alert.threshold?;
-            let high = alert
-                .params
-                .get("threshold_high")
-                .and_then(|v| v.as_float())?;
-            Some(ConditionType::Between { low, high })
+/// Alert indicators read straight from the ticker instead of computed
+/// from candles.
+pub const TICKER_INDICATORS: &[&str] = &[
+    "price",
+    "change_24h",
+    "volume_24h",
+    "quote_volume_24h",
+    "high_24h",
+    "low_24h",
+    "spread_pct",
+];
+
+/// Value of a ticker indicator, or `None` when `indicator` is not one or the
+/// ticker lacks the data (e.g. no best bid/ask).
+pub fn ticker_value(indicator: &str, ticker: &Ticker) -> Option<f64> {
+    let stats = &ticker.stats;
+    match indicator {
+        "price" => Some(ticker.price),
+        "change_24h" => Some(stats.change_pct_24h),
+        "volume_24h" => Some(ticker.volume),
+        "quote_volume_24h" => Some(stats.quote_volume_24h),
+        "high_24h" => Some(stats.high_24h),
+        "low_24h" => Some(stats.low_24h),
+        // Bid-ask spread as a percentage of the mid price
+        "spread_pct" => {
+            let (bid, ask) = (stats.best_bid?, stats.best_ask?);
+            let mid = (bid + ask) / 2.0;
+            (mid > 0.0).then(|| (ask - bid) / mid * 100.0)
+        }
+        _ => None,
+    }
+}
+
+/// Minutes `open_interest_change` compares across by default.
+pub const DEFAULT_OI_CHANGE_PERIOD: usize = 5;
+
+/// How much older than the time it stands for a sample compared by
+/// `open_interest_change` may be, e.g. with open interest polled every
+/// minute. Older samples mean the collector was down.
+pub const OI_SAMPLE_MAX_AGE_SECS: i64 = 180;
+
+/// Alert indicators read from the latest order book of the rule's coin.
+pub const ORDERBOOK_INDICATORS: &[&str] = &[
+    "book_imbalance",
+    "bid_wall_size",
+    "ask_wall_size",
+    "bid_wall_distance_pct",
+    "ask_wall_distance_pct",
+];
+
+/// Levels per side the order book indicators look at by default.
+pub const DEFAULT_BOOK_DEPTH: usize = 10;
+
+/// Value of an order book indicator over the best `depth` levels of each
+/// side of `book`: the bid minus ask size as a percentage of both
+/// (`book_imbalance`), or the size of the largest level of a side and its
+/// distance from the mid price in percent (the wall indicators). `None` when
+/// a side is empty.
+pub fn orderbook_value(indicator: &str, depth: usize, book: &OrderBook) -> Option<f64> {
+    let bids = &book.bids[..depth.min(book.bids.len())];
+    let asks = &book.asks[..depth.min(book.asks.len())];
+    let mid = (bids.first()?.price + asks.first()?.price) / 2.0;
+    let wall = |levels: &[OrderBookLevel]| {
+        levels
+            .iter()
+            .max_by(|a, b| a.size.total_cmp(&b.size))
+            .copied()
+    };
+    match indicator {
+        "book_imbalance" => {
+            let bid_size: f64 = bids.iter().map(|l| l.size).sum();
+            let ask_size: f64 = asks.iter().map(|l| l.size).sum();
+            let total = bid_size + ask_size;
+            (total > 0.0).then(|| (bid_size - ask_size) / total * 100.0)
         }
+        "bid_wall_size" => wall(bids).map(|l| l.size),
+        "ask_wall_size" => wall(asks).map(|l| l.size),
+        "bid_wall_distance_pct" => wall(bids).map(|l| (mid - l.price) / mid * 100.0),
+        "ask_wall_distance_pct" => wall(asks).map(|l| (l.price - mid) / mid * 100.0),
+        _ => None,
+    }
+}
+
+/// Whether alert `indicator` is computed from candles, as opposed to being
+/// read from the ticker, the order book or derivatives samples.
+pub fn uses_candles(indicator: &str) -> bool {
+    !TICKER_INDICATORS.contains(&indicator)
+        && !ORDERBOOK_INDICATORS.contains(&indicator)
+        && derivatives_metric(indicator).is_none()
+}
+
+/// Derivatives metric an alert indicator or signal input reads, or `None`
+/// when `indicator` is not a derivatives indicator.
+pub fn derivatives_metric(indicator: &str) -> Option<DerivativesMetric> {
+    match indicator {
+        "mark_price" => Some(DerivativesMetric::MarkPrice),
+        "funding_rate" => Some(DerivativesMetric::FundingRate),
+        "open_interest" | "open_interest_change" => Some(DerivativesMetric::OpenInterest),
+        _ => None,
+    }
+}
+
+/// How far before the evaluated time `derivatives_value` reads samples of
+/// `indicator`, besides the latest one: for `open_interest_change`, the
+/// period and how old its sample may be.
+pub fn derivatives_window(indicator: &str, period: usize) -> chrono::Duration {
+    match indicator {
+        "open_interest_change" => {
+            chrono::Duration::minutes(period as i64)
+                + chrono::Duration::seconds(OI_SAMPLE_MAX_AGE_SECS)
+        }
+        _ => chrono::Duration::zero(),
+    }
+}
+
+/// Value of a derivatives indicator as of `at` from its metric's samples,
+/// oldest first, using those taken up to `at`: the funding rate in
+/// percent, mark price and open interest as last sampled, and
+/// `open_interest_change` as the percent change from `period` minutes
+/// earlier. The change is `None` when the sample for either time is missing
+/// or older than `OI_SAMPLE_MAX_AGE_SECS`.
+pub fn derivatives_value(
+    indicator: &str,
+    period: usize,
+    samples: &[DerivativesSample],
+    at: DateTime<Utc>,
+) -> Option<f64> {
+    let latest = sample_before(samples, at)?;
+    match indicator {
+        "funding_rate" => Some(latest.value * 100.0),
+        "mark_price" | "open_interest" => Some(latest.value),
+        "open_interest_change" => {
+            let max_age = chrono::Duration::seconds(OI_SAMPLE_MAX_AGE_SECS);
+            let since = at - chrono::Duration::minutes(period as i64);
+            let base = sample_before(samples, since).filter(|s| since - s.timestamp <= max_age)?;
+            if at - latest.timestamp > max_age || base.value <= 0.0 {
+                return None;
+            }
+            Some((latest.value - base.value) / base.value * 100.0)
+        }
+        _ => None,
+    }
+}
+
+/// The last of `samples` (oldest first) taken at or before `at`.
+fn sample_before(samples: &[DerivativesSample], at: DateTime<Utc>) -> Option<&DerivativesSample> {
+    let i = samples.partition_point(|s| s.timestamp <= at);
+    samples.get(i.checked_sub(1)?)
+}
+
+/// Build a `ConditionType` from its config name and thresholds.
+pub fn parse_condition(
+    condition: &str,
+    threshold: Option<f64>,
+    threshold_high: Option<f64>,
+) -> Option<ConditionType> {
+    match condition {
+        "above" => Some(ConditionType::Above(threshold?)),
+        "below" => Some(ConditionType::Below(threshold?)),
+        "cross_above" => Some(ConditionType::CrossAbove(threshold?)),
+        "cross_below" => Some(ConditionType::CrossBelow(threshold?)),
+        "between" => Some(ConditionType::Between {
+            low: threshold?,
+            high: threshold_high?,
+        }),
         _ => None,
     }
 }
@@ -104,3 +289,120 @@ fn parse_indicator_params(alert: &AlertConfig) -> IndicatorParams {
         surge_multiplier: get_f64("surge_multiplier"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orderbook_values_measure_imbalance_and_walls() {
+        let level = |price, size| OrderBookLevel { price, size };
+        let book = OrderBook {
+            exchange: ExchangeKind::Binance,
+            symbol: "BTCUSDT".into(),
+            bids: vec![level(99.0, 1.0), level(98.0, 5.0), level(90.0, 50.0)],
+            asks: vec![level(101.0, 3.0), level(102.0, 1.0), level(110.0, 40.0)],
+            timestamp: DateTime::from_timestamp(1_704_067_200, 0).unwrap(),
+        };
+
+        // Only the best two levels of each side
+        assert_eq!(orderbook_value("book_imbalance", 2, &book), Some(20.0));
+        assert_eq!(orderbook_value("bid_wall_size", 2, &book), Some(5.0));
+        assert_eq!(
+            orderbook_value("bid_wall_distance_pct", 2, &book),
+            Some(2.0)
+        );
+        assert_eq!(orderbook_value("ask_wall_size", 2, &book), Some(3.0));
+        assert_eq!(
+            orderbook_value("ask_wall_distance_pct", 2, &book),
+            Some(1.0)
+        );
+        // Deeper, the far walls count
+        assert_eq!(orderbook_value("ask_wall_size", 3, &book), Some(40.0));
+        assert_eq!(
+            orderbook_value("bid_wall_distance_pct", 10, &book),
+            Some(10.0)
+        );
+
+        let one_sided = OrderBook {
+            asks: Vec::new(),
+            ..book
+        };
+        assert_eq!(orderbook_value("book_imbalance", 2, &one_sided), None);
+        assert!(!uses_candles("book_imbalance"));
+    }
+
+    fn minute(m: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_704_067_200 + m * 60, 0).unwrap()
+    }
+
+    fn samples(values: &[(i64, f64)]) -> Vec<DerivativesSample> {
+        values
+            .iter()
+            .map(|&(m, value)| DerivativesSample {
+                exchange: ExchangeKind::BinanceFutures,
+                symbol: "BTCUSDT".into(),
+                metric: DerivativesMetric::OpenInterest,
+                value,
+                timestamp: minute(m),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn derivatives_values_scale_funding_and_measure_oi_change() {
+        let at = minute(10);
+        assert_eq!(
+            derivatives_value("funding_rate", 0, &samples(&[(1, 0.0001), (2, 0.001)]), at),
+            Some(0.1)
+        );
+        assert_eq!(
+            derivatives_value("open_interest", 0, &samples(&[(1, 10.0), (2, 12.0)]), at),
+            Some(12.0)
+        );
+        assert_eq!(derivatives_value("funding_rate", 0, &[], at), None);
+        // Samples taken after `at` do not count
+        assert_eq!(
+            derivatives_value("open_interest", 0, &samples(&[(1, 10.0), (11, 12.0)]), at),
+            Some(10.0)
+        );
+
+        // Over 2 minutes, whatever the polling pace
+        let oi = samples(&[(6, 100.0), (7, 110.0), (8, 105.0), (9, 150.0)]);
+        assert_eq!(
+            derivatives_value("open_interest_change", 2, &oi, at),
+            Some(42.857142857142854)
+        );
+        let sparse = samples(&[(6, 100.0), (8, 120.0), (9, 150.0)]);
+        assert_eq!(
+            derivatives_value("open_interest_change", 3, &sparse, at),
+            Some(50.0)
+        );
+        // Nothing from back then
+        assert_eq!(derivatives_value("open_interest_change", 5, &oi, at), None);
+    }
+
+    #[test]
+    fn oi_change_is_not_measured_across_an_outage() {
+        // Polling stopped for half an hour between minutes 2 and 33
+        let oi = samples(&[(0, 100.0), (1, 100.0), (2, 100.0), (33, 150.0), (34, 160.0)]);
+        assert_eq!(
+            derivatives_value("open_interest_change", 5, &oi, minute(35)),
+            None
+        );
+        // Nor against a sample that stopped updating
+        assert_eq!(
+            derivatives_value("open_interest_change", 1, &oi, minute(45)),
+            None
+        );
+        let after_last = minute(34) + chrono::Duration::seconds(30);
+        assert_eq!(
+            derivatives_value("open_interest_change", 1, &oi, after_last),
+            Some(6.666666666666667)
+        );
+        assert_eq!(
+            derivatives_window("open_interest_change", 5),
+            chrono::Duration::seconds(5 * 60 + OI_SAMPLE_MAX_AGE_SECS)
+        );
+    }
+}
@@ -3,6 +3,7 @@ use error_stack::Report;
 use futures::future::BoxFuture;
 
 use crate::error::StorageError;
+use crate::model::PremiumSample;
 use crate::storage::Storage;
 use crate::strategy::{AlertRule, ConditionType};
 
@@ -43,6 +44,35 @@ pub fn evaluate(
     }
 }
 
+/// Evaluate a spread alert's `condition` against `sample`'s premium and the
+/// previous one.
+pub fn evaluate_spread(
+    sample: &PremiumSample,
+    condition: &ConditionType,
+    previous_premium: Option<f64>,
+) -> EvaluationResult {
+    let triggered = is_triggered(condition, sample.premium_pct, previous_premium);
+
+    let message = if triggered {
+        format!(
+            "[{}] {} premium {:.3}%",
+            sample.spread_name, sample.base, sample.premium_pct
+        )
+    } else {
+        format!(
+            "[{}] not triggered — premium={:.3}%",
+            sample.spread_name, sample.premium_pct
+        )
+    };
+
+    EvaluationResult {
+        triggered,
+        alert_name: sample.spread_name.clone(),
+        indicator_value: sample.premium_pct,
+        message,
+    }
+}
+
 fn is_triggered(condition: &ConditionType, current: f64, previous: Option<f64>) -> bool {
     match condition {
         ConditionType::Above(threshold) => current > *threshold,
@@ -64,10 +94,19 @@ fn is_triggered(condition: &ConditionType, current: f64, previous: Option<f64>)
 pub fn should_alert<'a>(
     storage: &'a dyn Storage,
     rule: &'a AlertRule,
+) -> BoxFuture<'a, Result<bool, Report<StorageError>>> {
+    cooldown_elapsed(storage, &rule.name, rule.cooldown_minutes)
+}
+
+/// Like `should_alert`, for alerts that are not `AlertRule`s, e.g. spreads.
+pub fn cooldown_elapsed<'a>(
+    storage: &'a dyn Storage,
+    alert_name: &'a str,
+    cooldown_minutes: u64,
 ) -> BoxFuture<'a, Result<bool, Report<StorageError>>> {
     Box::pin(async move {
-        let last_time = storage.last_alert_time(&rule.name).await?;
-        let cooldown = Duration::minutes(rule.cooldown_minutes as i64);
+        let last_time = storage.last_alert_time(alert_name).await?;
+        let cooldown = Duration::minutes(cooldown_minutes as i64);
         match last_time {
             Some(t) if Utc::now() - t < cooldown => Ok(false),
             _ => Ok(true),
@@ -78,8 +117,8 @@ pub fn should_alert<'a>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::ExchangeKind;
-    use crate::strategy::{ConditionType, IndicatorParams};
+    use crate::model::{ExchangeKind, TimeFrame};
+    use crate::strategy::{ConditionType, EvaluateOn, IndicatorParams};
 
     fn make_rule(condition: ConditionType) -> AlertRule {
         AlertRule {
@@ -95,8 +134,10 @@ mod tests {
                 std_dev_multiplier: None,
                 surge_multiplier: None,
             },
+            timeframe: TimeFrame::Min1,
             condition,
             cooldown_minutes: 5,
+            evaluate_on: EvaluateOn::Tick,
         }
     }
 
@@ -0,0 +1,170 @@
+//! Trade id bookkeeping for the trade-built candles.
+//!
+//! A WebSocket reconnect can deliver trades twice or drop them entirely.
+//! Trades whose id was already seen are reported as duplicates, and on
+//! exchanges with contiguous ids a jump in ids is reported as a gap, so the
+//! candles it spans can be fetched again over REST.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use chrono::{DateTime, Utc};
+
+use crate::exchange::contiguous_trade_ids;
+use crate::model::{ExchangeKind, Trade};
+
+/// Trade ids remembered per coin for spotting duplicates.
+const RECENT_TRADE_IDS: usize = 4096;
+
+/// Trades that were never delivered between two received ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeGap {
+    pub exchange: ExchangeKind,
+    pub symbol: String,
+    /// Number of trade ids skipped
+    pub missing: u64,
+    /// Time of the last trade before the gap
+    pub from: DateTime<Utc>,
+    /// Time of the first trade after the gap
+    pub to: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradeCheck {
+    /// Not seen before (or carries no id)
+    New,
+    /// Already delivered; must not be counted again
+    Duplicate,
+    /// New, but trades before it are missing
+    Gap(TradeGap),
+}
+
+/// Recently seen trade ids of one coin.
+#[derive(Debug, Default)]
+struct SeenTrades {
+    ids: HashSet<u64>,
+    /// `ids` in arrival order, for evicting the oldest
+    order: VecDeque<u64>,
+    /// Highest id seen and the time of that trade
+    newest: Option<(u64, DateTime<Utc>)>,
+}
+
+/// Tracks the trade ids of every coin to classify incoming trades.
+#[derive(Debug, Default)]
+pub struct TradeSequencer {
+    coins: HashMap<(ExchangeKind, String), SeenTrades>,
+}
+
+impl TradeSequencer {
+    /// Classify `trade` and remember its id.
+    pub fn check(&mut self, trade: &Trade) -> TradeCheck {
+        let Some(id) = trade.trade_id else {
+            return TradeCheck::New;
+        };
+        let seen = self
+            .coins
+            .entry((trade.exchange, trade.symbol.clone()))
+            .or_default();
+
+        if !seen.ids.insert(id) {
+            return TradeCheck::Duplicate;
+        }
+        seen.order.push_back(id);
+        if seen.order.len() > RECENT_TRADE_IDS
+            && let Some(oldest) = seen.order.pop_front()
+        {
+            seen.ids.remove(&oldest);
+        }
+
+        let gap = match seen.newest {
+            Some((newest, at)) if id > newest + 1 && contiguous_trade_ids(trade.exchange) => {
+                Some(TradeGap {
+                    exchange: trade.exchange,
+                    symbol: trade.symbol.clone(),
+                    missing: id - newest - 1,
+                    from: at,
+                    to: trade.timestamp,
+                })
+            }
+            _ => None,
+        };
+        if seen.newest.is_none_or(|(newest, _)| id > newest) {
+            seen.newest = Some((id, trade.timestamp));
+        }
+
+        gap.map_or(TradeCheck::New, TradeCheck::Gap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::instrument_for;
+    use crate::model::TradeSide;
+
+    fn trade(exchange: ExchangeKind, id: Option<u64>, secs: i64) -> Trade {
+        let symbol = match exchange {
+            ExchangeKind::Upbit => "KRW-SOL",
+            _ => "SOLUSDT",
+        };
+        Trade {
+            exchange,
+            symbol: symbol.into(),
+            instrument: instrument_for(exchange, symbol).unwrap(),
+            price: 100.0,
+            volume: 1.0,
+            side: TradeSide::Buy,
+            timestamp: DateTime::from_timestamp(secs, 0).unwrap(),
+            received_at: DateTime::from_timestamp(secs, 0).unwrap(),
+            trade_id: id,
+        }
+    }
+
+    #[test]
+    fn repeated_ids_are_duplicates() {
+        let mut sequencer = TradeSequencer::default();
+        let binance = |id| trade(ExchangeKind::Binance, Some(id), 60);
+
+        assert_eq!(sequencer.check(&binance(1)), TradeCheck::New);
+        assert_eq!(sequencer.check(&binance(2)), TradeCheck::New);
+        assert_eq!(sequencer.check(&binance(1)), TradeCheck::Duplicate);
+        assert_eq!(sequencer.check(&binance(2)), TradeCheck::Duplicate);
+        // Ids are tracked per coin
+        assert_eq!(
+            sequencer.check(&trade(ExchangeKind::Upbit, Some(1), 60)),
+            TradeCheck::New
+        );
+        // Trades without ids cannot be de-duplicated
+        let anonymous = trade(ExchangeKind::Binance, None, 60);
+        assert_eq!(sequencer.check(&anonymous), TradeCheck::New);
+        assert_eq!(sequencer.check(&anonymous), TradeCheck::New);
+    }
+
+    #[test]
+    fn skipped_ids_are_gaps_on_contiguous_exchanges() {
+        let mut sequencer = TradeSequencer::default();
+
+        sequencer.check(&trade(ExchangeKind::Binance, Some(10), 70));
+        assert_eq!(
+            sequencer.check(&trade(ExchangeKind::Binance, Some(14), 130)),
+            TradeCheck::Gap(TradeGap {
+                exchange: ExchangeKind::Binance,
+                symbol: "SOLUSDT".into(),
+                missing: 3,
+                from: DateTime::from_timestamp(70, 0).unwrap(),
+                to: DateTime::from_timestamp(130, 0).unwrap(),
+            })
+        );
+        // A late trade from inside the gap is new, not another gap
+        assert_eq!(
+            sequencer.check(&trade(ExchangeKind::Binance, Some(12), 100)),
+            TradeCheck::New
+        );
+
+        // Upbit ids are unique but not contiguous
+        sequencer.check(&trade(ExchangeKind::Upbit, Some(1_000), 70));
+        assert_eq!(
+            sequencer.check(&trade(ExchangeKind::Upbit, Some(5_000), 80)),
+            TradeCheck::New
+        );
+    }
+}